@@ -0,0 +1,59 @@
+use crate::{shortcodes::script_open_tag, Config};
+
+/// Returns the attribute that marks an interactive element for analytics, or nothing if
+/// analytics are disabled.
+pub(crate) fn attr(config: &Config, event: &str) -> String {
+    if config.analytics {
+        format!(" data-analytics-event=\"{}\"", event)
+    } else {
+        String::new()
+    }
+}
+
+/// Returns the script that reports interactions with marked elements in a chapter.
+///
+/// Events are passed to the configured dispatcher (e.g. `plausible`) as
+/// `dispatcher(event, { props: { page } })`, or otherwise sent as a
+/// `mdbook-shortcodes-analytics` event on `document` for the book's own script to handle.
+pub(crate) fn dispatcher(config: &Config) -> String {
+    let send = match &config.analytics_dispatcher {
+        Some(dispatcher) => format!(
+            "if (typeof window.{0} === \"function\") window.{0}(event, {{ props: {{ page: location.pathname }} }});",
+            dispatcher
+        ),
+        None => "document.dispatchEvent(new CustomEvent(\"mdbook-shortcodes-analytics\", \
+            { detail: { event: event, page: location.pathname } }));"
+            .to_owned(),
+    };
+    format!(
+        "{}(function () {{\
+        function send(event) {{ {} }}\
+        function handle(e) {{\
+        var target = e.target.closest && e.target.closest(\"[data-analytics-event]\");\
+        if (target && (e.type === \"click\") === (target.tagName !== \"DETAILS\")) \
+        send(target.dataset.analyticsEvent);\
+        }}\
+        document.addEventListener(\"click\", handle);\
+        document.addEventListener(\"toggle\", handle, true);\
+        }})();</script>",
+        script_open_tag(false, config),
+        send
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analytics() {
+        let mut config = Config::default();
+        assert_eq!(attr(&config, "tab"), "");
+        config.analytics = true;
+        assert_eq!(attr(&config, "tab"), " data-analytics-event=\"tab\"");
+
+        assert!(dispatcher(&config).contains("new CustomEvent"));
+        config.analytics_dispatcher = Some("plausible".to_owned());
+        assert!(dispatcher(&config).contains("window.plausible(event,"));
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use mdbook::errors::{Error, Result};
+
+/// Files used by shortcodes that have to be copied into the book.
+///
+/// Preprocessors run before the renderers, which copy every non-markdown file in the source
+/// directory into the build output. Hence, assets are staged into a directory inside the source
+/// directory rather than into the build directory itself.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub(crate) struct Assets {
+    /// Maps the destination of each asset, relative to the assets directory, to its source.
+    entries: BTreeMap<PathBuf, Source>,
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+enum Source {
+    /// A file or directory on disk.
+    Path(PathBuf),
+    /// A file generated by a shortcode.
+    Content(Vec<u8>),
+}
+
+impl Assets {
+    /// Queues the file or directory at `source` to be copied to `dest`, which is relative to
+    /// the assets directory.
+    pub(crate) fn add<P, Q>(&mut self, source: P, dest: Q)
+    where
+        P: Into<PathBuf>,
+        Q: Into<PathBuf>,
+    {
+        self.entries
+            .insert(dest.into(), Source::Path(source.into()));
+    }
+
+    /// Queues a file containing `content` to be written to `dest`, which is relative to the
+    /// assets directory.
+    pub(crate) fn add_content<P>(&mut self, content: Vec<u8>, dest: P)
+    where
+        P: Into<PathBuf>,
+    {
+        self.entries.insert(dest.into(), Source::Content(content));
+    }
+
+    /// Copies every queued asset into `assets_dir` inside `src_dir`.
+    ///
+    /// Files that are already up to date aren't rewritten, so that `mdbook serve` doesn't
+    /// detect a change and rebuild the book in a loop.
+    pub(crate) fn stage(&self, src_dir: &Path, assets_dir: &str) -> Result<()> {
+        for (dest, source) in &self.entries {
+            let dest = src_dir.join(assets_dir).join(dest);
+            match source {
+                Source::Path(source) => copy_if_changed(source, &dest).map_err(|e| {
+                    Error::from(e).context(format!("failed to copy asset {}", source.display()))
+                })?,
+                Source::Content(content) => write_if_changed(content, &dest).map_err(|e| {
+                    Error::from(e).context(format!("failed to write asset {}", dest.display()))
+                })?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn copy_if_changed(source: &Path, dest: &Path) -> std::io::Result<()> {
+    if source.is_dir() {
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_if_changed(&entry.path(), &dest.join(entry.file_name()))?;
+        }
+        return Ok(());
+    }
+
+    write_if_changed(&fs::read(source)?, dest)
+}
+
+fn write_if_changed(content: &[u8], dest: &Path) -> std::io::Result<()> {
+    if fs::read(dest).ok().as_deref() != Some(content) {
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(dest, content)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage() {
+        let dir = tempfile::tempdir().unwrap();
+        let pkg = dir.path().join("pkg");
+        fs::create_dir_all(pkg.join("snippets")).unwrap();
+        fs::write(pkg.join("demo.js"), "js").unwrap();
+        fs::write(pkg.join("snippets/a.js"), "a").unwrap();
+
+        let mut assets = Assets::default();
+        assets.add(&pkg, "wasm-demo/pkg");
+        assets.add_content(b"<svg/>".to_vec(), "badges/a.svg");
+        let src_dir = dir.path().join("src");
+        assets.stage(&src_dir, "assets").unwrap();
+
+        let staged = src_dir.join("assets/wasm-demo/pkg");
+        assert_eq!(fs::read_to_string(staged.join("demo.js")).unwrap(), "js");
+        assert_eq!(
+            fs::read_to_string(staged.join("snippets/a.js")).unwrap(),
+            "a"
+        );
+
+        let mut missing = Assets::default();
+        missing.add(dir.path().join("missing"), "missing");
+        assert!(missing.stage(&src_dir, "assets").is_err());
+    }
+}
@@ -0,0 +1,225 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use crate::{Error, Result};
+
+/// The attributes given in the opening tag of a shortcode, e.g. `warning title="Careful"`.
+///
+/// Attributes are separated by whitespace, and can be quoted to include whitespace. A
+/// `key=value` attribute is named, unless the whole attribute is quoted (e.g. `"FOO=1 cargo
+/// run"`); its value can be quoted on its own (e.g. `title="It's quoted"`).
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Attrs<'a> {
+    positional: Vec<&'a str>,
+    named: BTreeMap<&'a str, &'a str>,
+}
+
+impl<'a> Attrs<'a> {
+    pub(crate) fn parse(raw_attrs: &'a str) -> Result<Self> {
+        let mut result = Self::default();
+        let mut attr_start_index = 0;
+        let mut attr_end_index = 0;
+        // The quote character that opened the current string, if any.
+        let mut in_quote = None;
+        // Whether the current string is the value of a named attribute (e.g. `title="Foo bar"`),
+        // in which case the quotes are kept until the attribute is split.
+        let mut in_value = false;
+
+        let raw_attrs = raw_attrs.trim();
+
+        // TODO
+        if raw_attrs.is_empty() {
+            return Ok(result);
+        }
+
+        for (i, c) in raw_attrs.char_indices() {
+            if is_quote(&c) && (in_quote.is_none() || in_quote == Some(c)) {
+                if in_quote.is_some() {
+                    if !in_value {
+                        result.positional.push(&raw_attrs[attr_start_index..i]);
+                        attr_start_index = i + 1;
+                    }
+                    in_quote = None;
+                } else {
+                    in_value = i != attr_start_index;
+                    if !in_value {
+                        attr_start_index = i + 1;
+                    }
+                    in_quote = Some(c);
+                }
+            } else if c.is_whitespace() && in_quote.is_none() {
+                if i != attr_start_index {
+                    result.push(&raw_attrs[attr_start_index..i]);
+                }
+                attr_start_index = i + 1;
+                in_value = false;
+            }
+            attr_end_index = i;
+        }
+
+        if in_quote.is_some() {
+            return Err(Error::UnterminatedString);
+        } else if attr_start_index <= attr_end_index {
+            // `attr_start_index` is only greater than `attr_end_index` at the end of the loop
+            // if the last char of the string was a quote that closed a string. Hence, this
+            // block is only entered if the last character WASN'T a closing quote. Since,
+            // whitespace has been stripped, we are guaranteed to have missed the last attribute
+            // in the loop.
+            result.push(&raw_attrs[attr_start_index..=attr_end_index])
+        }
+
+        Ok(result)
+    }
+
+    /// Adds an unquoted attribute, which is named if it contains `=`.
+    fn push(&mut self, attr: &'a str) {
+        match attr.split_once('=') {
+            Some((key, value)) if !key.is_empty() => {
+                let mut chars = value.chars();
+                let value = match (chars.next(), chars.next_back()) {
+                    (Some(first), Some(last)) if is_quote(&first) && first == last => {
+                        chars.as_str()
+                    }
+                    _ => value,
+                };
+                self.named.insert(key, value);
+            }
+            _ => self.positional.push(attr),
+        }
+    }
+
+    /// The attributes that aren't named, in the order they were given.
+    pub fn positional(&self) -> &[&'a str] {
+        &self.positional
+    }
+
+    /// The `key=value` attributes, without any quotes surrounding the values. If a key is given
+    /// more than once, the last value is used.
+    pub fn named(&self) -> &BTreeMap<&'a str, &'a str> {
+        &self.named
+    }
+
+    /// Returns the value of the `key` attribute.
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.named.get(key).copied()
+    }
+
+    /// Returns the value of the `key` attribute parsed as a `T`, or an error describing the
+    /// invalid value.
+    pub fn get_parsed<T: FromStr>(&self, key: &str) -> std::result::Result<Option<T>, String> {
+        self.get(key)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| format!("invalid {} `{}`", key, value))
+            })
+            .transpose()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positional.is_empty() && self.named.is_empty()
+    }
+
+    /// Returns an error if there are more than `max_positional` positional attributes, or a
+    /// named attribute that isn't one of `keys`.
+    pub fn expect(&self, max_positional: usize, keys: &[&str]) -> std::result::Result<(), String> {
+        if self.positional.len() > max_positional {
+            return Err("too many arguments".to_owned());
+        }
+        match self.named.keys().find(|key| !keys.contains(key)) {
+            Some(key) => Err(format!("unknown argument `{}`", key)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn is_quote(c: &char) -> bool {
+    *c == '\'' || *c == '"'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        fn whitespace_variants(base: &str) -> Vec<String> {
+            let mut result = vec![base.to_owned()];
+
+            for w in [" ", "  "] {
+                let mut temp = w.to_owned();
+                temp.push_str(base);
+                result.push(temp);
+
+                let mut temp = base.to_owned();
+                temp.push_str(w);
+                result.push(temp);
+            }
+
+            result
+        }
+
+        // The positional and named attributes.
+        type Expected<'a> = Result<(Vec<&'a str>, Vec<(&'a str, &'a str)>)>;
+
+        let cases: Vec<(&str, Expected)> = vec![
+            ("", Ok((Vec::new(), Vec::new()))),
+            (
+                "my name is john",
+                Ok((vec!["my", "name", "is", "john"], Vec::new())),
+            ),
+            ("c", Ok((vec!["c"], Vec::new()))),
+            ("c a", Ok((vec!["c", "a"], Vec::new()))),
+            ("\"d\" \"q\"", Ok((vec!["d", "q"], Vec::new()))),
+            ("\"s\" \"q\"", Ok((vec!["s", "q"], Vec::new()))),
+            (
+                "\"Multiple words in quotes\" foo 'bar'",
+                Ok((vec!["Multiple words in quotes", "foo", "bar"], Vec::new())),
+            ),
+            (
+                "title=\"It's quoted\" open",
+                Ok((vec!["open"], vec![("title", "It's quoted")])),
+            ),
+            (
+                "warning type=tip title='Careful' \"FOO=1 ls\"",
+                Ok((
+                    vec!["warning", "FOO=1 ls"],
+                    vec![("title", "Careful"), ("type", "tip")],
+                )),
+            ),
+            ("\"Unterminated string", Err(Error::UnterminatedString)),
+            ("Unterminated string\"", Err(Error::UnterminatedString)),
+        ];
+
+        for (input, expected) in cases {
+            let expected = expected.map(|(positional, named)| Attrs {
+                positional,
+                named: named.into_iter().collect(),
+            });
+            for i in whitespace_variants(input) {
+                assert_eq!(Attrs::parse(&i), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_accessors() {
+        let attrs = Attrs::parse("a count=3 gap=x").unwrap();
+        assert_eq!(attrs.get("gap"), Some("x"));
+        assert_eq!(attrs.get_parsed::<u8>("count"), Ok(Some(3)));
+        assert_eq!(attrs.get_parsed::<u8>("missing"), Ok(None));
+        assert_eq!(
+            attrs.get_parsed::<u8>("gap"),
+            Err("invalid gap `x`".to_owned())
+        );
+
+        assert_eq!(attrs.expect(1, &["count", "gap"]), Ok(()));
+        assert_eq!(
+            attrs.expect(0, &["count", "gap"]),
+            Err("too many arguments".to_owned())
+        );
+        assert_eq!(
+            attrs.expect(1, &["count"]),
+            Err("unknown argument `gap`".to_owned())
+        );
+    }
+}
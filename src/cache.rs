@@ -0,0 +1,93 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use sha2::{Digest, Sha256};
+
+/// An on-disk cache for the results of expensive work done by shortcodes, such as downloading
+/// files or running commands.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub(crate) struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Returns a key that uniquely identifies `parts`.
+    pub(crate) fn key(parts: &[&[u8]]) -> String {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            // The length is included so that e.g. `["ab", "c"]` and `["a", "bc"]` differ.
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// Returns the cached value, unless it is older than `max_age`.
+    pub(crate) fn get(
+        &self,
+        namespace: &str,
+        key: &str,
+        max_age: Option<Duration>,
+    ) -> Option<Vec<u8>> {
+        let path = self.path(namespace, key);
+        if let Some(max_age) = max_age {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            let age = SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default();
+            if age > max_age {
+                return None;
+            }
+        }
+        fs::read(path).ok()
+    }
+
+    pub(crate) fn put(&self, namespace: &str, key: &str, value: &[u8]) -> std::io::Result<()> {
+        let path = self.path(namespace, key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, value)
+    }
+
+    fn path(&self, namespace: &str, key: &str) -> PathBuf {
+        self.dir.join(namespace).join(Path::new(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().to_owned());
+        let key = Cache::key(&[b"a", b"bc"]);
+        assert_ne!(key, Cache::key(&[b"ab", b"c"]));
+
+        assert_eq!(cache.get("test", &key, None), None);
+        cache.put("test", &key, b"value").unwrap();
+        assert_eq!(cache.get("test", &key, None), Some(b"value".to_vec()));
+        assert_eq!(
+            cache.get("test", &key, Some(Duration::from_secs(60))),
+            Some(b"value".to_vec())
+        );
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(
+            cache.get("test", &key, Some(Duration::from_millis(1))),
+            None
+        );
+    }
+}
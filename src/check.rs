@@ -0,0 +1,141 @@
+use std::{collections::BTreeMap, path::Path};
+
+use crate::{docs::doc, parse, Attrs, Delimiters, Error};
+
+/// The tags of mdbook's own preprocessors, which are written like shortcodes.
+const MDBOOK_TAGS: &[&str] = &[
+    "include",
+    "rustdoc_include",
+    "playground",
+    "playpen",
+    "title",
+];
+
+/// Tags that are part of the body of a shortcode, rather than shortcodes of their own.
+const SEPARATORS: &[&str] = &["column", "else"];
+
+/// Returns the problems with the shortcodes in `content`, the chapter at `chapter`, without
+/// expanding them: unclosed tags, tags that aren't shortcodes and attributes their shortcodes
+/// don't accept. `names` maps the names shortcodes can be called by to the names of the
+/// shortcodes, and `is_checked` returns whether tags with a name are meant for this
+/// preprocessor, rather than for another one (e.g. `columns = false`).
+pub(crate) fn check<F>(
+    chapter: &Path,
+    content: &str,
+    delimiters: &Delimiters,
+    names: &BTreeMap<String, String>,
+    is_checked: &F,
+) -> Vec<Error>
+where
+    F: Fn(&str) -> bool,
+{
+    let mut problems = Vec::new();
+    for (index, name) in parse::opening_tags(content, delimiters) {
+        let known =
+            names.contains_key(name) || MDBOOK_TAGS.contains(&name) || SEPARATORS.contains(&name);
+        if !name.is_empty() && !known && is_checked(name) {
+            problems.push((index, name, "no such shortcode".to_owned()));
+        }
+    }
+    check_nodes(content, 0, delimiters, names, &mut problems);
+
+    problems.sort_by_key(|&(index, _, _)| index);
+    problems
+        .into_iter()
+        .map(|(index, name, message)| {
+            let line_start = content[..index].rfind('\n').map_or(0, |i| i + 1);
+            Error::InvalidShortcode {
+                name: name.to_owned(),
+                chapter: chapter.to_owned(),
+                line: content[..index].matches('\n').count() + 1,
+                column: content[line_start..index].chars().count() + 1,
+                message,
+            }
+        })
+        .collect()
+}
+
+/// Adds the problems with the shortcodes in `content`, which starts at `offset` in its chapter,
+/// to `problems`, along with where their opening tags are.
+fn check_nodes<'a>(
+    content: &'a str,
+    offset: usize,
+    delimiters: &Delimiters,
+    names: &BTreeMap<String, String>,
+    problems: &mut Vec<(usize, &'a str, String)>,
+) {
+    let parsed = match parse::parse(content, delimiters, |name| names.contains_key(name)) {
+        Ok(parsed) => parsed,
+        // The rest of the content is checked as if the unclosed tag wasn't there, so that it
+        // doesn't hide other problems.
+        Err(unclosed) => {
+            let message = "no closing tag".to_owned();
+            problems.push((offset + unclosed.tag, unclosed.name, message));
+            let before = &content[..unclosed.tag];
+            check_nodes(before, offset, delimiters, names, problems);
+            if let Some(i) = content[unclosed.tag..].find(&delimiters.close) {
+                let after = unclosed.tag + i + delimiters.close.len();
+                check_nodes(
+                    &content[after..],
+                    offset + after,
+                    delimiters,
+                    names,
+                    problems,
+                );
+            }
+            return;
+        }
+    };
+
+    for node in parsed.nodes {
+        let name = &names[node.name];
+        let checked = Attrs::parse(node.attrs)
+            .map_err(|e| e.to_string())
+            .and_then(|attrs| match doc(name).and_then(|doc| doc.attrs) {
+                Some((max_positional, keys)) => attrs.expect(max_positional, keys),
+                None => Ok(()),
+            });
+        if let Err(message) = checked {
+            problems.push((offset + node.tag.start, node.name, message));
+        }
+        if let Some(body) = node.body {
+            check_nodes(body, offset + node.tag.end, delimiters, names, problems);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let names = [("hint", "hint"), ("callout", "hint"), ("kbd", "kbd")]
+            .iter()
+            .map(|&(alias, name)| (alias.to_owned(), name.to_owned()))
+            .collect();
+        let content = "# Intro\n\n{{#include a.rs}} {{#hitn info}}A{{/hitn}}\n\
+            {{#callout info colour=red}}\n`{{#nope}}` {{#hint a b}}B{{/hint}}\n{{/callout}}\n\
+            \\{{#nope}} {{#mermaid}}\n{{#kbd}} {{#hint c d}}C{{/hint}}";
+        let problems: Vec<_> = check(
+            Path::new("intro.md"),
+            content,
+            &Delimiters::default(),
+            &names,
+            &|name: &str| name != "mermaid",
+        )
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+        assert_eq!(
+            problems,
+            [
+                "intro.md:3:19: hitn shortcode: no such shortcode",
+                "intro.md:4:1: callout shortcode: unknown argument `colour`",
+                "intro.md:5:13: hint shortcode: too many arguments",
+                "intro.md:8:1: kbd shortcode: no closing tag",
+                "intro.md:8:10: hint shortcode: too many arguments",
+            ]
+        );
+    }
+}
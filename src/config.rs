@@ -0,0 +1,499 @@
+use std::{collections::BTreeMap, path::Path};
+
+use mdbook::{preprocess::PreprocessorContext, Config as BookConfig};
+use serde::{de::Visitor, Deserialize, Deserializer, Serialize};
+
+use crate::{builtins, theme};
+
+/// The options that can be set in the `[preprocessor.shortcodes]` table of `book.toml`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    /// The nonce added to every `<script>` tag generated by the preprocessor.
+    ///
+    /// Setting this enables CSP mode. As books are static, the value is usually a placeholder
+    /// (e.g. `"__CSP_NONCE__"`) that the web server replaces with a fresh nonce per request.
+    pub csp_nonce: Option<String>,
+    /// The directory, relative to the book's source directory, that files used by shortcodes
+    /// are copied into.
+    pub assets_dir: String,
+    /// The documentation URL of each crate linked to by the `rustdoc` shortcode, keyed by crate
+    /// name (e.g. `mycrate = "https://example.com/docs/mycrate/"`).
+    ///
+    /// The standard library crates default to `doc.rust-lang.org` and every other crate to
+    /// `docs.rs`.
+    pub rustdoc: BTreeMap<String, String>,
+    /// The directory, relative to the book's root directory, that downloaded files and other
+    /// expensive results are cached in.
+    pub cache_dir: String,
+    /// How the `crate-badge` and `repo-badge` shortcodes include badges.
+    pub badges: BadgeMode,
+    /// Whether shortcodes are kept from using the network, for builds without access to it.
+    /// Downloads (e.g. by the `remote` shortcode) only use what is in the `cache-dir`, and fail
+    /// when it isn't there.
+    pub offline: bool,
+    /// Whether shortcodes are allowed to run external programs (e.g. `rustc-output`).
+    pub allow_exec: bool,
+    /// What shortcodes are allowed to do beyond transforming their input (e.g.
+    /// `["filesystem"]`), or `None` for anything. Hosted and CI builds can use it to forbid
+    /// shortcodes that run programs or use the network, whose uses are then reported as errors.
+    /// Pure shortcodes are always allowed.
+    pub capabilities: Option<Vec<Capability>>,
+    /// The rustup toolchain used by the `rustc-output` shortcode (e.g. `"1.70"` or `"nightly"`),
+    /// or `None` for the default toolchain.
+    pub rustc_toolchain: Option<String>,
+    /// How many seconds the output of commands run by the `cmd-output` shortcode is reused for.
+    pub exec_cache_ttl: u64,
+    /// The unit system shown first by the `unit` shortcode.
+    pub units: UnitSystem,
+    /// The currency used by the `money` shortcode when none is given, e.g. `"EUR"`.
+    pub currency: String,
+    /// The command the `smiles` shortcode uses to draw structures, e.g.
+    /// `"obabel -:{smiles} -osvg"`. `{smiles}` is replaced by the (quoted) SMILES string and the
+    /// command must write an SVG image to its standard output.
+    pub smiles_command: Option<String>,
+    /// Whether the cards of the `flashcard` shortcode are exported as an Anki deck
+    /// (`flashcards.tsv` in the assets directory).
+    pub export_flashcards: bool,
+    /// Whether chapters split with the `slide` shortcode are also exported as reveal.js
+    /// presentations (in `slides/` in the assets directory) when building HTML.
+    pub slides: bool,
+    /// The kind of output the book is built for.
+    pub profile: Profile,
+    /// Whether interactive elements (copy buttons, collapsible sections such as flashcards, and
+    /// crossword checkers) are marked with `data-analytics-event` attributes, along with a
+    /// script that reports when readers use them.
+    pub analytics: bool,
+    /// The global function the analytics script calls with each event (e.g. `"plausible"`).
+    /// By default, a `mdbook-shortcodes-analytics` event is dispatched on `document` instead.
+    pub analytics_dispatcher: Option<String>,
+    /// A file, relative to the book's root directory, containing snippet definitions that can
+    /// be used in any chapter.
+    pub snippets_file: Option<String>,
+    /// A directory, relative to the book's root directory, of HTML templates that are each a
+    /// shortcode named after their file (e.g. `video.html` adds `{{#video ... /}}`).
+    pub templates_dir: String,
+    /// Where the styles and scripts used by shortcodes are added to each chapter. A
+    /// `{{#shortcode-assets /}}` placeholder in a chapter takes precedence.
+    pub header_position: HeaderPosition,
+    /// How the styles of shortcodes are included in the book.
+    pub css_mode: CssMode,
+    /// A directory, relative to the book's root directory, of stylesheets that override the
+    /// styles of the shortcodes they are named after (e.g. `hint.css`).
+    pub css_dir: String,
+    /// Whether the stylesheets in the `css-dir` replace the styles of their shortcodes or are
+    /// added after them.
+    pub css_override: CssOverride,
+    /// The shortcodes whose output is left out of the search index (e.g. `["flashcard"]`, so
+    /// that searching doesn't give away the answers). Their output is then shown by a script.
+    pub search_exclude: Vec<String>,
+    /// Chapters that aren't processed, relative to the book's source directory (e.g.
+    /// `["appendix/templates.md"]`). A directory skips every chapter in it. A chapter can also
+    /// skip itself by starting with `<!-- shortcodes: off -->`.
+    pub skip_chapters: Vec<String>,
+    /// Directories, relative to the book's root directory, that shortcodes read files from (e.g.
+    /// `["data", "partials"]`). Under `mdbook serve`, the book is rebuilt when a file in them
+    /// changes, and results cached by shortcodes that depend on them (e.g. the output of a
+    /// command run in one) are discarded.
+    pub watch_dirs: Vec<String>,
+    /// Where to write the files read by the shortcodes in each chapter, relative to the book's
+    /// root directory (e.g. `shortcode-deps.json`). Files ending in `.dot` are written as a
+    /// Graphviz graph, and other files as JSON.
+    pub deps_file: Option<String>,
+    /// Where to write statistics about the book (e.g. its word count, as shown by the
+    /// `book-stats` shortcode) as JSON, relative to the book's root directory. They include how
+    /// long each shortcode and chapter took to expand.
+    pub stats_file: Option<String>,
+    /// Where to write the source map of each chapter as JSON, relative to the book's root
+    /// directory, which maps ranges of the expanded chapter to the markdown they came from.
+    pub source_map_file: Option<String>,
+    /// How many milliseconds a use of a shortcode can take to expand before a warning is logged
+    /// (e.g. for a slow command), or `None` for no limit.
+    pub time_budget_ms: Option<u64>,
+    /// Where to record the shortcodes used in each chapter and their output, relative to the
+    /// book's root directory, so that `mdbook-shortcodes diff-build` can compare builds. A
+    /// record is written for each renderer (e.g. `html.json`).
+    pub record_dir: Option<String>,
+    /// Whether dangerous HTML (e.g. `<script>` tags) is removed from the bodies of shortcodes,
+    /// for books that accept contributions from untrusted authors. Requires the `sanitize`
+    /// feature.
+    pub sanitize_bodies: bool,
+    /// Whether a shortcode that is used incorrectly stops the build. Otherwise, its tag is left
+    /// as it was written and a warning is logged, so that drafts still build. An unclosed tag
+    /// doesn't stop the shortcodes around it from being expanded.
+    pub fail_on_error: bool,
+    /// Where errors are shown. `"inline"` shows each one in a card in place of the shortcode
+    /// it is about, along with its tag, and doesn't stop the build, for writing with
+    /// `mdbook serve`.
+    pub errors: ErrorDisplay,
+    /// The values of the CSS custom properties used by the styles of shortcodes, set in the
+    /// `[preprocessor.shortcodes.theme]` table without their `--mdbook-shortcodes-` prefix
+    /// (e.g. `hint-info-color = "#88f"`). The values are used under every mdBook theme, rather
+    /// than the defaults for its dark themes.
+    pub theme: BTreeMap<String, String>,
+    /// The values inserted by the `var` shortcode, set in the
+    /// `[preprocessor.shortcodes.variables]` table (e.g. `version = "1.2.0"`).
+    pub variables: BTreeMap<String, String>,
+    /// The environment variables the `env` shortcode can read (e.g. `["CI_COMMIT_SHA"]`).
+    pub env_allowlist: Vec<String>,
+    /// The flags tested by the `if` shortcode, set in the `[preprocessor.shortcodes.flags]`
+    /// table (e.g. `internal = true`), so that one book can be built in several editions.
+    pub flags: BTreeMap<String, bool>,
+    /// The delimiters around the tags of shortcodes, e.g. `{ open = "[[", close = "]]" }` for
+    /// `[[#hint info]]...[[/hint]]`, so that they can't be confused with the tags of other
+    /// preprocessors (e.g. mdBook's `{{#include}}`).
+    pub delimiters: Delimiters,
+    /// Other names shortcodes can be called by, set in the `[preprocessor.shortcodes.aliases]`
+    /// table (e.g. `note = "hint"`), so that content written for other tools can be processed.
+    pub aliases: BTreeMap<String, String>,
+    /// The only shortcodes that are expanded, or `None` for every shortcode.
+    pub enabled: Option<Vec<String>>,
+    /// Whether each shortcode is expanded, set with `<name> = false` (e.g. `columns = false`)
+    /// to leave a shortcode for another preprocessor. Shortcodes not listed are expanded, and
+    /// keys that are neither options nor shortcodes are warned about.
+    #[serde(skip)]
+    pub shortcodes: BTreeMap<String, bool>,
+}
+
+/// The delimiters around the tags of shortcodes. Opening tags start with `open` followed by
+/// `#`, and closing tags with `open` followed by `/`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Delimiters {
+    pub open: String,
+    pub close: String,
+}
+
+impl Default for Delimiters {
+    fn default() -> Self {
+        Self {
+            open: "{{".to_owned(),
+            close: "}}".to_owned(),
+        }
+    }
+}
+
+impl Delimiters {
+    /// Returns the opening tag of the `name` shortcode without any attributes, e.g.
+    /// `{{#column}}`.
+    pub(crate) fn tag(&self, name: &str) -> String {
+        format!("{}#{}{}", self.open, name, self.close)
+    }
+
+    /// Returns an error if the delimiters can't be told apart from the rest of a tag.
+    fn validate(&self) -> Result<(), String> {
+        for delimiter in [&self.open, &self.close] {
+            if delimiter.is_empty() || delimiter.contains(char::is_whitespace) {
+                return Err(format!("invalid delimiter `{}`", delimiter));
+            }
+        }
+        if self.close.starts_with(['#', '/']) {
+            return Err(format!(
+                "invalid delimiter `{}`; closing delimiters can't start with `#` or `/`",
+                self.close
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// How badges are included in the book.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BadgeMode {
+    /// Badges are loaded from shields.io by the reader's browser.
+    #[default]
+    Hotlink,
+    /// Badges are downloaded at build time and copied into the book, so that it works offline.
+    /// Requires the `remote` feature.
+    Static,
+}
+
+/// A system of measurement.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UnitSystem {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+/// Where the headers of shortcodes are added to a chapter.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HeaderPosition {
+    /// Before the chapter's content.
+    #[default]
+    Top,
+    /// After the chapter's first heading (e.g. `# Title`), so that the title comes first.
+    AfterHeading,
+    /// After the chapter's content.
+    Bottom,
+}
+
+/// How the styles of shortcodes are included in the book.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CssMode {
+    /// Every chapter contains the styles of the shortcodes it uses.
+    #[default]
+    Inline,
+    /// The styles of every shortcode used in the book are written to a single stylesheet
+    /// (`shortcodes.css` in the assets directory), which every chapter using them links to.
+    /// If the stylesheet is added to `output.html.additional-css` (e.g.
+    /// `"src/shortcodes-assets/shortcodes.css"`), mdBook links it from every page instead.
+    Hoist,
+}
+
+/// How a stylesheet in the `css-dir` overrides the styles of its shortcode.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CssOverride {
+    /// The stylesheet is used instead of the shortcode's styles.
+    #[default]
+    Replace,
+    /// The stylesheet is added after the shortcode's styles, so that it only needs to change
+    /// some of them.
+    Extend,
+}
+
+/// Where errors in the use of shortcodes are shown.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorDisplay {
+    /// Errors are only reported in the build's output.
+    #[default]
+    Log,
+    /// Errors are also shown in the chapters they occur in (by renderers that support HTML),
+    /// and are logged as warnings rather than stopping the build.
+    Inline,
+}
+
+/// What a shortcode does beyond transforming its input, which the `capabilities` option can
+/// forbid.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Capability {
+    /// Only transforms its input.
+    Pure,
+    /// Reads files from the book's directory (e.g. `sql`).
+    Filesystem,
+    /// Downloads resources (e.g. `crate-badge`).
+    Network,
+    /// Runs external programs (e.g. `cmd-output`).
+    Exec,
+}
+
+impl std::fmt::Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Capability::Pure => "pure",
+            Capability::Filesystem => "filesystem",
+            Capability::Network => "network",
+            Capability::Exec => "exec",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The kind of output a book is built for.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Profile {
+    #[default]
+    Web,
+    /// Interactive shortcodes are flattened so that printing a page (e.g. to a PDF handout)
+    /// shows all of their content: collapsible sections (e.g. flashcards and the code of demos)
+    /// are expanded, and crosswords are left blank.
+    Print,
+    /// A published web build. Editorial notes (e.g. `{{#todo}}`) are stripped, and listed once
+    /// the book has been processed.
+    Release,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            csp_nonce: None,
+            assets_dir: "shortcodes-assets".to_owned(),
+            rustdoc: BTreeMap::new(),
+            cache_dir: ".shortcodes-cache".to_owned(),
+            badges: BadgeMode::default(),
+            offline: false,
+            allow_exec: false,
+            capabilities: None,
+            rustc_toolchain: None,
+            exec_cache_ttl: 300,
+            units: UnitSystem::default(),
+            currency: "USD".to_owned(),
+            smiles_command: None,
+            export_flashcards: false,
+            slides: false,
+            profile: Profile::default(),
+            analytics: false,
+            analytics_dispatcher: None,
+            snippets_file: None,
+            templates_dir: "shortcodes".to_owned(),
+            header_position: HeaderPosition::default(),
+            css_mode: CssMode::default(),
+            css_dir: "shortcodes-css".to_owned(),
+            css_override: CssOverride::default(),
+            search_exclude: Vec::new(),
+            skip_chapters: Vec::new(),
+            watch_dirs: Vec::new(),
+            deps_file: None,
+            stats_file: None,
+            source_map_file: None,
+            time_budget_ms: None,
+            record_dir: None,
+            sanitize_bodies: false,
+            fail_on_error: true,
+            errors: ErrorDisplay::default(),
+            theme: BTreeMap::new(),
+            variables: BTreeMap::new(),
+            env_allowlist: Vec::new(),
+            flags: BTreeMap::new(),
+            delimiters: Delimiters::default(),
+            aliases: BTreeMap::new(),
+            enabled: None,
+            shortcodes: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn from_context(ctx: &PreprocessorContext) -> mdbook::errors::Result<Self> {
+        Self::from_book_config(&ctx.config)
+    }
+
+    /// Reads the `[preprocessor.shortcodes]` table of a book's configuration.
+    pub fn from_book_config(book_config: &BookConfig) -> mdbook::errors::Result<Self> {
+        let mut config: Self = book_config
+            .get_deserialized_opt("preprocessor.shortcodes")?
+            .unwrap_or_default();
+        // Shortcodes are toggled by keys named after them, which can't be listed as fields.
+        // Those named after an alias toggle the shortcode it is an alias of.
+        if let Some(table) = book_config
+            .get("preprocessor.shortcodes")
+            .and_then(|table| table.as_table())
+        {
+            let options = option_names();
+            let builtins = builtins();
+            config.shortcodes = table
+                .iter()
+                .filter(|(key, _)| !options.contains(&key.as_str()))
+                .filter_map(|(key, value)| {
+                    let name = config
+                        .aliases
+                        .get(key)
+                        .map(String::as_str)
+                        .or_else(|| {
+                            builtins
+                                .iter()
+                                .find(|h| h.aliases().contains(&key.as_str()))
+                                .map(|h| h.name())
+                        })
+                        .unwrap_or(key);
+                    Some((name.to_owned(), value.as_bool()?))
+                })
+                .collect();
+        }
+        if config.sanitize_bodies && !cfg!(feature = "sanitize") {
+            return Err(mdbook::errors::Error::msg(
+                "`sanitize-bodies` requires mdbook-shortcodes to be built with the `sanitize` \
+                feature",
+            ));
+        }
+        for (alias, name) in &config.aliases {
+            if alias.is_empty() || alias.contains(char::is_whitespace) || alias == name {
+                return Err(mdbook::errors::Error::msg(format!(
+                    "invalid alias `{}` for the `{}` shortcode",
+                    alias, name
+                )));
+            }
+        }
+        theme::validate(&config.theme).map_err(mdbook::errors::Error::msg)?;
+        config
+            .delimiters
+            .validate()
+            .map_err(mdbook::errors::Error::msg)?;
+        Ok(config)
+    }
+
+    /// Returns whether the chapter at `path`, relative to the source directory, is listed in the
+    /// `skip-chapters` option.
+    pub fn skips_chapter(&self, path: &Path) -> bool {
+        self.skip_chapters
+            .iter()
+            .any(|skipped| path.starts_with(skipped))
+    }
+
+    /// Returns whether errors stop the build, rather than being logged as warnings.
+    pub fn fails_on_error(&self) -> bool {
+        self.fail_on_error && self.errors != ErrorDisplay::Inline
+    }
+
+    /// Returns whether shortcodes that need `capability` are allowed by the `capabilities`
+    /// option.
+    pub fn allows(&self, capability: Capability) -> bool {
+        capability == Capability::Pure
+            || self
+                .capabilities
+                .as_ref()
+                .is_none_or(|allowed| allowed.contains(&capability))
+    }
+
+    /// Returns the keys toggling shortcodes that don't exist (e.g. the misspelt `colums = false`),
+    /// given the names of the shortcodes.
+    pub(crate) fn unknown_toggles<'a>(&'a self, names: &[&str]) -> Vec<&'a str> {
+        self.shortcodes
+            .keys()
+            .map(String::as_str)
+            .filter(|key| !names.contains(key))
+            .collect()
+    }
+
+    /// Returns whether the `name` shortcode should be expanded.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.shortcodes.get(name) != Some(&false)
+            && self
+                .enabled
+                .as_ref()
+                .is_none_or(|enabled| enabled.iter().any(|n| n == name))
+    }
+}
+
+/// Returns the keys of the options in the `[preprocessor.shortcodes]` table, which are told apart
+/// from the keys toggling shortcodes.
+fn option_names() -> &'static [&'static str] {
+    let mut fields: &'static [&'static str] = &[];
+    let _ = Config::deserialize(FieldNames(&mut fields));
+    fields
+}
+
+/// A deserializer that only records the fields of the struct it is asked for.
+struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+impl<'de> Deserializer<'de> for FieldNames<'_> {
+    type Error = serde::de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(serde::de::Error::custom("expected a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = fields;
+        Err(serde::de::Error::custom("only the fields are recorded"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+        ignored_any
+    }
+}
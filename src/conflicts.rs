@@ -0,0 +1,146 @@
+use mdbook::Config as BookConfig;
+
+/// The name this preprocessor is configured under in `book.toml`.
+const NAME: &str = "shortcodes";
+
+/// A preprocessor that is often used alongside this one.
+struct Known {
+    name: &'static str,
+    /// The tags it expands, e.g. `include` for `{{#include file.rs}}`.
+    tags: &'static [&'static str],
+    /// Why it has to run before this preprocessor.
+    reason: &'static str,
+}
+
+const KNOWN: &[Known] = &[
+    Known {
+        name: "links",
+        tags: &["include", "rustdoc_include", "playground", "title"],
+        reason: "so that shortcodes in included files are expanded",
+    },
+    Known {
+        name: "template",
+        tags: &["template"],
+        reason: "so that shortcodes in templates are expanded",
+    },
+    Known {
+        name: "katex",
+        tags: &[],
+        reason: "so that dollar signs output by shortcodes (e.g. `money`) aren't rendered as math",
+    },
+    Known {
+        name: "mermaid",
+        tags: &[],
+        reason: "so that diagrams inside shortcodes are rendered before they are wrapped in HTML",
+    },
+];
+
+/// Returns warnings about other preprocessors in `config` that run in the wrong order relative
+/// to this one, or that expand the same tags as the `shortcodes`.
+///
+/// The order is declared with mdBook's `before` and `after` keys, which silence the warnings.
+/// Undeclared preprocessors are run in alphabetical order.
+pub(crate) fn check(config: &BookConfig, shortcodes: &[&str]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for known in KNOWN {
+        let used = config
+            .get(&format!("preprocessor.{}", known.name))
+            .is_some()
+            || (known.name == "links" && config.build.use_default_preprocessors);
+        if !used {
+            continue;
+        }
+
+        match declared_first(config, known.name) {
+            Some(true) => {}
+            Some(false) => warnings.push(format!(
+                "the {} preprocessor should run before {} {}, but book.toml runs it after",
+                known.name, NAME, known.reason
+            )),
+            None if known.name > NAME => warnings.push(format!(
+                "the {0} preprocessor should run before {1} {2}; add `after = [\"{0}\"]` to \
+                [preprocessor.{1}]",
+                known.name, NAME, known.reason
+            )),
+            None => {}
+        }
+
+        for tag in known.tags.iter().filter(|tag| shortcodes.contains(tag)) {
+            warnings.push(format!(
+                "`{{{{#{0}}}}}` is expanded by both the {1} preprocessor and {2}; set `{0} = false` \
+                in [preprocessor.{2}] to leave it to {1}",
+                tag, known.name, NAME
+            ));
+        }
+    }
+    warnings
+}
+
+/// Returns whether `book.toml` declares that the `name` preprocessor runs before this one, or
+/// `None` if it doesn't declare an order.
+fn declared_first(config: &BookConfig, name: &str) -> Option<bool> {
+    let lists = |preprocessor: &str, key: &str, other: &str| {
+        config
+            .get(&format!("preprocessor.{}.{}", preprocessor, key))
+            .and_then(|value| value.as_array())
+            .is_some_and(|list| list.iter().any(|item| item.as_str() == Some(other)))
+    };
+    if lists(NAME, "after", name) || lists(name, "before", NAME) {
+        Some(true)
+    } else if lists(NAME, "before", name) || lists(name, "after", NAME) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_toml(toml: &str, shortcodes: &[&str]) -> Vec<String> {
+        check(&toml.parse().unwrap(), shortcodes)
+    }
+
+    #[test]
+    fn test_order() {
+        assert!(check_toml("[preprocessor.katex]\n[preprocessor.shortcodes]\n", &[]).is_empty());
+
+        let warnings = check_toml("[preprocessor.template]\n[preprocessor.shortcodes]\n", &[]);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("add `after = [\"template\"]`"));
+        assert!(check_toml(
+            "[preprocessor.template]\n[preprocessor.shortcodes]\nafter = [\"template\"]\n",
+            &[]
+        )
+        .is_empty());
+        assert!(check_toml(
+            "[preprocessor.template]\nbefore = [\"shortcodes\"]\n[preprocessor.shortcodes]\n",
+            &[]
+        )
+        .is_empty());
+
+        let warnings = check_toml(
+            "[preprocessor.katex]\nafter = [\"shortcodes\"]\n[preprocessor.shortcodes]\n",
+            &[],
+        );
+        assert!(warnings[0].contains("but book.toml runs it after"));
+    }
+
+    #[test]
+    fn test_tags() {
+        let warnings = check_toml("[preprocessor.shortcodes]\n", &["hint", "include"]);
+        assert_eq!(
+            warnings,
+            [
+                "`{{#include}}` is expanded by both the links preprocessor and shortcodes; set \
+            `include = false` in [preprocessor.shortcodes] to leave it to links"
+            ]
+        );
+        assert!(check_toml(
+            "[build]\nuse-default-preprocessors = false\n[preprocessor.shortcodes]\n",
+            &["include"]
+        )
+        .is_empty());
+    }
+}
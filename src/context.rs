@@ -0,0 +1,272 @@
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+};
+
+use crate::{
+    assets::Assets,
+    cache::Cache,
+    deps::{self, DependencyGraph},
+    export::Deck,
+    ids::IdGenerator,
+    record::Record,
+    shortcodes::{Note, Snippet},
+    source_map::SourceMap,
+    stats::Stats,
+    timing::Timings,
+    Config, CustomShortcode, Profile,
+};
+
+/// The headers of a book without a `css-dir`.
+static NO_OVERRIDES: BTreeMap<String, String> = BTreeMap::new();
+
+/// The state available to shortcodes while a book is being processed.
+pub struct Context<'a> {
+    pub(crate) config: &'a Config,
+    /// The book's root directory, i.e. the one containing `book.toml`.
+    pub(crate) root: PathBuf,
+    /// The book's source directory.
+    pub(crate) src_dir: PathBuf,
+    /// The language the book is written in (i.e. `book.language`), e.g. `en`.
+    pub(crate) language: String,
+    /// The name of the renderer the book is being processed for, e.g. `html`.
+    pub(crate) renderer: String,
+    /// The path of the current chapter, relative to the source directory.
+    pub(crate) chapter_path: PathBuf,
+    /// The content of the current chapter as it was written, used to locate errors.
+    pub(crate) source: String,
+    /// Where the input being expanded is in `source`: the offset it starts at, or, for the
+    /// output of a shortcode, the offset of the chapter's shortcode that it came from.
+    pub(crate) origin: usize,
+    /// The line and column of the shortcode being expanded in the current chapter, if any.
+    pub(crate) position: Option<(usize, usize)>,
+    /// The files to copy into the book once every chapter has been processed.
+    pub(crate) assets: Assets,
+    pub(crate) cache: Cache,
+    /// The flashcards to export once every chapter has been processed.
+    pub(crate) deck: Deck,
+    /// The snippets defined in the book, keyed by name.
+    pub(crate) snippets: BTreeMap<String, Snippet>,
+    /// Errors that don't stop the current chapter from being processed, but fail the build
+    /// once every chapter has been processed.
+    pub(crate) errors: Vec<String>,
+    /// The editorial notes (e.g. `{{#todo}}`) used in the chapters processed so far, in order.
+    pub(crate) notes: Vec<Note>,
+    /// Statistics about the chapters processed so far.
+    pub(crate) stats: Stats,
+    /// How long the shortcodes in the chapters processed so far took to expand.
+    pub(crate) timings: Timings,
+    /// The source map of the current chapter, if the `source-map-file` option is set.
+    pub(crate) source_map: Option<SourceMap>,
+    /// The source maps of the chapters processed so far, keyed by path.
+    pub(crate) source_maps: BTreeMap<PathBuf, SourceMap>,
+    /// The shortcodes used in the chapters processed so far, if the `record-dir` option is set.
+    pub(crate) record: Record,
+    /// The headers of the shortcodes used in the current chapter, in the order they were first
+    /// used.
+    pub(crate) headers: Vec<&'a str>,
+    /// The headers used anywhere in the book, in the order they were first used, if they are
+    /// hoisted into a stylesheet.
+    pub(crate) hoisted_headers: Vec<&'a str>,
+    /// Whether the stylesheet the headers are hoisted into is in `output.html.additional-css`,
+    /// in which case mdBook links it from every page and chapters don't need to.
+    pub(crate) stylesheet_linked: bool,
+    /// The headers used in the chapters processed so far, in the order they were first used.
+    /// A header's index identifies it on the print page, where every chapter is concatenated.
+    pub(crate) book_headers: Vec<&'a str>,
+    /// Generates the ids of elements created by shortcodes.
+    pub(crate) ids: IdGenerator,
+    /// The shortcodes registered by the user of this crate.
+    pub(crate) custom: &'a [Rc<dyn CustomShortcode>],
+    /// The headers given to shortcodes by the stylesheets in the `css-dir`, keyed by name.
+    pub(crate) header_overrides: &'a BTreeMap<String, String>,
+    /// The output of `rustc -vV` for each toolchain used by the `rustc-output` shortcode.
+    pub(crate) rustc_versions: BTreeMap<String, String>,
+    /// The files read by the shortcodes in each chapter.
+    pub(crate) deps: DependencyGraph,
+}
+
+impl<'a> Context<'a> {
+    pub(crate) fn new(config: &'a Config, root: PathBuf, src_dir: PathBuf) -> Self {
+        Self {
+            config,
+            cache: Cache::new(root.join(&config.cache_dir)),
+            root,
+            src_dir,
+            language: String::new(),
+            renderer: "html".to_owned(),
+            chapter_path: PathBuf::new(),
+            source: String::new(),
+            origin: 0,
+            position: None,
+            assets: Assets::default(),
+            deck: Deck::default(),
+            snippets: BTreeMap::new(),
+            errors: Vec::new(),
+            notes: Vec::new(),
+            stats: Stats::default(),
+            timings: Timings::default(),
+            source_map: None,
+            source_maps: BTreeMap::new(),
+            record: Record::default(),
+            headers: Vec::new(),
+            hoisted_headers: Vec::new(),
+            stylesheet_linked: false,
+            book_headers: Vec::new(),
+            ids: IdGenerator::default(),
+            custom: &[],
+            header_overrides: &NO_OVERRIDES,
+            rustc_versions: BTreeMap::new(),
+            deps: DependencyGraph::default(),
+        }
+    }
+
+    /// Resets the per-chapter state. Draft chapters don't have a path.
+    pub(crate) fn start_chapter(&mut self, path: Option<&Path>) {
+        self.chapter_path = path.map(Path::to_path_buf).unwrap_or_default();
+        self.position = None;
+        self.headers.clear();
+        self.ids.start_chapter(path);
+    }
+
+    /// Records a header to be added to the current chapter, unless it has been already.
+    pub(crate) fn add_header(&mut self, header: &'a str) {
+        if !header.is_empty() && !self.headers.contains(&header) {
+            self.headers.push(header);
+        }
+    }
+
+    /// Records the header of the `name` shortcode, or the one a stylesheet in the `css-dir`
+    /// gives it.
+    pub(crate) fn add_shortcode_header(&mut self, name: &str, header: &'static str) {
+        let overrides = self.header_overrides;
+        self.add_header(overrides.get(name).map_or(header, String::as_str));
+    }
+
+    /// The options set in `book.toml`.
+    pub fn config(&self) -> &Config {
+        self.config
+    }
+
+    /// The name of the renderer the book is being processed for, e.g. `html` or `markdown`.
+    pub fn renderer(&self) -> &str {
+        &self.renderer
+    }
+
+    /// Whether the book is being rendered to HTML, including in an EPUB. Otherwise, shortcodes
+    /// output plain markdown where they can, and their headers are left out.
+    pub(crate) fn is_html(&self) -> bool {
+        self.renderer == "html" || self.is_epub()
+    }
+
+    /// Whether the book is being rendered to an EPUB (by `mdbook-epub`), where scripts aren't
+    /// run.
+    pub(crate) fn is_epub(&self) -> bool {
+        self.renderer == "epub"
+    }
+
+    /// Whether interactive shortcodes are flattened so that all of their content is shown
+    /// without scripts, i.e. when printing or in an EPUB.
+    pub(crate) fn is_static(&self) -> bool {
+        self.config.profile == Profile::Print || self.is_epub()
+    }
+
+    /// The path of the current chapter, relative to the source directory.
+    pub fn chapter_path(&self) -> &Path {
+        &self.chapter_path
+    }
+
+    /// Generates the ids of elements created by shortcodes.
+    pub fn ids(&mut self) -> &mut IdGenerator {
+        &mut self.ids
+    }
+
+    /// Records that the current chapter uses `path`, relative to the book's root directory, so
+    /// that `mdbook serve` rebuilds the book when it changes.
+    pub fn watch<P: AsRef<Path>>(&mut self, path: P) {
+        if !self.chapter_path.as_os_str().is_empty() {
+            let chapter = self.src_dir.join(&self.chapter_path);
+            self.deps.add(chapter, self.root.join(path));
+        }
+    }
+
+    /// Returns a key that changes whenever a file in `path`, relative to the book's root
+    /// directory, does, if it's in one of the `watch-dirs`. Otherwise, returns an empty string.
+    ///
+    /// Results cached by shortcodes that depend on the contents of a directory (e.g. the
+    /// output of a command run in it) include the key, so that they are discarded when the
+    /// directory changes.
+    pub(crate) fn dir_fingerprint(&mut self, path: &str) -> String {
+        let in_watch_dir = self
+            .config
+            .watch_dirs
+            .iter()
+            .any(|dir| self.root.join(path).starts_with(self.root.join(dir)));
+        if in_watch_dir {
+            self.watch(path);
+            deps::fingerprint(&self.root.join(path))
+        } else {
+            String::new()
+        }
+    }
+
+    /// Logs a warning about a use of the `shortcode` shortcode in the current chapter.
+    pub fn warn(&self, shortcode: &str, message: &str) {
+        log::warn!("{}: {} shortcode: {}", self.location(), shortcode, message);
+    }
+
+    /// Records an error about a use of the `shortcode` shortcode in the current chapter.
+    pub fn fail(&mut self, shortcode: &str, message: &str) {
+        let error = format!("{}: {} shortcode: {}", self.location(), shortcode, message);
+        self.errors.push(error);
+    }
+
+    /// Returns the current chapter's path, followed by the line and column of the shortcode
+    /// being expanded if there is one (e.g. `intro.md:3:1`).
+    fn location(&self) -> String {
+        match self.position {
+            Some((line, column)) => {
+                format!("{}:{}:{}", self.chapter_path.display(), line, column)
+            }
+            None => self.chapter_path.display().to_string(),
+        }
+    }
+
+    /// Returns the relative URL from the current chapter to the root of the book (e.g. `../`).
+    pub fn path_to_root(&self) -> String {
+        let depth = self
+            .chapter_path
+            .parent()
+            .map(|p| {
+                p.components()
+                    .filter(|c| matches!(c, Component::Normal(_)))
+                    .count()
+            })
+            .unwrap_or(0);
+        "../".repeat(depth)
+    }
+}
+
+#[cfg(test)]
+impl<'a> Context<'a> {
+    pub(crate) fn test(config: &'a Config) -> Self {
+        Self::new(config, PathBuf::new(), PathBuf::from("src"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_to_root() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        assert_eq!(ctx.path_to_root(), "");
+        ctx.start_chapter(Some(Path::new("intro.md")));
+        assert_eq!(ctx.path_to_root(), "");
+        ctx.start_chapter(Some(Path::new("guide/setup/linux.md")));
+        assert_eq!(ctx.path_to_root(), "../../");
+    }
+}
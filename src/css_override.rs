@@ -0,0 +1,129 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use mdbook::errors::{Error, Result};
+
+use crate::{CssOverride, Handler};
+
+/// Returns the headers of the shortcodes whose styles are overridden by a stylesheet in `dir`,
+/// relative to the book's root directory `root`, keyed by name (e.g. `hint` for `hint.css`). A
+/// book without the directory overrides none.
+pub(crate) fn load(
+    root: &Path,
+    dir: &str,
+    mode: CssOverride,
+    handlers: &[Handler],
+) -> Result<BTreeMap<String, String>> {
+    let entries = match fs::read_dir(root.join(dir)) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(BTreeMap::new()),
+    };
+    let mut headers = BTreeMap::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension() != Some("css".as_ref()) {
+            continue;
+        }
+        let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(name) => name,
+            None => continue,
+        };
+        // A misspelt name would otherwise be ignored without a trace.
+        let handler = handlers.iter().find(|h| h.name() == name).ok_or_else(|| {
+            Error::msg(format!(
+                "{} doesn't override the styles of any shortcode, as none is named `{}`",
+                path.display(),
+                name
+            ))
+        })?;
+        let css = fs::read_to_string(&path)
+            .map_err(|e| Error::msg(format!("failed to read {}: {}", path.display(), e)))?;
+        headers.insert(name.to_owned(), header(handler.header(), &css, mode));
+    }
+    Ok(headers)
+}
+
+/// Returns `header` with its styles replaced by, or followed by, `css`. Its scripts are kept.
+fn header(header: &str, css: &str, mode: CssOverride) -> String {
+    const OPEN: &str = "<style>";
+    const CLOSE: &str = "</style>";
+    let css = css.trim_matches('\n');
+    let style = header
+        .find(OPEN)
+        .and_then(|start| Some(start..start + header[start..].find(CLOSE)? + CLOSE.len()));
+    match (style, mode) {
+        (Some(style), CssOverride::Replace) => format!(
+            "{}{}\n{}\n{}{}",
+            &header[..style.start],
+            OPEN,
+            css,
+            CLOSE,
+            &header[style.end..]
+        ),
+        (Some(style), CssOverride::Extend) => {
+            let end = style.end - CLOSE.len();
+            format!(
+                "{}{}\n{}{}",
+                &header[..end],
+                css,
+                CLOSE,
+                &header[style.end..]
+            )
+        }
+        (None, _) => format!("{}\n{}\n{}\n{}\n", header, OPEN, css, CLOSE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{handlers, Config, Kbd, Shortcode};
+
+    #[test]
+    fn test_css_override() {
+        let dir = tempfile::tempdir().unwrap();
+        let css_dir = dir.path().join("shortcodes-css");
+        fs::create_dir(&css_dir).unwrap();
+        fs::write(css_dir.join("hint.css"), ".x { color: red; }\n").unwrap();
+        fs::write(css_dir.join("kbd.css"), "kbd { color: red; }\n").unwrap();
+        fs::write(css_dir.join("README.md"), "").unwrap();
+        let config = Config::default();
+        let handlers = handlers(&config, &[]);
+
+        let headers = load(
+            dir.path(),
+            "shortcodes-css",
+            CssOverride::Replace,
+            &handlers,
+        )
+        .unwrap();
+        assert_eq!(headers["hint"], "\n<style>\n.x { color: red; }\n</style>\n");
+        // A shortcode without styles of its own is given them.
+        assert_eq!(Kbd::HEADER, "");
+        assert_eq!(headers["kbd"], "\n<style>\nkbd { color: red; }\n</style>\n");
+
+        let headers = load(dir.path(), "shortcodes-css", CssOverride::Extend, &handlers).unwrap();
+        assert!(headers["hint"].starts_with("\n<style>\n    .mdbook-shortcodes-hint {"));
+        assert!(headers["hint"].ends_with("}\n.x { color: red; }\n</style>\n"));
+
+        assert_eq!(
+            header(
+                "<style>a</style><script>b</script>",
+                "c",
+                CssOverride::Replace
+            ),
+            "<style>\nc\n</style><script>b</script>"
+        );
+
+        fs::write(css_dir.join("hnit.css"), "").unwrap();
+        assert!(load(
+            dir.path(),
+            "shortcodes-css",
+            CssOverride::Replace,
+            &handlers
+        )
+        .is_err());
+        assert!(load(dir.path(), "missing", CssOverride::Replace, &handlers)
+            .unwrap()
+            .is_empty());
+    }
+}
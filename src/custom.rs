@@ -0,0 +1,37 @@
+use crate::{Attrs, Capability, Context};
+
+/// A shortcode defined outside of this crate, added with [`ShortcodesProcessor::register`].
+///
+/// Custom shortcodes are expanded after the built-in ones, so their output isn't searched for
+/// other shortcodes.
+///
+/// [`ShortcodesProcessor::register`]: crate::ShortcodesProcessor::register
+pub trait CustomShortcode {
+    /// The name that is used to call the shortcode, e.g. `"youtube"` for `{{#youtube ... /}}`.
+    fn name(&self) -> &str;
+
+    /// Other names the shortcode can be called by, e.g. `&["yt"]`.
+    fn aliases(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Any code that should be placed once in each page using the shortcode (e.g. css).
+    fn header(&self) -> &'static str {
+        ""
+    }
+
+    /// What the shortcode does beyond transforming its input, which the `capabilities` option
+    /// can forbid.
+    fn capability(&self) -> Capability {
+        Capability::Pure
+    }
+
+    /// Returns the replacement for a use of the shortcode. `input` is the content between the
+    /// opening and closing tags, and is empty for self-closing shortcodes. `attrs` are the
+    /// attributes given in the opening tag.
+    ///
+    /// An error stops the build, and is reported along with where the shortcode was used.
+    /// Problems that shouldn't stop the other chapters from being processed can instead be
+    /// reported with [`Context::fail`].
+    fn process(&self, input: &str, attrs: &Attrs, ctx: &mut Context) -> Result<String, String>;
+}
@@ -0,0 +1,184 @@
+use std::{cmp::Ordering, path::Path};
+
+use serde_json::Value;
+
+/// Reads the records in a YAML, JSON or CSV file, chosen by its extension.
+///
+/// YAML and JSON files must contain a list. Every field of a CSV record is a string.
+pub(crate) fn load(path: &Path) -> Result<Vec<Value>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let value = match extension.as_str() {
+        "json" => serde_json::from_str(&content).map_err(|e| e.to_string())?,
+        "yaml" | "yml" => serde_yaml::from_str(&content).map_err(|e| e.to_string())?,
+        "csv" => {
+            let mut reader = csv::Reader::from_reader(content.as_bytes());
+            let mut records = Vec::new();
+            for record in reader.deserialize() {
+                records.push(Value::Object(record.map_err(|e| e.to_string())?));
+            }
+            Value::Array(records)
+        }
+        _ => return Err("unknown file type, expected YAML, JSON or CSV".to_owned()),
+    };
+    match value {
+        Value::Array(records) => Ok(records),
+        _ => Err("expected a list of records".to_owned()),
+    }
+}
+
+/// Returns a field of a record as text, where `path` is a list of dot-separated keys (e.g.
+/// `owner.name`). An empty path returns the record itself.
+pub(crate) fn field(record: &Value, path: &str) -> Option<String> {
+    let mut value = record;
+    for key in path.split('.').filter(|k| !k.is_empty()) {
+        value = match value {
+            Value::Object(map) => map.get(key)?,
+            Value::Array(items) => items.get(key.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    })
+}
+
+/// Sorts records by a field, in descending order if the field starts with `-` (e.g. `-stars`).
+pub(crate) fn sort(records: &mut [Value], field: &str) {
+    let (field, descending) = match field.strip_prefix('-') {
+        Some(field) => (field, true),
+        None => (field, false),
+    };
+    records.sort_by(|a, b| {
+        let ordering = compare(
+            &self::field(a, field).unwrap_or_default(),
+            &self::field(b, field).unwrap_or_default(),
+        );
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
+
+/// Compares two values as numbers if they both are, and as text otherwise.
+fn compare(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
+
+/// A condition on a field of a record, e.g. `stars > 100` or `license == "MIT"`.
+pub(crate) struct Filter {
+    field: String,
+    operator: String,
+    value: String,
+}
+
+impl Filter {
+    pub(crate) fn parse(expression: &str) -> Result<Self, String> {
+        // Two character operators are checked first so that e.g. `>=` isn't parsed as `>`.
+        for operator in ["==", "!=", ">=", "<=", ">", "<"] {
+            if let Some((field, value)) = expression.split_once(operator) {
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .unwrap_or(value);
+                return Ok(Self {
+                    field: field.trim().to_owned(),
+                    operator: operator.to_owned(),
+                    value: value.to_owned(),
+                });
+            }
+        }
+        Err(format!(
+            "invalid filter `{}`, expected e.g. `stars > 100`",
+            expression
+        ))
+    }
+
+    /// Returns whether a record satisfies the condition. Records without the field never do.
+    pub(crate) fn matches(&self, record: &Value) -> bool {
+        let field = match field(record, &self.field) {
+            Some(field) => field,
+            None => return false,
+        };
+        let ordering = compare(&field, &self.value);
+        match self.operator.as_str() {
+            "==" => ordering == Ordering::Equal,
+            "!=" => ordering != Ordering::Equal,
+            ">=" => ordering != Ordering::Less,
+            "<=" => ordering != Ordering::Greater,
+            ">" => ordering == Ordering::Greater,
+            _ => ordering == Ordering::Less,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let yaml = dir.path().join("plugins.yaml");
+        std::fs::write(&yaml, "- name: a\n  stars: 3\n- name: b\n").unwrap();
+        let csv = dir.path().join("plugins.csv");
+        std::fs::write(&csv, "name,stars\na,3\n").unwrap();
+
+        assert_eq!(load(&yaml).unwrap().len(), 2);
+        assert_eq!(
+            field(&load(&csv).unwrap()[0], "stars"),
+            Some("3".to_owned())
+        );
+        std::fs::write(&yaml, "name: a\n").unwrap();
+        assert!(load(&yaml).is_err());
+    }
+
+    #[test]
+    fn test_field() {
+        let record = serde_json::json!({
+            "name": "serde",
+            "owner": { "login": "dtolnay" },
+            "tags": ["a", "b"],
+            "stars": 9000,
+            "license": null,
+        });
+        assert_eq!(field(&record, "name"), Some("serde".to_owned()));
+        assert_eq!(field(&record, "owner.login"), Some("dtolnay".to_owned()));
+        assert_eq!(field(&record, "tags.1"), Some("b".to_owned()));
+        assert_eq!(field(&record, "stars"), Some("9000".to_owned()));
+        assert_eq!(field(&record, "license"), Some(String::new()));
+        assert_eq!(field(&record, "missing"), None);
+        assert_eq!(field(&serde_json::json!("x"), ""), Some("x".to_owned()));
+    }
+
+    #[test]
+    fn test_sort_and_filter() {
+        let mut records = vec![
+            serde_json::json!({ "name": "b", "stars": 20 }),
+            serde_json::json!({ "name": "a", "stars": 100 }),
+            serde_json::json!({ "name": "c", "stars": 3 }),
+        ];
+        sort(&mut records, "name");
+        assert_eq!(field(&records[0], "name"), Some("a".to_owned()));
+        sort(&mut records, "-stars");
+        assert_eq!(field(&records[2], "stars"), Some("3".to_owned()));
+
+        let filter = Filter::parse("stars >= 20").unwrap();
+        assert_eq!(records.iter().filter(|r| filter.matches(r)).count(), 2);
+        let filter = Filter::parse("name != \"a\"").unwrap();
+        assert_eq!(records.iter().filter(|r| filter.matches(r)).count(), 2);
+        assert!(!Filter::parse("missing == 1").unwrap().matches(&records[0]));
+        assert!(Filter::parse("stars").is_err());
+    }
+}
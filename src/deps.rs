@@ -0,0 +1,203 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::cache::Cache;
+
+/// The files and directories read by the shortcodes in each chapter. All paths are absolute.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct DependencyGraph {
+    chapters: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+impl DependencyGraph {
+    /// Records that `chapter` depends on `path`.
+    pub(crate) fn add(&mut self, chapter: PathBuf, path: PathBuf) {
+        self.chapters.entry(chapter).or_default().insert(path);
+    }
+
+    /// Returns the chapters that depend on `path`, or on a directory containing it.
+    pub(crate) fn dependents(&self, path: &Path) -> Vec<&Path> {
+        self.chapters
+            .iter()
+            .filter(|(_, deps)| deps.iter().any(|dep| path.starts_with(dep)))
+            .map(|(chapter, _)| chapter.as_path())
+            .collect()
+    }
+
+    /// Returns every path that a chapter depends on.
+    pub(crate) fn paths(&self) -> BTreeSet<&Path> {
+        self.chapters
+            .values()
+            .flatten()
+            .map(PathBuf::as_path)
+            .collect()
+    }
+
+    /// Writes the graph to `path`, as a Graphviz graph if its extension is `dot` and as JSON
+    /// otherwise. Paths are written relative to `root`.
+    pub(crate) fn write(&self, root: &Path, path: &Path) -> io::Result<()> {
+        let relative = |path: &Path| {
+            path.strip_prefix(root)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .into_owned()
+        };
+        let chapters: BTreeMap<String, Vec<String>> = self
+            .chapters
+            .iter()
+            .map(|(chapter, deps)| {
+                (
+                    relative(chapter),
+                    deps.iter().map(|d| relative(d)).collect(),
+                )
+            })
+            .collect();
+
+        let content = if path.extension().is_some_and(|ext| ext == "dot") {
+            let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+            let mut dot = "digraph dependencies {\n".to_owned();
+            for (chapter, deps) in &chapters {
+                for dep in deps {
+                    dot.push_str(&format!("    {} -> {};\n", quote(chapter), quote(dep)));
+                }
+            }
+            dot.push_str("}\n");
+            dot
+        } else {
+            serde_json::to_string_pretty(&chapters)? + "\n"
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)
+    }
+
+    /// Removes the dependencies for which `f` returns false, along with the chapters left
+    /// without any.
+    pub(crate) fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Path) -> bool,
+    {
+        for deps in self.chapters.values_mut() {
+            deps.retain(|dep| f(dep));
+        }
+        self.chapters.retain(|_, deps| !deps.is_empty());
+    }
+}
+
+/// The size and modification time of every file in a directory (or of a single file), which
+/// change when the files are edited.
+pub(crate) type Snapshot = BTreeMap<PathBuf, (u64, Option<SystemTime>)>;
+
+/// Adds the files in `path`, including those in subdirectories, to `snapshot`.
+pub(crate) fn snapshot(path: &Path, snapshot: &mut Snapshot) {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return,
+    };
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path).into_iter().flatten().flatten() {
+            self::snapshot(&entry.path(), snapshot);
+        }
+    } else {
+        snapshot.insert(path.to_owned(), (metadata.len(), metadata.modified().ok()));
+    }
+}
+
+/// Returns a key that changes whenever a file in `dir` does.
+pub(crate) fn fingerprint(dir: &Path) -> String {
+    let mut files = Snapshot::new();
+    snapshot(dir, &mut files);
+    let parts: Vec<Vec<u8>> = files
+        .iter()
+        .map(|(path, (len, modified))| {
+            let modified = modified
+                .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+                .unwrap_or_default()
+                .as_nanos();
+            format!("{}\0{}\0{}", path.display(), len, modified).into_bytes()
+        })
+        .collect();
+    Cache::key(&parts.iter().map(Vec::as_slice).collect::<Vec<_>>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dependents() {
+        let mut graph = DependencyGraph::default();
+        graph.add("/book/src/a.md".into(), "/book/data".into());
+        graph.add("/book/src/b.md".into(), "/book/data/b.csv".into());
+        graph.add("/book/src/b.md".into(), "/book/src/c.csv".into());
+
+        let dependents = |path: &str| graph.dependents(Path::new(path));
+        assert_eq!(
+            dependents("/book/data/b.csv"),
+            [Path::new("/book/src/a.md"), Path::new("/book/src/b.md")]
+        );
+        assert_eq!(
+            dependents("/book/data/x.csv"),
+            [Path::new("/book/src/a.md")]
+        );
+        assert!(dependents("/book/other.csv").is_empty());
+
+        graph.retain(|path| !path.starts_with("/book/data"));
+        assert_eq!(
+            graph.paths().into_iter().collect::<Vec<_>>(),
+            [Path::new("/book/src/c.csv")]
+        );
+    }
+
+    #[test]
+    fn test_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let mut graph = DependencyGraph::default();
+        graph.add(root.join("src/a.md"), root.join("data/a.json"));
+        graph.add(root.join("src/a.md"), root.join("data/\"b\".csv"));
+        graph.add(root.join("src/b.md"), "/elsewhere/c.txt".into());
+
+        graph.write(root, &root.join("deps.json")).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(
+                &fs::read_to_string(root.join("deps.json")).unwrap()
+            )
+            .unwrap(),
+            serde_json::json!({
+                "src/a.md": ["data/\"b\".csv", "data/a.json"],
+                "src/b.md": ["/elsewhere/c.txt"],
+            })
+        );
+
+        graph.write(root, &root.join("out/deps.dot")).unwrap();
+        assert_eq!(
+            fs::read_to_string(root.join("out/deps.dot")).unwrap(),
+            "digraph dependencies {
+    \"src/a.md\" -> \"data/\\\"b\\\".csv\";
+    \"src/a.md\" -> \"data/a.json\";
+    \"src/b.md\" -> \"/elsewhere/c.txt\";
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub/a.txt"), "a").unwrap();
+        let before = fingerprint(dir.path());
+        assert_eq!(fingerprint(dir.path()), before);
+
+        fs::write(dir.path().join("sub/a.txt"), "ab").unwrap();
+        assert_ne!(fingerprint(dir.path()), before);
+    }
+}
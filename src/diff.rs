@@ -0,0 +1,63 @@
+/// Returns a unified-style line diff between `old` and `new`, where removed lines start with `-`,
+/// added lines with `+` and unchanged lines with a space.
+pub(crate) fn lines(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    let mut result = String::new();
+    for pair in align(&old, &new) {
+        match pair {
+            (Some(i), Some(_)) => result.push_str(&format!(" {}\n", old[i])),
+            (Some(i), None) => result.push_str(&format!("-{}\n", old[i])),
+            (None, Some(j)) => result.push_str(&format!("+{}\n", new[j])),
+            (None, None) => unreachable!(),
+        }
+    }
+    result
+}
+
+/// Aligns the items of `old` and `new` along their longest common subsequence. Each pair holds
+/// the indices of an item that is in both, or of an item that was removed (`(Some(i), None)`)
+/// or added (`(None, Some(j))`), in order.
+pub(crate) fn align<T: PartialEq>(old: &[T], new: &[T]) -> Vec<(Option<usize>, Option<usize>)> {
+    // `lcs[i][j]` is the length of the longest common subsequence of `old[i..]` and `new[j..]`.
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            result.push((Some(i), Some(j)));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            result.push((Some(i), None));
+            i += 1;
+        } else {
+            result.push((None, Some(j)));
+            j += 1;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        assert_eq!(lines("a\nb\nc", "a\nc\nd"), " a\n-b\n c\n+d\n");
+        assert_eq!(lines("", "a"), "+a\n");
+        assert_eq!(lines("a", "a"), " a\n");
+    }
+}
@@ -0,0 +1,747 @@
+/// What editors and the `check` and `list` commands are told about a built-in shortcode.
+pub(crate) struct Doc {
+    pub(crate) name: &'static str,
+    pub(crate) summary: &'static str,
+    /// A use of the shortcode, written on one line.
+    pub(crate) example: &'static str,
+    /// The number of positional attributes and the named attributes it accepts, if they are
+    /// fixed.
+    pub(crate) attrs: Option<(usize, &'static [&'static str])>,
+}
+
+/// The kind of value a named attribute of a built-in shortcode takes and its default, for the
+/// attributes that aren't text without a default.
+struct AttrDoc {
+    shortcode: &'static str,
+    name: &'static str,
+    kind: &'static str,
+    default: Option<&'static str>,
+}
+
+const DOCS: &[Doc] = &[
+    Doc {
+        name: "assert-output",
+        summary: "Checks that the output of the command in the first code block of its body \
+            matches the second code block.",
+        example: "{{#assert-output cwd=\"examples\"}}...{{/assert-output}}",
+        attrs: Some((0, &["cwd"])),
+    },
+    Doc {
+        name: "book-stats",
+        summary: "Shows the number of chapters, words and figures in the whole book, and how long \
+            it takes to read.",
+        example: "{{#book-stats /}}",
+        attrs: Some((0, &[])),
+    },
+    Doc {
+        name: "cargo-features",
+        summary: "Lists the features of a crate, along with the features and dependencies they \
+            enable.",
+        example: "{{#cargo-features crate=\"mycrate\" /}}",
+        attrs: Some((0, &["crate", "manifest"])),
+    },
+    Doc {
+        name: "chem",
+        summary: "Shows a chemical formula with subscripts and charges, such as `SO4^2-` or \
+            `CuSO4.5H2O`.",
+        example: "{{#chem H2SO4 /}}",
+        attrs: None,
+    },
+    Doc {
+        name: "chess",
+        summary: "Draws a chess position given in Forsyth–Edwards Notation.",
+        example: "{{#chess fen=\"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1\" \
+            /}}",
+        attrs: Some((1, &["fen"])),
+    },
+    Doc {
+        name: "citation-needed",
+        summary: "Flags a claim that needs a source.",
+        example: "{{#citation-needed /}}",
+        attrs: Some((0, &[])),
+    },
+    Doc {
+        name: "cli-help",
+        summary: "Runs a command at build time and shows its help output.",
+        example: "{{#cli-help command=\"mytool --help\" /}}",
+        attrs: Some((1, &["command", "cwd"])),
+    },
+    Doc {
+        name: "cmd-output",
+        summary: "Runs a command at build time and shows its output.",
+        example: "{{#cmd-output \"ls -la\" cwd=\"examples\" /}}",
+        attrs: Some((1, &["cwd", "lang"])),
+    },
+    Doc {
+        name: "columns",
+        summary: "Lays its body out in columns, separated by `{{#column}}`.",
+        example: "{{#columns}}Left{{#column}}Right{{/columns}}",
+        attrs: Some((2, &["count"])),
+    },
+    Doc {
+        name: "config-schema",
+        summary: "A configuration reference generated from a JSON Schema.",
+        example: "{{#config-schema path=\"schema/config.schema.json\" /}}",
+        attrs: Some((0, &["path"])),
+    },
+    Doc {
+        name: "crate-badge",
+        summary: "A badge showing information about a crate.",
+        example: "{{#crate-badge serde downloads /}}",
+        attrs: Some((2, &["style"])),
+    },
+    Doc {
+        name: "crossword",
+        summary: "An interactive crossword, with one `ANSWER :: clue` line per word.",
+        example: "{{#crossword}}RUST :: A systems programming language{{/crossword}}",
+        attrs: None,
+    },
+    Doc {
+        name: "define-snippet",
+        summary: "Defines a snippet that can be used in any chapter.",
+        example: "{{#define-snippet install-step platform=linux}}...{{/define-snippet}}",
+        attrs: None,
+    },
+    Doc {
+        name: "demo",
+        summary: "Embeds a live HTML, CSS and JavaScript demo next to its source.",
+        example: "{{#demo height=\"200px\"}}...{{/demo}}",
+        attrs: Some((0, &["height"])),
+    },
+    Doc {
+        name: "env",
+        summary: "Inserts the value of an environment variable at build time, which must be in \
+            the `env-allowlist` option.",
+        example: "{{#env CI_COMMIT_SHA default=local /}}",
+        attrs: Some((1, &["default"])),
+    },
+    Doc {
+        name: "envvars",
+        summary: "A reference table of environment variables, with one `NAME :: default :: \
+            description` line per variable.",
+        example: "{{#envvars}}PORT :: 8080 :: The port to listen on{{/envvars}}",
+        attrs: None,
+    },
+    Doc {
+        name: "error-catalog",
+        summary: "A table of the variants of the error enums in a Rust file, along with their doc \
+            comments.",
+        example: "{{#error-catalog path=\"src/error.rs\" /}}",
+        attrs: Some((0, &["path", "enum"])),
+    },
+    Doc {
+        name: "flashcard",
+        summary: "A card that shows its back when clicked, with the front and back separated by a \
+            `---` line.",
+        example: "{{#flashcard}}...{{/flashcard}}",
+        attrs: None,
+    },
+    Doc {
+        name: "foreach",
+        summary: "Repeats its body for every record in a YAML, JSON or CSV file.",
+        example: "{{#foreach data=\"data/plugins.yaml\" as=p}} - \
+            [{{p.name}}]({{p.url}}){{/foreach}}",
+        attrs: Some((0, &["data", "as", "sort", "filter", "limit"])),
+    },
+    Doc {
+        name: "go",
+        summary: "Draws a go position.",
+        example: "{{#go size=9 black=\"C3 D4\" white=\"E5\" /}}",
+        attrs: Some((0, &["size", "black", "white"])),
+    },
+    Doc {
+        name: "hint",
+        summary: "A callout box of type `info`, `ok`, `warning` or `danger`.",
+        example: "{{#hint warning title=\"Careful\"}}...{{/hint}}",
+        attrs: Some((1, &["type", "title"])),
+    },
+    Doc {
+        name: "http",
+        summary: "An example HTTP exchange, given as a raw request optionally followed by the raw \
+            response (starting with a `HTTP/1.1 200 OK` status line).",
+        example: "{{#http}}GET /users HTTP/1.1{{/http}}",
+        attrs: None,
+    },
+    Doc {
+        name: "if",
+        summary: "Includes its body only if a flag from the `[preprocessor.shortcodes.flags]` \
+            table is set (or, with `!`, isn't), and the content after an `{{#else}}` otherwise.",
+        example: "{{#if internal}}\nSee the on-call runbook.\n{{#else}}\nContact support.\n{{/if}}",
+        attrs: Some((1, &[])),
+    },
+    Doc {
+        name: "ipa",
+        summary: "Shows a pronunciation in the International Phonetic Alphabet, optionally \
+            linking to a recording with `audio=\"rust.mp3\"`.",
+        example: "{{#ipa /ˈrʌst/ /}}",
+        attrs: Some((1, &["audio"])),
+    },
+    Doc {
+        name: "kbd",
+        summary: "Shows a keyboard shortcut.",
+        example: "{{#kbd Ctrl+Shift+P /}}",
+        attrs: None,
+    },
+    Doc {
+        name: "license",
+        summary: "Shows a license notice, marked up with RDFa so that the license can be read by \
+            machines.",
+        example: "{{#license CC-BY-4.0 author=\"Jane Doe\" year=2024 /}}",
+        attrs: Some((1, &["author", "year", "title", "url"])),
+    },
+    Doc {
+        name: "manifest",
+        summary: "An annotated YAML example (e.g. a Kubernetes manifest), given as a fenced code \
+            block followed by a numbered list of annotations.",
+        example: "{{#manifest}}...{{/manifest}}",
+        attrs: None,
+    },
+    Doc {
+        name: "money",
+        summary: "Shows an amount of money formatted for the book's language, e.g. \"$1,234.50\" \
+            in English and \"1.234,50 $\" in German.",
+        example: "{{#money 1234.5 USD /}}",
+        attrs: Some((2, &[])),
+    },
+    Doc {
+        name: "num",
+        summary: "Shows a number formatted for the book's language, e.g. \"1,234,567.89\" in \
+            English and \"1.234.567,89\" in German.",
+        example: "{{#num 1234567.891 precision=2 /}}",
+        attrs: Some((1, &["precision"])),
+    },
+    Doc {
+        name: "options",
+        summary: "A man page style list of command line options, with one `--flag, -f <VALUE> :: \
+            description` line per option.",
+        example: "{{#options}}--verbose, -v :: Print more output{{/options}}",
+        attrs: None,
+    },
+    Doc {
+        name: "proto",
+        summary: "A table of the fields of a protobuf message, along with their comments.",
+        example: "{{#proto path=\"proto/api.proto\" message=User /}}",
+        attrs: Some((0, &["path", "message", "include"])),
+    },
+    Doc {
+        name: "pullout",
+        summary: "Floats supplementary content beside the text on wide screens.",
+        example: "{{#pullout side=right width=35%}}...{{/pullout}}",
+        attrs: Some((0, &["side", "width"])),
+    },
+    Doc {
+        name: "ratio",
+        summary: "Constrains its content to an aspect ratio.",
+        example: "{{#ratio 16:9}}...{{/ratio}}",
+        attrs: Some((1, &[])),
+    },
+    Doc {
+        name: "regex",
+        summary: "Shows a highlighted regular expression along with an explanation of its \
+            components.",
+        example: "{{#regex}}^a(b|c)+${{/regex}}",
+        attrs: None,
+    },
+    Doc {
+        name: "remote",
+        summary: "Includes a file downloaded at build time, which is cached according to its HTTP \
+            caching headers.",
+        example: "{{#remote url=\"https://example.com/README.md\" /}}",
+        attrs: Some((0, &["url", "lang"])),
+    },
+    Doc {
+        name: "repo-badge",
+        summary: "A badge showing information about a GitHub repository.",
+        example: "{{#repo-badge org/repo stars /}}",
+        attrs: Some((2, &["style"])),
+    },
+    Doc {
+        name: "review",
+        summary: "Attaches a note for reviewers to a passage.",
+        example: "{{#review \"check this number\"}}42%{{/review}}",
+        attrs: Some((1, &[])),
+    },
+    Doc {
+        name: "rustc-output",
+        summary: "Compiles a Rust snippet at build time and shows the compiler's output below it.",
+        example: "{{#rustc-output edition=2021}}...{{/rustc-output}}",
+        attrs: Some((0, &["edition"])),
+    },
+    Doc {
+        name: "rustdoc",
+        summary: "Links to the documentation of a Rust item.",
+        example: "{{#rustdoc struct@std::collections::HashMap /}}",
+        attrs: Some((1, &["text"])),
+    },
+    Doc {
+        name: "script",
+        summary: "An inline script, which can be `defer` or `module`.",
+        example: "{{#script defer}}...{{/script}}",
+        attrs: None,
+    },
+    Doc {
+        name: "shortcuts",
+        summary: "A table of keyboard shortcuts, with one `Ctrl+S :: Save file` line per \
+            shortcut.",
+        example: "{{#shortcuts}}Ctrl+S :: Save file{{/shortcuts}}",
+        attrs: Some((1, &[])),
+    },
+    Doc {
+        name: "slide",
+        summary: "Marks the start of a new slide.",
+        example: "{{#slide /}}",
+        attrs: None,
+    },
+    Doc {
+        name: "smiles",
+        summary: "Draws the structure of a molecule given in SMILES notation, using the command \
+            set by `smiles-command`.",
+        example: "{{#smiles CCO /}}",
+        attrs: None,
+    },
+    Doc {
+        name: "snippet",
+        summary:
+            "Shows a file, relative to the book's root directory, as a code block, optionally \
+            only the `lines` in a range (e.g. `\"10:30\"`) or between the comments of an \
+            mdBook-style `anchor`.",
+        example: "{{#snippet path=\"src/main.rs\" lines=\"10:30\" /}}",
+        attrs: Some((0, &["path", "lines", "anchor", "lang"])),
+    },
+    Doc {
+        name: "spacer",
+        summary: "Inserts vertical whitespace.",
+        example: "{{#spacer 3rem /}}",
+        attrs: Some((1, &[])),
+    },
+    Doc {
+        name: "sql",
+        summary: "Runs a query against an SQLite database at build time and shows the result as a \
+            table.",
+        example: "{{#sql db=\"fixtures/demo.sqlite\"}}SELECT * FROM users LIMIT 5{{/sql}}",
+        attrs: Some((0, &["db"])),
+    },
+    Doc {
+        name: "tabs",
+        summary: "Tabbed content.",
+        example: "{{#tabs}}...{{/tabs}}",
+        attrs: None,
+    },
+    Doc {
+        name: "todo",
+        summary: "Marks unfinished work, with the note as its body or attribute.",
+        example: "{{#todo}}Add an example{{/todo}}",
+        attrs: Some((1, &[])),
+    },
+    Doc {
+        name: "todo-list",
+        summary: "Lists every editorial note in the book, linking to where each one is.",
+        example: "{{#todo-list /}}",
+        attrs: Some((0, &[])),
+    },
+    Doc {
+        name: "unit",
+        summary: "Shows a measurement in both metric and imperial units, e.g. \"5 km (3.1 mi)\".",
+        example: "{{#unit 5 km /}}",
+        attrs: Some((2, &["precision"])),
+    },
+    Doc {
+        name: "use-snippet",
+        summary: "Inserts a snippet.",
+        example: "{{#use-snippet install-step platform=macos /}}",
+        attrs: None,
+    },
+    Doc {
+        name: "var",
+        summary: "Inserts the value of a variable from the `[preprocessor.shortcodes.variables]` \
+            table.",
+        example: "{{#var version /}}",
+        attrs: Some((1, &[])),
+    },
+    Doc {
+        name: "wasm-demo",
+        summary: "Embeds the output of `wasm-pack build --target web` (or `wasm-bindgen --target \
+            web`).",
+        example: "{{#wasm-demo pkg=\"demos/counter\" /}}",
+        attrs: Some((0, &["pkg", "name", "entry"])),
+    },
+    Doc {
+        name: "watermark",
+        summary: "Overlays a diagonal, repeating watermark across the chapter.",
+        example: "{{#watermark \"DRAFT — do not distribute\" /}}",
+        attrs: Some((1, &["profile"])),
+    },
+    Doc {
+        name: "workspace-crates",
+        summary: "A table of the members of a Cargo workspace, with their versions, descriptions \
+            and documentation links.",
+        example: "{{#workspace-crates manifest=\"../Cargo.toml\" /}}",
+        attrs: Some((0, &["manifest"])),
+    },
+    Doc {
+        name: "xiangqi",
+        summary: "Draws a xiangqi (Chinese chess) position given in FEN, where uppercase pieces \
+            are red.",
+        example: "{{#xiangqi fen=\"rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR \
+            w\" /}}",
+        attrs: Some((0, &["fen"])),
+    },
+];
+
+/// Returns what is known about the built-in shortcode called `name`.
+pub(crate) fn doc(name: &str) -> Option<&'static Doc> {
+    DOCS.iter().find(|doc| doc.name == name)
+}
+
+const ATTR_DOCS: &[AttrDoc] = &[
+    AttrDoc {
+        shortcode: "assert-output",
+        name: "cwd",
+        kind: "path",
+        default: Some("the book's root directory"),
+    },
+    AttrDoc {
+        shortcode: "cargo-features",
+        name: "crate",
+        kind: "crate name",
+        default: Some("the package of the manifest"),
+    },
+    AttrDoc {
+        shortcode: "cargo-features",
+        name: "manifest",
+        kind: "path",
+        default: Some("`Cargo.toml`"),
+    },
+    AttrDoc {
+        shortcode: "chess",
+        name: "fen",
+        kind: "FEN",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "cli-help",
+        name: "command",
+        kind: "command",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "cli-help",
+        name: "cwd",
+        kind: "path",
+        default: Some("the book's root directory"),
+    },
+    AttrDoc {
+        shortcode: "cmd-output",
+        name: "cwd",
+        kind: "path",
+        default: Some("the book's root directory"),
+    },
+    AttrDoc {
+        shortcode: "cmd-output",
+        name: "lang",
+        kind: "language",
+        default: Some("`text`"),
+    },
+    AttrDoc {
+        shortcode: "columns",
+        name: "count",
+        kind: "number, in flow mode",
+        default: Some("`2`"),
+    },
+    AttrDoc {
+        shortcode: "config-schema",
+        name: "path",
+        kind: "path",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "crate-badge",
+        name: "style",
+        kind: "shields.io style",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "demo",
+        name: "height",
+        kind: "CSS length",
+        default: Some("`150px`"),
+    },
+    AttrDoc {
+        shortcode: "error-catalog",
+        name: "path",
+        kind: "path",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "error-catalog",
+        name: "enum",
+        kind: "enum name",
+        default: Some("every enum ending in `Error`"),
+    },
+    AttrDoc {
+        shortcode: "foreach",
+        name: "data",
+        kind: "path",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "foreach",
+        name: "as",
+        kind: "name",
+        default: Some("`item`"),
+    },
+    AttrDoc {
+        shortcode: "foreach",
+        name: "sort",
+        kind: "field, `-` first for descending order",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "foreach",
+        name: "filter",
+        kind: "expression",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "foreach",
+        name: "limit",
+        kind: "number",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "go",
+        name: "size",
+        kind: "number",
+        default: Some("`19`"),
+    },
+    AttrDoc {
+        shortcode: "go",
+        name: "black",
+        kind: "points",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "go",
+        name: "white",
+        kind: "points",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "hint",
+        name: "title",
+        kind: "inline markdown",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "hint",
+        name: "type",
+        kind: "`info`, `ok`, `warning` or `danger`",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "ipa",
+        name: "audio",
+        kind: "path",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "license",
+        name: "year",
+        kind: "year",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "license",
+        name: "url",
+        kind: "URL",
+        default: Some("the URL of a known license"),
+    },
+    AttrDoc {
+        shortcode: "num",
+        name: "precision",
+        kind: "number",
+        default: Some("as written"),
+    },
+    AttrDoc {
+        shortcode: "proto",
+        name: "path",
+        kind: "path",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "proto",
+        name: "message",
+        kind: "message name",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "proto",
+        name: "include",
+        kind: "path",
+        default: Some("the directory of the file"),
+    },
+    AttrDoc {
+        shortcode: "pullout",
+        name: "side",
+        kind: "`left` or `right`",
+        default: Some("`right`"),
+    },
+    AttrDoc {
+        shortcode: "pullout",
+        name: "width",
+        kind: "CSS length",
+        default: Some("`35%`"),
+    },
+    AttrDoc {
+        shortcode: "repo-badge",
+        name: "style",
+        kind: "shields.io style",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "rustc-output",
+        name: "edition",
+        kind: "Rust edition",
+        default: Some("`2021`"),
+    },
+    AttrDoc {
+        shortcode: "snippet",
+        name: "path",
+        kind: "path",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "snippet",
+        name: "lines",
+        kind: "line range",
+        default: Some("every line"),
+    },
+    AttrDoc {
+        shortcode: "snippet",
+        name: "lang",
+        kind: "language",
+        default: Some("detected from the extension"),
+    },
+    AttrDoc {
+        shortcode: "sql",
+        name: "db",
+        kind: "path",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "unit",
+        name: "precision",
+        kind: "number",
+        default: Some("one more decimal place than written"),
+    },
+    AttrDoc {
+        shortcode: "wasm-demo",
+        name: "pkg",
+        kind: "path",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "wasm-demo",
+        name: "name",
+        kind: "module name",
+        default: Some("the name of the `pkg` directory"),
+    },
+    AttrDoc {
+        shortcode: "wasm-demo",
+        name: "entry",
+        kind: "function name",
+        default: None,
+    },
+    AttrDoc {
+        shortcode: "watermark",
+        name: "profile",
+        kind: "`web`, `print` or `release`",
+        default: Some("every profile"),
+    },
+    AttrDoc {
+        shortcode: "workspace-crates",
+        name: "manifest",
+        kind: "path",
+        default: Some("`Cargo.toml`"),
+    },
+    AttrDoc {
+        shortcode: "xiangqi",
+        name: "fen",
+        kind: "FEN",
+        default: None,
+    },
+];
+
+/// Returns a description of the shortcode called `name` for the `list` command: its summary,
+/// the attributes it accepts and an example, each on a line of its own. Shortcodes that
+/// aren't built in (i.e. custom ones) are only described by their name and `aliases`.
+pub(crate) fn describe(name: &str, aliases: &[&str]) -> String {
+    let mut result = name.to_owned();
+    if !aliases.is_empty() {
+        result.push_str(&format!(" (also {})", aliases.join(", ")));
+    }
+    result.push('\n');
+    let doc = match doc(name) {
+        Some(doc) => doc,
+        None => return result + "    A custom shortcode.\n",
+    };
+    result.push_str(&format!("    {}\n", doc.summary));
+    if let Some((max_positional, keys)) = doc.attrs {
+        let mut attrs = Vec::new();
+        if max_positional > 0 {
+            attrs.push(format!("up to {} positional", max_positional));
+        }
+        for key in keys {
+            let details = ATTR_DOCS
+                .iter()
+                .find(|attr| attr.shortcode == name && attr.name == *key);
+            attrs.push(match details {
+                Some(AttrDoc {
+                    kind,
+                    default: Some(default),
+                    ..
+                }) => format!("{} ({}, default {})", key, kind, default),
+                Some(AttrDoc { kind, .. }) => format!("{} ({})", key, kind),
+                None => format!("{} (text)", key),
+            });
+        }
+        if attrs.is_empty() {
+            attrs.push("none".to_owned());
+        }
+        result.push_str(&format!("    Attributes: {}\n", attrs.join("; ")));
+    }
+    result.push_str(&format!("    Example: {}\n", doc.example));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(
+            describe("pullout", &[]),
+            "pullout\n    Floats supplementary content beside the text on wide screens.\n    \
+            Attributes: side (`left` or `right`, default `right`); width (CSS length, default \
+            `35%`)\n    Example: {{#pullout side=right width=35%}}...{{/pullout}}\n"
+        );
+        assert!(describe("hint", &["callout"]).starts_with("hint (also callout)\n"));
+        assert!(describe("hint", &[]).contains("Attributes: up to 1 positional; type ("));
+        assert_eq!(
+            describe("youtube", &[]),
+            "youtube\n    A custom shortcode.\n"
+        );
+
+        // Every attribute that is described is accepted by its shortcode.
+        for attr in ATTR_DOCS {
+            let keys = doc(attr.shortcode).and_then(|doc| doc.attrs).unwrap().1;
+            assert!(
+                keys.contains(&attr.name),
+                "{} {}",
+                attr.shortcode,
+                attr.name
+            );
+        }
+    }
+}
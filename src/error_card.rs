@@ -0,0 +1,51 @@
+use crate::html;
+
+/// The styles of the cards that errors are shown in when `errors` is `"inline"`.
+pub(crate) const HEADER: &str = "
+<style>
+    .mdbook-shortcodes-error {
+        margin: 1em 0;
+        padding: .5em 1em;
+        border: 2px solid var(--mdbook-shortcodes-hint-danger-color, #d33);
+        border-radius: var(--mdbook-shortcodes-hint-border-radius, 4px);
+        background: var(--mdbook-shortcodes-hint-danger-background, rgba(221, 51, 51, .1));
+    }
+    .mdbook-shortcodes-error p {
+        margin: 0 0 .5em;
+        font-weight: bold;
+    }
+    .mdbook-shortcodes-error pre {
+        margin: 0;
+        white-space: pre-wrap;
+    }
+</style>
+";
+
+/// Returns a card showing `message` and the part of the chapter it is about, `excerpt`, to
+/// take the place of a shortcode that couldn't be expanded.
+pub(crate) fn render(message: &str, excerpt: &str) -> String {
+    // Braces are escaped so that the excerpt's tags aren't expanded by later preprocessors
+    // (e.g. mdBook's `{{#include}}`).
+    let escape = |text: &str| html::escape(text).replace('{', "&#123;");
+    format!(
+        "<div class=\"mdbook-shortcodes-error\" role=\"alert\">\
+        <p>{}</p><pre><code>{}</code></pre></div>",
+        escape(message),
+        escape(excerpt.trim()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render("intro.md:1:1: kbd shortcode: <oops>", "{{#kbd /}}\n"),
+            "<div class=\"mdbook-shortcodes-error\" role=\"alert\">\
+            <p>intro.md:1:1: kbd shortcode: &lt;oops&gt;</p>\
+            <pre><code>&#123;&#123;#kbd /}}</code></pre></div>"
+        );
+    }
+}
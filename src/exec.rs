@@ -0,0 +1,51 @@
+use std::process::{Command, Output};
+
+use crate::Context;
+
+/// Runs an external program on behalf of a shortcode.
+///
+/// Running programs is disabled unless `allow-exec` is set, as books can come from untrusted
+/// sources.
+pub(crate) fn run(ctx: &Context, shortcode: &str, command: &mut Command) -> Result<Output, String> {
+    allowed(ctx, shortcode)?;
+    command
+        .output()
+        .map_err(|e| format!("failed to run {:?}: {}", command.get_program(), e))
+}
+
+/// Returns an error unless `allow-exec` is set. Shortcodes that cache the output of programs
+/// check it before using the cache, so that turning it off stops cached output from being used.
+pub(crate) fn allowed(ctx: &Context, shortcode: &str) -> Result<(), String> {
+    if ctx.config.allow_exec {
+        Ok(())
+    } else {
+        Err(format!(
+            "the {} shortcode runs external programs, which requires `allow-exec = true`",
+            shortcode
+        ))
+    }
+}
+
+/// Returns a command that runs `script` using the system shell.
+pub(crate) fn shell(script: &str) -> Command {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C");
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c");
+        command
+    };
+    command.arg(script);
+    command
+}
+
+/// Quotes `arg` so that the system shell treats it as a single argument.
+pub(crate) fn quote(arg: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{}\"", arg.replace('"', "\"\""))
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
@@ -0,0 +1,81 @@
+use std::path::Path;
+
+/// The flashcards collected from every chapter, exported as a single deck per book.
+#[derive(Default)]
+pub(crate) struct Deck {
+    cards: Vec<Card>,
+}
+
+struct Card {
+    front: String,
+    back: String,
+    tag: String,
+}
+
+impl Deck {
+    /// Adds a card from the chapter at `chapter_path`, which is used to tag it.
+    pub(crate) fn add(&mut self, front: &str, back: &str, chapter_path: &Path) {
+        self.cards.push(Card {
+            front: front.to_owned(),
+            back: back.to_owned(),
+            tag: tag(chapter_path),
+        });
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    /// Returns the deck as a tab-separated file that Anki can import directly.
+    pub(crate) fn to_tsv(&self) -> String {
+        let mut result = String::from("#separator:tab\n#html:true\n#tags column:3\n");
+        for card in &self.cards {
+            result.push_str(&format!(
+                "{}\t{}\t{}\n",
+                field(&card.front),
+                field(&card.back),
+                card.tag
+            ));
+        }
+        result
+    }
+}
+
+/// Anki fields can't contain tabs or newlines, so they are replaced with HTML equivalents.
+fn field(text: &str) -> String {
+    text.trim().replace('\t', " ").replace('\n', "<br>")
+}
+
+/// Returns the tag for a chapter, e.g. `guide::setup` for `guide/setup.md`. Tags can't contain
+/// spaces.
+fn tag(chapter_path: &Path) -> String {
+    chapter_path
+        .with_extension("")
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().replace(' ', "_"))
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_tsv() {
+        let mut deck = Deck::default();
+        assert!(deck.is_empty());
+        deck.add(
+            "What is 2 + 2?",
+            "4\n\nFour",
+            Path::new("maths/basic arithmetic.md"),
+        );
+        deck.add("a\tb", "c", Path::new("intro.md"));
+        assert_eq!(
+            deck.to_tsv(),
+            "#separator:tab\n#html:true\n#tags column:3\n\
+            What is 2 + 2?\t4<br><br>Four\tmaths::basic_arithmetic\n\
+            a b\tc\tintro\n"
+        );
+    }
+}
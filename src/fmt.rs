@@ -0,0 +1,96 @@
+use crate::{parse, Attrs, Delimiters, Result};
+
+/// Returns `content` with the opening tags of its shortcodes written consistently: a single
+/// space between the name and each attribute, the positional attributes before the named ones
+/// (which are sorted), values only quoted when they need to be (with `"` unless they contain
+/// it), and ` /` before the closing delimiter of self-closing tags. Everything else, including
+/// the bodies of shortcodes apart from the tags in them, is left as it is.
+pub(crate) fn format<F>(content: &str, delimiters: &Delimiters, is_shortcode: &F) -> Result<String>
+where
+    F: Fn(&str) -> bool,
+{
+    let parsed = parse::parse(content, delimiters, is_shortcode)?;
+    let mut result = String::with_capacity(content.len());
+    let mut last = 0;
+    for node in parsed.nodes {
+        result.push_str(&content[last..node.tag.start]);
+        let attrs = Attrs::parse(node.attrs)?;
+        match tag(node.name, &attrs, node.body.is_none(), delimiters) {
+            Some(tag) => result.push_str(&tag),
+            // Values that can't be quoted are left as they were written.
+            None => result.push_str(&content[node.tag.clone()]),
+        }
+        if let Some(body) = node.body {
+            result.push_str(&format(body, delimiters, is_shortcode)?);
+        }
+        last = node.tag.end + node.body.map_or(0, str::len);
+    }
+    result.push_str(&content[last..]);
+    Ok(result)
+}
+
+/// Returns the opening tag of a `name` shortcode, or `None` if one of its values contains both
+/// kinds of quote.
+fn tag(name: &str, attrs: &Attrs, self_closing: bool, delimiters: &Delimiters) -> Option<String> {
+    let mut result = format!("{}#{}", delimiters.open, name);
+    for value in attrs.positional() {
+        // An unquoted `=` would make the attribute named.
+        result.push(' ');
+        result.push_str(&quote(value, value.contains('='))?);
+    }
+    for (key, value) in attrs.named() {
+        result.push_str(&format!(" {}={}", key, quote(value, false)?));
+    }
+    if self_closing {
+        result.push_str(" /");
+    }
+    result.push_str(&delimiters.close);
+    Some(result)
+}
+
+/// Returns `value` quoted if it has to be, or if `force` is set.
+fn quote(value: &str, force: bool) -> Option<String> {
+    // A lone slash would make the tag self-closing.
+    let needs_quotes = force
+        || value.is_empty()
+        || value == "/"
+        || value.contains(|c: char| c.is_whitespace() || c == '"' || c == '\'');
+    if !needs_quotes {
+        Some(value.to_owned())
+    } else if !value.contains('"') {
+        Some(format!("\"{}\"", value))
+    } else if !value.contains('\'') {
+        Some(format!("'{}'", value))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let is_shortcode = |name: &str| name != "include";
+        let input =
+            "{{#hint   warning title='Careful'  }}\n`{{#kbd  A/}}` {{#kbd  Ctrl   /}}\n{{/hint}}\n\
+            {{#include  file.rs}} {{#cmd-output 'FOO=1 ls' cwd=\"a b\" lang=sh /}} \
+            {{#unit title=\"It's\" text='\"A\"' /}}";
+        assert_eq!(
+            format(input, &Delimiters::default(), &is_shortcode),
+            Ok(
+                "{{#hint warning title=Careful}}\n`{{#kbd  A/}}` {{#kbd Ctrl /}}\n{{/hint}}\n\
+                {{#include  file.rs}} {{#cmd-output \"FOO=1 ls\" cwd=\"a b\" lang=sh /}} \
+                {{#unit text='\"A\"' title=\"It's\" /}}"
+                    .to_owned()
+            )
+        );
+
+        let once = format(input, &Delimiters::default(), &is_shortcode).unwrap();
+        assert_eq!(
+            format(&once, &Delimiters::default(), &is_shortcode),
+            Ok(once.clone())
+        );
+    }
+}
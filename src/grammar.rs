@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::Delimiters;
+
+/// A description of the syntax of shortcodes, for syntax highlighters and grammars (e.g. for
+/// tree-sitter) to be generated from, so that they match the parser.
+///
+/// The patterns are regular expressions in the syntax shared by most engines. Self-closing tags
+/// also match `opening-tag`, so `self-closing-tag` should be tried first. Tags in code blocks and
+/// inline code aren't shortcodes, and a backslash before a tag escapes it.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Grammar {
+    pub delimiters: Delimiters,
+    /// The names of the shortcodes, including user-registered ones.
+    pub names: Vec<String>,
+    /// Other names shortcodes can be called by, mapped to the names of the shortcodes.
+    pub aliases: BTreeMap<String, String>,
+    pub patterns: Patterns,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Patterns {
+    /// Any name a shortcode can be called by.
+    pub name: String,
+    /// The opening tag of a shortcode with a body, e.g. `{{#hint info}}`. The first group is the
+    /// name, and the second the attributes.
+    pub opening_tag: String,
+    /// A self-closing tag, e.g. `{{#kbd Ctrl /}}`, with the same groups as `opening-tag`.
+    pub self_closing_tag: String,
+    /// The tag that closes a shortcode, e.g. `{{/hint}}`. The first group is the name.
+    pub closing_tag: String,
+    /// An attribute, e.g. `info` or `title="Be careful"`. The first group is the key of a named
+    /// attribute, and the second the value, including any quotes.
+    pub attribute: String,
+    /// A backslash and the start of the tag it escapes, e.g. `\{{#`.
+    pub escape: String,
+}
+
+impl Grammar {
+    /// Returns the grammar of shortcodes written with `delimiters`, which can be called by the
+    /// keys of `names`.
+    pub(crate) fn new(delimiters: &Delimiters, names: &BTreeMap<String, String>) -> Self {
+        let mut all: Vec<&String> = names.keys().collect();
+        // Longer names are tried first, so that a name isn't matched by one it starts with.
+        all.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        let name = format!(
+            "(?:{})",
+            all.iter()
+                .map(|name| escape(name))
+                .collect::<Vec<_>>()
+                .join("|")
+        );
+        let open = escape(&delimiters.open);
+        let close = escape(&delimiters.close);
+        // A tag ends at the first closing delimiter, even in a quoted attribute. This is
+        // approximated by attributes not containing its first character, as not every engine
+        // supports lookahead.
+        let first = delimiters.close.chars().next().unwrap();
+        let attrs = format!("[^{}]*?", escape_class(first));
+
+        let mut shortcodes: Vec<String> = names.values().cloned().collect();
+        shortcodes.sort();
+        shortcodes.dedup();
+        Self {
+            delimiters: delimiters.clone(),
+            names: shortcodes,
+            aliases: names
+                .iter()
+                .filter(|(alias, name)| alias != name)
+                .map(|(alias, name)| (alias.clone(), name.clone()))
+                .collect(),
+            patterns: Patterns {
+                opening_tag: format!(r"{}#({})(?:\s+({}))?\s*{}", open, name, attrs, close),
+                // A slash after other attributes only closes the tag if whitespace separates it
+                // from them.
+                self_closing_tag: format!(r"{}#({})(?:\s+({})\s|\s*)/{}", open, name, attrs, close),
+                closing_tag: format!(r"{}/({}){}", open, name, close),
+                attribute: r#"(?:([^\s"'=]+)=)?("[^"]*"|'[^']*'|[^\s"']+)"#.to_owned(),
+                escape: format!(r"\\{}[#/]", open),
+                name,
+            },
+        }
+    }
+}
+
+/// Escapes `c` for use in a character class.
+fn escape_class(c: char) -> String {
+    if r"\^-[]".contains(c) {
+        format!("\\{}", c)
+    } else {
+        c.to_string()
+    }
+}
+
+/// Escapes the characters of `text` that have a meaning in regular expressions.
+fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        if r"\.+*?()|[]{}^$".contains(c) {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    #[test]
+    fn test_grammar() {
+        let names = [
+            ("hint", "hint"),
+            ("callout", "hint"),
+            ("kbd", "kbd"),
+            ("kbd-list", "kbd"),
+        ]
+        .iter()
+        .map(|&(alias, name)| (alias.to_owned(), name.to_owned()))
+        .collect();
+        let grammar = Grammar::new(&Delimiters::default(), &names);
+        assert_eq!(grammar.names, ["hint", "kbd"]);
+        assert_eq!(grammar.patterns.name, "(?:kbd-list|callout|hint|kbd)");
+        let aliases: Vec<_> = grammar.aliases.keys().collect();
+        assert_eq!(aliases, ["callout", "kbd-list"]);
+
+        let pattern = |pattern: &str| Regex::new(&format!("^{}$", pattern)).unwrap();
+        let opening = pattern(&grammar.patterns.opening_tag);
+        let captures = opening.captures("{{#hint info title=\"A b\" }}").unwrap();
+        assert_eq!(&captures[1], "hint");
+        assert_eq!(&captures[2], "info title=\"A b\"");
+        assert!(!opening.is_match("{{#include file.rs}}"));
+
+        let self_closing = pattern(&grammar.patterns.self_closing_tag);
+        for tag in ["{{#kbd Ctrl /}}", "{{#kbd/}}", "{{#kbd-list a b  /}}"] {
+            assert!(self_closing.is_match(tag), "{}", tag);
+        }
+        assert!(!self_closing.is_match("{{#kbd Ctrl/}}"));
+        assert!(!self_closing.is_match("{{#kbd a}} b /}}"));
+        assert!(pattern(&grammar.patterns.closing_tag).is_match("{{/callout}}"));
+
+        let attribute = Regex::new(&grammar.patterns.attribute).unwrap();
+        let attributes: Vec<_> = attribute
+            .captures_iter("info title='A b' \"c=d\"")
+            .map(|c| (c.get(1).map(|m| m.as_str()), c[2].to_owned()))
+            .collect();
+        assert_eq!(
+            attributes,
+            [
+                (None, "info".to_owned()),
+                (Some("title"), "'A b'".to_owned()),
+                (None, "\"c=d\"".to_owned())
+            ]
+        );
+
+        let delimiters = Delimiters {
+            open: "[[".to_owned(),
+            close: "]]".to_owned(),
+        };
+        let grammar = Grammar::new(&delimiters, &names);
+        assert!(pattern(&grammar.patterns.closing_tag).is_match("[[/hint]]"));
+        assert!(pattern(&grammar.patterns.escape).is_match("\\[[#"));
+    }
+}
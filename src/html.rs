@@ -0,0 +1,86 @@
+/// Escapes the characters that have a special meaning in HTML text and attribute values.
+pub(crate) fn escape(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#39;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Escapes an attribute value that must stay on a single line.
+///
+/// A blank line ends a raw HTML block in markdown, so multi-line values (e.g. `srcdoc`) would
+/// otherwise be cut short.
+pub(crate) fn escape_single_line(input: &str) -> String {
+    escape(input).replace('\n', "&#10;").replace('\r', "")
+}
+
+/// Escapes the end tags of the `name` element (e.g. `</script`, in any case) in the content of
+/// such an element, so that they don't end it early. `<\/` means the same in JavaScript and CSS.
+pub(crate) fn escape_end_tags(content: &str, name: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(i) = rest.find("</") {
+        let after = &rest[i + 2..];
+        let is_end_tag = after
+            .get(..name.len())
+            .is_some_and(|tag| tag.eq_ignore_ascii_case(name));
+        result.push_str(&rest[..i]);
+        result.push_str(if is_end_tag { "<\\/" } else { "</" });
+        rest = after;
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Removes every `<script>` element from `input`, for renderers that don't run scripts.
+pub(crate) fn strip_scripts(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find("<script") {
+        result.push_str(&rest[..start]);
+        rest = match rest[start..].find("</script>") {
+            Some(end) => &rest[start + end + "</script>".len()..],
+            None => "",
+        };
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("<a href=\"x\">Tom & 'Jerry'</a>"),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#39;Jerry&#39;&lt;/a&gt;"
+        );
+        assert_eq!(escape_single_line("a\n\nb"), "a&#10;&#10;b");
+    }
+
+    #[test]
+    fn test_escape_end_tags() {
+        assert_eq!(
+            escape_end_tags("'</script>' + '</SCRIPT' + '</b>'", "script"),
+            "'<\\/script>' + '<\\/SCRIPT' + '</b>'"
+        );
+    }
+
+    #[test]
+    fn test_strip_scripts() {
+        assert_eq!(
+            strip_scripts("<p>A</p><script nonce=\"x\">f();</script>B<script>"),
+            "<p>A</p>B"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+/// Generates the ids of the elements created by shortcodes.
+///
+/// Ids are unique across the whole book, rather than just the chapter they are in, as every
+/// chapter is concatenated on the print page. They only depend on the chapter's path and the
+/// order of shortcodes within it, so they stay the same between builds.
+#[derive(Clone, Debug, Default)]
+pub struct IdGenerator {
+    /// The path of the current chapter, without its extension, as used in ids.
+    chapter: String,
+    /// The number of times each shortcode has been used in the current chapter.
+    instances: HashMap<String, usize>,
+    /// The ids given out anywhere in the book.
+    used: HashSet<String>,
+}
+
+impl IdGenerator {
+    /// Resets the per-chapter state. Draft chapters don't have a path.
+    pub(crate) fn start_chapter(&mut self, path: Option<&Path>) {
+        self.chapter = path
+            .map(|p| {
+                p.with_extension("")
+                    .to_string_lossy()
+                    .chars()
+                    .map(|c| if c.is_alphanumeric() { c } else { '-' })
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.instances.clear();
+    }
+
+    /// Returns the index of the next use of the `name` shortcode in the current chapter.
+    pub fn next_instance(&mut self, name: &str) -> usize {
+        let count = self.instances.entry(name.to_owned()).or_insert(0);
+        *count += 1;
+        *count - 1
+    }
+
+    /// Returns an id for the next use of the `name` shortcode in the current chapter, e.g.
+    /// `mdbook-shortcodes-tabs-guide-setup-0` for the first `tabs` in `guide/setup.md`.
+    pub fn generate(&mut self, name: &str) -> String {
+        let instance = self.next_instance(name);
+        let id = if self.chapter.is_empty() {
+            format!("mdbook-shortcodes-{}-{}", name, instance)
+        } else {
+            format!("mdbook-shortcodes-{}-{}-{}", name, self.chapter, instance)
+        };
+        self.unique(id)
+    }
+
+    /// Returns `id`, prefixed by the current chapter's path if it has already been used in
+    /// another chapter. Used for ids readers link to, such as `#env-HOME`, which should be left
+    /// as they are where possible.
+    pub fn unique(&mut self, id: String) -> String {
+        let mut unique = id.clone();
+        let mut n = 1;
+        while self.used.contains(&unique) {
+            unique = if n == 1 {
+                format!("{}-{}", self.chapter, id)
+            } else {
+                format!("{}-{}-{}", self.chapter, id, n - 1)
+            };
+            n += 1;
+        }
+        self.used.insert(unique.clone());
+        unique
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_instance() {
+        let mut ids = IdGenerator::default();
+        assert_eq!(ids.next_instance("a"), 0);
+        assert_eq!(ids.next_instance("a"), 1);
+        assert_eq!(ids.next_instance("b"), 0);
+        ids.start_chapter(None);
+        assert_eq!(ids.next_instance("a"), 0);
+    }
+
+    #[test]
+    fn test_generate() {
+        let mut ids = IdGenerator::default();
+        assert_eq!(ids.generate("tabs"), "mdbook-shortcodes-tabs-0");
+        ids.start_chapter(Some(Path::new("guide/setup.md")));
+        assert_eq!(ids.generate("tabs"), "mdbook-shortcodes-tabs-guide-setup-0");
+        assert_eq!(ids.generate("tabs"), "mdbook-shortcodes-tabs-guide-setup-1");
+    }
+
+    #[test]
+    fn test_unique() {
+        let mut ids = IdGenerator::default();
+        ids.start_chapter(Some(Path::new("intro.md")));
+        assert_eq!(ids.unique("env-HOME".to_owned()), "env-HOME");
+        ids.start_chapter(Some(Path::new("guide/setup.md")));
+        assert_eq!(ids.unique("env-HOME".to_owned()), "guide-setup-env-HOME");
+        assert_eq!(ids.unique("env-HOME".to_owned()), "guide-setup-env-HOME-1");
+        assert_eq!(ids.unique("env-PATH".to_owned()), "env-PATH");
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    env,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use mdbook::errors::{Error, Result};
+use toml_edit::{value, Array, DocumentMut, Item, Table};
+
+use crate::{builtins, theme};
+
+/// The stylesheet written into the book's theme directory, which sets the default values of the
+/// properties that can be themed so that they can be edited.
+const THEME_CSS: &str = "shortcodes-theme.css";
+
+/// Sets up the book at `root` to use the preprocessor, by adding the `[preprocessor.shortcodes]`
+/// table to its `book.toml`. With `css`, the default theme is also written into the book's theme
+/// directory and added to `output.html.additional-css`.
+///
+/// Returns a description of each change that was made, followed by a warning if the
+/// preprocessor isn't on the `PATH`, where mdBook looks for it. Nothing is changed if the book
+/// is already set up.
+pub fn install(root: &Path, css: bool) -> Result<Vec<String>> {
+    let book_toml = root.join("book.toml");
+    let source = fs::read_to_string(&book_toml)
+        .map_err(|e| Error::msg(format!("failed to read {}: {}", book_toml.display(), e)))?;
+    let mut doc: DocumentMut = source.parse()?;
+    let mut changes = Vec::new();
+
+    let preprocessors = table(doc.as_table_mut(), "preprocessor")?;
+    if !preprocessors.contains_key("shortcodes") {
+        preprocessors.insert("shortcodes", Item::Table(Table::new()));
+        changes.push("added `[preprocessor.shortcodes]` to book.toml".to_owned());
+    }
+
+    if css {
+        let html = table(table(doc.as_table_mut(), "output")?, "html")?;
+        let theme_dir = html
+            .get("theme")
+            .and_then(Item::as_str)
+            .unwrap_or("theme")
+            .trim_end_matches('/')
+            .to_owned();
+        let css_path = format!("{}/{}", theme_dir, THEME_CSS);
+        let headers: Vec<_> = builtins().iter().map(|handler| handler.header()).collect();
+        let css = theme::css(&theme::defaults(&headers, false))
+            + &theme::dark_css(&theme::defaults(&headers, true));
+        if fs::read_to_string(root.join(&css_path)).ok().as_deref() != Some(css.as_str()) {
+            fs::create_dir_all(root.join(&theme_dir))?;
+            fs::write(root.join(&css_path), css)?;
+            changes.push(format!("wrote the default theme to {}", css_path));
+        }
+
+        let additional_css = html
+            .entry("additional-css")
+            .or_insert_with(|| value(Array::new()))
+            .as_array_mut()
+            .ok_or_else(|| Error::msg("`output.html.additional-css` isn't an array"))?;
+        if !additional_css
+            .iter()
+            .any(|css| css.as_str() == Some(&css_path))
+        {
+            additional_css.push(css_path.as_str());
+            changes.push(format!(
+                "added {} to `output.html.additional-css`",
+                css_path
+            ));
+        }
+    }
+
+    let updated = doc.to_string();
+    if updated != source {
+        fs::write(&book_toml, updated)?;
+    }
+    if find_on_path(
+        "mdbook-shortcodes",
+        &env::var_os("PATH").unwrap_or_default(),
+    )
+    .is_none()
+    {
+        changes.push(
+            "warning: mdbook-shortcodes isn't on the PATH, so mdBook won't find it; install it \
+             with `cargo install mdbook-shortcodes`"
+                .to_owned(),
+        );
+    }
+    Ok(changes)
+}
+
+/// Returns the table at `key` in `parent`, adding it if it doesn't exist. Tables that are only
+/// added to hold others aren't written as headers of their own (e.g. `[output]`).
+fn table<'a>(parent: &'a mut Table, key: &str) -> Result<&'a mut Table> {
+    parent
+        .entry(key)
+        .or_insert_with(|| {
+            let mut table = Table::new();
+            table.set_implicit(true);
+            Item::Table(table)
+        })
+        .as_table_mut()
+        .ok_or_else(|| Error::msg(format!("`{}` in book.toml isn't a table", key)))
+}
+
+/// Returns the path of the executable called `name` in the directories of `path`, a list in
+/// the format of the `PATH` environment variable.
+fn find_on_path(name: &str, path: &OsStr) -> Option<PathBuf> {
+    let file = format!("{}{}", name, env::consts::EXE_SUFFIX);
+    env::split_paths(path)
+        .map(|dir| dir.join(&file))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install() {
+        let dir = tempfile::tempdir().unwrap();
+        let book_toml = dir.path().join("book.toml");
+        fs::write(&book_toml, "[book]\ntitle = \"A\" # The title.\n").unwrap();
+
+        let changes = install(dir.path(), true).unwrap();
+        assert_eq!(
+            changes[..3],
+            [
+                "added `[preprocessor.shortcodes]` to book.toml",
+                "wrote the default theme to theme/shortcodes-theme.css",
+                "added theme/shortcodes-theme.css to `output.html.additional-css`",
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(&book_toml).unwrap(),
+            "[book]\ntitle = \"A\" # The title.\n\n[preprocessor.shortcodes]\n\n\
+            [output.html]\nadditional-css = [\"theme/shortcodes-theme.css\"]\n"
+        );
+        let css = fs::read_to_string(dir.path().join("theme/shortcodes-theme.css")).unwrap();
+        assert!(css.starts_with(":root {\n"));
+        assert!(css.contains("    --mdbook-shortcodes-hint-info-color: #6bf;\n"));
+        assert!(css.contains("\n.navy, .coal, .ayu {\n"));
+
+        // Installing again changes nothing.
+        let changes = install(dir.path(), true).unwrap();
+        assert!(changes.iter().all(|change| change.starts_with("warning:")));
+
+        let bin = dir.path().join(format!("tool{}", env::consts::EXE_SUFFIX));
+        fs::write(&bin, "").unwrap();
+        let path = env::join_paths([Path::new("/nonexistent"), dir.path()]).unwrap();
+        assert_eq!(find_on_path("tool", &path), Some(bin));
+        assert_eq!(find_on_path("other", &path), None);
+    }
+}
@@ -1,17 +1,112 @@
+mod analytics;
+mod assets;
+mod attrs;
+mod cache;
+mod check;
+mod config;
+mod conflicts;
+mod context;
+mod css_override;
+mod custom;
+mod data;
+mod deps;
+mod diff;
+mod docs;
+mod error_card;
+mod exec;
+mod export;
+mod fmt;
+mod grammar;
+mod html;
+mod ids;
+mod install;
+mod locale;
+#[cfg(feature = "lsp")]
+mod lsp;
+mod markdown;
+mod outline;
+mod parse;
+mod record;
+mod remote;
+#[cfg(test)]
+mod render_tests;
+mod sanitize;
+mod search;
+mod shortcodes;
+mod slides;
+mod source_map;
+mod stats;
+mod stream;
+mod template;
+mod templates;
+mod theme;
+mod timing;
+mod translate;
+mod watch;
+
+pub use attrs::Attrs;
+pub use config::{
+    BadgeMode, Capability, Config, CssMode, CssOverride, Delimiters, ErrorDisplay, HeaderPosition,
+    Profile, UnitSystem,
+};
+pub use context::Context;
+pub use custom::CustomShortcode;
+pub use grammar::{Grammar, Patterns};
+pub use ids::IdGenerator;
+pub use install::install;
+pub use outline::Instance;
+pub use record::diff_build;
+pub use source_map::{Segment, SourceMap};
+pub use stream::Expander;
+pub use watch::watch;
+
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
 use mdbook::{
-    book::{Book, BookItem},
+    book::{Book, BookItem, Chapter},
     preprocess::{Preprocessor, PreprocessorContext},
+    MDBook,
+};
+use shortcodes::{
+    book_stats, script_open_tag, todo_list, AssertOutput, BookStats, CargoFeatures, Chem, Chess,
+    CitationNeeded, CliHelp, CmdOutput, ConfigSchema, CrateBadge, Crossword, DefineSnippet, Demo,
+    Env, Envvars, ErrorCatalog, FileSnippet, Flashcard, Foreach, Http, If, Ipa, Kbd, License,
+    Manifest, Money, Note, Num, Options, Pullout, Ratio, Regex, RemoteInclude, RepoBadge, Review,
+    RustcOutput, Rustdoc, Script, Shortcuts, Slide, Smiles, Spacer, Sql, Todo, TodoList, Unit,
+    UseSnippet, Var, WasmDemo, Watermark, WorkspaceCrates, BOOK_STATS_MARKER, TODO_LIST_MARKER,
 };
 
 // The CSS class names used are purposefully verbose to ensure they don't conflict with anything.
 
-const START_OPENING_DELIMETER: &str = "{{#";
-const START_CLOSING_DELIMETER: &str = "}}";
-const END_OPENING_DELIMETER: &str = "{{/";
-const END_CLOSING_DELIMETER: &str = "}}";
+/// The number of times the output of a shortcode is expanded in turn, which stops snippets that
+/// use themselves from expanding forever.
+const MAX_DEPTH: usize = 32;
+
+#[derive(Default)]
+pub struct ShortcodesProcessor {
+    custom: Vec<Rc<dyn CustomShortcode>>,
+    /// Whether to start a watcher for the files read by shortcodes under `mdbook serve`.
+    spawn_watcher: bool,
+}
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
-pub struct ShortcodesProcessor;
+impl std::fmt::Debug for ShortcodesProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShortcodesProcessor")
+            .field(
+                "custom",
+                &self.custom.iter().map(|s| s.name()).collect::<Vec<_>>(),
+            )
+            .field("spawn_watcher", &self.spawn_watcher)
+            .finish()
+    }
+}
 
 impl Preprocessor for ShortcodesProcessor {
     fn name(&self) -> &str {
@@ -20,32 +115,524 @@ impl Preprocessor for ShortcodesProcessor {
 
     fn run(
         &self,
-        _ctx: &PreprocessorContext,
+        ctx: &PreprocessorContext,
         mut book: Book,
     ) -> std::result::Result<Book, mdbook::errors::Error> {
-        for item in &mut book.sections {
-            if let BookItem::Chapter(chapter) = item {
-                chapter.content = process_chapter(&chapter.content)?;
+        let config = Config::from_context(ctx)?;
+        let custom = self.custom_shortcodes(&ctx.root, &config)?;
+        for key in unknown_toggles(&config, &custom) {
+            log::warn!(
+                "`{}` in `[preprocessor.shortcodes]` isn't an option or the name of a shortcode",
+                key
+            );
+        }
+        let mut context = Context::new(
+            &config,
+            ctx.root.clone(),
+            ctx.root.join(&ctx.config.book.src),
+        );
+        context.language = ctx.config.book.language.clone().unwrap_or_default();
+        context.renderer = ctx.renderer.clone();
+        context.custom = &custom;
+        let header_overrides = css_override::load(
+            &ctx.root,
+            &config.css_dir,
+            config.css_override,
+            &handlers(&config, &custom),
+        )?;
+        context.header_overrides = &header_overrides;
+        context.stylesheet_linked = config.css_mode == CssMode::Hoist
+            && is_additional_css(
+                ctx,
+                &context.src_dir.join(&config.assets_dir).join(HOISTED_CSS),
+            );
+
+        // Tags written with other delimiters can't be mistaken for those of other preprocessors.
+        let names: Vec<&str> = if config.delimiters == Delimiters::default() {
+            handlers(&config, &custom)
+                .iter()
+                .flat_map(|h| std::iter::once(h.name()).chain(h.aliases().iter().copied()))
+                .chain(config.aliases.keys().map(String::as_str))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        for warning in conflicts::check(&ctx.config, &names) {
+            log::warn!("{}", warning);
+        }
+
+        // Snippets can be used before the chapter that defines them, so every definition is
+        // collected first.
+        if let Some(file) = &config.snippets_file {
+            let content = std::fs::read_to_string(ctx.root.join(file))?;
+            context.start_chapter(Some(Path::new(file)));
+            DefineSnippet::process_raw(&content, &mut context)?;
+        }
+        for_each_chapter(&mut book.sections, &mut |chapter| {
+            if is_skipped(chapter, &config) {
+                return Ok(());
+            }
+            context.start_chapter(chapter.path.as_deref());
+            chapter.content = DefineSnippet::process_raw(&chapter.content, &mut context)?;
+            Ok(())
+        })?;
+
+        for_each_chapter(&mut book.sections, &mut |chapter| {
+            context.start_chapter(chapter.path.as_deref());
+            if !is_skipped(chapter, &config) {
+                let start = Instant::now();
+                chapter.content = process_chapter(&chapter.content, &mut context)?;
+                if let Some(path) = &chapter.path {
+                    context.timings.add_chapter(path, start.elapsed());
+                    if let Some(map) = context.source_map.take() {
+                        context.source_maps.insert(path.clone(), map);
+                    }
+                }
+            }
+            if chapter.path.is_some() {
+                context.stats.add_chapter(&chapter.content);
+            }
+            if config.slides && ctx.renderer == "html" && chapter.content.contains(slides::MARKER) {
+                let deck = slides::deck(&chapter.name, &chapter.content);
+                context
+                    .assets
+                    .add_content(deck.into_bytes(), slides::path(&context.chapter_path));
+            }
+            Ok(())
+        })?;
+        // What depends on the whole book is filled in once every chapter has been processed.
+        for_each_chapter(&mut book.sections, &mut |chapter| {
+            context.start_chapter(chapter.path.as_deref());
+            let path = chapter.path.clone().unwrap_or_default();
+            let mut map = context.source_maps.remove(&path);
+            if chapter.content.contains(TODO_LIST_MARKER) {
+                let list = todo_list(&context);
+                let content = &mut chapter.content;
+                replace_all(content, TODO_LIST_MARKER, &list, usize::MAX, map.as_mut());
+            }
+            if chapter.content.contains(BOOK_STATS_MARKER) {
+                let stats = book_stats(&context);
+                let content = &mut chapter.content;
+                replace_all(content, BOOK_STATS_MARKER, &stats, usize::MAX, map.as_mut());
+            }
+            if let Some(map) = map {
+                context.source_maps.insert(path, map);
+            }
+            Ok(())
+        })?;
+        if config.profile == Profile::Release && !context.notes.is_empty() {
+            let notes: Vec<String> = context.notes.iter().map(Note::to_string).collect();
+            log::warn!(
+                "Stripped {} editorial notes from the release build:\n  {}",
+                context.notes.len(),
+                notes.join("\n  ")
+            );
+        }
+        if !context.errors.is_empty() {
+            if config.fails_on_error() {
+                return Err(mdbook::errors::Error::msg(context.errors.join("\n\n")));
+            }
+            for error in &context.errors {
+                log::warn!("{}", error);
+            }
+        }
+        if !context.deck.is_empty() {
+            let deck = context.deck.to_tsv().into_bytes();
+            context.assets.add_content(deck, "flashcards.tsv");
+        }
+        if !context.hoisted_headers.is_empty() {
+            let css = theme::css(&config.theme) + &hoisted_css(&context.hoisted_headers);
+            let css = css.into_bytes();
+            context.assets.add_content(css, HOISTED_CSS);
+        }
+        context.assets.stage(&context.src_dir, &config.assets_dir)?;
+        if let Some(file) = &config.deps_file {
+            context.deps.write(&ctx.root, &ctx.root.join(file))?;
+        }
+        if let Some(file) = &config.stats_file {
+            let mut stats = serde_json::to_value(&context.stats)?;
+            stats["timings"] = serde_json::to_value(&context.timings)?;
+            let stats = serde_json::to_string_pretty(&stats)? + "\n";
+            std::fs::write(ctx.root.join(file), stats)?;
+        }
+        if let Some(file) = &config.source_map_file {
+            let maps = serde_json::to_string(&context.source_maps)? + "\n";
+            std::fs::write(ctx.root.join(file), maps)?;
+        }
+        if let Some(dir) = &config.record_dir {
+            context.record.write(&ctx.root.join(dir), &ctx.renderer)?;
+        }
+
+        if self.spawn_watcher && watch::is_serving(&ctx.config) {
+            let list = watch::WatchList::new(context.deps, &ctx.root, &ctx.config, &config);
+            if let Err(e) = watch::spawn(list, &ctx.root.join(&config.cache_dir)) {
+                log::warn!("Failed to watch the files used by shortcodes: {}", e);
             }
         }
         Ok(book)
     }
 
-    fn supports_renderer(&self, renderer: &str) -> bool {
-        renderer == "html" || renderer == "markdown"
+    fn supports_renderer(&self, _renderer: &str) -> bool {
+        // Renderers other than HTML are given plain markdown where shortcodes output HTML that
+        // needs their styles.
+        true
     }
 }
 
 impl ShortcodesProcessor {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Adds a shortcode defined outside of this crate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a shortcode with the same name has already been registered.
+    pub fn register<S>(mut self, shortcode: S) -> Self
+    where
+        S: CustomShortcode + 'static,
+    {
+        if self.custom.iter().any(|s| s.name() == shortcode.name()) {
+            panic!(
+                "a shortcode named {} is already registered",
+                shortcode.name()
+            );
+        }
+        self.custom.push(Rc::new(shortcode));
+        self
+    }
+
+    /// Starts a watcher after every build under `mdbook serve`, which touches the chapters
+    /// using a file read by shortcodes (e.g. the data of a `foreach`) when it changes, so that
+    /// the book is rebuilt. Files in the source directory are watched by mdBook already.
+    ///
+    /// The watcher runs the current executable with the arguments `watch <path>`, which should
+    /// call [`watch`], as the `mdbook-shortcodes` binary does.
+    pub fn spawn_watcher(mut self) -> Self {
+        self.spawn_watcher = true;
+        self
+    }
+
+    /// Returns an expander for markdown that is generated a piece at a time, outside of a book,
+    /// with the options in `config`. Files used by shortcodes are read relative to `dir`, and
+    /// copied into the `assets-dir` in it. Only the registered shortcodes are custom ones, as
+    /// templates are loaded from a book.
+    pub fn expander<'a>(&'a self, config: &'a Config, dir: &Path) -> Expander<'a> {
+        Expander::new(config, &self.custom, dir)
+    }
+
+    /// Rewrites the opening tags of the shortcodes in the chapters of the book at `root` so
+    /// that they are written consistently, e.g. with the same quoting. Returns the chapters
+    /// that were changed, which are only listed if `check` is set.
+    pub fn format(&self, root: &Path, check: bool) -> mdbook::errors::Result<Vec<PathBuf>> {
+        let book = MDBook::load(root)?;
+        let config = Config::from_book_config(&book.config)?;
+        let custom = self.custom_shortcodes(root, &config)?;
+        let handlers = handlers(&config, &custom);
+        let is_shortcode = |name: &str| find_handler(&handlers, name, &config).is_some();
+
+        let mut result = Vec::new();
+        for item in book.iter() {
+            let (chapter, path) = match item {
+                BookItem::Chapter(
+                    chapter @ Chapter {
+                        path: Some(path), ..
+                    },
+                ) => (chapter, path),
+                _ => continue,
+            };
+            if is_skipped(chapter, &config) {
+                continue;
+            }
+            let formatted = fmt::format(&chapter.content, &config.delimiters, &is_shortcode)?;
+            if formatted != chapter.content {
+                if !check {
+                    std::fs::write(book.source_dir().join(path), formatted)?;
+                }
+                result.push(path.clone());
+            }
+        }
+        Ok(result)
+    }
+
+    /// Returns the problems with the uses of shortcodes in the chapters of the book at `root`,
+    /// found without expanding them: unclosed tags, unknown shortcodes and attributes that
+    /// aren't accepted. Tags of mdbook's own preprocessors aren't reported, and neither are
+    /// those of shortcodes that are disabled, as they are left for another preprocessor.
+    pub fn check(&self, root: &Path) -> mdbook::errors::Result<Vec<Error>> {
+        let book = MDBook::load(root)?;
+        let config = Config::from_book_config(&book.config)?;
+        let names = Self::names(&config, &self.custom_shortcodes(root, &config)?);
+        let is_checked = |name: &str| config.is_enabled(name);
+
+        let mut result = Vec::new();
+        for item in book.iter() {
+            let (chapter, path) = match item {
+                BookItem::Chapter(
+                    chapter @ Chapter {
+                        path: Some(path), ..
+                    },
+                ) => (chapter, path),
+                _ => continue,
+            };
+            if !is_skipped(chapter, &config) {
+                result.extend(check::check(
+                    path,
+                    &chapter.content,
+                    &config.delimiters,
+                    &names,
+                    &is_checked,
+                ));
+            }
+        }
+        Ok(result)
+    }
+
+    /// Returns a description of the syntax of shortcodes in the book at `root`, with its
+    /// delimiters and the names of its shortcodes (including custom ones), for editors to
+    /// generate highlighting grammars from.
+    pub fn grammar(&self, root: &Path) -> mdbook::errors::Result<Grammar> {
+        let config = Self::load_config(root)?;
+        let names = Self::names(&config, &self.custom_shortcodes(root, &config)?);
+        Ok(Grammar::new(&config.delimiters, &names))
+    }
+
+    /// Returns a description of every shortcode that can be used in the book at `root`, custom
+    /// ones included: what it does, the attributes it accepts and an example of its use.
+    pub fn list(&self, root: &Path) -> mdbook::errors::Result<String> {
+        let config = Self::load_config(root)?;
+        let custom = self.custom_shortcodes(root, &config)?;
+        let names = Self::names(&config, &custom);
+        let mut shortcodes: Vec<&str> = handlers(&config, &custom)
+            .iter()
+            .map(Handler::name)
+            .collect();
+        shortcodes.sort_unstable();
+        let descriptions: Vec<String> = shortcodes
+            .iter()
+            .map(|&name| {
+                let aliases: Vec<&str> = names
+                    .iter()
+                    .filter(|&(alias, shortcode)| shortcode == name && alias != name)
+                    .map(|(alias, _)| alias.as_str())
+                    .collect();
+                docs::describe(name, &aliases)
+            })
+            .collect();
+        Ok(descriptions.join("\n"))
+    }
+
+    /// Runs a language server for the shortcodes of the book at `root` over stdin and stdout,
+    /// which completes their names and attributes, describes them on hover and reports
+    /// malformed uses.
+    #[cfg(feature = "lsp")]
+    pub fn lsp(&self, root: &Path) -> mdbook::errors::Result<()> {
+        let config = Self::load_config(root)?;
+        let names = Self::names(&config, &self.custom_shortcodes(root, &config)?);
+        let stdin = std::io::stdin();
+        lsp::Server::new(&config, names).run(stdin.lock(), std::io::stdout())?;
+        Ok(())
+    }
+
+    /// Returns the options in the `book.toml` of the book at `root`, or the defaults if it
+    /// doesn't have one.
+    fn load_config(root: &Path) -> mdbook::errors::Result<Config> {
+        let book_toml = root.join("book.toml");
+        if book_toml.exists() {
+            Config::from_book_config(&mdbook::Config::from_disk(book_toml)?)
+        } else {
+            Ok(Config::default())
+        }
+    }
+
+    /// Returns the registered shortcodes, followed by those defined by the templates in the
+    /// `templates-dir` of the book at `root`.
+    fn custom_shortcodes(
+        &self,
+        root: &Path,
+        config: &Config,
+    ) -> mdbook::errors::Result<Vec<Rc<dyn CustomShortcode>>> {
+        let mut custom = self.custom.clone();
+        for template in templates::load(root, &config.templates_dir)? {
+            let name = template.name();
+            if builtins().iter().any(|h| h.name() == name)
+                || custom.iter().any(|s| s.name() == name)
+            {
+                return Err(mdbook::errors::Error::msg(format!(
+                    "the template {}/{}.html has the same name as another shortcode",
+                    config.templates_dir, name
+                )));
+            }
+            custom.push(Rc::new(template));
+        }
+        Ok(custom)
+    }
+
+    /// Returns every name a shortcode can be called by, mapped to the name of the shortcode.
+    fn names(config: &Config, custom: &[Rc<dyn CustomShortcode>]) -> BTreeMap<String, String> {
+        let handlers = handlers(config, custom);
+        // A shortcode's own name takes precedence over the aliases of others.
+        let mut names = BTreeMap::new();
+        for (alias, name) in &config.aliases {
+            if handlers.iter().any(|h| h.name() == name) {
+                names.insert(alias.clone(), name.clone());
+            }
+        }
+        for handler in &handlers {
+            for alias in handler.aliases() {
+                names.insert((*alias).to_owned(), handler.name().to_owned());
+            }
+        }
+        for handler in &handlers {
+            names.insert(handler.name().to_owned(), handler.name().to_owned());
+        }
+        names
+    }
+
+    /// Returns the translatable text of the shortcodes in the book at `root` (e.g. the bodies
+    /// of hints and their titles) as a PO template, which [`merge`](Self::merge) fills in the
+    /// book with once it is translated.
+    pub fn extract(&self, root: &Path) -> mdbook::errors::Result<String> {
+        let mut catalog = translate::Catalog::default();
+        for (path, content, segments) in self.segments(root)? {
+            catalog.add(&path, &content, &segments);
+        }
+        Ok(catalog.to_pot())
+    }
+
+    /// Writes the chapters of the book at `root` into `out_dir`, with the text extracted by
+    /// [`extract`](Self::extract) replaced by its translation in the PO file at `po`. Text
+    /// without a translation is left as it is.
+    pub fn merge(&self, root: &Path, po: &Path, out_dir: &Path) -> mdbook::errors::Result<()> {
+        let translations = translate::parse_po(&std::fs::read_to_string(po)?).map_err(|e| {
+            mdbook::errors::Error::msg(format!("invalid PO file {}: {}", po.display(), e))
+        })?;
+        for (path, content, segments) in self.segments(root)? {
+            let path = out_dir.join(path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, translate::merge(&content, &segments, &translations))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the path, content and translatable segments of each chapter of the book at
+    /// `root`. Skipped chapters have no segments.
+    fn segments(
+        &self,
+        root: &Path,
+    ) -> mdbook::errors::Result<Vec<(PathBuf, String, Vec<translate::Segment>)>> {
+        let book = MDBook::load(root)?;
+        let config = Config::from_book_config(&book.config)?;
+        let custom = self.custom_shortcodes(root, &config)?;
+        let handlers = handlers(&config, &custom);
+        let resolve = |name: &str| find_handler(&handlers, name, &config).map(Handler::name);
+
+        let mut result = Vec::new();
+        for item in book.iter() {
+            let (chapter, path) = match item {
+                BookItem::Chapter(
+                    chapter @ Chapter {
+                        path: Some(path), ..
+                    },
+                ) => (chapter, path),
+                _ => continue,
+            };
+            let segments = if is_skipped(chapter, &config) {
+                Vec::new()
+            } else {
+                translate::segments(&chapter.content, 0, &config.delimiters, &resolve)?
+            };
+            result.push((path.clone(), chapter.content.clone(), segments));
+        }
+        Ok(result)
     }
+
+    /// Returns the uses of shortcodes in the chapters of the book at `root`, without expanding
+    /// them. Chapters that are skipped aren't included.
+    pub fn outline(&self, root: &Path) -> mdbook::errors::Result<Vec<Instance>> {
+        let book = MDBook::load(root)?;
+        let config = Config::from_book_config(&book.config)?;
+        let custom = self.custom_shortcodes(root, &config)?;
+        let handlers = handlers(&config, &custom);
+        let is_shortcode = |name: &str| find_handler(&handlers, name, &config).is_some();
+
+        let mut result = Vec::new();
+        for item in book.iter() {
+            let chapter = match item {
+                BookItem::Chapter(chapter) if !is_skipped(chapter, &config) => chapter,
+                _ => continue,
+            };
+            if let Some(path) = &chapter.path {
+                let content = &chapter.content;
+                result.extend(outline::instances(
+                    content,
+                    content,
+                    0,
+                    path,
+                    &config.delimiters,
+                    &is_shortcode,
+                )?);
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Calls `f` on every chapter in `items`, including nested ones, in the order they appear in the
+/// book. Unlike `Book::for_each_mut`, chapters are visited before their sub-chapters, matching
+/// the order of the print page.
+fn for_each_chapter<F>(items: &mut [BookItem], f: &mut F) -> mdbook::errors::Result<()>
+where
+    F: FnMut(&mut Chapter) -> mdbook::errors::Result<()>,
+{
+    for item in items {
+        if let BookItem::Chapter(chapter) = item {
+            f(chapter)?;
+            for_each_chapter(&mut chapter.sub_items, f)?;
+        }
+    }
+    Ok(())
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+/// The comment a chapter starts with to be left as it is, e.g. because it shows the tags of
+/// shortcodes literally.
+const SKIP_MARKER: &str = "shortcodes: off";
+
+/// Returns whether `chapter` is left unprocessed, either because the `skip-chapters` option
+/// lists it or because it starts with `<!-- shortcodes: off -->`.
+fn is_skipped(chapter: &Chapter, config: &Config) -> bool {
+    let listed = chapter
+        .path
+        .as_deref()
+        .is_some_and(|path| config.skips_chapter(path));
+    let marked = chapter
+        .content
+        .trim_start()
+        .strip_prefix("<!--")
+        .and_then(|rest| rest.split_once("-->"))
+        .is_some_and(|(comment, _)| comment.trim() == SKIP_MARKER);
+    listed || marked
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
 pub enum Error {
     NoClosingShortcode,
     UnterminatedString,
+    /// A shortcode was used incorrectly, e.g. with an unknown attribute.
+    InvalidShortcode {
+        /// The name of the shortcode.
+        name: String,
+        /// The path of the chapter containing the shortcode, relative to the source directory.
+        chapter: PathBuf,
+        /// The line of the shortcode's opening tag, starting at 1.
+        line: usize,
+        /// The column of the shortcode's opening tag, in characters and starting at 1.
+        column: usize,
+        message: String,
+    },
 }
 
 impl std::fmt::Display for Error {
@@ -53,6 +640,23 @@ impl std::fmt::Display for Error {
         let result = match self {
             Error::NoClosingShortcode => "an opening shortcode had no matching closing shortcode",
             Error::UnterminatedString => "a string did not contain a closing quote",
+            Error::InvalidShortcode {
+                name,
+                chapter,
+                line,
+                column,
+                message,
+            } => {
+                return write!(
+                    f,
+                    "{}:{}:{}: {} shortcode: {}",
+                    chapter.display(),
+                    line,
+                    column,
+                    name,
+                    message
+                )
+            }
         };
         write!(f, "{}", result)
     }
@@ -62,127 +666,397 @@ impl std::error::Error for Error {}
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// The result of expanding a single use of a shortcode. The error describes what is wrong with
+/// that use, and is reported along with where it is.
+type MatchResult = std::result::Result<String, String>;
+
 trait Shortcode {
     /// The name that is used to call the shortcode.
     const NAME: &'static str;
-    /// Any code that should be placed once at the start of the page (e.g. css).
+    /// Other names the shortcode can be called by, e.g. those used for it by other tools.
+    const ALIASES: &'static [&'static str] = &[];
+    /// Any code that should be placed once in the pages that use the shortcode (e.g. css). Where
+    /// it is placed is set by the `header-position` option.
     const HEADER: &'static str;
+    /// What the shortcode does beyond transforming its input. Shortcodes that only sometimes
+    /// need a capability (e.g. `smiles` with the `smiles-command` option) always need it.
+    const CAPABILITY: Capability = Capability::Pure;
 
-    fn process_match(input: &str, attrs: Vec<&str>) -> String;
-
-    // TODO custom error type
-    fn process_raw(input: &str) -> Result<String> {
-        // The start can contain attributes e.g. `{{#hint info}}` or `{{#details "Title" open}}`
-        // so we only look for the opening delimiter followed by the name. The closing delimeter
-        // (i.e. "}}") is taken into account later.
-        let start_sequence = format!("{}{}", START_OPENING_DELIMETER, Self::NAME);
-        let end_sequence = format!(
-            "{}{}{}",
-            END_OPENING_DELIMETER,
-            Self::NAME,
-            END_CLOSING_DELIMETER
-        );
-
-        let mut result = input.to_owned();
-        // `i` in the loop is the index that a match was found in the input string.
-        // However, at the end of every iteration we change result. This creates an
-        // issue as the replacement content is usually larger than the original content
-        // so the pointers such as `content_start_index` are all wrong. To account for
-        // this, we add an offset that tracks how much the length of `result` has changed.
-        // As the matches are processed is sequential order this means that we simply
-        // need to add the offset to the index of the match in `input` to get the index
-        // of the match in `result`.
-        let mut offset = 0;
-
-        for (i, _) in input.match_indices(&start_sequence) {
-            let i = i + offset;
-            // The index of the attributes start.
-            // {{#columns 3em}}
-            //           ^ here
-            let attrs_start_index = i + start_sequence.len();
-            // The index of the end of the attributes.
-            // {{#columns 3em}}
-            //               ^ here
-            let attrs_end_index = match result[attrs_start_index..].find(START_CLOSING_DELIMETER) {
-                Some(i) => attrs_start_index + i,
-                // TODO technically this is a different error than the one below, so it shouldn't
-                // use this error variant.
-                None => return Err(Error::NoClosingShortcode),
-            };
-            let attrs = split_attrs(&result[attrs_start_index..attrs_end_index])?;
-
-            // The index of the start of the content.
-            // {{#columns 3em}}
-            //                 ^ here (it is usually on a new line)
-            let content_start_index = attrs_end_index + START_CLOSING_DELIMETER.len();
-            // The index of the end of the content.
-            // {{/columns}}
-            // ^ here (note this is a closing tag)
-            let content_end_index = match result[content_start_index..].find(&end_sequence) {
-                Some(i) => content_start_index + i,
-                // No closing tag.
-                None => return Err(Error::NoClosingShortcode),
-            };
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult;
 
-            let replacement_content =
-                Self::process_match(&result[content_start_index..content_end_index], attrs);
+    /// Returns the replacement for a use of the shortcode when the book isn't rendered to HTML.
+    /// Shortcodes whose HTML doesn't make sense without their styles (e.g. layouts) return a
+    /// plain markdown equivalent instead.
+    fn process_plain(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        Self::process_match(input, attrs, ctx)
+    }
 
-            result.replace_range(
-                i..content_end_index + end_sequence.len(),
-                &replacement_content,
-            );
-            offset += replacement_content.len() - (content_end_index + end_sequence.len() - i);
+    fn process_raw(input: &str, ctx: &mut Context) -> Result<String>
+    where
+        Self: Sized,
+    {
+        if !ctx.config.is_enabled(Self::NAME) {
+            return Ok(input.to_owned());
         }
-
-        Ok(Self::HEADER.to_owned() + &result)
+        expand(input, &[builtin::<Self>()], ctx, 0)
     }
 }
 
-fn split_attrs(raw_attrs: &str) -> Result<Vec<&str>> {
-    let mut result = Vec::new();
-    let mut attr_start_index = 0;
-    let mut attr_end_index = 0;
-    let mut in_quote = false;
+/// A shortcode that can be expanded, either built in or registered with
+/// `ShortcodesProcessor::register`.
+#[derive(Clone, Copy)]
+enum Handler<'a> {
+    Builtin {
+        name: &'static str,
+        aliases: &'static [&'static str],
+        header: &'static str,
+        capability: Capability,
+        process_match: fn(&str, Attrs, &mut Context) -> MatchResult,
+        process_plain: fn(&str, Attrs, &mut Context) -> MatchResult,
+    },
+    Custom(&'a dyn CustomShortcode),
+}
+
+impl<'a> Handler<'a> {
+    fn name(&self) -> &'a str {
+        match self {
+            Handler::Builtin { name, .. } => name,
+            Handler::Custom(shortcode) => shortcode.name(),
+        }
+    }
+
+    fn aliases(&self) -> &'a [&'a str] {
+        match self {
+            Handler::Builtin { aliases, .. } => aliases,
+            Handler::Custom(shortcode) => shortcode.aliases(),
+        }
+    }
 
-    let raw_attrs = raw_attrs.trim();
+    fn header(&self) -> &'static str {
+        match self {
+            Handler::Builtin { header, .. } => header,
+            Handler::Custom(shortcode) => shortcode.header(),
+        }
+    }
 
-    // TODO
-    if raw_attrs.is_empty() {
-        return Ok(Vec::new());
+    fn capability(&self) -> Capability {
+        match self {
+            Handler::Builtin { capability, .. } => *capability,
+            Handler::Custom(shortcode) => shortcode.capability(),
+        }
     }
 
-    for (i, c) in raw_attrs.char_indices() {
-        if is_quote(&c) {
-            if in_quote {
-                result.push(&raw_attrs[attr_start_index..i]);
-            }
-            attr_start_index = i + 1;
-            in_quote = !in_quote;
-        } else if c.is_whitespace() && !in_quote {
-            if i != attr_start_index {
-                result.push(&raw_attrs[attr_start_index..i]);
+    fn process(&self, input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        match self {
+            Handler::Builtin { process_plain, .. } if !ctx.is_html() => {
+                process_plain(input, attrs, ctx)
             }
-            attr_start_index = i + 1;
+            Handler::Builtin { process_match, .. } => process_match(input, attrs, ctx),
+            Handler::Custom(shortcode) => shortcode.process(input, &attrs, ctx),
         }
-        attr_end_index = i;
     }
+}
+
+/// Returns the handler of the shortcode called `name`. A shortcode's own name takes precedence
+/// over the aliases of others.
+fn find_handler<'h, 'a>(
+    handlers: &'h [Handler<'a>],
+    name: &str,
+    config: &Config,
+) -> Option<&'h Handler<'a>> {
+    let alias = config.aliases.get(name).map(String::as_str);
+    handlers.iter().find(|h| h.name() == name).or_else(|| {
+        handlers
+            .iter()
+            .find(|h| h.aliases().contains(&name) || alias == Some(h.name()))
+    })
+}
+
+fn builtin<S: Shortcode>() -> Handler<'static> {
+    Handler::Builtin {
+        name: S::NAME,
+        aliases: S::ALIASES,
+        header: S::HEADER,
+        capability: S::CAPABILITY,
+        process_match: S::process_match,
+        process_plain: S::process_plain,
+    }
+}
+
+/// Replaces every use of the `handlers` in `input` with their output.
+///
+/// The output of a shortcode is expanded in turn, so that shortcodes in its body (e.g. a hint
+/// in a column) or inserted by it (e.g. by a snippet) are expanded too. `depth` is the number
+/// of shortcodes the input is the output of. Escaped tags are unescaped in the text between the
+/// shortcodes, so an escaped tag in the body of a shortcode is output literally once its
+/// output is expanded. The header of every shortcode that is used is added to the chapter.
+/// Unless `fail-on-error` is set, a shortcode that can't be expanded is left as it is (or shown as
+/// an error if `errors` is `"inline"`).
+fn expand(input: &str, handlers: &[Handler], ctx: &mut Context, depth: usize) -> Result<String> {
+    let parsed = match parse::parse(input, &ctx.config.delimiters, |name| {
+        find_handler(handlers, name, ctx.config).is_some()
+    }) {
+        Ok(parsed) => parsed,
+        Err(unclosed) => {
+            let error = located(
+                unclosed.name,
+                unclosed.tag,
+                input,
+                depth,
+                ctx,
+                Error::from(unclosed.clone()).to_string(),
+            );
+            if ctx.config.fails_on_error() {
+                return Err(error);
+            }
+            log::warn!("{}", error);
+            return expand_around(input, unclosed.tag, &error, handlers, ctx, depth);
+        }
+    };
+    // The output of shortcodes is usually longer than their tags, and any headers are inserted
+    // into the result afterwards, so room is left for both rather than growing the result (and
+    // copying it) as it's built.
+    let mut result = String::with_capacity(input.len() + input.len() / 8);
+    let mut last = 0;
+
+    // Only the chapter itself is mapped, rather than the output of shortcodes.
+    let mut map = match depth {
+        0 => ctx.source_map.take(),
+        _ => None,
+    };
+    let mut push = |result: &mut String, text: &str, span: Range<usize>, verbatim: bool| {
+        if let Some(map) = &mut map {
+            map.push(result.len()..result.len() + text.len(), span, verbatim);
+        }
+        result.push_str(text);
+    };
+    for node in &parsed.nodes {
+        let output = match expand_node(node, input, handlers, ctx, depth) {
+            Ok(output) => Cow::Owned(output),
+            // The shortcode is left as it was written, unless the error is shown in its place.
+            Err(e) if !ctx.config.fails_on_error() => {
+                log::warn!("{}", e);
+                match error_card(&e.to_string(), &input[node.tag.clone()], ctx) {
+                    Some(card) => Cow::Owned(card),
+                    None => Cow::Borrowed(&input[node.span.clone()]),
+                }
+            }
+            Err(e) => return Err(e),
+        };
 
-    if in_quote {
-        return Err(Error::UnterminatedString);
-    } else if attr_start_index <= attr_end_index {
-        // `attr_start_index` is only greater than `attr_end_index` at the end of the loop
-        // if the last char of the string was a quote that closed a string. Hence, this
-        // block is only entered if the last character WASN'T a closing quote. Since,
-        // whitespace has been stripped, we are guaranteed to have missed the last attribute
-        // in the loop.
-        result.push(&raw_attrs[attr_start_index..=attr_end_index])
+        let text = parsed.unescaped(input, last..node.span.start);
+        let verbatim = matches!(text, Cow::Borrowed(_));
+        push(&mut result, &text, last..node.span.start, verbatim);
+        let verbatim = matches!(output, Cow::Borrowed(_));
+        push(&mut result, &output, node.span.clone(), verbatim);
+        last = node.span.end;
     }
+    let text = parsed.unescaped(input, last..input.len());
+    let verbatim = matches!(text, Cow::Borrowed(_));
+    push(&mut result, &text, last..input.len(), verbatim);
+    if depth == 0 {
+        ctx.source_map = map;
+    }
+
+    Ok(result)
+}
 
+/// Expands `input` as if the unclosed opening tag at `tag` were text, which is left as it was
+/// written (or replaced by a card showing `error`), so that a typo doesn't stop the rest of the
+/// input from being expanded.
+fn expand_around(
+    input: &str,
+    tag: usize,
+    error: &Error,
+    handlers: &[Handler],
+    ctx: &mut Context,
+    depth: usize,
+) -> Result<String> {
+    let close = &ctx.config.delimiters.close;
+    let after = input[tag..]
+        .find(close.as_str())
+        .map_or(input.len(), |i| tag + i + close.len());
+
+    let mut map = match depth {
+        0 => ctx.source_map.take(),
+        _ => None,
+    };
+    let mut result = String::with_capacity(input.len());
+    for (range, expanded) in [
+        (0..tag, true),
+        (tag..after, false),
+        (after..input.len(), true),
+    ] {
+        let start = result.len();
+        if !expanded {
+            let card = error_card(&error.to_string(), &input[range.clone()], ctx);
+            result.push_str(card.as_deref().unwrap_or(&input[range.clone()]));
+            if let Some(map) = &mut map {
+                map.push(start..result.len(), range, card.is_none());
+            }
+            continue;
+        }
+        if map.is_some() {
+            ctx.source_map = Some(SourceMap::default());
+        }
+        let origin = ctx.origin;
+        if depth == 0 {
+            ctx.origin += range.start;
+        }
+        let expanded = expand(&input[range.clone()], handlers, ctx, depth);
+        ctx.origin = origin;
+        result.push_str(&expanded?);
+        if let (Some(map), Some(part)) = (&mut map, ctx.source_map.take()) {
+            map.append(part, start, range.start);
+        }
+    }
+    ctx.source_map = map;
     Ok(result)
 }
 
-fn is_quote(c: &char) -> bool {
-    *c == '\'' || *c == '"'
+/// Returns the expanded output of a use of a shortcode in `input`.
+fn expand_node(
+    node: &parse::Node,
+    input: &str,
+    handlers: &[Handler],
+    ctx: &mut Context,
+    depth: usize,
+) -> Result<String> {
+    if depth == MAX_DEPTH {
+        let message = "shortcodes are nested too deeply; does a snippet use itself?";
+        return Err(invalid(node, input, depth, ctx, message.to_owned()));
+    }
+    let handler = find_handler(handlers, node.name, ctx.config).unwrap();
+    ctx.position = Some(position(node.tag.start, input, depth, ctx));
+    let capability = handler.capability();
+    if !ctx.config.allows(capability) {
+        let message = format!(
+            "needs the `{}` capability, which the `capabilities` option doesn't allow",
+            capability
+        );
+        return Err(invalid(node, input, depth, ctx, message));
+    }
+    let attrs =
+        Attrs::parse(node.attrs).map_err(|e| invalid(node, input, depth, ctx, e.to_string()))?;
+    let body = match node.body {
+        Some(body) if ctx.config.sanitize_bodies => Cow::Owned(sanitize::body(body)),
+        Some(body) => Cow::Borrowed(body),
+        None => Cow::Borrowed(""),
+    };
+
+    let errors = ctx.errors.len();
+    let start = Instant::now();
+    let output = handler.process(&body, attrs, ctx);
+    let elapsed = start.elapsed();
+    ctx.timings.add_use(handler.name(), elapsed);
+    if let Some(budget) = ctx.config.time_budget_ms {
+        if elapsed > Duration::from_millis(budget) {
+            let message = format!(
+                "took {} ms to expand, more than the time budget of {} ms",
+                elapsed.as_millis(),
+                budget
+            );
+            ctx.warn(handler.name(), &message);
+        }
+    }
+    let mut output = output.map_err(|message| invalid(node, input, depth, ctx, message))?;
+    // Errors recorded by the shortcode are shown before its output.
+    for error in ctx.errors[errors..].to_vec().iter().rev() {
+        if let Some(card) = error_card(error, &input[node.tag.clone()], ctx) {
+            output.insert_str(0, &card);
+        }
+    }
+    if ctx.is_epub() {
+        output = html::strip_scripts(&output);
+    }
+    if ctx.is_html() {
+        ctx.add_shortcode_header(handler.name(), handler.header());
+    }
+    // Output without tags (or escaped tags) is kept as it is, rather than copied.
+    if output.contains(&ctx.config.delimiters.open) {
+        // Shortcodes in the output are located at this one.
+        let origin = ctx.origin;
+        if depth == 0 {
+            ctx.origin += node.tag.start;
+        }
+        let expanded = expand(&output, handlers, ctx, depth + 1);
+        ctx.origin = origin;
+        output = expanded?;
+    }
+    let output = exclude_from_search(handler.name(), output, ctx);
+    if depth == 0 && ctx.config.record_dir.is_some() {
+        let chapter = ctx.chapter_path.clone();
+        ctx.record
+            .add(&chapter, handler.name(), node.attrs, &output);
+    }
+    Ok(output)
+}
+
+/// Returns a card showing `message` and `excerpt`, the part of the chapter it is about, if
+/// errors are shown inline and the renderer supports HTML.
+fn error_card(message: &str, excerpt: &str, ctx: &mut Context) -> Option<String> {
+    if ctx.config.errors != ErrorDisplay::Inline || !ctx.is_html() {
+        return None;
+    }
+    ctx.add_header(error_card::HEADER);
+    Some(error_card::render(message, excerpt))
+}
+
+/// Returns the error for a use of a shortcode in `input`, which is the output of `depth`
+/// shortcodes.
+fn invalid(node: &parse::Node, input: &str, depth: usize, ctx: &Context, message: String) -> Error {
+    located(node.name, node.tag.start, input, depth, ctx, message)
+}
+
+/// Returns the error for a use of the `name` shortcode whose opening tag is at `index` in
+/// `input`, which is the output of `depth` shortcodes.
+fn located(
+    name: &str,
+    index: usize,
+    input: &str,
+    depth: usize,
+    ctx: &Context,
+    message: String,
+) -> Error {
+    let (line, column) = position(index, input, depth, ctx);
+    Error::InvalidShortcode {
+        name: name.to_owned(),
+        chapter: ctx.chapter_path.clone(),
+        line,
+        column,
+        message,
+    }
+}
+
+/// Returns the line and column of the opening tag at `index` in `input`, which is the output of
+/// `depth` shortcodes.
+///
+/// Shortcodes are located in the chapter as it was written: those in the chapter where they
+/// are, and those output by another shortcode at the chapter's shortcode they came from, as in
+/// the source map. Input that isn't part of a chapter (e.g. in tests) is located on its own.
+fn position(index: usize, input: &str, depth: usize, ctx: &Context) -> (usize, usize) {
+    let (text, index) = match depth {
+        0 if ctx.origin + input.len() <= ctx.source.len() => {
+            (ctx.source.as_str(), ctx.origin + index)
+        }
+        1.. if ctx.origin < ctx.source.len() => (ctx.source.as_str(), ctx.origin),
+        _ => (input, index),
+    };
+    let line_start = text[..index].rfind('\n').map_or(0, |i| i + 1);
+    (
+        text[..index].matches('\n').count() + 1,
+        text[line_start..index].chars().count() + 1,
+    )
+}
+
+/// Hides the output of a shortcode from the search index if the `search-exclude` option lists it.
+fn exclude_from_search(name: &str, output: String, ctx: &Context) -> String {
+    // The excluded output is shown by a script, which an EPUB wouldn't run.
+    if ctx.is_html() && !ctx.is_epub() && ctx.config.search_exclude.iter().any(|n| n == name) {
+        search::exclude(&output, ctx.config)
+    } else {
+        output
+    }
 }
 
 struct Columns;
@@ -191,23 +1065,52 @@ impl Shortcode for Columns {
     const NAME: &'static str = "columns";
     const HEADER: &'static str = "
 <style>
+    /* Readers without flexbox (e.g. some EPUB readers) lay the columns out as a table. */
     .mdbook-shortcodes-columns-container {
+        display: table;
+        table-layout: fixed;
+        width: 100%;
         display: flex;
-        margin: 0 -1em;
+        margin: 0 calc(-1 * var(--mdbook-shortcodes-column-padding, 1em));
     }
     .mdbook-shortcodes-column {
+        display: table-cell;
         flex: 50%;
-        padding: 0 1em;
+        padding: 0 var(--mdbook-shortcodes-column-padding, 1em);
+    }
+    .mdbook-shortcodes-columns-flow {
+        column-gap: var(--mdbook-shortcodes-columns-gap, 2em);
+    }
+    .mdbook-shortcodes-column-break {
+        break-before: column;
     }
 </style>
 ";
 
-    fn process_match(input: &str, attrs: Vec<&str>) -> String {
-        let padding = match attrs.len() {
-            0 => None,
-            1 => Some(attrs[0]),
-            _ => panic!("too many arguments given to columns shortcode"),
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(2, &["count"])?;
+        let (flow, padding) = match attrs.positional() {
+            [] => (false, None),
+            ["flow"] => (true, None),
+            ["flow", padding] | [padding, "flow"] => (true, Some(*padding)),
+            [padding] => (false, Some(*padding)),
+            _ => return Err("too many arguments given to columns shortcode".to_owned()),
+        };
+        let count = match attrs.get_parsed::<u8>("count")? {
+            Some(_) if !flow => {
+                return Err(
+                    "count is only supported by the flow mode of the columns shortcode".to_owned(),
+                )
+            }
+            Some(0) => return Err("invalid count `0`".to_owned()),
+            Some(count) => count,
+            None => 2,
         };
+
+        if flow {
+            return Ok(flow_columns(input, count, padding, ctx));
+        }
+
         let (container_style, column_style) = match padding {
             Some(p) => (
                 format!("style=\"margin: 0 -{}\"", p),
@@ -223,60 +1126,122 @@ impl Shortcode for Columns {
             container_style
         ));
 
-        for column_content in input.split("{{#column}}") {
+        for column_content in split_columns(input, ctx) {
             result.push_str(&format!(
                 "<div class=\"mdbook-shortcodes-column\" {}>",
                 column_style
             ));
-            result.push_str(column_content);
+            result.push_str(&markdown::block(column_content));
             result.push_str("</div>");
         }
 
         result.push_str("</div>");
 
-        result
+        Ok(result)
     }
+
+    fn process_plain(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        Self::process_match(input, attrs, ctx)?;
+        let columns: Vec<&str> = split_columns(input, ctx)
+            .into_iter()
+            .map(|column| column.trim_matches('\n'))
+            .collect();
+        Ok(markdown::block(&columns.join("\n\n")))
+    }
+}
+
+/// Splits the body of a `columns` shortcode at its `{{#column}}`s.
+fn split_columns<'a>(input: &'a str, ctx: &Context) -> Vec<&'a str> {
+    let delimiters = &ctx.config.delimiters;
+    parse::split_outside(input, &delimiters.tag("column"), Columns::NAME, delimiters)
+}
+
+/// Lays out `input` in `count` columns that the content flows between, like a newspaper. A
+/// `{{#column}}` starts a new column instead of separating boxes, and `gap` is the space
+/// between the columns.
+fn flow_columns(input: &str, count: u8, gap: Option<&str>, ctx: &Context) -> String {
+    let gap = gap.map_or_else(String::new, |gap| format!(" column-gap: {};", gap));
+    let mut result = String::with_capacity(input.len());
+    result.push_str(&format!(
+        "<div class=\"mdbook-shortcodes-columns-flow\" style=\"column-count: {};{}\">",
+        count, gap
+    ));
+    for (i, column_content) in split_columns(input, ctx).into_iter().enumerate() {
+        if i > 0 {
+            result.push_str("<div class=\"mdbook-shortcodes-column-break\"></div>");
+        }
+        result.push_str(&markdown::block(column_content));
+    }
+    result.push_str("</div>");
+    result
 }
 
 struct Hint;
 
 impl Shortcode for Hint {
     const NAME: &'static str = "hint";
+    const ALIASES: &'static [&'static str] = &["admonition", "callout"];
     const HEADER: &'static str = "
 <style>
     .mdbook-shortcodes-hint {
         padding: .5rem 2rem .5rem 1.75rem;
         border-inline-start: .5rem solid #fff;
-        border-radius: .5rem;
+        border-radius: var(--mdbook-shortcodes-hint-border-radius, .5rem);
         margin: 2.5rem 0;
     }
 
     .mdbook-shortcodes-hint-info {
-        border-color: #6bf;
-        background-color: rgba(102,187,255,.1);
+        border-color: var(--mdbook-shortcodes-hint-info-color, #6bf);
+        background-color: var(--mdbook-shortcodes-hint-info-background, rgba(102,187,255,.1));
     }
 
     .mdbook-shortcodes-hint-ok {
-        border-color: #5b6;
-        background-color: rgba(85,187,102,.1);
+        border-color: var(--mdbook-shortcodes-hint-ok-color, #5b6);
+        background-color: var(--mdbook-shortcodes-hint-ok-background, rgba(85,187,102,.1));
     }
 
     .mdbook-shortcodes-hint-warning {
-        border-color: #fd6;
-        background-color: rgba(255,221,102,.1);
+        border-color: var(--mdbook-shortcodes-hint-warning-color, #fd6);
+        background-color: var(--mdbook-shortcodes-hint-warning-background, rgba(255,221,102,.1));
     }
 
     .mdbook-shortcodes-hint-danger {
-        border-color: #f66;
-        background-color: rgba(255,102,102,.1);
+        border-color: var(--mdbook-shortcodes-hint-danger-color, #f66);
+        background-color: var(--mdbook-shortcodes-hint-danger-background, rgba(255,102,102,.1));
+    }
+
+    /* mdBook's dark themes (see `theme::DARK_THEMES`). */
+    .navy .mdbook-shortcodes-hint-info, .coal .mdbook-shortcodes-hint-info,
+    .ayu .mdbook-shortcodes-hint-info {
+        border-color: var(--mdbook-shortcodes-hint-info-color, #3b8eea);
+        background-color: var(--mdbook-shortcodes-hint-info-background, rgba(59,142,234,.15));
+    }
+
+    .navy .mdbook-shortcodes-hint-ok, .coal .mdbook-shortcodes-hint-ok,
+    .ayu .mdbook-shortcodes-hint-ok {
+        border-color: var(--mdbook-shortcodes-hint-ok-color, #3fa55a);
+        background-color: var(--mdbook-shortcodes-hint-ok-background, rgba(63,165,90,.15));
+    }
+
+    .navy .mdbook-shortcodes-hint-warning, .coal .mdbook-shortcodes-hint-warning,
+    .ayu .mdbook-shortcodes-hint-warning {
+        border-color: var(--mdbook-shortcodes-hint-warning-color, #d9a514);
+        background-color: var(--mdbook-shortcodes-hint-warning-background, rgba(217,165,20,.15));
+    }
+
+    .navy .mdbook-shortcodes-hint-danger, .coal .mdbook-shortcodes-hint-danger,
+    .ayu .mdbook-shortcodes-hint-danger {
+        border-color: var(--mdbook-shortcodes-hint-danger-color, #e0525a);
+        background-color: var(--mdbook-shortcodes-hint-danger-background, rgba(224,82,90,.15));
     }
 </style>
 ";
 
-    fn process_match(input: &str, attrs: Vec<&str>) -> String {
-        let ty = match attrs.len() {
-            1 => attrs[0],
-            _ => panic!("too many arguments given to columns shortcode"),
+    fn process_match(input: &str, attrs: Attrs, _ctx: &mut Context) -> MatchResult {
+        attrs.expect(1, &["type", "title"])?;
+        let ty = match (attrs.positional(), attrs.get("type")) {
+            (&[ty], None) | (&[], Some(ty)) => ty,
+            _ => return Err("wrong number of arguments given to hint shortcode".to_owned()),
         };
 
         if let "info" | "ok" | "warning" | "danger" = ty {
@@ -285,13 +1250,44 @@ impl Shortcode for Hint {
                 "<div class=\"mdbook-shortcodes-hint mdbook-shortcodes-hint-{}\">",
                 ty
             );
-            result += input;
+            if let Some(title) = attrs.get("title") {
+                result += &format!(
+                    "<p class=\"mdbook-shortcodes-hint-title\"><strong>{}</strong></p>",
+                    markdown::inline(title)
+                );
+            }
+            result += &markdown::block(input);
             result += "</div>";
-            result
+            Ok(result)
         } else {
-            panic!("unknown hint type");
+            Err(format!("unknown hint type `{}`", ty))
         }
     }
+
+    fn process_plain(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        Self::process_match(input, attrs.clone(), ctx)?;
+        let title = match (attrs.get("title"), attrs.get("type")) {
+            (Some(title), _) => title,
+            (None, Some(ty)) => hint_label(ty),
+            (None, None) => hint_label(attrs.positional()[0]),
+        };
+        Ok(markdown::blockquote(&format!(
+            "**{}**\n\n{}",
+            title,
+            input.trim_matches('\n')
+        )))
+    }
+}
+
+/// The title given to a hint of type `ty` when it isn't rendered to HTML, where it isn't
+/// coloured.
+fn hint_label(ty: &str) -> &'static str {
+    match ty {
+        "ok" => "Tip",
+        "warning" => "Warning",
+        "danger" => "Danger",
+        _ => "Note",
+    }
 }
 
 struct Tabs;
@@ -300,24 +1296,348 @@ impl Shortcode for Tabs {
     const NAME: &'static str = "tabs";
     const HEADER: &'static str = "";
 
-    fn process_match(_input: &str, _attrs: Vec<&str>) -> String {
-        todo!();
+    fn process_match(_input: &str, _attrs: Attrs, _ctx: &mut Context) -> MatchResult {
+        Err("tabs shortcode is not implemented yet".to_owned())
     }
 }
 
-fn process_chapter(content: &str) -> Result<String> {
-    let mut result = content.to_owned();
+/// Returns the built-in shortcodes, apart from `define-snippet`, which is expanded in a pass of
+/// its own.
+fn builtins() -> Vec<Handler<'static>> {
+    #[allow(unused_mut)]
+    let mut builtins = vec![
+        builtin::<UseSnippet>(),
+        builtin::<FileSnippet>(),
+        builtin::<RemoteInclude>(),
+        builtin::<Var>(),
+        builtin::<Env>(),
+        builtin::<If>(),
+        builtin::<Foreach>(),
+        builtin::<Columns>(),
+        builtin::<Hint>(),
+        builtin::<Ratio>(),
+        builtin::<Spacer>(),
+        builtin::<Pullout>(),
+        builtin::<Watermark>(),
+        builtin::<License>(),
+        builtin::<Todo>(),
+        builtin::<Review>(),
+        builtin::<CitationNeeded>(),
+        builtin::<TodoList>(),
+        builtin::<BookStats>(),
+        builtin::<Tabs>(),
+        builtin::<Script>(),
+        builtin::<Demo>(),
+        builtin::<WasmDemo>(),
+        builtin::<Rustdoc>(),
+        builtin::<CrateBadge>(),
+        builtin::<RepoBadge>(),
+        builtin::<RustcOutput>(),
+        builtin::<CmdOutput>(),
+        builtin::<AssertOutput>(),
+        builtin::<Sql>(),
+        builtin::<Regex>(),
+        builtin::<Kbd>(),
+        builtin::<Shortcuts>(),
+        builtin::<Unit>(),
+        builtin::<Money>(),
+        builtin::<Num>(),
+        builtin::<Ipa>(),
+        builtin::<Chem>(),
+        builtin::<Smiles>(),
+        builtin::<Chess>(),
+        builtin::<Crossword>(),
+        builtin::<Flashcard>(),
+        builtin::<Slide>(),
+        builtin::<WorkspaceCrates>(),
+        builtin::<CargoFeatures>(),
+        builtin::<ErrorCatalog>(),
+        builtin::<CliHelp>(),
+        builtin::<Options>(),
+        builtin::<ConfigSchema>(),
+        builtin::<Envvars>(),
+        builtin::<Http>(),
+        builtin::<Manifest>(),
+    ];
+    #[cfg(feature = "go")]
+    builtins.push(builtin::<shortcodes::Go>());
+    #[cfg(feature = "xiangqi")]
+    builtins.push(builtin::<shortcodes::Xiangqi>());
+    #[cfg(feature = "proto")]
+    builtins.push(builtin::<shortcodes::Proto>());
+    builtins
+}
 
-    result = Columns::process_raw(&result)?;
-    result = Hint::process_raw(&result)?;
-    result = Tabs::process_raw(&result)?;
+/// Returns the keys of the `[preprocessor.shortcodes]` table that toggle shortcodes which don't
+/// exist, enabled or not.
+fn unknown_toggles<'c>(config: &'c Config, custom: &[Rc<dyn CustomShortcode>]) -> Vec<&'c str> {
+    let names: Vec<&str> = builtins()
+        .iter()
+        .map(|h| h.name())
+        .chain(custom.iter().map(|s| s.name()))
+        .collect();
+    config.unknown_toggles(&names)
+}
 
-    Ok(result)
+/// Returns the enabled shortcodes, in the order their headers are added to chapters.
+fn handlers<'a>(config: &Config, custom: &'a [Rc<dyn CustomShortcode>]) -> Vec<Handler<'a>> {
+    builtins()
+        .into_iter()
+        .chain(
+            custom
+                .iter()
+                .map(|shortcode| Handler::Custom(shortcode.as_ref())),
+        )
+        .filter(|handler| config.is_enabled(handler.name()))
+        .collect()
+}
+
+fn process_chapter(content: &str, ctx: &mut Context) -> Result<String> {
+    ctx.headers.clear();
+    // The buffer is reused between chapters, rather than reallocated for each.
+    ctx.source.clear();
+    ctx.source.push_str(content);
+    ctx.source_map = ctx
+        .config
+        .source_map_file
+        .as_ref()
+        .map(|_| SourceMap::default());
+
+    let handlers = handlers(ctx.config, ctx.custom);
+    let mut result = expand(content, &handlers, ctx, 0)?;
+
+    if ctx.config.analytics
+        && ctx.is_html()
+        && !ctx.is_epub()
+        && result.contains("data-analytics-event")
+    {
+        result.push_str(&analytics::dispatcher(ctx.config));
+    }
+
+    let headers = match ctx.config.css_mode {
+        _ if ctx.headers.is_empty() => String::new(),
+        // An EPUB only contains the chapters, so each one includes its own styles.
+        _ if ctx.is_epub() => inline_headers(ctx),
+        CssMode::Inline => inline_headers(ctx),
+        CssMode::Hoist => {
+            for header in &ctx.headers {
+                if !ctx.hoisted_headers.contains(header) {
+                    ctx.hoisted_headers.push(header);
+                }
+            }
+            if ctx.stylesheet_linked {
+                return Ok(result);
+            }
+            format!(
+                "\n<link rel=\"stylesheet\" href=\"{}{}/{}\">\n",
+                ctx.path_to_root(),
+                ctx.config.assets_dir,
+                HOISTED_CSS
+            )
+        }
+    };
+    Ok(insert_headers(
+        result,
+        &headers,
+        ctx.config.header_position,
+        &ctx.config.delimiters,
+        ctx.source_map.as_mut(),
+    ))
+}
+
+/// Removes every marked header but the first with each key. Chapters are only ever viewed on
+/// their own or concatenated on the print page, where this leaves a single copy of each style.
+const DEDUPE_HEADERS: &str = "document.querySelectorAll(\"style[data-mdbook-shortcodes-header]\")\
+    .forEach(function (style) {\
+    var key = style.getAttribute(\"data-mdbook-shortcodes-header\");\
+    if (document.querySelector(\"style[data-mdbook-shortcodes-header='\" + key + \"']\") !== style) style.remove();\
+    });";
+
+/// Returns the current chapter's headers, each marked with its index in the book so that the
+/// print page only loads it once.
+fn inline_headers(ctx: &mut Context) -> String {
+    let mut result = String::new();
+    let mut repeated = false;
+    let theme = theme::css(&ctx.config.theme);
+    if !theme.is_empty() {
+        result.push_str(&format!(
+            "\n<style data-mdbook-shortcodes-header=\"theme\">\n{}</style>\n",
+            theme
+        ));
+        // The theme is added to every chapter with headers.
+        repeated = !ctx.book_headers.is_empty();
+    }
+    for header in &ctx.headers {
+        let key = match ctx.book_headers.iter().position(|h| h == header) {
+            Some(key) => {
+                repeated = true;
+                key
+            }
+            None => {
+                ctx.book_headers.push(header);
+                ctx.book_headers.len() - 1
+            }
+        };
+        result.push_str(&header.replacen(
+            "<style>",
+            &format!("<style data-mdbook-shortcodes-header=\"{}\">", key),
+            1,
+        ));
+    }
+    // Only headers used by an earlier chapter can be duplicated on the print page, which an
+    // EPUB doesn't have.
+    if repeated && !ctx.is_epub() {
+        result.push_str(&format!(
+            "\n{}{}</script>\n",
+            script_open_tag(false, ctx.config),
+            DEDUPE_HEADERS
+        ));
+    }
+    result
+}
+
+/// The stylesheet, in the assets directory, that headers are hoisted into.
+const HOISTED_CSS: &str = "shortcodes.css";
+
+/// Returns whether `path` is one of the stylesheets that the HTML renderer adds to every page,
+/// which are given relative to the book's root directory.
+fn is_additional_css(ctx: &PreprocessorContext, path: &Path) -> bool {
+    ctx.config.html_config().is_some_and(|html| {
+        html.additional_css
+            .iter()
+            .any(|css| ctx.root.join(css) == path)
+    })
+}
+
+/// Returns the stylesheet containing every hoisted header.
+fn hoisted_css(headers: &[&str]) -> String {
+    headers
+        .iter()
+        .flat_map(|header| header.lines())
+        .filter(|line| !matches!(line.trim(), "" | "<style>" | "</style>"))
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+/// Inserts the headers of the shortcodes used in a chapter. A `{{#shortcode-assets /}}`
+/// placeholder, written with `delimiters`, overrides the `position`.
+///
+/// The headers are inserted into `content` in place, as chapters can be large, and recorded in
+/// its source `map`.
+fn insert_headers(
+    mut content: String,
+    headers: &str,
+    position: HeaderPosition,
+    delimiters: &Delimiters,
+    mut map: Option<&mut SourceMap>,
+) -> String {
+    let placeholder = format!("{}#shortcode-assets /{}", delimiters.open, delimiters.close);
+    if content.contains(&placeholder) {
+        replace_all(&mut content, &placeholder, headers, 1, map.as_deref_mut());
+        replace_all(&mut content, &placeholder, "", usize::MAX, map);
+        return content;
+    }
+    let offset = match position {
+        HeaderPosition::Top => 0,
+        HeaderPosition::AfterHeading => heading_end(&content).unwrap_or(0),
+        HeaderPosition::Bottom => content.len(),
+    };
+    content.insert_str(offset, headers);
+    if let Some(map) = map {
+        map.replace(offset..offset, headers.len());
+    }
+    content
+}
+
+/// Replaces the first `count` occurrences of `from` in `content` with `to` in place, recording
+/// the replacements in its source `map`.
+fn replace_all(
+    content: &mut String,
+    from: &str,
+    to: &str,
+    count: usize,
+    mut map: Option<&mut SourceMap>,
+) {
+    let mut start = 0;
+    for _ in 0..count {
+        let index = match content[start..].find(from) {
+            Some(i) => start + i,
+            None => break,
+        };
+        content.replace_range(index..index + from.len(), to);
+        if let Some(map) = map.as_deref_mut() {
+            map.replace(index..index + from.len(), to.len());
+        }
+        start = index + to.len();
+    }
+}
+
+/// Returns the index of the end of the first line of `content` that's a heading.
+fn heading_end(content: &str) -> Option<usize> {
+    let mut offset = 0;
+    // Comments in code blocks (e.g. `# install`) aren't headings.
+    let mut in_code = false;
+    for line in content.split_inclusive('\n') {
+        offset += line.len();
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+        }
+        if in_code {
+            continue;
+        }
+        let hashes = line.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&hashes) && line[hashes..].starts_with([' ', '\t', '\n']) {
+            return Some(offset);
+        }
+    }
+    None
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+    };
+
+    /// Counts the bytes allocated by each thread, so that tests can check how much memory is
+    /// used while they run in parallel.
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+    }
+
+    fn count(size: usize) {
+        // The count can't be reached while the thread is being torn down.
+        let _ = ALLOCATED.try_with(|allocated| allocated.set(allocated.get() + size));
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            count(layout.size());
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            count(new_size);
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    /// Returns the number of bytes allocated by the current thread.
+    fn allocated() -> usize {
+        ALLOCATED.with(Cell::get)
+    }
 
     #[test]
     fn test_columns() {
@@ -336,13 +1656,24 @@ Column 2
 ";
         let expected = "
 <style>
+    /* Readers without flexbox (e.g. some EPUB readers) lay the columns out as a table. */
     .mdbook-shortcodes-columns-container {
+        display: table;
+        table-layout: fixed;
+        width: 100%;
         display: flex;
-        margin: 0 -1em;
+        margin: 0 calc(-1 * var(--mdbook-shortcodes-column-padding, 1em));
     }
     .mdbook-shortcodes-column {
+        display: table-cell;
         flex: 50%;
-        padding: 0 1em;
+        padding: 0 var(--mdbook-shortcodes-column-padding, 1em);
+    }
+    .mdbook-shortcodes-columns-flow {
+        column-gap: var(--mdbook-shortcodes-columns-gap, 2em);
+    }
+    .mdbook-shortcodes-column-break {
+        break-before: column;
     }
 </style>
 
@@ -357,46 +1688,818 @@ Column 2
 
 </div></div>
 ";
-        assert_eq!(Columns::process_raw(input), Ok(expected.to_owned()));
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let result = Columns::process_raw(input, &mut ctx).unwrap();
+        assert_eq!(
+            insert_headers(
+                result,
+                &ctx.headers.concat(),
+                HeaderPosition::Top,
+                &Delimiters::default(),
+                None
+            ),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_hint() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let expected = "<div class=\"mdbook-shortcodes-hint mdbook-shortcodes-hint-warning\">\
+            \n\nText\n\n</div>";
+        assert_eq!(
+            Hint::process_raw("{{#hint warning}}Text{{/hint}}", &mut ctx),
+            Ok(expected.to_owned())
+        );
+        assert_eq!(
+            Hint::process_raw("{{#hint type=\"warning\"}}Text{{/hint}}", &mut ctx),
+            Ok(expected.to_owned())
+        );
+        assert_eq!(
+            Hint::process_raw(
+                "{{#hint type=ok title=\"Use `unsafe` *carefully* & quickly\"}}Text{{/hint}}",
+                &mut ctx
+            ),
+            Ok(
+                "<div class=\"mdbook-shortcodes-hint mdbook-shortcodes-hint-ok\">\
+                <p class=\"mdbook-shortcodes-hint-title\"><strong>Use <code>unsafe</code> \
+                <em>carefully</em> &amp; quickly</strong></p>\
+                \n\nText\n\n</div>"
+                    .to_owned()
+            )
+        );
+        assert!(Hint::process_raw("{{#hint info type=ok}}Text{{/hint}}", &mut ctx).is_err());
+        assert!(Hint::process_raw("{{#hint info kind=ok}}Text{{/hint}}", &mut ctx).is_err());
     }
 
     #[test]
-    fn test_split_attributes() {
-        fn whitespace_variants(base: &str) -> Vec<String> {
-            let mut result = vec![base.to_owned()];
+    fn test_flow_columns() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        assert_eq!(
+            Columns::process_raw(
+                "{{#columns flow count=3 1em}}A{{#column}}B{{/columns}}",
+                &mut ctx
+            ),
+            Ok("<div class=\"mdbook-shortcodes-columns-flow\" \
+                style=\"column-count: 3; column-gap: 1em;\">\n\nA\n\n\
+                <div class=\"mdbook-shortcodes-column-break\"></div>\n\nB\n\n</div>"
+                .to_owned())
+        );
+        assert_eq!(
+            Columns::process_raw("{{#columns flow}}A{{/columns}}", &mut ctx),
+            Ok(
+                "<div class=\"mdbook-shortcodes-columns-flow\" style=\"column-count: 2;\">\
+                \n\nA\n\n</div>"
+                    .to_owned()
+            )
+        );
+        assert!(Columns::process_raw("{{#columns flow count=0}}A{{/columns}}", &mut ctx).is_err());
+        assert!(Columns::process_raw("{{#columns count=2}}A{{/columns}}", &mut ctx).is_err());
+    }
 
-            for w in [" ", "  "] {
-                let mut temp = w.to_owned();
-                temp.push_str(base);
-                result.push(temp);
+    #[test]
+    fn test_hoist_headers() {
+        let config = Config {
+            css_mode: CssMode::Hoist,
+            ..Config::default()
+        };
+        let mut ctx = Context::test(&config);
+        ctx.start_chapter(Some(std::path::Path::new("guide/intro.md")));
+        let result = process_chapter("{{#columns}}A{{/columns}}", &mut ctx).unwrap();
+        assert!(result.starts_with(
+            "\n<link rel=\"stylesheet\" href=\"../shortcodes-assets/shortcodes.css\">\n"
+        ));
+        assert!(!result.contains("<style>"));
+
+        let hoisted = ctx.hoisted_headers.len();
+        let result = process_chapter("Another chapter", &mut ctx).unwrap();
+        assert_eq!(result, "Another chapter");
+        assert_eq!(ctx.hoisted_headers.len(), hoisted);
+        let css = hoisted_css(&ctx.hoisted_headers);
+        assert!(css.contains(".mdbook-shortcodes-columns-container {"));
+        assert!(!css.contains(".mdbook-shortcodes-hint {"));
+        assert!(!css.contains("<style>"));
+    }
 
-                let mut temp = base.to_owned();
-                temp.push_str(w);
-                result.push(temp);
+    #[test]
+    fn test_additional_css() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = [
+            ("css-mode", serde_json::json!("hoist")),
+            (
+                "output.html.additional-css",
+                serde_json::json!(["src/shortcodes-assets/shortcodes.css"]),
+            ),
+        ];
+        let book = build(
+            dir.path(),
+            &options,
+            &[("a.md", "{{#hint info}}A{{/hint}}")],
+        );
+        match &book.sections[0] {
+            BookItem::Chapter(chapter) => {
+                assert!(chapter
+                    .content
+                    .starts_with("<div class=\"mdbook-shortcodes-hint"));
             }
+            _ => unreachable!(),
+        }
+        let css = dir.path().join("src/shortcodes-assets/shortcodes.css");
+        assert!(std::fs::read_to_string(css)
+            .unwrap()
+            .contains(".mdbook-shortcodes-hint {"));
+    }
+
+    #[test]
+    fn test_inline_headers() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let input = "{{#hint info}}A{{/hint}} {{#hint ok}}B{{/hint}}";
+        let first = process_chapter(input, &mut ctx).unwrap();
+        assert_eq!(first.matches("<style").count(), 1);
+        assert!(first.contains("<style data-mdbook-shortcodes-header=\"0\">"));
+        assert!(!first.contains("<script>"));
+
+        ctx.start_chapter(Some(std::path::Path::new("guide/intro.md")));
+        let second = process_chapter(input, &mut ctx).unwrap();
+        assert!(second.contains("<style data-mdbook-shortcodes-header=\"0\">"));
+        assert!(second.contains(DEDUPE_HEADERS));
+    }
+
+    #[test]
+    fn test_theme_headers() {
+        let config = Config {
+            theme: [("hint-info-color".to_owned(), "#88f".to_owned())].into(),
+            ..Config::default()
+        };
+        let mut ctx = Context::test(&config);
+        let result = process_chapter("{{#hint info}}A{{/hint}}", &mut ctx).unwrap();
+        assert!(result.starts_with(
+            "\n<style data-mdbook-shortcodes-header=\"theme\">\n:root {\n    \
+            --mdbook-shortcodes-hint-info-color: #88f;\n}\n</style>\n"
+        ));
+        assert_eq!(process_chapter("Text", &mut ctx).unwrap(), "Text");
+
+        ctx.start_chapter(Some(std::path::Path::new("b.md")));
+        let result = process_chapter("{{#hint info}}A{{/hint}}", &mut ctx).unwrap();
+        assert!(result.contains(DEDUPE_HEADERS));
+    }
+
+    #[test]
+    fn test_unused_headers() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let result = process_chapter("{{#kbd Ctrl /}} `{{#hint info}}`", &mut ctx).unwrap();
+        assert!(!result.contains("<style"));
+        assert!(ctx.headers.is_empty());
+    }
+
+    #[test]
+    fn test_plain_markdown() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        ctx.renderer = "markdown".to_owned();
+        let input = "{{#columns}}{{#hint warning}}Careful{{/hint}}{{#column}}\
+            {{#hint info title=\"Did you know?\"}}A\nB{{/hint}}{{/columns}}{{#spacer /}}\
+            {{#kbd Ctrl /}}";
+        assert_eq!(
+            process_chapter(input, &mut ctx),
+            Ok("\n\n\n\n> **Warning**\n>\n> Careful\n\n\n\n\n\n> **Did you know?**\n>\n> A\n> B\n\n\n\n\
+                <kbd class=\"mdbook-shortcodes-kbd\"><kbd>Ctrl</kbd></kbd>"
+                .to_owned())
+        );
+        assert!(ctx.headers.is_empty());
+        assert!(process_chapter("{{#hint tip}}A{{/hint}}", &mut ctx).is_err());
+    }
+
+    #[test]
+    fn test_epub() {
+        let config = Config {
+            css_mode: CssMode::Hoist,
+            search_exclude: vec!["hint".to_owned()],
+            ..Config::default()
+        };
+        let mut ctx = Context::test(&config);
+        ctx.renderer = "epub".to_owned();
+        let input = "{{#hint info}}A{{/hint}}{{#script}}f();{{/script}}";
+        let result = process_chapter(input, &mut ctx).unwrap();
+        assert!(!result.contains("<script"));
+        assert!(!result.contains("<link"));
+        assert!(result.contains("<style data-mdbook-shortcodes-header=\"0\">"));
+        assert!(result.ends_with(
+            "<div class=\"mdbook-shortcodes-hint mdbook-shortcodes-hint-info\">\n\nA\n\n</div>"
+        ));
+    }
+
+    /// Creates a book in `dir` with the given chapters, which are nested by their depth in the
+    /// list, and runs the preprocessor on it.
+    fn build(dir: &Path, options: &[(&str, serde_json::Value)], chapters: &[(&str, &str)]) -> Book {
+        let mut summary = String::from("# Summary\n\n");
+        for (i, (path, content)) in chapters.iter().enumerate() {
+            summary.push_str(&format!("{}- [{}]({})\n", "  ".repeat(i), path, path));
+            let path = dir.join("src").join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        std::fs::write(dir.join("src/SUMMARY.md"), summary).unwrap();
+        std::fs::write(dir.join("book.toml"), "[book]\ntitle = \"Test\"\n").unwrap();
+
+        let mut md = mdbook::MDBook::load(dir).unwrap();
+        // Setting the options in `book.toml` would make mdBook run the preprocessor as a command.
+        // Keys containing a dot are set as they are (e.g. `output.html.mathjax-support`).
+        for (key, value) in options {
+            let key = if key.contains('.') {
+                (*key).to_owned()
+            } else {
+                format!("preprocessor.shortcodes.{}", key)
+            };
+            md.config.set(key, value).unwrap();
+        }
+        md.with_preprocessor(ShortcodesProcessor::new());
+        md.preprocess_book(&mdbook::renderer::HtmlHandlebars::new())
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn test_toggles() {
+        let mut book_config = mdbook::Config::default();
+        for (key, value) in [
+            ("offline", true),
+            ("fail-on-error", false),
+            ("kbd", false),
+            ("admonition", false),
+            ("colums", false),
+        ] {
+            book_config
+                .set(format!("preprocessor.shortcodes.{}", key), value)
+                .unwrap();
+        }
+        let config = Config::from_book_config(&book_config).unwrap();
+        // Options aren't toggles, and aliases toggle the shortcode they are an alias of.
+        assert_eq!(
+            config.shortcodes.keys().collect::<Vec<_>>(),
+            ["colums", "hint", "kbd"]
+        );
+        assert!(config.offline);
+        assert!(!config.is_enabled("hint"));
+        assert!(config.is_enabled("columns"));
+        assert_eq!(unknown_toggles(&config, &[]), ["colums"]);
+    }
+
+    #[test]
+    fn test_delimiters() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = [(
+            "delimiters",
+            serde_json::json!({ "open": "[[", "close": "]]" }),
+        )];
+        let content = "[[#columns]]A[[#column]][[#kbd Ctrl /]][[/columns]] {{#kbd Alt /}}";
+        let book = build(dir.path(), &options, &[("intro.md", content)]);
+        let chapter = match &book.sections[0] {
+            BookItem::Chapter(chapter) => chapter,
+            _ => unreachable!(),
+        };
+        assert!(chapter.content.contains("<kbd>Ctrl</kbd>"));
+        assert_eq!(
+            chapter
+                .content
+                .matches("mdbook-shortcodes-column\"")
+                .count(),
+            2
+        );
+        assert!(chapter.content.ends_with(" {{#kbd Alt /}}"));
+    }
+
+    #[test]
+    fn test_skip_chapters() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = [("skip-chapters", serde_json::json!(["appendix"]))];
+        let book = build(
+            dir.path(),
+            &options,
+            &[
+                ("intro.md", "{{#kbd Ctrl /}}"),
+                ("literal.md", "<!-- shortcodes: off -->\n{{#kbd Ctrl /}}"),
+                ("appendix/a.md", "{{#kbd Ctrl /}}"),
+            ],
+        );
+        let contents: Vec<_> = book
+            .iter()
+            .filter_map(|item| match item {
+                BookItem::Chapter(chapter) => Some(chapter.content.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert!(contents[0].contains("<kbd>Ctrl</kbd>"));
+        assert!(contents[1..]
+            .iter()
+            .all(|content| content.ends_with("{{#kbd Ctrl /}}")));
+    }
+
+    #[test]
+    fn test_fail_on_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "{{#hint nope}}A{{/hint}} {{#kbd Ctrl /}} {{#flashcard}}A{{/flashcard}}";
+        let options = [("fail-on-error", serde_json::json!(false))];
+        let book = build(dir.path(), &options, &[("intro.md", content)]);
+        let chapter = match &book.sections[0] {
+            BookItem::Chapter(chapter) => chapter,
+            _ => unreachable!(),
+        };
+        assert!(chapter.content.contains("{{#hint nope}}A{{/hint}} <kbd"));
+
+        // The shortcodes around an unclosed one are still expanded.
+        let config = Config {
+            fail_on_error: false,
+            source_map_file: Some("source-map.json".to_owned()),
+            ..Config::default()
+        };
+        let mut ctx = Context::test(&config);
+        let content = "{{#kbd A /}} {{#hint info}} \\{{#kbd}} {{#kbd B /}}";
+        let result = process_chapter(content, &mut ctx).unwrap();
+        assert!(result.ends_with(
+            "<kbd>A</kbd></kbd> {{#hint info}} {{#kbd}} \
+            <kbd class=\"mdbook-shortcodes-kbd\"><kbd>B</kbd></kbd>"
+        ));
+        let map = ctx.source_map.unwrap();
+        let offset = result.find("{{#hint").unwrap();
+        assert_eq!(map.input_offset(offset + 2), content.find("#hint"));
+
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        assert!(Hint::process_raw("{{#hint nope}}A{{/hint}}", &mut ctx).is_err());
+    }
+
+    #[test]
+    fn test_inline_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            errors: ErrorDisplay::Inline,
+            ..Config::default()
+        };
+        let mut ctx = Context::new(&config, dir.path().to_owned(), dir.path().join("src"));
+        let content =
+            "{{#hint nope}}A{{/hint}}\n\n{{#snippet path=a.rs /}} {{#kbd B /}} {{#hint info}}";
+        let result = process_chapter(content, &mut ctx).unwrap();
+        assert!(result.contains(".mdbook-shortcodes-error {"));
+        assert_eq!(
+            result.matches("class=\"mdbook-shortcodes-error\"").count(),
+            3
+        );
+        assert!(result.contains("<code>&#123;&#123;#hint nope}}</code>"));
+        assert!(result.contains("snippet shortcode: couldn&#39;t read `a.rs`"));
+        assert!(result.contains("<kbd>B</kbd>"));
+        assert!(result.contains("<code>&#123;&#123;#hint info}}</code>"));
+        assert!(!config.fails_on_error());
+
+        // Renderers that don't support HTML are given the shortcode as it was written.
+        ctx.renderer = "markdown".to_owned();
+        let result = process_chapter(content, &mut ctx).unwrap();
+        assert!(result.starts_with("{{#hint nope}}A{{/hint}}"));
+        assert!(!result.contains("mdbook-shortcodes-error"));
+    }
+
+    #[test]
+    fn test_tabs() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        assert_eq!(
+            Tabs::process_raw("\n{{#tabs}}A{{/tabs}}", &mut ctx)
+                .unwrap_err()
+                .to_string(),
+            ":2:1: tabs shortcode: tabs shortcode is not implemented yet"
+        );
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let config = Config {
+            allow_exec: true,
+            capabilities: Some(vec![Capability::Filesystem]),
+            ..Config::default()
+        };
+        let mut ctx = Context::test(&config);
+        let result = CmdOutput::process_raw("{{#cmd-output \"echo hi\" /}}", &mut ctx);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            ":1:1: cmd-output shortcode: needs the `exec` capability, which the `capabilities` \
+            option doesn't allow"
+        );
+        assert!(config.allows(Capability::Pure));
+        assert!(config.allows(Capability::Filesystem));
+        assert!(!config.allows(Capability::Network));
+        assert!(Config::default().allows(Capability::Exec));
+    }
+
+    #[test]
+    fn test_timings() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = [
+            ("stats-file", serde_json::json!("stats.json")),
+            ("time-budget-ms", serde_json::json!(0)),
+        ];
+        let content = "{{#kbd Ctrl /}} {{#hint info}}{{#kbd Alt /}}{{/hint}}";
+        build(dir.path(), &options, &[("intro.md", content)]);
+        let stats = std::fs::read_to_string(dir.path().join("stats.json")).unwrap();
+        let stats: serde_json::Value = serde_json::from_str(&stats).unwrap();
+        assert_eq!(stats["chapters"], 1);
+        let timings = &stats["timings"];
+        assert_eq!(timings["shortcodes"]["kbd"]["uses"], 2);
+        assert_eq!(timings["shortcodes"]["hint"]["uses"], 1);
+        assert!(timings["chapters"]["intro.md"].is_f64());
+    }
 
-            result
+    #[test]
+    fn test_aliases() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = [(
+            "aliases",
+            serde_json::json!({ "key": "kbd", "hint": "kbd" }),
+        )];
+        let content = "{{#callout info}}A{{/callout}} {{#key Ctrl /}} {{#hint ok}}B{{/hint}}";
+        let book = build(dir.path(), &options, &[("intro.md", content)]);
+        let chapter = match &book.sections[0] {
+            BookItem::Chapter(chapter) => chapter,
+            _ => unreachable!(),
+        };
+        assert!(chapter
+            .content
+            .contains("mdbook-shortcodes-hint mdbook-shortcodes-hint-info\""));
+        assert!(chapter.content.contains("<kbd>Ctrl</kbd>"));
+        // An alias doesn't replace a shortcode with the same name.
+        assert!(chapter
+            .content
+            .contains("mdbook-shortcodes-hint mdbook-shortcodes-hint-ok\""));
+    }
+
+    #[test]
+    fn test_nested_chapters() {
+        let dir = tempfile::tempdir().unwrap();
+        let book = build(
+            dir.path(),
+            &[],
+            &[
+                ("intro.md", "{{#kbd Ctrl /}}"),
+                ("guide/setup.md", "{{#kbd Ctrl /}}"),
+                ("guide/linux.md", "{{#kbd Ctrl /}}"),
+            ],
+        );
+        let chapters: Vec<_> = book
+            .iter()
+            .filter_map(|item| match item {
+                BookItem::Chapter(chapter) => Some(chapter),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(chapters.len(), 3);
+        assert_eq!(chapters[2].parent_names, ["intro.md", "guide/setup.md"]);
+        for chapter in chapters {
+            assert!(
+                chapter.content.contains("<kbd>Ctrl</kbd>"),
+                "{}",
+                chapter.name
+            );
         }
+    }
+
+    #[test]
+    fn test_disabled_shortcodes() {
+        let chapter = [(
+            "intro.md",
+            "{{#kbd Ctrl /}}\n{{#hint info}}\nText\n{{/hint}}",
+        )];
+        let content = |book: Book| match &book.sections[0] {
+            BookItem::Chapter(chapter) => chapter.content.clone(),
+            _ => unreachable!(),
+        };
 
-        let cases: Vec<(&str, Result<Vec<&str>>)> = vec![
-            ("", Ok(Vec::new())),
-            ("my name is john", Ok(vec!["my", "name", "is", "john"])),
-            ("c", Ok(vec!["c"])),
-            ("c a", Ok(vec!["c", "a"])),
-            ("\"d\" \"q\"", Ok(vec!["d", "q"])),
-            ("\"s\" \"q\"", Ok(vec!["s", "q"])),
+        let dir = tempfile::tempdir().unwrap();
+        let result = content(build(
+            dir.path(),
+            &[("kbd", serde_json::Value::Bool(false))],
+            &chapter,
+        ));
+        assert!(result.contains("{{#kbd Ctrl /}}"));
+        assert!(result.contains("mdbook-shortcodes-hint mdbook-shortcodes-hint-info\""));
+
+        let dir = tempfile::tempdir().unwrap();
+        let result = content(build(
+            dir.path(),
+            &[("enabled", serde_json::json!(["kbd"]))],
+            &chapter,
+        ));
+        assert!(result.contains("<kbd>Ctrl</kbd>"));
+        assert!(result.contains("{{#hint info}}"));
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let chapters = [
+            (
+                "intro.md",
+                "# Intro\n{{#hint info}}\n## Note\n{{/hint}}\n\
+                {{#crossword}}\nCargo :: Package manager\nRust :: Language\nTOML :: Format\n{{/crossword}}\n\
+                {{#options}}\n--verbose, -v :: Be verbose.\n{{/options}}\n",
+            ),
             (
-                "\"Multiple words in quotes\" foo 'bar'",
-                Ok(vec!["Multiple words in quotes", "foo", "bar"]),
+                "guide/setup.md",
+                "{{#envvars}}\nRUST_LOG :: info :: The log level.\n{{/envvars}}\n\
+                {{#flashcard}}\nQuestion\n---\nAnswer\n{{/flashcard}}\n\
+                {{#options}}\n--verbose :: Be verbose.\n{{/options}}\n{{#kbd Ctrl+C /}}",
             ),
-            ("\"Unterminated string", Err(Error::UnterminatedString)),
-            ("Unterminated string\"", Err(Error::UnterminatedString)),
         ];
+        let options = [("export-flashcards", serde_json::Value::Bool(true))];
+        // Building in different directories also checks that absolute paths don't leak into
+        // the output.
+        let (first, second) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let a = build(first.path(), &options, &chapters);
+        let b = build(second.path(), &options, &chapters);
+        assert_eq!(
+            serde_json::to_string(&a).unwrap(),
+            serde_json::to_string(&b).unwrap()
+        );
+        let deck = |dir: &Path| std::fs::read(dir.join("src/shortcodes-assets/flashcards.tsv"));
+        assert_eq!(deck(first.path()).unwrap(), deck(second.path()).unwrap());
+    }
+
+    struct Greet;
+
+    impl CustomShortcode for Greet {
+        fn name(&self) -> &str {
+            "greet"
+        }
+
+        fn header(&self) -> &'static str {
+            "<style>.greet { color: red; }</style>\n"
+        }
+
+        fn process(
+            &self,
+            input: &str,
+            attrs: &Attrs,
+            ctx: &mut Context,
+        ) -> std::result::Result<String, String> {
+            let id = ctx.ids().generate(self.name());
+            let name = attrs.get("name").unwrap_or("world");
+            Ok(format!(
+                "<span id=\"{}\" class=\"greet\">Hello, {}{}</span>",
+                id, name, input
+            ))
+        }
+    }
+
+    #[test]
+    fn test_register() {
+        let processor = ShortcodesProcessor::new().register(Greet);
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        ctx.custom = &processor.custom;
+        let result = process_chapter(
+            "{{#greet name=\"Ferris\" /}} {{#greet}}!{{/greet}}",
+            &mut ctx,
+        )
+        .unwrap();
+        assert!(result.contains("\">.greet { color: red; }</style>"));
+        assert!(result.ends_with(
+            "<span id=\"mdbook-shortcodes-greet-0\" class=\"greet\">Hello, Ferris</span> \
+            <span id=\"mdbook-shortcodes-greet-1\" class=\"greet\">Hello, world!</span>"
+        ));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_register_twice() {
+        ShortcodesProcessor::new().register(Greet).register(Greet);
+    }
+
+    #[test]
+    fn test_invalid_shortcode() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        ctx.start_chapter(Some(std::path::Path::new("guide/intro.md")));
+        let input = "# Intro\n\n{{#columns}}\nA\n{{/columns}}\n\nSee: {{#hint tip}}\nB\n{{/hint}}";
+        let error = process_chapter(input, &mut ctx).unwrap_err();
+        assert_eq!(
+            error,
+            Error::InvalidShortcode {
+                name: "hint".to_owned(),
+                chapter: PathBuf::from("guide/intro.md"),
+                line: 7,
+                column: 6,
+                message: "unknown hint type `tip`".to_owned(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "guide/intro.md:7:6: hint shortcode: unknown hint type `tip`"
+        );
+
+        let error = process_chapter("A\n  {{#hint info}}\nB", &mut ctx).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "guide/intro.md:2:3: hint shortcode: an opening shortcode had no matching closing \
+            shortcode"
+        );
+        let error = process_chapter("{{#kbd \"Ctrl /}}", &mut ctx).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("guide/intro.md:1:1: kbd shortcode: a string"));
+
+        // Errors recorded by shortcodes are located too.
+        process_chapter("A\n{{#flashcard}}B{{/flashcard}}", &mut ctx).unwrap();
+        assert!(ctx.errors[0].starts_with("guide/intro.md:2:1: flashcard shortcode: "));
+
+        // Shortcodes are located at the tag that was used, rather than an identical one before
+        // it, and so are the warnings about them (which are logged at `ctx.position`).
+        let card = "{{#flashcard}}\nQ\n---\nA\n{{/flashcard}}\n\n";
+        let input = format!(
+            "{}{}  {{{{#flashcard}}}}No back{{{{/flashcard}}}}",
+            card, card
+        );
+        process_chapter(&input, &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 2);
+        assert!(ctx.errors[1].starts_with("guide/intro.md:13:3: flashcard shortcode: "));
+        assert_eq!(ctx.position, Some((13, 3)));
+
+        // Shortcodes output by others are located at the shortcode they came from.
+        let definition = "{{#define-snippet tip}}{{#hint tip}}B{{/hint}}{{/define-snippet}}";
+        DefineSnippet::process_raw(definition, &mut ctx).unwrap();
+        let error = process_chapter("A\n\n  {{#use-snippet tip /}}", &mut ctx).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "guide/intro.md:3:3: hint shortcode: unknown hint type `tip`"
+        );
+    }
+
+    #[test]
+    fn test_nested_shortcodes() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let input = "{{#columns}}
+{{#hint info}}
+Outer
+{{#hint warning}}
+Inner
+{{/hint}}
+{{/hint}}
+{{#column}}
+{{#columns}}
+A
+{{#column}}
+B
+{{/columns}}
+{{/columns}}";
+        let result = process_chapter(input, &mut ctx).unwrap();
+        let body = &result[result.find("<div").unwrap()..];
+        assert!(!body.contains("{{"));
+        assert!(body.contains(
+            "<div class=\"mdbook-shortcodes-hint mdbook-shortcodes-hint-info\">\n\nOuter\n\
+            <div class=\"mdbook-shortcodes-hint mdbook-shortcodes-hint-warning\">\n\nInner\n\n\
+            </div>\n\n</div>"
+        ));
+        assert_eq!(
+            body.matches("mdbook-shortcodes-columns-container").count(),
+            2
+        );
+        assert_eq!(
+            body.matches("<div class=\"mdbook-shortcodes-column\" >")
+                .count(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_expand_output() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let definitions = "{{#define-snippet money}}{{#money 5 USD /}}{{/define-snippet}}\
+            {{#define-snippet loop}}{{#use-snippet loop /}}{{/define-snippet}}";
+        DefineSnippet::process_raw(definitions, &mut ctx).unwrap();
+
+        let result = process_chapter("Costs {{#use-snippet money /}}.", &mut ctx).unwrap();
+        assert!(result.ends_with("Costs $5.00."));
+
+        let error = process_chapter("{{#use-snippet loop /}}", &mut ctx).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::InvalidShortcode { name, line: 1, column: 1, .. } if name == "use-snippet"
+        ));
+    }
+
+    #[test]
+    fn test_escaped_shortcodes() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let input = "Write \\{{#kbd Ctrl /}}.\n\n\
+            {{#hint info}}Close with \\{{/hint}}.{{/hint}}\n\n\
+            \\{{#define-snippet name}}";
+        let result = process_chapter(input, &mut ctx).unwrap();
+        assert!(result.contains("Write {{#kbd Ctrl /}}."));
+        assert!(result.contains("Close with {{/hint}}."));
+        assert!(result.ends_with("{{#define-snippet name}}"));
+        assert!(!result.contains("<kbd>"));
+    }
 
-        for (input, expected) in cases {
-            for i in whitespace_variants(input) {
-                assert_eq!(split_attrs(&i), expected);
+    #[test]
+    fn test_large_chapter() {
+        let mut content = String::from("# Reference\n");
+        while content.len() < 4_000_000 {
+            content.push_str("{{#hint info}}An escaped \\{{#kbd}} tag.{{/hint}}\n\n");
+            for _ in 0..1000 {
+                content.push_str("Press Ctrl+C to copy the text of the chapter.\n\n");
             }
         }
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        // Parsing the markdown to find the code in it takes memory of its own.
+        let before = allocated();
+        parse::parse(&content, &config.delimiters, |_| true).unwrap();
+        let parsing = allocated() - before;
+
+        let before = allocated();
+        let result = process_chapter(&content, &mut ctx).unwrap();
+        let copies = (allocated() - before - parsing) as f64 / content.len() as f64;
+        assert!(result.contains("An escaped {{#kbd}} tag."));
+        // The chapter is copied once, so that errors can be located in it, and the result is
+        // built in a single buffer that the headers are inserted into.
+        assert!(copies < 3.0, "the chapter was copied {:.1} times", copies);
+    }
+
+    #[test]
+    fn test_source_map() {
+        let config = Config {
+            source_map_file: Some("source-map.json".to_owned()),
+            ..Config::default()
+        };
+        let mut ctx = Context::test(&config);
+        let content = "# Title\n{{#hint info}}A{{/hint}}\n\\{{#kbd}} {{#kbd C /}} end\n";
+        let result = process_chapter(content, &mut ctx).unwrap();
+        let map = ctx.source_map.unwrap();
+
+        let offset = |text: &str, pattern: &str| text.find(pattern).unwrap();
+        assert_eq!(map.input_offset(offset(&result, "# Title")), Some(0));
+        assert_eq!(map.input_offset(offset(&result, "<style")), None);
+        assert_eq!(
+            map.input_offset(offset(&result, "\n\nA\n\n")),
+            Some(offset(content, "{{#hint"))
+        );
+        // The escape was removed from the text after the hint, which is mapped as a whole.
+        assert_eq!(
+            map.input_offset(offset(&result, "{{#kbd}}")),
+            Some(offset(content, "\n\\"))
+        );
+        assert_eq!(
+            map.input_offset(offset(&result, " end")),
+            Some(offset(content, " end"))
+        );
+    }
+
+    #[test]
+    fn test_insert_headers() {
+        let content = "```sh\n# Comment\n```\n## Title\nText\n";
+        assert_eq!(
+            insert_headers(
+                content.to_owned(),
+                "<style></style>\n",
+                HeaderPosition::AfterHeading,
+                &Delimiters::default(),
+                None
+            ),
+            "```sh\n# Comment\n```\n## Title\n<style></style>\nText\n"
+        );
+        assert_eq!(
+            insert_headers(
+                "No heading\n".to_owned(),
+                "H",
+                HeaderPosition::AfterHeading,
+                &Delimiters::default(),
+                None
+            ),
+            "HNo heading\n"
+        );
+        assert_eq!(
+            insert_headers(
+                content.to_owned(),
+                "H",
+                HeaderPosition::Bottom,
+                &Delimiters::default(),
+                None
+            ),
+            "```sh\n# Comment\n```\n## Title\nText\nH"
+        );
+        assert_eq!(
+            insert_headers(
+                "A{{#shortcode-assets /}}B{{#shortcode-assets /}}".to_owned(),
+                "H",
+                HeaderPosition::Top,
+                &Delimiters::default(),
+                None
+            ),
+            "AHB"
+        );
     }
 }
@@ -0,0 +1,104 @@
+/// How numbers are written in a language.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub(crate) struct Locale {
+    pub(crate) decimal_separator: char,
+    pub(crate) group_separator: char,
+    /// Whether currency symbols are written after the amount (e.g. `1.234,50 €`).
+    pub(crate) currency_after: bool,
+}
+
+const ENGLISH: Locale = Locale {
+    decimal_separator: '.',
+    group_separator: ',',
+    currency_after: false,
+};
+
+/// A period for grouping and a comma for decimals, e.g. German.
+const PERIOD_GROUPS: Locale = Locale {
+    decimal_separator: ',',
+    group_separator: '.',
+    currency_after: true,
+};
+
+/// A (narrow no-break) space for grouping and a comma for decimals, e.g. French.
+const SPACE_GROUPS: Locale = Locale {
+    decimal_separator: ',',
+    group_separator: '\u{202f}',
+    currency_after: true,
+};
+
+impl Locale {
+    /// Returns the locale for a language code such as `en`, `de-AT` or `pt_BR`.
+    pub(crate) fn from_language(language: &str) -> Self {
+        let language = language.to_ascii_lowercase().replace('_', "-");
+        match language.as_str() {
+            "de-ch" => Locale {
+                decimal_separator: '.',
+                group_separator: '\'',
+                currency_after: false,
+            },
+            _ => match language.split('-').next().unwrap_or("") {
+                "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" | "ro" | "hr" => {
+                    PERIOD_GROUPS
+                }
+                "fr" | "ru" | "pl" | "sv" | "fi" | "nb" | "no" | "cs" | "sk" | "uk" | "hu" => {
+                    SPACE_GROUPS
+                }
+                _ => ENGLISH,
+            },
+        }
+    }
+
+    /// Formats `value` with exactly `precision` decimal places and grouped thousands.
+    pub(crate) fn format_number(&self, value: f64, precision: usize) -> String {
+        let formatted = format!("{:.*}", precision, value.abs());
+        let (integer, fraction) = match formatted.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (formatted.as_str(), None),
+        };
+
+        let mut result = String::new();
+        // A negative number can round to zero, in which case the sign is omitted.
+        if value < 0.0 && formatted.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            result.push('-');
+        }
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                result.push(self.group_separator);
+            }
+            result.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            result.push(self.decimal_separator);
+            result.push_str(fraction);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(
+            Locale::from_language("en").format_number(1234567.891, 2),
+            "1,234,567.89"
+        );
+        assert_eq!(
+            Locale::from_language("de-AT").format_number(-1234.5, 2),
+            "-1.234,50"
+        );
+        assert_eq!(Locale::from_language("fr").format_number(999.0, 0), "999");
+        assert_eq!(
+            Locale::from_language("fr").format_number(1000.0, 0),
+            "1\u{202f}000"
+        );
+        assert_eq!(
+            Locale::from_language("de_CH").format_number(1000.0, 1),
+            "1'000.0"
+        );
+        assert_eq!(Locale::from_language("en").format_number(-0.001, 2), "0.00");
+    }
+}
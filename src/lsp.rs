@@ -0,0 +1,407 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, Write},
+};
+
+use serde_json::{json, Value};
+
+use crate::{docs::doc, parse, Attrs, Config};
+
+/// A language server for the shortcodes in markdown files, which completes the names and
+/// attributes of shortcodes, describes them on hover and reports malformed uses.
+///
+/// Diagnostics only come from the syntax of shortcodes and their attributes, as expanding them
+/// could run commands on every keystroke.
+pub(crate) struct Server<'a> {
+    config: &'a Config,
+    /// The names shortcodes can be called by, mapped to the shortcodes they call.
+    names: BTreeMap<String, String>,
+    /// The text of the open documents, keyed by URI.
+    documents: BTreeMap<String, String>,
+}
+
+impl<'a> Server<'a> {
+    pub(crate) fn new(config: &'a Config, names: BTreeMap<String, String>) -> Self {
+        Self {
+            config,
+            names,
+            documents: BTreeMap::new(),
+        }
+    }
+
+    /// Answers the messages in `input` until the client asks the server to exit.
+    pub(crate) fn run<R: BufRead, W: Write>(
+        &mut self,
+        mut input: R,
+        mut output: W,
+    ) -> io::Result<()> {
+        while let Some(message) = read_message(&mut input)? {
+            if message["method"] == "exit" {
+                break;
+            }
+            for reply in self.handle(&message) {
+                write_message(&mut output, &reply)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the messages to send in reply to `message`.
+    fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let result = match message["method"].as_str().unwrap_or_default() {
+            "initialize" => json!({
+                "capabilities": {
+                    // The whole document is sent on every change.
+                    "textDocumentSync": 1,
+                    "completionProvider": { "triggerCharacters": ["#", " "] },
+                    "hoverProvider": true,
+                },
+                "serverInfo": { "name": "mdbook-shortcodes" },
+            }),
+            "shutdown" => Value::Null,
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.to_owned(), text.to_owned());
+                return vec![self.publish_diagnostics(uri)];
+            }
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes.and_then(|c| c.last()?["text"].as_str()) {
+                    self.documents.insert(uri.to_owned(), text.to_owned());
+                }
+                return vec![self.publish_diagnostics(uri)];
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                return Vec::new();
+            }
+            "textDocument/completion" => match self.cursor(params) {
+                Some((text, offset)) => Value::Array(self.completions(text, offset)),
+                None => Value::Null,
+            },
+            "textDocument/hover" => match self.cursor(params) {
+                Some((text, offset)) => self.hover(text, offset).unwrap_or_default(),
+                None => Value::Null,
+            },
+            // Other notifications (e.g. `initialized`) don't need a reply.
+            _ if message.get("id").is_none() => return Vec::new(),
+            method => {
+                return vec![json!({
+                    "jsonrpc": "2.0",
+                    "id": message["id"],
+                    "error": { "code": -32601, "message": format!("unknown method `{}`", method) },
+                })]
+            }
+        };
+        vec![json!({ "jsonrpc": "2.0", "id": message["id"], "result": result })]
+    }
+
+    /// Returns the text of the document a request is about, and the offset of its position.
+    fn cursor(&self, params: &Value) -> Option<(&str, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let text = self.documents.get(uri)?;
+        let line = params["position"]["line"].as_u64()? as usize;
+        let character = params["position"]["character"].as_u64()? as usize;
+        Some((text, offset(text, line, character)))
+    }
+
+    /// Returns the name of the shortcode being written at `offset`, and what follows it in the
+    /// tag up to `offset`, if `offset` is in an opening tag.
+    fn tag_at<'t>(&self, text: &'t str, offset: usize) -> Option<(&'t str, &'t str)> {
+        let start_opening = format!("{}#", self.config.delimiters.open);
+        let start = text[..offset].rfind(&start_opening)? + start_opening.len();
+        let tag = &text[start..offset];
+        if tag.contains(&self.config.delimiters.close) || tag.contains('\n') {
+            return None;
+        }
+        Some(match tag.find(char::is_whitespace) {
+            Some(i) => (&tag[..i], &tag[i..]),
+            None => (tag, ""),
+        })
+    }
+
+    fn completions(&self, text: &str, offset: usize) -> Vec<Value> {
+        let (name, attrs) = match self.tag_at(text, offset) {
+            Some(tag) => tag,
+            None => return Vec::new(),
+        };
+        if attrs.is_empty() {
+            return self
+                .names
+                .iter()
+                .filter(|(alias, _)| alias.starts_with(name))
+                .map(|(alias, name)| {
+                    json!({
+                        "label": alias,
+                        // A function.
+                        "kind": 3,
+                        "detail": doc(name).map_or("", |doc| doc.summary),
+                    })
+                })
+                .collect();
+        }
+
+        // Named attributes are completed at the start of an attribute.
+        if !attrs.ends_with(char::is_whitespace) {
+            return Vec::new();
+        }
+        let keys = match self.names.get(name).and_then(|name| doc(name)?.attrs) {
+            Some((_, keys)) => keys,
+            None => return Vec::new(),
+        };
+        let given = Attrs::parse(attrs).unwrap_or_default();
+        keys.iter()
+            .filter(|key| given.get(key).is_none())
+            .map(|key| {
+                json!({
+                    "label": key,
+                    // A property.
+                    "kind": 10,
+                    "insertText": format!("{}=", key),
+                })
+            })
+            .collect()
+    }
+
+    fn hover(&self, text: &str, offset: usize) -> Option<Value> {
+        // The rest of the name is included, so that hovering anywhere over it works.
+        let end = text[offset..]
+            .find(|c: char| {
+                c.is_whitespace() || c == '/' || self.config.delimiters.close.starts_with(c)
+            })
+            .map_or(text.len(), |i| offset + i);
+        let (name, attrs) = self.tag_at(text, end)?;
+        if !attrs.is_empty() {
+            return None;
+        }
+        let doc = doc(self.names.get(name)?)?;
+        let mut contents = format!("**{}**\n\n{}\n\n`{}`", doc.name, doc.summary, doc.example);
+        if let Some((_, keys)) = doc.attrs.filter(|(_, keys)| !keys.is_empty()) {
+            let keys: Vec<String> = keys.iter().map(|key| format!("`{}`", key)).collect();
+            contents.push_str(&format!("\n\nAttributes: {}", keys.join(", ")));
+        }
+        Some(json!({ "contents": { "kind": "markdown", "value": contents } }))
+    }
+
+    fn publish_diagnostics(&self, uri: &str) -> Value {
+        let text = self.documents.get(uri).map_or("", String::as_str);
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": self.diagnostics(text, 0, text) },
+        })
+    }
+
+    /// Returns the problems with the uses of shortcodes in `content`, which is at `offset` in
+    /// the document's `text`.
+    fn diagnostics(&self, text: &str, offset: usize, content: &str) -> Vec<Value> {
+        let is_shortcode = |name: &str| self.names.contains_key(name);
+        let parsed = match parse::parse(content, &self.config.delimiters, is_shortcode) {
+            Ok(parsed) => parsed,
+            Err(unclosed) => {
+                let start = offset + unclosed.tag;
+                let end = start + unclosed.name.len() + self.config.delimiters.open.len() + 1;
+                let message = format!("`{}` shortcode has no closing tag", unclosed.name);
+                return vec![diagnostic(text, start..end, &message)];
+            }
+        };
+
+        let mut result = Vec::new();
+        for node in parsed.nodes {
+            let tag = offset + node.tag.start..offset + node.tag.end;
+            let name = &self.names[node.name];
+            let checked = Attrs::parse(node.attrs)
+                .map_err(|e| e.to_string())
+                .and_then(|attrs| match doc(name).and_then(|doc| doc.attrs) {
+                    Some((max_positional, keys)) => attrs.expect(max_positional, keys),
+                    None => Ok(()),
+                });
+            if let Err(message) = checked {
+                result.push(diagnostic(
+                    text,
+                    tag,
+                    &format!("{} shortcode: {}", name, message),
+                ));
+            }
+            if let Some(body) = node.body {
+                // The body starts right after the opening tag.
+                let body_offset = offset + node.tag.end;
+                result.extend(self.diagnostics(text, body_offset, body));
+            }
+        }
+        result
+    }
+}
+
+fn diagnostic(text: &str, range: std::ops::Range<usize>, message: &str) -> Value {
+    json!({
+        "range": { "start": position(text, range.start), "end": position(text, range.end) },
+        // An error.
+        "severity": 1,
+        "source": "mdbook-shortcodes",
+        "message": message,
+    })
+}
+
+/// Returns the byte offset of a position given as a line and a number of UTF-16 code units, as
+/// positions are in the language server protocol.
+fn offset(text: &str, line: usize, character: usize) -> usize {
+    let line_start = match line {
+        0 => 0,
+        _ => text
+            .match_indices('\n')
+            .nth(line - 1)
+            .map_or(text.len(), |(i, _)| i + 1),
+    };
+    let mut units = 0;
+    for (i, c) in text[line_start..].char_indices() {
+        if units >= character || c == '\n' {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+/// Returns the position of the byte offset `offset` in `text`.
+fn position(text: &str, offset: usize) -> Value {
+    let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    json!({
+        "line": text[..offset].matches('\n').count(),
+        "character": text[line_start..offset].encode_utf16().count(),
+    })
+}
+
+/// Reads a message, or returns `None` at the end of the input.
+fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse().ok();
+        }
+    }
+    let length =
+        length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no Content-Length"))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+fn write_message<W: Write>(output: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(config: &Config) -> Server<'_> {
+        let names = [("hint", "hint"), ("callout", "hint"), ("kbd", "kbd")]
+            .iter()
+            .map(|&(alias, name)| (alias.to_owned(), name.to_owned()))
+            .collect();
+        Server::new(config, names)
+    }
+
+    #[test]
+    fn test_completions() {
+        let config = Config::default();
+        let server = server(&config);
+        let labels = |text: &str| -> Vec<Value> {
+            server
+                .completions(text, text.len())
+                .iter()
+                .map(|item| item["label"].clone())
+                .collect()
+        };
+        assert_eq!(labels("A {{#"), ["callout", "hint", "kbd"]);
+        assert_eq!(labels("A {{#h"), ["hint"]);
+        assert_eq!(labels("{{#callout info "), ["type", "title"]);
+        assert_eq!(labels("{{#hint info title=A "), ["type"]);
+        assert!(labels("{{#hint info}} ").is_empty());
+    }
+
+    #[test]
+    fn test_hover() {
+        let config = Config::default();
+        let server = server(&config);
+        let text = "é {{#callout info}}A{{/callout}}";
+        let hover = server.hover(text, text.find("llout").unwrap()).unwrap();
+        let contents = hover["contents"]["value"].as_str().unwrap();
+        assert!(contents.starts_with("**hint**\n\nA callout box"));
+        assert!(contents.ends_with("Attributes: `type`, `title`"));
+        assert_eq!(server.hover(text, text.find("info").unwrap()), None);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let config = Config::default();
+        let server = server(&config);
+        let text = "{{#hint info}}\n{{#kbd Ctrl gap=1 /}}\n{{/hint}} {{#hint 'A}}";
+        let diagnostics = server.diagnostics(text, 0, text);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0]["message"],
+            "`hint` shortcode has no closing tag"
+        );
+
+        let text = "é {{#hint info}}\n{{#kbd Ctrl gap=1 /}}\n{{/hint}} {{#hint a b c}}B{{/hint}}";
+        let diagnostics = server.diagnostics(text, 0, text);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0]["message"],
+            "hint shortcode: too many arguments"
+        );
+        assert_eq!(
+            diagnostics[0]["range"]["start"],
+            json!({ "line": 2, "character": 10 })
+        );
+    }
+
+    #[test]
+    fn test_run() {
+        let config = Config::default();
+        let mut server = server(&config);
+        let mut input = Vec::new();
+        for message in [
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+            json!({ "jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {
+                "textDocument": { "uri": "file:///a.md", "text": "é {{#kbd" },
+            } }),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "textDocument/completion", "params": {
+                "textDocument": { "uri": "file:///a.md" },
+                "position": { "line": 0, "character": 5 },
+            } }),
+            json!({ "jsonrpc": "2.0", "method": "exit" }),
+        ] {
+            write_message(&mut input, &message).unwrap();
+        }
+        let mut output = Vec::new();
+        server.run(&input[..], &mut output).unwrap();
+
+        let mut output = &output[..];
+        let mut replies = Vec::new();
+        while let Some(reply) = read_message(&mut output).unwrap() {
+            replies.push(reply);
+        }
+        assert_eq!(replies.len(), 3);
+        assert_eq!(replies[0]["result"]["capabilities"]["hoverProvider"], true);
+        assert_eq!(
+            replies[1]["params"]["diagnostics"][0]["range"]["start"],
+            json!({ "line": 0, "character": 2 })
+        );
+        assert_eq!(replies[2]["result"][0]["label"], "callout");
+    }
+}
@@ -1,7 +1,7 @@
-mod lib;
-use lib::ShortcodesProcessor;
+use mdbook_shortcodes::ShortcodesProcessor;
 
 use std::io;
+use std::path::{Path, PathBuf};
 use std::process;
 
 use clap::{App, Arg, ArgMatches, SubCommand};
@@ -12,23 +12,181 @@ use mdbook::{
 use semver::{Version, VersionReq};
 
 pub fn make_app() -> App<'static, 'static> {
-    App::new("nop-preprocessor")
+    let app = App::new("nop-preprocessor")
         .about("A mdbook preprocessor which does precisely nothing")
         .subcommand(
             SubCommand::with_name("supports")
                 .arg(Arg::with_name("renderer").required(true))
                 .about("Check whether a renderer is supported by this preprocessor"),
         )
+        .subcommand(
+            SubCommand::with_name("watch")
+                .arg(Arg::with_name("list").required(true))
+                .about("Rebuild the book when a file used by shortcodes changes (used by serve)"),
+        )
+        .subcommand(
+            SubCommand::with_name("install")
+                .arg(Arg::with_name("dir").default_value("."))
+                .arg(
+                    Arg::with_name("css")
+                        .long("css")
+                        .help("Also write the default theme into the book's theme directory"),
+                )
+                .about("Add the preprocessor to the book's book.toml"),
+        )
+        .subcommand(
+            SubCommand::with_name("list")
+                .arg(Arg::with_name("dir").default_value("."))
+                .about("Describe every shortcode, with its attributes and an example"),
+        )
+        .subcommand(
+            SubCommand::with_name("outline")
+                .arg(Arg::with_name("dir").default_value("."))
+                .about("Print every use of a shortcode in the book as JSON, without expanding it"),
+        )
+        .subcommand(
+            SubCommand::with_name("fmt")
+                .arg(Arg::with_name("dir").default_value("."))
+                .arg(
+                    Arg::with_name("check")
+                        .long("check")
+                        .help("List the chapters that would change without changing them"),
+                )
+                .about("Write the tags of shortcodes in the book's chapters consistently"),
+        )
+        .subcommand(
+            SubCommand::with_name("check")
+                .arg(Arg::with_name("dir").default_value("."))
+                .about("Report malformed and unknown shortcodes in the book without building it"),
+        )
+        .subcommand(
+            SubCommand::with_name("extract")
+                .arg(Arg::with_name("dir").default_value("."))
+                .about("Print the translatable text of the book's shortcodes as a PO template"),
+        )
+        .subcommand(
+            SubCommand::with_name("merge")
+                .arg(Arg::with_name("po-file").required(true))
+                .arg(Arg::with_name("out-dir").required(true))
+                .arg(Arg::with_name("dir").default_value("."))
+                .about("Write the book's chapters into out-dir, translated by a PO file"),
+        )
+        .subcommand(
+            SubCommand::with_name("grammar")
+                .arg(Arg::with_name("dir").default_value("."))
+                .about("Print a description of the syntax of the book's shortcodes as JSON"),
+        )
+        .subcommand(
+            SubCommand::with_name("diff-build")
+                .arg(Arg::with_name("old-dir").required(true))
+                .arg(Arg::with_name("new-dir"))
+                .about(
+                    "Compare the shortcodes used in two builds recorded by the record-dir \
+                     option (the new one defaults to the book's record-dir)",
+                ),
+        );
+    #[cfg(feature = "lsp")]
+    let app = app.subcommand(
+        SubCommand::with_name("lsp")
+            .arg(Arg::with_name("dir").default_value("."))
+            .about("Run a language server for the syntax of shortcodes over stdin and stdout"),
+    );
+    app
 }
 
 fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     let matches = make_app().get_matches();
 
     // Users will want to construct their own preprocessor here
-    let preprocessor = ShortcodesProcessor::new();
+    let preprocessor = ShortcodesProcessor::new().spawn_watcher();
+
+    #[cfg(feature = "lsp")]
+    if let Some(sub_args) = matches.subcommand_matches("lsp") {
+        let dir = sub_args.value_of("dir").expect("Has a default");
+        if let Err(e) = preprocessor.lsp(Path::new(dir)) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
 
     if let Some(sub_args) = matches.subcommand_matches("supports") {
         handle_supports(&preprocessor, sub_args);
+    } else if let Some(sub_args) = matches.subcommand_matches("watch") {
+        let list = sub_args.value_of("list").expect("Required argument");
+        if let Err(e) = mdbook_shortcodes::watch(Path::new(list)) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    } else if let Some(sub_args) = matches.subcommand_matches("install") {
+        let dir = sub_args.value_of("dir").expect("Has a default");
+        match mdbook_shortcodes::install(Path::new(dir), sub_args.is_present("css")) {
+            Ok(changes) if changes.is_empty() => println!("Already installed."),
+            Ok(changes) => {
+                for change in changes {
+                    println!("{}", change);
+                }
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    } else if let Some(sub_args) = matches.subcommand_matches("list") {
+        let dir = sub_args.value_of("dir").expect("Has a default");
+        match preprocessor.list(Path::new(dir)) {
+            Ok(list) => print!("{}", list),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    } else if let Some(sub_args) = matches.subcommand_matches("outline") {
+        let dir = sub_args.value_of("dir").expect("Has a default");
+        if let Err(e) = handle_outline(&preprocessor, Path::new(dir)) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    } else if let Some(sub_args) = matches.subcommand_matches("fmt") {
+        if let Err(e) = handle_fmt(&preprocessor, sub_args) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    } else if let Some(sub_args) = matches.subcommand_matches("check") {
+        let dir = sub_args.value_of("dir").expect("Has a default");
+        if let Err(e) = handle_check(&preprocessor, Path::new(dir)) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    } else if let Some(sub_args) = matches.subcommand_matches("extract") {
+        let dir = sub_args.value_of("dir").expect("Has a default");
+        match preprocessor.extract(Path::new(dir)) {
+            Ok(template) => print!("{}", template),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    } else if let Some(sub_args) = matches.subcommand_matches("merge") {
+        let po = sub_args.value_of("po-file").expect("Required argument");
+        let out_dir = sub_args.value_of("out-dir").expect("Required argument");
+        let dir = sub_args.value_of("dir").expect("Has a default");
+        if let Err(e) = preprocessor.merge(Path::new(dir), Path::new(po), Path::new(out_dir)) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    } else if let Some(sub_args) = matches.subcommand_matches("grammar") {
+        let dir = sub_args.value_of("dir").expect("Has a default");
+        if let Err(e) = handle_grammar(&preprocessor, Path::new(dir)) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    } else if let Some(sub_args) = matches.subcommand_matches("diff-build") {
+        if let Err(e) = handle_diff_build(sub_args) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
     } else if let Err(e) = handle_preprocessing(&preprocessor) {
         eprintln!("{}", e);
         process::exit(1);
@@ -57,6 +215,71 @@ fn handle_preprocessing(pre: &dyn Preprocessor) -> Result<(), Error> {
     Ok(())
 }
 
+fn handle_fmt(pre: &ShortcodesProcessor, sub_args: &ArgMatches) -> Result<(), Error> {
+    let dir = sub_args.value_of("dir").expect("Has a default");
+    let check = sub_args.is_present("check");
+    let changed = pre.format(Path::new(dir), check)?;
+    for path in &changed {
+        println!("{}", path.display());
+    }
+    // Like `cargo fmt --check`, unformatted chapters fail the check.
+    if check && !changed.is_empty() {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn handle_check(pre: &ShortcodesProcessor, dir: &Path) -> Result<(), Error> {
+    let problems = pre.check(dir)?;
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    if !problems.is_empty() {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn handle_outline(pre: &ShortcodesProcessor, dir: &Path) -> Result<(), Error> {
+    let instances = pre.outline(dir)?;
+    serde_json::to_writer_pretty(io::stdout(), &instances)?;
+    println!();
+    Ok(())
+}
+
+fn handle_grammar(pre: &ShortcodesProcessor, dir: &Path) -> Result<(), Error> {
+    let grammar = pre.grammar(dir)?;
+    serde_json::to_writer_pretty(io::stdout(), &grammar)?;
+    println!();
+    Ok(())
+}
+
+fn handle_diff_build(sub_args: &ArgMatches) -> Result<(), Error> {
+    let old = sub_args.value_of("old-dir").expect("Required argument");
+    let new = match sub_args.value_of("new-dir") {
+        Some(new) => PathBuf::from(new),
+        None => {
+            let config = mdbook::Config::from_disk("book.toml")?;
+            match config.get_deserialized_opt::<String, _>("preprocessor.shortcodes.record-dir")? {
+                Some(dir) => PathBuf::from(dir),
+                None => {
+                    let message = "no new directory given, and the book has no `record-dir` option";
+                    return Err(Error::msg(message));
+                }
+            }
+        }
+    };
+
+    let changes = mdbook_shortcodes::diff_build(Path::new(old), &new)?;
+    if changes.is_empty() {
+        println!("No shortcode-level changes.");
+    }
+    for change in changes {
+        println!("{}", change);
+    }
+    Ok(())
+}
+
 fn handle_supports(pre: &dyn Preprocessor, sub_args: &ArgMatches) -> ! {
     let renderer = sub_args.value_of("renderer").expect("Required argument");
     let supported = pre.supports_renderer(renderer);
@@ -0,0 +1,210 @@
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+use crate::html;
+
+/// A fenced code block found in markdown.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub(crate) struct FencedBlock<'a> {
+    /// The first word of the info string, i.e. the language.
+    pub(crate) lang: &'a str,
+    /// The content between the fences, including the final newline.
+    pub(crate) content: &'a str,
+}
+
+/// Returns the top level fenced code blocks (using backticks or tildes) in `input`.
+///
+/// An unterminated block runs until the end of the input, as per the CommonMark spec.
+pub(crate) fn fenced_blocks(input: &str) -> Vec<FencedBlock<'_>> {
+    let mut result = Vec::new();
+    // The fence (e.g. "```") and language of the current block, and the index its content
+    // starts at.
+    let mut current: Option<(&str, &str, usize)> = None;
+    let mut line_start = 0;
+
+    for line in input.split_inclusive('\n') {
+        let trimmed = line.trim();
+        match current {
+            Some((fence, lang, content_start)) => {
+                if opening_fence(trimmed).is_some_and(|f| f.starts_with(fence))
+                    && trimmed.trim_start_matches(&fence[..1]).is_empty()
+                {
+                    result.push(FencedBlock {
+                        lang,
+                        content: &input[content_start..line_start],
+                    });
+                    current = None;
+                }
+            }
+            None => {
+                if let Some(fence) = opening_fence(trimmed) {
+                    let lang = trimmed[fence.len()..]
+                        .split(|c: char| c.is_whitespace() || c == ',')
+                        .next()
+                        .unwrap_or("");
+                    current = Some((fence, lang, line_start + line.len()));
+                }
+            }
+        }
+        line_start += line.len();
+    }
+
+    if let Some((_, lang, content_start)) = current {
+        result.push(FencedBlock {
+            lang,
+            content: &input[content_start.min(input.len())..],
+        });
+    }
+
+    result
+}
+
+/// Returns the fence that `line` opens with, if any.
+fn opening_fence(line: &str) -> Option<&str> {
+    let c = line.chars().next()?;
+    if c != '`' && c != '~' {
+        return None;
+    }
+    let len = line.chars().take_while(|&x| x == c).count();
+    if len >= 3 {
+        Some(&line[..len])
+    } else {
+        None
+    }
+}
+
+/// Surrounds `content` with blank lines so that it is parsed as markdown, rather than as part of
+/// the HTML block it is wrapped in. Otherwise, headings and lists directly after the opening tag
+/// are shown as text.
+pub(crate) fn block(content: &str) -> String {
+    format!("\n\n{}\n\n", content.trim_matches('\n'))
+}
+
+/// Quotes every line of `content`, so that it is shown as a blockquote by any markdown renderer.
+pub(crate) fn blockquote(content: &str) -> String {
+    let quoted: Vec<String> = content
+        .trim_matches('\n')
+        .lines()
+        .map(|line| format!("> {}", line).trim_end().to_owned())
+        .collect();
+    block(&quoted.join("\n"))
+}
+
+/// Renders the inline markdown in `input` (e.g. a title given as an attribute) to HTML: code
+/// spans, emphasis, strikethrough and links. Anything else is shown as text, so that a title
+/// can't open a block or add raw HTML to the page it is in.
+pub(crate) fn inline(input: &str) -> String {
+    let input = paragraph(input);
+    let mut result = String::with_capacity(input.len());
+    // Images are replaced by their alt text, which is given as text events.
+    for event in Parser::new_ext(&input, Options::ENABLE_STRIKETHROUGH) {
+        match event {
+            Event::Text(text) | Event::Html(text) | Event::InlineHtml(text) => {
+                result += &html::escape(&text)
+            }
+            Event::Code(code) => result += &format!("<code>{}</code>", html::escape(&code)),
+            Event::SoftBreak | Event::HardBreak => result.push(' '),
+            Event::Start(Tag::Emphasis) => result += "<em>",
+            Event::End(TagEnd::Emphasis) => result += "</em>",
+            Event::Start(Tag::Strong) => result += "<strong>",
+            Event::End(TagEnd::Strong) => result += "</strong>",
+            Event::Start(Tag::Strikethrough) => result += "<del>",
+            Event::End(TagEnd::Strikethrough) => result += "</del>",
+            Event::Start(Tag::Link {
+                dest_url, title, ..
+            }) => {
+                result += &format!("<a href=\"{}\"", html::escape(&dest_url));
+                if !title.is_empty() {
+                    result += &format!(" title=\"{}\"", html::escape(&title));
+                }
+                result.push('>');
+            }
+            Event::End(TagEnd::Link) => result += "</a>",
+            _ => {}
+        }
+    }
+    result
+}
+
+/// Joins the lines of `input` and escapes its start if it would otherwise begin a block other
+/// than a paragraph (e.g. `# A`, `1. A` or `<div>`).
+fn paragraph(input: &str) -> String {
+    let mut input = input.trim().replace('\n', " ");
+    let is_paragraph = |input: &str| {
+        input.is_empty()
+            || matches!(
+                Parser::new(input).next(),
+                Some(Event::Start(Tag::Paragraph))
+            )
+    };
+    if !is_paragraph(&input) {
+        // The marker of an ordered list follows its number.
+        let start = input.find(|c: char| !c.is_ascii_digit()).unwrap_or(0);
+        input.insert(start, '\\');
+    }
+    input
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fenced_blocks() {
+        let input = "
+Some text.
+
+```html
+<p>Hi</p>
+```
+
+~~~css,ignore
+p { color: red; }
+~~~
+
+````
+```
+````
+";
+        assert_eq!(
+            fenced_blocks(input),
+            vec![
+                FencedBlock {
+                    lang: "html",
+                    content: "<p>Hi</p>\n"
+                },
+                FencedBlock {
+                    lang: "css",
+                    content: "p { color: red; }\n"
+                },
+                FencedBlock {
+                    lang: "",
+                    content: "```\n"
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inline() {
+        assert_eq!(
+            inline("Use `unsafe` *carefully* & [read](https://a.b/?x=1&y=2 \"Docs\")"),
+            "Use <code>unsafe</code> <em>carefully</em> &amp; \
+            <a href=\"https://a.b/?x=1&amp;y=2\" title=\"Docs\">read</a>"
+        );
+        assert_eq!(
+            inline("**A** ~~B~~ <b>C</b> ![D](d.png)"),
+            "<strong>A</strong> <del>B</del> &lt;b&gt;C&lt;/b&gt; D"
+        );
+        assert_eq!(inline("# Not a heading"), "# Not a heading");
+        assert_eq!(inline("1986. A year"), "1986. A year");
+        assert_eq!(inline("<div>\n- A"), "&lt;div&gt; - A");
+    }
+
+    #[test]
+    fn test_blockquote() {
+        assert_eq!(
+            blockquote("\nA\n\n- B\n  C\n"),
+            "\n\n> A\n>\n> - B\n>   C\n\n"
+        );
+    }
+}
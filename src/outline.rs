@@ -0,0 +1,113 @@
+use std::{
+    collections::BTreeMap,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{parse, Attrs, Delimiters, Result};
+
+/// A use of a shortcode as it was written, before it is expanded. Serialized by
+/// `mdbook-shortcodes outline` for tools that work with the structure of a book (e.g. linters
+/// and translation pipelines).
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct Instance {
+    /// The path of the chapter it is used in, relative to the source directory.
+    pub chapter: PathBuf,
+    /// The name it is called by, which may be an alias.
+    pub name: String,
+    /// The attributes that aren't named, in the order they were given.
+    pub positional: Vec<String>,
+    /// The `key=value` attributes.
+    pub named: BTreeMap<String, String>,
+    /// The content between the opening and closing tags, or `None` if it is self-closing.
+    pub body: Option<String>,
+    /// The span of the whole shortcode in the chapter, in bytes.
+    pub span: Range<usize>,
+    /// The line and column its opening tag starts at, counting from 1.
+    pub line: usize,
+    pub column: usize,
+    /// The shortcodes used in its body.
+    pub children: Vec<Instance>,
+}
+
+/// Returns the uses of shortcodes in `content`, the content of the chapter at `chapter`.
+/// `offset` is where `content` starts in the chapter's `source`.
+pub(crate) fn instances<F>(
+    source: &str,
+    content: &str,
+    offset: usize,
+    chapter: &Path,
+    delimiters: &Delimiters,
+    is_shortcode: &F,
+) -> Result<Vec<Instance>>
+where
+    F: Fn(&str) -> bool,
+{
+    let parsed = parse::parse(content, delimiters, is_shortcode)?;
+    let mut result = Vec::with_capacity(parsed.nodes.len());
+    for node in parsed.nodes {
+        let attrs = Attrs::parse(node.attrs)?;
+        let start = offset + node.span.start;
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let children = match node.body {
+            Some(body) => {
+                // The body is a slice of `content`, so its offset can be found from its address.
+                let body_offset = body.as_ptr() as usize - content.as_ptr() as usize;
+                let body_offset = offset + body_offset;
+                instances(source, body, body_offset, chapter, delimiters, is_shortcode)?
+            }
+            None => Vec::new(),
+        };
+        result.push(Instance {
+            chapter: chapter.to_path_buf(),
+            name: node.name.to_owned(),
+            positional: attrs.positional().iter().map(|&a| a.to_owned()).collect(),
+            named: attrs
+                .named()
+                .iter()
+                .map(|(&key, &value)| (key.to_owned(), value.to_owned()))
+                .collect(),
+            body: node.body.map(str::to_owned),
+            span: start..offset + node.span.end,
+            line: source[..start].matches('\n').count() + 1,
+            column: source[line_start..start].chars().count() + 1,
+            children,
+        });
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instances() {
+        let source = "# Intro\n\n{{#columns}}A{{#column}}{{#kbd Ctrl /}}{{/columns}}";
+        let chapter = Path::new("intro.md");
+        // Columns are separated by `{{#column}}`, which isn't a shortcode of its own.
+        let is_shortcode = |name: &str| name != "column";
+        let result = instances(
+            source,
+            source,
+            0,
+            chapter,
+            &Delimiters::default(),
+            &is_shortcode,
+        )
+        .unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].name, "columns");
+        assert_eq!((result[0].line, result[0].column), (3, 1));
+        assert_eq!(result[0].span, 9..source.len());
+
+        assert_eq!(result[0].children.len(), 1);
+        let kbd = &result[0].children[0];
+        assert_eq!(&source[kbd.span.clone()], "{{#kbd Ctrl /}}");
+        assert_eq!(kbd.positional, ["Ctrl"]);
+        assert_eq!((kbd.line, kbd.column), (3, 25));
+        assert_eq!(kbd.body, None);
+    }
+}
@@ -0,0 +1,474 @@
+use std::{borrow::Cow, ops::Range};
+
+use pulldown_cmark::{Event, Tag};
+
+use crate::{Delimiters, Error};
+
+/// A use of a shortcode, e.g. `{{#hint info}}Be careful.{{/hint}}`.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct Node<'a> {
+    pub(crate) name: &'a str,
+    /// The attributes, as written between the name and the closing delimiter of the opening
+    /// tag, without the slash of a self-closing shortcode.
+    pub(crate) attrs: &'a str,
+    /// The content between the opening and closing tags, or `None` if the shortcode is
+    /// self-closing.
+    pub(crate) body: Option<&'a str>,
+    /// The span of the opening tag in the input.
+    pub(crate) tag: Range<usize>,
+    /// The span of the whole shortcode in the input, up to the end of its closing tag.
+    pub(crate) span: Range<usize>,
+}
+
+/// The shortcodes in a piece of markdown.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub(crate) struct Parsed<'a> {
+    /// The uses of shortcodes, in the order they appear.
+    pub(crate) nodes: Vec<Node<'a>>,
+    /// The indices of the backslashes that escape tags outside of the nodes (e.g.
+    /// `\{{#hint info}}`), which are removed from the output.
+    pub(crate) escapes: Vec<usize>,
+}
+
+impl Parsed<'_> {
+    /// Returns `input[range]` without the escaping backslashes in it.
+    pub(crate) fn unescaped<'b>(&self, input: &'b str, range: Range<usize>) -> Cow<'b, str> {
+        let first = self.escapes.partition_point(|&i| i < range.start);
+        let last = self.escapes.partition_point(|&i| i < range.end);
+        if first == last {
+            return Cow::Borrowed(&input[range]);
+        }
+        let mut text = String::with_capacity(range.len());
+        let mut start = range.start;
+        for &i in &self.escapes[first..last] {
+            text.push_str(&input[start..i]);
+            start = i + 1;
+        }
+        text.push_str(&input[start..range.end]);
+        Cow::Owned(text)
+    }
+}
+
+/// A shortcode without a closing tag, which stops `input` from being parsed.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct Unclosed<'a> {
+    pub(crate) name: &'a str,
+    /// The index of its opening tag in the input.
+    pub(crate) tag: usize,
+}
+
+impl From<Unclosed<'_>> for Error {
+    fn from(_: Unclosed<'_>) -> Self {
+        Error::NoClosingShortcode
+    }
+}
+
+/// Finds the uses of shortcodes in `input` in a single pass, in the order they appear. Tags
+/// are written with `delimiters`, e.g. `{{#hint info}}` and `{{/hint}}` by default.
+///
+/// Only the names accepted by `is_shortcode` are matched, so that tags belonging to other
+/// preprocessors (e.g. `{{#include file.rs}}`) are left alone. Shortcodes inside the body of
+/// another are part of its body, rather than nodes of their own. A shortcode can be nested in
+/// another with the same name, in which case its closing tag doesn't close the outer one.
+///
+/// Tags in code blocks and inline code are left as they are, so that the syntax of shortcodes
+/// can be documented. A tag preceded by a backslash (e.g. `\{{#hint info}}`) is escaped: it is
+/// output literally, without the backslash.
+pub(crate) fn parse<'a, F>(
+    input: &'a str,
+    delimiters: &Delimiters,
+    is_shortcode: F,
+) -> std::result::Result<Parsed<'a>, Unclosed<'a>>
+where
+    F: Fn(&str) -> bool,
+{
+    let code = code_spans(input);
+    let mut parsed = Parsed::default();
+    let mut cursor = 0;
+    let start_opening = format!("{}#", delimiters.open);
+    let end_opening = format!("{}/", delimiters.open);
+
+    while let Some((start, delimeter)) = next_tag(input, cursor, [&start_opening, &end_opening]) {
+        // {{#columns 3em}}
+        //    ^ here
+        let name_start = start + delimeter.len();
+        // {{#columns 3em}}
+        //           ^ here
+        let name_end = name_start + name_len(&input[name_start..], &delimiters.close);
+        let name = &input[name_start..name_end];
+        cursor = name_start;
+        if !is_shortcode(name) || in_spans(&code, start) {
+            continue;
+        }
+        if is_escaped(input, start) {
+            parsed.escapes.push(start - 1);
+            continue;
+        }
+        if delimeter == end_opening {
+            // A stray closing tag, which is left as it is.
+            continue;
+        }
+
+        let attrs_end = match input[name_end..].find(&delimiters.close) {
+            Some(i) => name_end + i,
+            // TODO technically this is a different error than the one below, so it shouldn't
+            // use this error variant.
+            None => return Err(Unclosed { name, tag: start }),
+        };
+        let tag_end = attrs_end + delimiters.close.len();
+        let raw_attrs = &input[name_end..attrs_end];
+
+        // Self-closing shortcodes (e.g. `{{#wasm-demo pkg="demos/counter" /}}`) have no
+        // content or closing tag.
+        let node = match self_closing_attrs(raw_attrs) {
+            Some(attrs) => Node {
+                name,
+                attrs,
+                body: None,
+                tag: start..tag_end,
+                span: start..tag_end,
+            },
+            None => {
+                let end_sequence = format!("{}{}{}", end_opening, name, delimiters.close);
+                let body_end = match find_closing(input, tag_end, name, delimiters, &code) {
+                    Some(i) => i,
+                    // No closing tag.
+                    None => return Err(Unclosed { name, tag: start }),
+                };
+                Node {
+                    name,
+                    attrs: raw_attrs,
+                    body: Some(&input[tag_end..body_end]),
+                    tag: start..tag_end,
+                    span: start..body_end + end_sequence.len(),
+                }
+            }
+        };
+        cursor = node.span.end;
+        parsed.nodes.push(node);
+    }
+
+    Ok(parsed)
+}
+
+/// Returns the index and name of every opening tag in `input` that isn't escaped or in code,
+/// whether or not it is a shortcode, in the order they appear.
+pub(crate) fn opening_tags<'a>(input: &'a str, delimiters: &Delimiters) -> Vec<(usize, &'a str)> {
+    let code = code_spans(input);
+    let start_opening = format!("{}#", delimiters.open);
+    input
+        .match_indices(&start_opening)
+        .map(|(start, _)| start)
+        .filter(|&start| !in_spans(&code, start) && !is_escaped(input, start))
+        .map(|start| {
+            let name_start = start + start_opening.len();
+            let name_end = name_start + name_len(&input[name_start..], &delimiters.close);
+            (start, &input[name_start..name_end])
+        })
+        .collect()
+}
+
+/// Returns the index of the next opening or closing tag in `input` from `from`, along with the
+/// one of `delimeters` it starts with.
+fn next_tag<'d>(input: &str, from: usize, delimeters: [&'d str; 2]) -> Option<(usize, &'d str)> {
+    delimeters
+        .iter()
+        .filter_map(|&delimeter| Some((from + input[from..].find(delimeter)?, delimeter)))
+        .min()
+}
+
+/// Returns the length of the name at the start of `rest`, which ends at whitespace, a slash or
+/// the `close` delimiter.
+fn name_len(rest: &str, close: &str) -> usize {
+    rest.char_indices()
+        .find(|&(i, c)| c.is_whitespace() || c == '/' || rest[i..].starts_with(close))
+        .map_or(rest.len(), |(i, _)| i)
+}
+
+/// Returns whether the tag at `index` is escaped with a backslash.
+fn is_escaped(input: &str, index: usize) -> bool {
+    input[..index].ends_with('\\')
+}
+
+/// Returns the index of the tag that closes a `name` shortcode whose body starts at `from`,
+/// skipping over the `name` shortcodes nested in it and any tags that are escaped or in `code`.
+fn find_closing(
+    input: &str,
+    from: usize,
+    name: &str,
+    delimiters: &Delimiters,
+    code: &[Range<usize>],
+) -> Option<usize> {
+    let start_sequence = format!("{}#{}", delimiters.open, name);
+    let end_sequence = format!("{}/{}{}", delimiters.open, name, delimiters.close);
+    let mut depth = 0;
+    let mut cursor = from;
+
+    loop {
+        let end = input[cursor..]
+            .match_indices(&end_sequence)
+            .map(|(i, _)| cursor + i)
+            .find(|&i| !in_spans(code, i) && !is_escaped(input, i))?;
+        let nested = input[cursor..end]
+            .match_indices(&start_sequence)
+            .map(|(i, _)| cursor + i)
+            .filter(|&i| !in_spans(code, i) && !is_escaped(input, i))
+            .map(|i| i + start_sequence.len())
+            .find(|&attrs_start| opens_body(&input[attrs_start..], &delimiters.close));
+        match nested {
+            Some(attrs_start) => {
+                depth += 1;
+                cursor = attrs_start;
+            }
+            None if depth == 0 => return Some(end),
+            None => {
+                depth -= 1;
+                cursor = end + end_sequence.len();
+            }
+        }
+    }
+}
+
+/// Returns the spans of the code blocks (fenced or indented) and inline code in `input`, in
+/// the order they appear.
+fn code_spans(input: &str) -> Vec<Range<usize>> {
+    mdbook::utils::new_cmark_parser(input, false)
+        .into_offset_iter()
+        .filter_map(|(event, range)| match event {
+            Event::Start(Tag::CodeBlock(_)) | Event::Code(_) => Some(range),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns whether `index` is in one of the sorted `spans`.
+fn in_spans(spans: &[Range<usize>], index: usize) -> bool {
+    let after = spans.partition_point(|span| span.start <= index);
+    after > 0 && spans[after - 1].contains(&index)
+}
+
+/// Returns whether `rest`, which follows the name in an opening tag, is the rest of a tag that
+/// has a body, rather than a self-closing one or a longer name (e.g. `{{#hints}}`).
+fn opens_body(rest: &str, close: &str) -> bool {
+    if !rest.starts_with(char::is_whitespace) && !rest.starts_with(close) {
+        return false;
+    }
+    match rest.find(close) {
+        Some(attrs_end) => self_closing_attrs(&rest[..attrs_end]).is_none(),
+        None => false,
+    }
+}
+
+/// Splits `input` at every `separator` that isn't inside a `name` shortcode, e.g. at the
+/// `{{#column}}`s of a `columns` shortcode, but not at those of the columns nested in it.
+pub(crate) fn split_outside<'a>(
+    input: &'a str,
+    separator: &str,
+    name: &str,
+    delimiters: &Delimiters,
+) -> Vec<&'a str> {
+    let nested = parse(input, delimiters, |n| n == name).unwrap_or_default();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for (i, _) in input.match_indices(separator) {
+        if !nested.nodes.iter().any(|node| node.span.contains(&i)) {
+            parts.push(&input[start..i]);
+            start = i + separator.len();
+        }
+    }
+    parts.push(&input[start..]);
+    parts
+}
+
+/// Escapes the tags of the shortcodes accepted by `is_shortcode` in `input`, so that it is
+/// output as it was written rather than expanded (e.g. when it was downloaded).
+pub(crate) fn escape<F>(input: &str, delimiters: &Delimiters, is_shortcode: F) -> String
+where
+    F: Fn(&str) -> bool,
+{
+    let code = code_spans(input);
+    let start_opening = format!("{}#", delimiters.open);
+    let end_opening = format!("{}/", delimiters.open);
+    let mut result = String::with_capacity(input.len());
+    let mut last = 0;
+    let mut cursor = 0;
+    while let Some((start, delimeter)) = next_tag(input, cursor, [&start_opening, &end_opening]) {
+        let name_start = start + delimeter.len();
+        let name_end = name_start + name_len(&input[name_start..], &delimiters.close);
+        cursor = name_start;
+        if is_shortcode(&input[name_start..name_end]) && !in_spans(&code, start) {
+            result.push_str(&input[last..start]);
+            result.push('\\');
+            last = start;
+        }
+    }
+    result.push_str(&input[last..]);
+    result
+}
+
+/// Returns the attributes of a self-closing shortcode without the trailing slash, or `None` if
+/// the shortcode isn't self-closing.
+fn self_closing_attrs(raw_attrs: &str) -> Option<&str> {
+    let raw_attrs = raw_attrs.trim_end().strip_suffix('/')?;
+    if raw_attrs.is_empty() || raw_attrs.ends_with(char::is_whitespace) {
+        Some(raw_attrs)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Result;
+
+    fn parse_known(input: &str) -> Result<Vec<Node<'_>>> {
+        parse(input, &Delimiters::default(), |name| {
+            ["hint", "kbd"].contains(&name)
+        })
+        .map(|parsed| parsed.nodes)
+        .map_err(Error::from)
+    }
+
+    #[test]
+    fn test_parse() {
+        let input = "{{#include a.rs}} {{#hints}}\n{{#hint info}}A {{#kbd Ctrl /}}{{/hint}} \
+            {{#kbd/}}";
+        assert_eq!(
+            parse_known(input),
+            Ok(vec![
+                Node {
+                    name: "hint",
+                    attrs: " info",
+                    body: Some("A {{#kbd Ctrl /}}"),
+                    tag: 29..43,
+                    span: 29..69,
+                },
+                Node {
+                    name: "kbd",
+                    attrs: "",
+                    body: None,
+                    tag: 70..79,
+                    span: 70..79,
+                },
+            ])
+        );
+        assert_eq!(&input[29..43], "{{#hint info}}");
+        assert_eq!(&input[70..79], "{{#kbd/}}");
+    }
+
+    #[test]
+    fn test_unclosed() {
+        assert_eq!(
+            parse_known("{{#hint info}}Body"),
+            Err(Error::NoClosingShortcode)
+        );
+        assert_eq!(parse_known("{{#kbd Ctrl"), Err(Error::NoClosingShortcode));
+        assert_eq!(parse_known("{{#unknown"), Ok(Vec::new()));
+
+        let is_shortcode = |name: &str| name == "hint";
+        assert_eq!(
+            parse("A {{#hint info}}B", &Delimiters::default(), is_shortcode),
+            Err(Unclosed {
+                name: "hint",
+                tag: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_nested() {
+        let input = "{{#hint info}}A{{#hint warning}}B{{#hint/}}{{#hints}}{{/hint}}C{{/hint}}D";
+        let nodes = parse_known(input).unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(
+            nodes[0].body,
+            Some("A{{#hint warning}}B{{#hint/}}{{#hints}}{{/hint}}C")
+        );
+        assert_eq!(&input[nodes[0].span.end..], "D");
+
+        assert_eq!(
+            parse_known("{{#hint info}}{{#hint info}}{{/hint}}"),
+            Err(Error::NoClosingShortcode)
+        );
+    }
+
+    #[test]
+    fn test_code() {
+        let input = "`{{#kbd Ctrl /}}` {{#kbd Alt /}}\n\n\
+            ```md\n{{#hint info}}\n```\n\n    {{#kbd Tab /}}\n\n\
+            {{#hint info}}\nClose with `{{/hint}}`.\n{{/hint}}";
+        let nodes = parse_known(input).unwrap();
+        assert_eq!(
+            nodes
+                .iter()
+                .map(|node| &input[node.tag.clone()])
+                .collect::<Vec<_>>(),
+            ["{{#kbd Alt /}}", "{{#hint info}}"]
+        );
+        assert_eq!(nodes[1].body, Some("\nClose with `{{/hint}}`.\n"));
+    }
+
+    #[test]
+    fn test_escape() {
+        let input = "\\{{#hint info}} {{#hint info}}Close with \\{{/hint}}.{{/hint}} \\{{/hint}} \
+            \\{{#include a.rs}} `\\{{#kbd/}}`";
+        let parsed = parse(input, &Delimiters::default(), |name| {
+            name == "hint" || name == "kbd"
+        })
+        .unwrap();
+        assert_eq!(parsed.nodes.len(), 1);
+        assert_eq!(parsed.nodes[0].body, Some("Close with \\{{/hint}}."));
+        let span = parsed.nodes[0].span.clone();
+        assert_eq!(parsed.unescaped(input, 0..span.start), "{{#hint info}} ");
+        assert_eq!(
+            parsed.unescaped(input, span.end..input.len()),
+            " {{/hint}} \\{{#include a.rs}} `\\{{#kbd/}}`"
+        );
+    }
+
+    #[test]
+    fn test_escape_tags() {
+        let input = "{{#hint info}}A \\{{#kbd B/}}{{/hint}} {{#include a.rs}} `{{#kbd C/}}`";
+        let is_shortcode = |name: &str| name == "hint" || name == "kbd";
+        let escaped = escape(input, &Delimiters::default(), is_shortcode);
+        assert_eq!(
+            escaped,
+            "\\{{#hint info}}A \\\\{{#kbd B/}}\\{{/hint}} {{#include a.rs}} `{{#kbd C/}}`"
+        );
+        let parsed = parse(&escaped, &Delimiters::default(), is_shortcode).unwrap();
+        assert!(parsed.nodes.is_empty());
+        assert_eq!(parsed.unescaped(&escaped, 0..escaped.len()), input);
+    }
+
+    #[test]
+    fn test_split_outside() {
+        let input = "A{{#column}}{{#columns}}B{{#column}}C{{/columns}}{{#column}}D";
+        assert_eq!(
+            split_outside(input, "{{#column}}", "columns", &Delimiters::default()),
+            ["A", "{{#columns}}B{{#column}}C{{/columns}}", "D"]
+        );
+    }
+
+    #[test]
+    fn test_delimiters() {
+        let delimiters = Delimiters {
+            open: "[[".to_owned(),
+            close: "]]".to_owned(),
+        };
+        let input = "{{#include a.rs}} [[#hint info]]A [[#hint]][[/hint]] [[#kbd Ctrl/]]\
+            [[/hint]] \\[[#kbd/]]";
+        let parsed = parse(input, &delimiters, |name| name == "hint" || name == "kbd").unwrap();
+        assert_eq!(parsed.nodes.len(), 1);
+        assert_eq!(parsed.nodes[0].attrs, " info");
+        assert_eq!(
+            parsed.nodes[0].body,
+            Some("A [[#hint]][[/hint]] [[#kbd Ctrl/]]")
+        );
+        assert_eq!(parsed.escapes.len(), 1);
+        assert_eq!(
+            split_outside("A[[#column]]B", "[[#column]]", "columns", &delimiters),
+            ["A", "B"]
+        );
+    }
+}
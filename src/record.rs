@@ -0,0 +1,205 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::diff;
+
+/// The shortcodes used in each chapter of a build and what they expanded to, which
+/// `diff-build` compares between builds.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Record {
+    chapters: BTreeMap<PathBuf, Vec<Use>>,
+}
+
+/// A use of a shortcode in a chapter.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+struct Use {
+    name: String,
+    attrs: String,
+    output: String,
+}
+
+impl Use {
+    /// Describes the use as it was written, e.g. `hint info`.
+    fn describe(&self) -> String {
+        let attrs = self.attrs.trim();
+        if attrs.is_empty() {
+            format!("`{}`", self.name)
+        } else {
+            format!("`{} {}`", self.name, attrs)
+        }
+    }
+}
+
+impl Record {
+    /// Records a use of the `name` shortcode in `chapter`.
+    pub(crate) fn add(&mut self, chapter: &Path, name: &str, attrs: &str, output: &str) {
+        self.chapters
+            .entry(chapter.to_path_buf())
+            .or_default()
+            .push(Use {
+                name: name.to_owned(),
+                attrs: attrs.to_owned(),
+                output: output.to_owned(),
+            });
+    }
+
+    /// Writes the record for the `renderer` into `dir`.
+    pub(crate) fn write(&self, dir: &Path, renderer: &str) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        let content = serde_json::to_string_pretty(self)? + "\n";
+        fs::write(dir.join(renderer).with_extension("json"), content)
+    }
+}
+
+/// Describes how the shortcodes in the `new` build differ from those in the `old` one, e.g.
+/// "guide.md: `hint warning` added".
+pub(crate) fn changes(old: &Record, new: &Record) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut chapters: Vec<&PathBuf> = old.chapters.keys().chain(new.chapters.keys()).collect();
+    chapters.sort();
+    chapters.dedup();
+
+    for chapter in chapters {
+        let (old, new) = match (old.chapters.get(chapter), new.chapters.get(chapter)) {
+            (Some(old), Some(new)) => (old, new),
+            (Some(_), None) => {
+                result.push(format!("{}: chapter removed", chapter.display()));
+                continue;
+            }
+            (None, Some(_)) => {
+                result.push(format!("{}: chapter added", chapter.display()));
+                continue;
+            }
+            (None, None) => unreachable!(),
+        };
+
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        // Changes are described once the uses between two unchanged ones have been collected,
+        // so that a removed use can be paired with an added one in its place.
+        let mut flush = |removed: &mut Vec<&Use>, added: &mut Vec<&Use>| {
+            for (before, after) in pair(removed, added) {
+                let change = match (before, after) {
+                    (Some(before), Some(after)) if before.attrs == after.attrs => {
+                        format!("{} output changed", after.describe())
+                    }
+                    (Some(before), Some(after)) => {
+                        format!("{} changed to {}", before.describe(), after.describe())
+                    }
+                    (Some(before), None) => format!("{} removed", before.describe()),
+                    (None, Some(after)) => format!("{} added", after.describe()),
+                    (None, None) => unreachable!(),
+                };
+                result.push(format!("{}: {}", chapter.display(), change));
+            }
+            removed.clear();
+            added.clear();
+        };
+        for pair in diff::align(old, new) {
+            match pair {
+                (Some(i), None) => removed.push(&old[i]),
+                (None, Some(j)) => added.push(&new[j]),
+                _ => flush(&mut removed, &mut added),
+            }
+        }
+        flush(&mut removed, &mut added);
+    }
+    result
+}
+
+/// Pairs each of the `removed` uses with the next `added` use of the same shortcode, if there
+/// is one. The rest are left unpaired.
+fn pair<'a>(removed: &[&'a Use], added: &[&'a Use]) -> Vec<(Option<&'a Use>, Option<&'a Use>)> {
+    let mut result = Vec::new();
+    let mut paired = vec![false; added.len()];
+    for before in removed {
+        let after = (0..added.len()).find(|&j| !paired[j] && added[j].name == before.name);
+        if let Some(j) = after {
+            paired[j] = true;
+        }
+        result.push((Some(*before), after.map(|j| added[j])));
+    }
+    for (j, after) in added.iter().enumerate() {
+        if !paired[j] {
+            result.push((None, Some(*after)));
+        }
+    }
+    result
+}
+
+/// Compares the records of every renderer in the `old` directory with those in the `new` one.
+pub fn diff_build(old: &Path, new: &Path) -> io::Result<Vec<String>> {
+    let mut result = Vec::new();
+    for entry in fs::read_dir(new)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let old_path = old.join(path.file_name().unwrap());
+        if !old_path.exists() {
+            continue;
+        }
+        let read = |path: &Path| -> io::Result<Record> {
+            Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+        };
+        let changes = changes(&read(&old_path)?, &read(&path)?);
+        let renderer = path.file_stem().unwrap().to_string_lossy();
+        result.extend(
+            changes
+                .into_iter()
+                .map(|change| format!("{}: {}", renderer, change)),
+        );
+    }
+    result.sort();
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes() {
+        let chapter = Path::new("guide.md");
+        let mut old = Record::default();
+        old.add(chapter, "kbd", " Ctrl /", "<kbd>Ctrl</kbd>");
+        old.add(chapter, "hint", " info", "A");
+        old.add(chapter, "unit", " 5 km /", "5 km");
+        old.add(Path::new("old.md"), "kbd", " Alt /", "<kbd>Alt</kbd>");
+        let mut new = Record::default();
+        new.add(chapter, "kbd", " Ctrl /", "<kbd>Ctrl</kbd>");
+        new.add(chapter, "hint", " warning", "A");
+        new.add(chapter, "unit", " 5 km /", "5 km (3.1 mi)");
+        new.add(chapter, "spacer", "", "");
+        assert_eq!(
+            changes(&old, &new),
+            [
+                "guide.md: `hint info` changed to `hint warning`",
+                "guide.md: `unit 5 km /` output changed",
+                "guide.md: `spacer` added",
+                "old.md: chapter removed",
+            ]
+        );
+        assert!(changes(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_diff_build() {
+        let old = tempfile::tempdir().unwrap();
+        let new = tempfile::tempdir().unwrap();
+        let mut record = Record::default();
+        record.write(old.path(), "html").unwrap();
+        record.add(Path::new("a.md"), "kbd", " Ctrl /", "<kbd>Ctrl</kbd>");
+        record.write(new.path(), "html").unwrap();
+        record.write(new.path(), "markdown").unwrap();
+        assert_eq!(
+            diff_build(old.path(), new.path()).unwrap(),
+            ["html: a.md: chapter added"]
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::cache::Cache;
+
+/// Downloads `url`, reusing a cached copy that is younger than `max_age`.
+///
+/// If the download fails, a stale copy is used when there is one, so that books still build
+/// offline. With `offline`, the cached copy is used without trying to download it.
+pub(crate) fn fetch(
+    cache: &Cache,
+    url: &str,
+    max_age: Duration,
+    offline: bool,
+) -> Result<Vec<u8>, String> {
+    let key = Cache::key(&[url.as_bytes()]);
+    if offline {
+        return cached(cache, &key, url);
+    }
+    if let Some(content) = cache.get("remote", &key, Some(max_age)) {
+        return Ok(content);
+    }
+
+    match download(url, None) {
+        Ok(Response {
+            content: Some(content),
+            ..
+        }) => {
+            if let Err(e) = cache.put("remote", &key, &content) {
+                log::warn!("Failed to cache {}: {}", url, e);
+            }
+            Ok(content)
+        }
+        Ok(_) => Err(format!("{} wasn't modified, but isn't cached", url)),
+        Err(e) => stale(cache, &key, url, e),
+    }
+}
+
+/// Downloads `url`, following the caching headers it is served with: a cached copy is reused
+/// until it is older than its `Cache-Control: max-age`, after which it is only downloaded again
+/// if its `ETag` changed.
+///
+/// As with [`fetch`], a stale copy is used if the download fails, and only the cached copy is
+/// used with `offline`.
+pub(crate) fn fetch_revalidated(
+    cache: &Cache,
+    url: &str,
+    offline: bool,
+) -> Result<Vec<u8>, String> {
+    let key = Cache::key(&[url.as_bytes()]);
+    if offline {
+        return cached(cache, &key, url);
+    }
+    let validators: Validators = cache
+        .get("remote-validators", &key, None)
+        .and_then(|json| serde_json::from_slice(&json).ok())
+        .unwrap_or_default();
+    let content = match cache.get("remote", &key, None) {
+        Some(content) if now() < validators.fresh_until => return Ok(content),
+        content => content,
+    };
+
+    let etag = content.as_ref().and(validators.etag.as_deref());
+    let response = match download(url, etag) {
+        Ok(response) => response,
+        Err(e) => return stale(cache, &key, url, e),
+    };
+    let validators = Validators {
+        etag: response.etag.or(validators.etag),
+        fresh_until: now()
+            + response
+                .cache_control
+                .as_deref()
+                .and_then(max_age)
+                .unwrap_or(0),
+    };
+    let content = match (response.content, content) {
+        (Some(content), _) => {
+            if let Err(e) = cache.put("remote", &key, &content) {
+                log::warn!("Failed to cache {}: {}", url, e);
+            }
+            content
+        }
+        (None, Some(content)) => content,
+        (None, None) => return Err(format!("{} wasn't modified, but isn't cached", url)),
+    };
+    let json = serde_json::to_vec(&validators).unwrap();
+    if let Err(e) = cache.put("remote-validators", &key, &json) {
+        log::warn!("Failed to cache the headers of {}: {}", url, e);
+    }
+    Ok(content)
+}
+
+/// What is known about the cached copy of a download, to tell whether it can be reused.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Validators {
+    etag: Option<String>,
+    /// The time until which the copy can be reused without asking the server, in seconds since
+    /// the Unix epoch.
+    fresh_until: u64,
+}
+
+/// The response to a download.
+struct Response {
+    /// The content, or `None` if it wasn't modified since the copy with the given ETag.
+    content: Option<Vec<u8>>,
+    etag: Option<String>,
+    cache_control: Option<String>,
+}
+
+fn cached(cache: &Cache, key: &str, url: &str) -> Result<Vec<u8>, String> {
+    cache.get("remote", key, None).ok_or_else(|| {
+        format!(
+            "{} isn't cached, and the `offline` option forbids downloading it",
+            url
+        )
+    })
+}
+
+fn stale(cache: &Cache, key: &str, url: &str, error: String) -> Result<Vec<u8>, String> {
+    match cache.get("remote", key, None) {
+        Some(content) => {
+            log::warn!("Failed to download {}, using cached copy: {}", url, error);
+            Ok(content)
+        }
+        None => Err(error),
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Returns the `max-age` in a `Cache-Control` header, which is zero if the response can't be
+/// reused without asking the server.
+fn max_age(cache_control: &str) -> Option<u64> {
+    let directives = cache_control
+        .split(',')
+        .map(|d| d.trim().to_ascii_lowercase());
+    let mut max_age = None;
+    for directive in directives {
+        match directive.as_str() {
+            "no-cache" | "no-store" => return Some(0),
+            _ => {
+                if let Some(seconds) = directive.strip_prefix("max-age=") {
+                    max_age = seconds.trim_matches('"').parse().ok();
+                }
+            }
+        }
+    }
+    max_age
+}
+
+#[cfg(feature = "remote")]
+fn download(url: &str, etag: Option<&str>) -> Result<Response, String> {
+    use std::io::Read;
+
+    let mut request = ureq::get(url).timeout(Duration::from_secs(30));
+    if let Some(etag) = etag {
+        request = request.set("If-None-Match", etag);
+    }
+    let response = request.call().map_err(|e| e.to_string())?;
+    let etag = response.header("ETag").map(str::to_owned);
+    let cache_control = response.header("Cache-Control").map(str::to_owned);
+    if response.status() == 304 {
+        return Ok(Response {
+            content: None,
+            etag,
+            cache_control,
+        });
+    }
+    let mut content = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut content)
+        .map_err(|e| e.to_string())?;
+    Ok(Response {
+        content: Some(content),
+        etag,
+        cache_control,
+    })
+}
+
+#[cfg(not(feature = "remote"))]
+fn download(_url: &str, _etag: Option<&str>) -> Result<Response, String> {
+    Err("mdbook-shortcodes was built without the `remote` feature".to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fetch_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().to_owned());
+        let url = "https://example.invalid/badge.svg";
+        let key = Cache::key(&[url.as_bytes()]);
+        cache.put("remote", &key, b"<svg/>").unwrap();
+        assert_eq!(
+            fetch(&cache, url, Duration::from_secs(60), false),
+            Ok(b"<svg/>".to_vec())
+        );
+
+        // A fresh copy is reused, and an offline build uses whatever is cached.
+        let validators = Validators {
+            etag: Some("\"v1\"".to_owned()),
+            fresh_until: now() + 60,
+        };
+        let json = serde_json::to_vec(&validators).unwrap();
+        cache.put("remote-validators", &key, &json).unwrap();
+        assert_eq!(
+            fetch_revalidated(&cache, url, false),
+            Ok(b"<svg/>".to_vec())
+        );
+        let missing = "https://example.invalid/missing.md";
+        assert!(fetch_revalidated(&cache, missing, true)
+            .unwrap_err()
+            .contains("`offline`"));
+        assert!(fetch(&cache, missing, Duration::from_secs(60), true).is_err());
+    }
+
+    #[test]
+    fn test_max_age() {
+        assert_eq!(max_age("public, max-age=300"), Some(300));
+        assert_eq!(max_age("max-age=300, no-cache"), Some(0));
+        assert_eq!(max_age("private"), None);
+    }
+}
@@ -0,0 +1,138 @@
+//! Tests that render chapters the way mdBook does and check the final HTML, rather than the
+//! markdown shortcodes expand to, so that structure and class name regressions are caught where
+//! readers would see them.
+
+use scraper::{Html, Selector};
+
+use crate::{process_chapter, Config, Context};
+
+/// Expands the shortcodes in `chapter` and renders it with mdBook's markdown renderer.
+fn render(chapter: &str, config: &Config) -> Html {
+    let mut ctx = Context::test(config);
+    let expanded = process_chapter(chapter, &mut ctx).unwrap();
+    assert!(ctx.errors.is_empty(), "{:?}", ctx.errors);
+    Html::parse_fragment(&mdbook::utils::render_markdown(&expanded, false))
+}
+
+fn select<'a>(html: &'a Html, selector: &str) -> Vec<scraper::ElementRef<'a>> {
+    html.select(&Selector::parse(selector).unwrap()).collect()
+}
+
+fn text(element: &scraper::ElementRef) -> String {
+    element.text().collect::<String>().trim().to_owned()
+}
+
+#[test]
+fn columns() {
+    let html = render(
+        "{{#columns}}\n\nColumn *1*\n\n{{#column}}\n\nColumn 2\n\n{{/columns}}",
+        &Config::default(),
+    );
+    let columns = select(
+        &html,
+        ".mdbook-shortcodes-columns-container > .mdbook-shortcodes-column > p",
+    );
+    assert_eq!(columns.len(), 2);
+    assert_eq!(columns[0].inner_html(), "Column <em>1</em>");
+    // Headers are rendered as style elements rather than leaking into the text.
+    assert!(!select(&html, "style").is_empty());
+    assert!(select(&html, "p").iter().all(|p| !text(p).contains('{')));
+}
+
+#[test]
+fn hint() {
+    let html = render(
+        "{{#hint warning}}\n\n**Careful**\n\n{{/hint}}",
+        &Config::default(),
+    );
+    let hints = select(
+        &html,
+        ".mdbook-shortcodes-hint.mdbook-shortcodes-hint-warning > p > strong",
+    );
+    assert_eq!(hints.len(), 1);
+}
+
+#[test]
+fn headings() {
+    // mdBook adds anchors to the headings in the rendered HTML, so they must be rendered as
+    // headings rather than left in the HTML blocks as text.
+    let html = render(
+        "{{#hint info}}\n## Setup\nText\n{{/hint}}\n\n\
+        {{#columns}}\n### Left\n{{#column}}\n### Right\n{{/columns}}",
+        &Config::default(),
+    );
+    assert_eq!(
+        text(&select(&html, ".mdbook-shortcodes-hint > h2")[0]),
+        "Setup"
+    );
+    assert_eq!(select(&html, ".mdbook-shortcodes-column > h3").len(), 2);
+}
+
+#[test]
+fn flashcard() {
+    let html = render(
+        "{{#flashcard}}\nWhat is `&mut`?\n---\nA *mutable* borrow.\n{{/flashcard}}",
+        &Config::default(),
+    );
+    let summary = select(&html, "details.mdbook-shortcodes-flashcard > summary");
+    assert_eq!(text(&summary[0]), "What is &mut?");
+    assert_eq!(
+        select(
+            &html,
+            "details.mdbook-shortcodes-flashcard > summary > code"
+        )
+        .len(),
+        1
+    );
+    assert_eq!(
+        select(&html, "details.mdbook-shortcodes-flashcard > p > em").len(),
+        1
+    );
+}
+
+#[test]
+fn search_exclude() {
+    let config = Config {
+        search_exclude: vec!["flashcard".to_owned()],
+        ..Config::default()
+    };
+    let html = render(
+        "{{#flashcard}}\nQuestion\n---\nA *mutable* borrow.\n{{/flashcard}}",
+        &config,
+    );
+    // The search index skips scripts, so the answer must only appear inside one.
+    assert!(select(&html, "details").is_empty());
+    let hidden = &select(&html, "script.mdbook-shortcodes-noindex")[0];
+    assert!(text(hidden).contains("<p>A <em>mutable</em> borrow.</p>"));
+}
+
+#[test]
+fn options() {
+    let html = render(
+        "{{#options}}\n--output, -o <FILE> :: Where to write.\n{{/options}}",
+        &Config::default(),
+    );
+    let option = &select(&html, "dl.mdbook-shortcodes-options > dt#option-output")[0];
+    assert_eq!(text(option), "--output, -o <FILE>");
+    assert_eq!(
+        select(&html, "dt#option-output > a[href='#option-output'] > em").len(),
+        1
+    );
+}
+
+#[test]
+fn envvars() {
+    let html = render(
+        "{{#envvars}}\nRUST_LOG :: info :: The log level.\n{{/envvars}}",
+        &Config::default(),
+    );
+    let button = &select(&html, "tr#env-RUST_LOG button.mdbook-shortcodes-copy")[0];
+    assert_eq!(button.value().attr("data-copy"), Some("RUST_LOG"));
+    assert_eq!(select(&html, "script").len(), 1);
+}
+
+#[test]
+fn kbd_in_paragraph() {
+    let html = render("Press {{#kbd Ctrl+C /}} to copy.", &Config::default());
+    assert_eq!(select(&html, "p kbd").len(), 3);
+}
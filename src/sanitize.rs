@@ -0,0 +1,87 @@
+/// Removes dangerous HTML (e.g. `<script>` tags and `onclick` attributes) from the body of a
+/// shortcode, leaving its markdown as it is.
+///
+/// HTML blocks are cleaned as a whole. Inline tags are cleaned one at a time, as markdown
+/// separates them from their content.
+#[cfg(feature = "sanitize")]
+pub(crate) fn body(content: &str) -> String {
+    use pulldown_cmark::{Event, Tag, TagEnd};
+
+    let mut result = String::with_capacity(content.len());
+    let mut last = 0;
+    let mut in_block = false;
+    for (event, range) in mdbook::utils::new_cmark_parser(content, false).into_offset_iter() {
+        let clean = match event {
+            Event::Start(Tag::HtmlBlock) => {
+                in_block = true;
+                ammonia::clean(&content[range.clone()])
+            }
+            Event::End(TagEnd::HtmlBlock) => {
+                in_block = false;
+                continue;
+            }
+            Event::InlineHtml(tag) if !in_block => inline_tag(&tag),
+            _ => continue,
+        };
+        result.push_str(&content[last..range.start]);
+        result.push_str(&clean);
+        last = range.end;
+    }
+    result.push_str(&content[last..]);
+    result
+}
+
+/// Without the `sanitize` feature, the whole body is escaped, which is safe but shows any HTML
+/// in it as text.
+#[cfg(not(feature = "sanitize"))]
+pub(crate) fn body(content: &str) -> String {
+    crate::html::escape(content)
+}
+
+/// Cleans a single opening or closing tag, e.g. `<a href="..." onclick="...">`.
+#[cfg(feature = "sanitize")]
+fn inline_tag(tag: &str) -> String {
+    let tag = tag.trim();
+    if let Some(name) = tag.strip_prefix("</") {
+        let name = name.trim_end_matches('>').trim().to_ascii_lowercase();
+        return if ammonia::Builder::default()
+            .clone_tags()
+            .contains(name.as_str())
+        {
+            format!("</{}>", name)
+        } else {
+            String::new()
+        };
+    }
+
+    // Ammonia closes the elements it opens, but the closing tag is a separate event.
+    let clean = ammonia::clean(tag);
+    let name: String = clean
+        .chars()
+        .skip(1)
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect();
+    match clean.strip_suffix(&format!("</{}>", name)) {
+        Some(open) => open.to_owned(),
+        None => clean,
+    }
+}
+
+#[cfg(all(test, feature = "sanitize"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_body() {
+        let input = "Some **markdown** with <b onclick=\"steal()\">bold</b> and \
+            <img src=\"a.png\" onerror=\"steal()\">.\n\n\
+            <div>\n<script>steal()</script>\n</div>\n\n\
+            ```html\n<script>shown as code</script>\n```\n\n> a quote <!-- comment -->\n";
+        assert_eq!(
+            body(input),
+            "Some **markdown** with <b>bold</b> and <img src=\"a.png\">.\n\n\
+            <div>\n\n</div>\n\n\
+            ```html\n<script>shown as code</script>\n```\n\n> a quote \n"
+        );
+    }
+}
@@ -0,0 +1,36 @@
+use crate::{shortcodes::script_open_tag, Config};
+
+/// Hides the output of a shortcode from mdBook's search index, which skips the contents of
+/// `<script>` tags.
+///
+/// The output is rendered to HTML (as markdown inside a script isn't) and inserted into the
+/// page by a script once it loads, so readers still see it.
+pub(crate) fn exclude(content: &str, config: &Config) -> String {
+    let html = mdbook::utils::render_markdown(content, false);
+    format!(
+        "\n<script type=\"text/html\" class=\"mdbook-shortcodes-noindex\">{}</script>\
+        {}(function (t) {{\
+        t.replaceWith(document.createRange().createContextualFragment(\
+        t.textContent.replace(/<\\\\\\/script/gi, \"</script\")));\
+        }})(document.currentScript.previousElementSibling);</script>\n",
+        // The contents of a script end at the first `</script`, wherever it is.
+        html.replace("</script", "<\\/script"),
+        script_open_tag(false, config)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exclude() {
+        let config = Config::default();
+        let result = exclude("**Answer**<script>alert(1);</script>", &config);
+        assert!(result.starts_with(
+            "\n<script type=\"text/html\" class=\"mdbook-shortcodes-noindex\">\
+            <p><strong>Answer</strong><script>alert(1);<\\/script></p>\n</script><script>"
+        ));
+        assert_eq!(result.matches("</script>").count(), 2);
+    }
+}
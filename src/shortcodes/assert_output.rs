@@ -0,0 +1,151 @@
+use crate::{diff, exec, markdown, Attrs, Capability, Context, MatchResult, Shortcode};
+
+/// Checks that the output of a command matches the output shown in the book.
+///
+/// The first fenced code block in the body is run using the system shell (for `console` blocks,
+/// only the lines starting with `$ ` are run) and its output is compared with the second block.
+/// A command that can't be run or exits with an error is reported like changed output.
+pub(crate) struct AssertOutput;
+
+impl Shortcode for AssertOutput {
+    const NAME: &'static str = "assert-output";
+    const HEADER: &'static str = "";
+    const CAPABILITY: Capability = Capability::Exec;
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &["cwd"])?;
+        let cwd = attrs.get("cwd").unwrap_or(".");
+
+        let blocks = markdown::fenced_blocks(input);
+        let (script, expected) = match blocks.as_slice() {
+            [command, expected, ..] => (script(command), expected.content),
+            _ => {
+                return Err(
+                    "assert-output shortcode needs a command block and an output block".to_owned(),
+                )
+            }
+        };
+
+        let mut command = exec::shell(&script);
+        command.current_dir(ctx.root.join(cwd));
+        match exec::run(ctx, Self::NAME, &mut command) {
+            Ok(output) if !output.status.success() => ctx.fail(
+                Self::NAME,
+                &format!(
+                    "`{}` failed: {}: {}",
+                    script.trim(),
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            ),
+            Ok(output) => {
+                let actual = normalize(&String::from_utf8_lossy(&output.stdout));
+                let expected = normalize(expected);
+                if actual != expected {
+                    ctx.fail(
+                        Self::NAME,
+                        &format!(
+                            "the output of `{}` has changed:\n{}",
+                            script.trim(),
+                            diff::lines(&expected, &actual)
+                        ),
+                    );
+                }
+            }
+            Err(e) => ctx.fail(Self::NAME, &format!("output not checked: {}", e)),
+        }
+
+        Ok(input.to_owned())
+    }
+}
+
+fn script(block: &markdown::FencedBlock) -> String {
+    if block.lang == "console" {
+        block
+            .content
+            .lines()
+            .filter_map(|line| line.strip_prefix("$ "))
+            .flat_map(|line| [line, "\n"])
+            .collect()
+    } else {
+        block.content.to_owned()
+    }
+}
+
+/// Removes trailing whitespace, which is easy to lose when copying output into a book.
+fn normalize(output: &str) -> String {
+    output
+        .trim_end()
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_assert_output() {
+        let config = Config {
+            allow_exec: true,
+            ..Default::default()
+        };
+        let mut ctx = Context::test(&config);
+        let input = "{{#assert-output}}
+```console
+$ echo hello
+$ echo world
+```
+
+```text
+hello
+world  
+```
+{{/assert-output}}";
+        assert_eq!(
+            AssertOutput::process_raw(input, &mut ctx),
+            Ok(input[18..input.len() - 18].to_owned())
+        );
+        assert!(ctx.errors.is_empty());
+
+        let input = "{{#assert-output}}
+```sh
+echo hello
+```
+
+```text
+goodbye
+```
+{{/assert-output}}";
+        AssertOutput::process_raw(input, &mut ctx).unwrap();
+        assert_eq!(
+            ctx.errors,
+            vec![
+                ":1:1: assert-output shortcode: the output of `echo hello` has changed:\n-goodbye\n+hello\n"
+            ]
+        );
+
+        // A command that fails is an error, even if its output matches.
+        let input = "{{#assert-output}}
+```sh
+echo hello; echo oops >&2; exit 3
+```
+
+```text
+hello
+```
+{{/assert-output}}";
+        AssertOutput::process_raw(input, &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 2);
+        assert!(ctx.errors[1].contains("exit status: 3: oops"));
+
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        AssertOutput::process_raw(input, &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 1);
+        assert!(ctx.errors[0].contains("requires `allow-exec = true`"));
+    }
+}
@@ -0,0 +1,167 @@
+use std::time::Duration;
+
+use crate::{
+    cache::Cache, html, remote, Attrs, BadgeMode, Capability, Context, MatchResult, Shortcode,
+};
+
+/// How long downloaded badges are reused for before being downloaded again.
+const BADGE_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A badge showing information about a crate, e.g. `{{#crate-badge serde downloads /}}`.
+pub(crate) struct CrateBadge;
+
+impl Shortcode for CrateBadge {
+    const NAME: &'static str = "crate-badge";
+    const HEADER: &'static str = "";
+    const CAPABILITY: Capability = Capability::Network;
+
+    fn process_match(_input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(2, &["style"])?;
+        let (krate, kind) = match attrs.positional() {
+            [krate] => (*krate, "version"),
+            [krate, kind] => (*krate, *kind),
+            _ => return Err("wrong number of arguments given to crate-badge shortcode".to_owned()),
+        };
+        let path = match kind {
+            "version" => "v",
+            "downloads" => "d",
+            "license" => "l",
+            _ => return Err("unknown crate-badge type".to_owned()),
+        };
+
+        Ok(badge(
+            ctx,
+            Self::NAME,
+            &format!("https://img.shields.io/crates/{}/{}", path, krate),
+            &format!("https://crates.io/crates/{}", krate),
+            &format!("{} {}", krate, kind),
+            attrs.get("style"),
+        ))
+    }
+}
+
+/// A badge showing information about a GitHub repository, e.g. `{{#repo-badge org/repo stars /}}`.
+pub(crate) struct RepoBadge;
+
+impl Shortcode for RepoBadge {
+    const NAME: &'static str = "repo-badge";
+    const HEADER: &'static str = "";
+    const CAPABILITY: Capability = Capability::Network;
+
+    fn process_match(_input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(2, &["style"])?;
+        let (repo, kind) = match attrs.positional() {
+            [repo] => (*repo, "stars"),
+            [repo, kind] => (*repo, *kind),
+            _ => return Err("wrong number of arguments given to repo-badge shortcode".to_owned()),
+        };
+        let path = match kind {
+            "stars" | "forks" | "license" | "last-commit" => format!("{}/{}", kind, repo),
+            "issues" => format!("issues/{}", repo),
+            "release" => format!("v/release/{}", repo),
+            _ => return Err("unknown repo-badge type".to_owned()),
+        };
+
+        Ok(badge(
+            ctx,
+            Self::NAME,
+            &format!("https://img.shields.io/github/{}", path),
+            &format!("https://github.com/{}", repo),
+            &format!("{} {}", repo, kind),
+            attrs.get("style"),
+        ))
+    }
+}
+
+fn badge(
+    ctx: &mut Context,
+    name: &str,
+    image: &str,
+    link: &str,
+    alt: &str,
+    style: Option<&str>,
+) -> String {
+    let image = match style {
+        Some(style) => format!("{}?style={}", image, style),
+        None => image.to_owned(),
+    };
+
+    let src = match ctx.config.badges {
+        BadgeMode::Hotlink => image,
+        BadgeMode::Static => {
+            match remote::fetch(&ctx.cache, &image, BADGE_MAX_AGE, ctx.config.offline) {
+                Ok(svg) => {
+                    let dest = format!("badges/{}.svg", Cache::key(&[image.as_bytes()]));
+                    ctx.assets.add_content(svg, &dest);
+                    format!("{}{}/{}", ctx.path_to_root(), ctx.config.assets_dir, dest)
+                }
+                Err(e) => {
+                    ctx.warn(
+                        name,
+                        &format!("failed to download {}, hotlinking it instead: {}", image, e),
+                    );
+                    image
+                }
+            }
+        }
+    };
+
+    format!(
+        "<a class=\"mdbook-shortcodes-badge\" href=\"{}\"><img src=\"{}\" alt=\"{}\"></a>",
+        html::escape(link),
+        html::escape(&src),
+        html::escape(alt)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_badges() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        assert_eq!(
+            CrateBadge::process_raw("{{#crate-badge serde /}}", &mut ctx),
+            Ok(
+                "<a class=\"mdbook-shortcodes-badge\" href=\"https://crates.io/crates/serde\">\
+                <img src=\"https://img.shields.io/crates/v/serde\" alt=\"serde version\"></a>"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            RepoBadge::process_raw(
+                "{{#repo-badge rust-lang/mdBook release style=flat-square /}}",
+                &mut ctx
+            ),
+            Ok("<a class=\"mdbook-shortcodes-badge\" href=\"https://github.com/rust-lang/mdBook\">\
+                <img src=\"https://img.shields.io/github/v/release/rust-lang/mdBook?style=flat-square\" \
+                alt=\"rust-lang/mdBook release\"></a>"
+                .to_owned())
+        );
+    }
+
+    #[test]
+    fn test_static_badge() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            badges: BadgeMode::Static,
+            ..Default::default()
+        };
+        let mut ctx = Context::new(&config, dir.path().to_owned(), dir.path().join("src"));
+        let image = "https://img.shields.io/crates/d/serde";
+        let key = Cache::key(&[image.as_bytes()]);
+        ctx.cache.put("remote", &key, b"<svg/>").unwrap();
+
+        assert_eq!(
+            CrateBadge::process_raw("{{#crate-badge serde downloads /}}", &mut ctx),
+            Ok(format!(
+                "<a class=\"mdbook-shortcodes-badge\" href=\"https://crates.io/crates/serde\">\
+                <img src=\"shortcodes-assets/badges/{}.svg\" alt=\"serde downloads\"></a>",
+                key
+            ))
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{locale::Locale, Attrs, Context, MatchResult, Shortcode};
+
+/// What the `book-stats` shortcode is replaced with until every chapter has been counted.
+pub(crate) const BOOK_STATS_MARKER: &str = "<!-- mdbook-shortcodes-book-stats -->";
+
+/// Shows the number of chapters, words and figures in the whole book, and how long it takes
+/// to read, e.g. `{{#book-stats /}}` on a colophon page.
+pub(crate) struct BookStats;
+
+impl Shortcode for BookStats {
+    const NAME: &'static str = "book-stats";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-book-stats {
+        display: grid;
+        grid-template-columns: max-content auto;
+        gap: .25em 1.5em;
+    }
+    .mdbook-shortcodes-book-stats dt {
+        font-weight: bold;
+    }
+    .mdbook-shortcodes-book-stats dd {
+        margin: 0;
+    }
+</style>
+";
+
+    fn process_match(input: &str, attrs: Attrs, _ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &[])?;
+        if !input.trim().is_empty() {
+            return Err("book-stats shortcode must be self-closing".to_owned());
+        }
+        // Later chapters haven't been counted yet.
+        Ok(BOOK_STATS_MARKER.to_owned())
+    }
+}
+
+/// Returns the statistics of the whole book.
+pub(crate) fn book_stats(ctx: &Context) -> String {
+    let locale = Locale::from_language(&ctx.language);
+    let count = |n: usize| locale.format_number(n as f64, 0);
+    let stats = &ctx.stats;
+    format!(
+        "<dl class=\"mdbook-shortcodes-book-stats\">\
+        <dt>Chapters</dt><dd>{}</dd>\
+        <dt>Words</dt><dd>{}</dd>\
+        <dt>Figures</dt><dd>{}</dd>\
+        <dt>Reading time</dt><dd>{}</dd></dl>",
+        count(stats.chapters),
+        count(stats.words),
+        count(stats.figures),
+        reading_time(stats.reading_minutes)
+    )
+}
+
+/// Returns a duration such as `1 hour 5 minutes`.
+fn reading_time(minutes: usize) -> String {
+    let plural = |n: usize, unit: &str| format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" });
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => plural(minutes, "minute"),
+        (hours, 0) => plural(hours, "hour"),
+        (hours, minutes) => format!("{} {}", plural(hours, "hour"), plural(minutes, "minute")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_book_stats() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        assert_eq!(
+            BookStats::process_raw("{{#book-stats /}}", &mut ctx),
+            Ok(BOOK_STATS_MARKER.to_owned())
+        );
+        ctx.stats.chapters = 12;
+        ctx.stats.words = 15300;
+        ctx.stats.figures = 1;
+        ctx.stats.reading_minutes = 77;
+        assert_eq!(
+            book_stats(&ctx),
+            "<dl class=\"mdbook-shortcodes-book-stats\">\
+            <dt>Chapters</dt><dd>12</dd>\
+            <dt>Words</dt><dd>15,300</dd>\
+            <dt>Figures</dt><dd>1</dd>\
+            <dt>Reading time</dt><dd>1 hour 17 minutes</dd></dl>"
+        );
+        assert_eq!(reading_time(1), "1 minute");
+        assert_eq!(reading_time(120), "2 hours");
+    }
+}
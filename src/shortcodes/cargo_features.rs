@@ -0,0 +1,203 @@
+use std::path::{Path, PathBuf};
+
+use toml_edit::{DocumentMut, Item, Table};
+
+use crate::{html, Attrs, Capability, Context, MatchResult, Shortcode};
+
+/// Lists the features of a crate, along with the features and dependencies they enable, e.g.
+/// `{{#cargo-features crate="mycrate" /}}`.
+///
+/// The crate is looked up in the workspace whose manifest is given by `manifest` (by default
+/// `Cargo.toml` in the book's root directory). The comment above each feature in the manifest
+/// is used as its description.
+pub(crate) struct CargoFeatures;
+
+impl Shortcode for CargoFeatures {
+    const NAME: &'static str = "cargo-features";
+    const HEADER: &'static str = "";
+    const CAPABILITY: Capability = Capability::Filesystem;
+
+    fn process_match(_input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &["crate", "manifest"])?;
+        let manifest = ctx.root.join(attrs.get("manifest").unwrap_or("Cargo.toml"));
+        ctx.watch(&manifest);
+
+        let document = match find_crate(&manifest, attrs.get("crate")) {
+            Ok(document) => document,
+            Err(e) => {
+                ctx.fail(Self::NAME, &e);
+                return Ok(String::new());
+            }
+        };
+        match document.get("features").and_then(Item::as_table) {
+            Some(features) => Ok(render(features)),
+            None => Ok("<p>This crate has no features.</p>".to_owned()),
+        }
+    }
+}
+
+fn read(path: &Path) -> Result<DocumentMut, String> {
+    std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())?
+        .parse()
+        .map_err(|e| format!("invalid manifest `{}`: {}", path.display(), e))
+}
+
+fn package_name(document: &DocumentMut) -> Option<&str> {
+    document.get("package")?.get("name")?.as_str()
+}
+
+/// Returns the manifest of the crate called `name` in the workspace or package at `manifest`,
+/// or the manifest itself if no name is given.
+fn find_crate(manifest: &Path, name: Option<&str>) -> Result<DocumentMut, String> {
+    let document = read(manifest)?;
+    let name = match name {
+        Some(name) if package_name(&document) != Some(name) => name,
+        _ => return Ok(document),
+    };
+
+    let dir = manifest.parent().unwrap_or_else(|| Path::new(""));
+    let members = document
+        .get("workspace")
+        .and_then(|workspace| workspace.get("members"))
+        .and_then(Item::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|member| member.as_str());
+    for member in members {
+        // Only trailing wildcards (e.g. `crates/*`) are supported, as they are by far the most
+        // common.
+        let mut dirs: Vec<PathBuf> = match member.strip_suffix("/*") {
+            Some(parent) => std::fs::read_dir(dir.join(parent))
+                .map_err(|e| e.to_string())?
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .collect(),
+            None => vec![dir.join(member)],
+        };
+        // Directory listings aren't ordered, and the result must be the same on every platform.
+        dirs.sort();
+        for member_dir in dirs {
+            let member_manifest = member_dir.join("Cargo.toml");
+            if !member_manifest.is_file() {
+                continue;
+            }
+            let member = read(&member_manifest)?;
+            if package_name(&member) == Some(name) {
+                return Ok(member);
+            }
+        }
+    }
+    Err(format!(
+        "couldn't find crate `{}` in `{}`",
+        name,
+        manifest.display()
+    ))
+}
+
+fn render(features: &Table) -> String {
+    let defaults: Vec<&str> = features
+        .get("default")
+        .and_then(Item::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|f| f.as_str())
+        .collect();
+
+    let mut result = String::from("<dl class=\"mdbook-shortcodes-cargo-features\">");
+    for (name, item) in features.iter() {
+        if name == "default" {
+            continue;
+        }
+        result.push_str(&format!("<dt><code>{}</code>", html::escape(name)));
+        if defaults.contains(&name) {
+            result.push_str(" (default)");
+        }
+        result.push_str("</dt><dd>");
+
+        let description = features
+            .key(name)
+            .and_then(|key| key.leaf_decor().prefix())
+            .and_then(|prefix| prefix.as_str())
+            .map(doc_comment)
+            .unwrap_or_default();
+        result.push_str(&html::escape(&description));
+
+        let enables: Vec<String> = item
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|f| f.as_str())
+            .map(|f| format!("<code>{}</code>", html::escape(f)))
+            .collect();
+        if !enables.is_empty() {
+            if !description.is_empty() {
+                result.push(' ');
+            }
+            result.push_str(&format!("Enables {}.", enables.join(", ")));
+        }
+        result.push_str("</dd>");
+    }
+    result.push_str("</dl>");
+    result
+}
+
+/// Returns the text of the comment lines directly above a key.
+fn doc_comment(prefix: &str) -> String {
+    let mut lines = Vec::new();
+    for line in prefix.lines().rev() {
+        match line.trim().strip_prefix('#') {
+            Some(comment) => lines.push(comment.trim()),
+            None => break,
+        }
+    }
+    lines.reverse();
+    lines.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_cargo_features() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(dir.path().join("crates/core")).unwrap();
+        std::fs::write(
+            dir.path().join("crates/core/Cargo.toml"),
+            "[package]
+name = \"mycrate\"
+
+[features]
+default = [\"std\"]
+# Uses the standard library.
+std = []
+
+# Adds serialisation
+# support.
+serde = [\"dep:serde\", \"std\"]
+",
+        )
+        .unwrap();
+
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        ctx.root = dir.path().to_owned();
+        let expected = "<dl class=\"mdbook-shortcodes-cargo-features\">\
+            <dt><code>std</code> (default)</dt><dd>Uses the standard library.</dd>\
+            <dt><code>serde</code></dt><dd>Adds serialisation support. \
+            Enables <code>dep:serde</code>, <code>std</code>.</dd></dl>";
+        assert_eq!(
+            CargoFeatures::process_raw("{{#cargo-features crate=mycrate /}}", &mut ctx),
+            Ok(expected.to_owned())
+        );
+
+        CargoFeatures::process_raw("{{#cargo-features crate=other /}}", &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 1);
+    }
+}
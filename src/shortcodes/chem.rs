@@ -0,0 +1,193 @@
+use crate::{cache::Cache, exec, html, Attrs, Capability, Context, MatchResult, Shortcode};
+
+/// Shows a chemical formula with subscripts and charges, e.g. `{{#chem H2SO4 /}}`,
+/// `{{#chem SO4^2- /}}` or `{{#chem CuSO4.5H2O /}}`.
+pub(crate) struct Chem;
+
+impl Shortcode for Chem {
+    const NAME: &'static str = "chem";
+    const HEADER: &'static str = "";
+
+    fn process_match(_input: &str, attrs: Attrs, _ctx: &mut Context) -> MatchResult {
+        match attrs.positional() {
+            [formula] => Ok(format!(
+                "<span class=\"mdbook-shortcodes-chem\">{}</span>",
+                render_formula(formula)
+            )),
+            _ => Err("wrong number of arguments given to chem shortcode".to_owned()),
+        }
+    }
+}
+
+fn render_formula(formula: &str) -> String {
+    let mut result = String::new();
+    let chars: Vec<char> = formula.chars().collect();
+    // Whether the previous character can take a subscript (i.e. is part of an element or closes
+    // a group), as opposed to digits at the start of a term being a coefficient.
+    let mut after_element = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_ascii_digit() && after_element {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let digits: String = chars[start..i].iter().collect();
+            result.push_str(&format!("<sub>{}</sub>", digits));
+            continue;
+        }
+
+        match c {
+            // An explicit charge, e.g. `^2-` or `^+`.
+            '^' => {
+                let start = i + 1;
+                i = start;
+                while i < chars.len()
+                    && (chars[i].is_ascii_digit() || chars[i] == '+' || chars[i] == '-')
+                {
+                    i += 1;
+                }
+                let charge: String = chars[start..i].iter().collect();
+                result.push_str(&format!("<sup>{}</sup>", html::escape(&charge)));
+                after_element = false;
+                continue;
+            }
+            // A charge at the end of an ion, e.g. `Na+`.
+            '+' | '-' if after_element && chars.get(i + 1).is_none_or(|c| c.is_whitespace()) => {
+                result.push_str(&format!("<sup>{}</sup>", c));
+                after_element = false;
+            }
+            // Water of crystallisation, e.g. `CuSO4.5H2O`.
+            '.' | '*' => {
+                result.push('·');
+                after_element = false;
+            }
+            _ => {
+                result.push_str(&html::escape(&c.to_string()));
+                after_element = c.is_ascii_alphabetic() || c == ')' || c == ']';
+            }
+        }
+        i += 1;
+    }
+
+    result
+}
+
+/// Draws the structure of a molecule given in SMILES notation, e.g. `{{#smiles CCO /}}`, using
+/// the command set by `smiles-command`. Without a command, the SMILES string is shown instead.
+pub(crate) struct Smiles;
+
+impl Shortcode for Smiles {
+    const NAME: &'static str = "smiles";
+    const HEADER: &'static str = "";
+    const CAPABILITY: Capability = Capability::Exec;
+
+    fn process_match(_input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        let smiles = match attrs.positional() {
+            [smiles] => *smiles,
+            _ => return Err("wrong number of arguments given to smiles shortcode".to_owned()),
+        };
+        let fallback = format!(
+            "<code class=\"mdbook-shortcodes-smiles\">{}</code>",
+            html::escape(smiles)
+        );
+        let command = match &ctx.config.smiles_command {
+            Some(command) => command.replace("{smiles}", &exec::quote(smiles)),
+            None => return Ok(fallback),
+        };
+
+        let key = Cache::key(&[command.as_bytes()]);
+        let svg = match ctx.cache.get("smiles", &key, None) {
+            Some(svg) => svg,
+            None => match exec::run(ctx, Self::NAME, &mut exec::shell(&command)) {
+                Ok(output) if output.status.success() => {
+                    if let Err(e) = ctx.cache.put("smiles", &key, &output.stdout) {
+                        ctx.warn(Self::NAME, &format!("failed to cache structure: {}", e));
+                    }
+                    output.stdout
+                }
+                Ok(output) => {
+                    ctx.warn(
+                        Self::NAME,
+                        &format!(
+                            "`{}` failed: {}",
+                            command,
+                            String::from_utf8_lossy(&output.stderr).trim()
+                        ),
+                    );
+                    return Ok(fallback);
+                }
+                Err(e) => {
+                    ctx.warn(Self::NAME, &e);
+                    return Ok(fallback);
+                }
+            },
+        };
+
+        let dest = format!("smiles/{}.svg", key);
+        ctx.assets.add_content(svg, &dest);
+        Ok(format!(
+            "<img class=\"mdbook-shortcodes-smiles\" src=\"{}{}/{}\" alt=\"{}\">",
+            ctx.path_to_root(),
+            ctx.config.assets_dir,
+            dest,
+            html::escape(smiles)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_render_formula() {
+        let cases = [
+            ("H2SO4", "H<sub>2</sub>SO<sub>4</sub>"),
+            ("2H2O", "2H<sub>2</sub>O"),
+            ("SO4^2-", "SO<sub>4</sub><sup>2-</sup>"),
+            ("Na+", "Na<sup>+</sup>"),
+            ("Ca(OH)2", "Ca(OH)<sub>2</sub>"),
+            ("CuSO4.5H2O", "CuSO<sub>4</sub>·5H<sub>2</sub>O"),
+        ];
+        for (input, expected) in cases.iter() {
+            assert_eq!(render_formula(input), *expected);
+        }
+    }
+
+    #[test]
+    fn test_smiles_without_command() {
+        let config = Config::default();
+        assert_eq!(
+            Smiles::process_raw("{{#smiles CCO /}}", &mut Context::test(&config)),
+            Ok("<code class=\"mdbook-shortcodes-smiles\">CCO</code>".to_owned())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_smiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            allow_exec: true,
+            smiles_command: Some("printf '<svg>%s</svg>' {smiles}".to_owned()),
+            ..Default::default()
+        };
+        let mut ctx = Context::new(&config, dir.path().to_owned(), dir.path().join("src"));
+        let key = Cache::key(&[b"printf '<svg>%s</svg>' 'CCO'"]);
+        assert_eq!(
+            Smiles::process_raw("{{#smiles CCO /}}", &mut ctx),
+            Ok(format!(
+                "<img class=\"mdbook-shortcodes-smiles\" src=\"shortcodes-assets/smiles/{}.svg\" alt=\"CCO\">",
+                key
+            ))
+        );
+        assert_eq!(
+            ctx.cache.get("smiles", &key, None),
+            Some(b"<svg>CCO</svg>".to_vec())
+        );
+    }
+}
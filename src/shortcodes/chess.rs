@@ -0,0 +1,203 @@
+use crate::{html, markdown, Attrs, Context, MatchResult, Shortcode};
+
+/// The size of a square in the generated SVG.
+const SQUARE: usize = 45;
+
+/// Draws a chess position given in Forsyth–Edwards Notation, e.g.
+/// `{{#chess fen="rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1" /}}`. The body, if
+/// any, is used as a caption and `flip` shows the board from black's side.
+pub(crate) struct Chess;
+
+impl Shortcode for Chess {
+    const NAME: &'static str = "chess";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-board {
+        margin: 1em 0;
+        text-align: center;
+    }
+    .mdbook-shortcodes-board svg {
+        max-width: 100%;
+        height: auto;
+    }
+</style>
+";
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(1, &["fen"])?;
+        let flip = match attrs.positional() {
+            [] => false,
+            ["flip"] => true,
+            _ => return Err("unknown argument given to chess shortcode".to_owned()),
+        };
+        let fen = attrs.get("fen").ok_or("no fen given to chess shortcode")?;
+
+        let board = match parse_fen(fen) {
+            Ok(board) => board,
+            Err(e) => {
+                ctx.fail(Self::NAME, &format!("invalid FEN `{}`: {}", fen, e));
+                return Ok(String::new());
+            }
+        };
+        Ok(figure(
+            &render_svg(&board, flip),
+            input,
+            &format!("Chess position: {}", fen),
+        ))
+    }
+}
+
+/// Wraps a board in a figure, with an optional caption.
+pub(crate) fn figure(svg: &str, caption: &str, label: &str) -> String {
+    let caption = caption.trim();
+    let caption = if caption.is_empty() {
+        String::new()
+    } else {
+        format!("<figcaption>{}</figcaption>", markdown::inline(caption))
+    };
+    format!(
+        "<figure class=\"mdbook-shortcodes-board\" role=\"img\" aria-label=\"{}\">{}{}</figure>",
+        html::escape(label),
+        svg,
+        caption
+    )
+}
+
+/// Returns the piece on each square, starting from a8 and going rank by rank.
+fn parse_fen(fen: &str) -> Result<Vec<Vec<Option<char>>>, String> {
+    let placement = fen.split_whitespace().next().unwrap_or("");
+    let mut board = Vec::new();
+    for rank in placement.split('/') {
+        let mut squares = Vec::new();
+        for c in rank.chars() {
+            match c {
+                '1'..='8' => {
+                    for _ in 0..c.to_digit(10).unwrap() {
+                        squares.push(None);
+                    }
+                }
+                'p' | 'n' | 'b' | 'r' | 'q' | 'k' | 'P' | 'N' | 'B' | 'R' | 'Q' | 'K' => {
+                    squares.push(Some(c))
+                }
+                _ => return Err(format!("unknown piece `{}`", c)),
+            }
+        }
+        if squares.len() != 8 {
+            return Err("every rank must have 8 squares".to_owned());
+        }
+        board.push(squares);
+    }
+    if board.len() != 8 {
+        return Err("there must be 8 ranks".to_owned());
+    }
+    Ok(board)
+}
+
+fn render_svg(board: &[Vec<Option<char>>], flip: bool) -> String {
+    let size = SQUARE * 8;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {0} {0}\" width=\"{0}\" height=\"{0}\">",
+        size
+    );
+
+    for row in 0..8 {
+        for col in 0..8 {
+            let (rank, file) = if flip { (7 - row, 7 - col) } else { (row, col) };
+            let (x, y) = (col * SQUARE, row * SQUARE);
+            let dark = (row + col) % 2 == 1;
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\" fill=\"{3}\"/>",
+                x,
+                y,
+                SQUARE,
+                if dark { "#b58863" } else { "#f0d9b5" }
+            ));
+
+            if let Some(piece) = board[rank][file] {
+                // The solid glyphs are used for both sides, so that white pieces aren't see-through.
+                let glyph = match piece.to_ascii_lowercase() {
+                    'k' => '♚',
+                    'q' => '♛',
+                    'r' => '♜',
+                    'b' => '♝',
+                    'n' => '♞',
+                    _ => '♟',
+                };
+                let fill = if piece.is_uppercase() { "#fff" } else { "#000" };
+                svg.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-size=\"{}\" text-anchor=\"middle\" \
+                    dominant-baseline=\"central\" fill=\"{}\" stroke=\"{}\">{}\u{fe0e}</text>",
+                    x + SQUARE / 2,
+                    y + SQUARE / 2,
+                    SQUARE * 4 / 5,
+                    fill,
+                    "#000",
+                    glyph
+                ));
+            }
+
+            // Coordinates are drawn along the bottom and left edges.
+            let label_fill = if dark { "#f0d9b5" } else { "#b58863" };
+            if row == 7 {
+                svg.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-size=\"10\" text-anchor=\"end\" fill=\"{}\">{}</text>",
+                    x + SQUARE - 2,
+                    y + SQUARE - 2,
+                    label_fill,
+                    (b'a' + file as u8) as char
+                ));
+            }
+            if col == 0 {
+                svg.push_str(&format!(
+                    "<text x=\"2\" y=\"{}\" font-size=\"10\" fill=\"{}\">{}</text>",
+                    y + 11,
+                    label_fill,
+                    8 - rank
+                ));
+            }
+        }
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_parse_fen() {
+        let board =
+            parse_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        assert_eq!(board[0][4], Some('k'));
+        assert_eq!(board[4][4], Some('P'));
+        assert_eq!(board[6][4], None);
+        assert!(parse_fen("8/8/8").is_err());
+        assert!(parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX").is_err());
+    }
+
+    #[test]
+    fn test_chess() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let result = Chess::process_raw(
+            "{{#chess fen=\"4k3/8/8/8/8/8/8/4K3 w - - 0 1\" flip}}Kings only{{/chess}}",
+            &mut ctx,
+        )
+        .unwrap();
+        assert_eq!(ctx.headers, [Chess::HEADER]);
+        assert!(result.contains("aria-label=\"Chess position: 4k3/8/8/8/8/8/8/4K3 w - - 0 1\""));
+        assert!(result.contains("<figcaption>Kings only</figcaption>"));
+        // Flipped, the white king on e1 is on the fourth column of the top row.
+        assert!(result.contains(
+            "<text x=\"157\" y=\"22\" font-size=\"36\" text-anchor=\"middle\" \
+            dominant-baseline=\"central\" fill=\"#fff\" stroke=\"#000\">♚\u{fe0e}</text>"
+        ));
+        assert!(!result.contains('\n'));
+
+        Chess::process_raw("{{#chess fen=\"8/8\" /}}", &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 1);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{
+    analytics, cache::Cache, diff, exec, html, Attrs, Capability, Context, MatchResult, Shortcode,
+};
+
+/// Runs a command at build time and shows its help output, e.g.
+/// `{{#cli-help command="mytool --help" /}}`.
+///
+/// With `diff`, the output is compared with that of the previous build, and any changes are
+/// reported as a warning and shown below the help.
+pub(crate) struct CliHelp;
+
+impl Shortcode for CliHelp {
+    const NAME: &'static str = "cli-help";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-cli-help {
+        border-inline-start: .25rem solid var(--quote-border);
+    }
+    .mdbook-shortcodes-cli-help-diff .mdbook-shortcodes-cli-help-added {
+        color: #5b6;
+    }
+    .mdbook-shortcodes-cli-help-diff .mdbook-shortcodes-cli-help-removed {
+        color: #f66;
+    }
+</style>
+";
+    const CAPABILITY: Capability = Capability::Exec;
+
+    fn process_match(_input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(1, &["command", "cwd"])?;
+        let show_diff = match attrs.positional() {
+            [] => false,
+            ["diff"] => true,
+            _ => return Err("unknown argument given to cli-help shortcode".to_owned()),
+        };
+        let command = attrs
+            .get("command")
+            .ok_or("no command given to cli-help shortcode")?;
+        let cwd = attrs.get("cwd").unwrap_or(".");
+
+        let mut shell = exec::shell(command);
+        shell.current_dir(ctx.root.join(cwd));
+        let help = match exec::run(ctx, Self::NAME, &mut shell) {
+            // Some programs print their help to stderr.
+            Ok(output) if output.stdout.is_empty() => {
+                String::from_utf8_lossy(&output.stderr).into_owned()
+            }
+            Ok(output) => String::from_utf8_lossy(&output.stdout).into_owned(),
+            Err(e) => {
+                ctx.fail(Self::NAME, &e);
+                return Ok(String::new());
+            }
+        };
+        let help = help.trim_end();
+
+        let mut result = format!(
+            "<pre class=\"mdbook-shortcodes-cli-help\"><code>{}</code></pre>",
+            html::escape(help)
+        );
+        if show_diff {
+            let key = Cache::key(&[command.as_bytes(), cwd.as_bytes()]);
+            if let Some(previous) = ctx.cache.get(Self::NAME, &key, None) {
+                let previous = String::from_utf8_lossy(&previous);
+                if previous != help {
+                    let message =
+                        format!("the help of `{}` changed since the previous build", command);
+                    ctx.warn(Self::NAME, &message);
+                    let mut attrs = analytics::attr(ctx.config, "cli-help-diff-toggle");
+                    if ctx.is_static() {
+                        attrs.insert_str(0, " open");
+                    }
+                    result.push_str(&render_diff(&diff::lines(&previous, help), &attrs));
+                }
+            }
+            if let Err(e) = ctx.cache.put(Self::NAME, &key, help.as_bytes()) {
+                ctx.warn(Self::NAME, &format!("failed to cache output: {}", e));
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Renders a diff in a `<details>` with the given `attrs`.
+fn render_diff(diff: &str, attrs: &str) -> String {
+    let mut result = format!(
+        "<details class=\"mdbook-shortcodes-cli-help-diff\"{}>\
+        <summary>Changes since the previous build</summary><pre><code>",
+        attrs
+    );
+    for line in diff.lines() {
+        let line = match line.chars().next() {
+            Some('+') => format!(
+                "<span class=\"mdbook-shortcodes-cli-help-added\">{}</span>",
+                html::escape(line)
+            ),
+            Some('-') => format!(
+                "<span class=\"mdbook-shortcodes-cli-help-removed\">{}</span>",
+                html::escape(line)
+            ),
+            _ => html::escape(line),
+        };
+        result.push_str(&line);
+        result.push('\n');
+    }
+    result.push_str("</code></pre></details>");
+    result
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_cli_help() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            allow_exec: true,
+            ..Default::default()
+        };
+        let mut ctx = Context::new(&config, dir.path().to_owned(), dir.path().join("src"));
+
+        let input = "{{#cli-help command=\"echo 'Usage: tool <a>'\" diff /}}";
+        assert!(CliHelp::process_raw(input, &mut ctx).unwrap().ends_with(
+            "<pre class=\"mdbook-shortcodes-cli-help\"><code>Usage: tool &lt;a&gt;</code></pre>"
+        ));
+        // The same output again isn't a change.
+        assert!(!CliHelp::process_raw(input, &mut ctx)
+            .unwrap()
+            .contains("<details"));
+
+        let key = Cache::key(&[b"echo 'Usage: tool <a>'", b"."]);
+        ctx.cache.put("cli-help", &key, b"Usage: tool").unwrap();
+        let result = CliHelp::process_raw(input, &mut ctx).unwrap();
+        assert!(result.contains(
+            "<span class=\"mdbook-shortcodes-cli-help-removed\">-Usage: tool</span>\n\
+            <span class=\"mdbook-shortcodes-cli-help-added\">+Usage: tool &lt;a&gt;</span>\n"
+        ));
+
+        // Running commands is disabled by default.
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        CliHelp::process_raw("{{#cli-help command=\"echo\" /}}", &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 1);
+    }
+}
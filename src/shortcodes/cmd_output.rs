@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use crate::{cache::Cache, exec, Attrs, Capability, Context, MatchResult, Shortcode};
+
+/// Runs a command at build time and shows its output, e.g.
+/// `{{#cmd-output "ls -la" cwd="examples" /}}`.
+pub(crate) struct CmdOutput;
+
+impl Shortcode for CmdOutput {
+    const NAME: &'static str = "cmd-output";
+    const HEADER: &'static str = "";
+    const CAPABILITY: Capability = Capability::Exec;
+
+    fn process_match(_input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(1, &["cwd", "lang"])?;
+        let command = match attrs.positional() {
+            [command] => *command,
+            _ => return Err("no command given to cmd-output shortcode".to_owned()),
+        };
+        let cwd = attrs.get("cwd").unwrap_or(".");
+        let lang = attrs.get("lang").unwrap_or("text");
+
+        let stdout = match run_command(ctx, command, cwd) {
+            Ok(stdout) => stdout,
+            Err(e) => {
+                ctx.fail(Self::NAME, &format!("`{}` failed: {}", command, e));
+                return Ok(String::new());
+            }
+        };
+
+        // The fence must be longer than any run of backticks in the output.
+        let fence = "`".repeat(longest_backtick_run(&stdout).max(2) + 1);
+        Ok(format!(
+            "\n{fence}{}\n{}\n{fence}\n",
+            lang,
+            stdout.trim_end(),
+            fence = fence
+        ))
+    }
+}
+
+/// Runs `command` using the system shell and returns its standard output.
+fn run_command(ctx: &mut Context, command: &str, cwd: &str) -> Result<String, String> {
+    exec::allowed(ctx, CmdOutput::NAME)?;
+    let fingerprint = ctx.dir_fingerprint(cwd);
+    let key = Cache::key(&[command.as_bytes(), cwd.as_bytes(), fingerprint.as_bytes()]);
+    let max_age = Duration::from_secs(ctx.config.exec_cache_ttl);
+    if let Some(stdout) = ctx.cache.get("cmd-output", &key, Some(max_age)) {
+        return Ok(String::from_utf8_lossy(&stdout).into_owned());
+    }
+
+    let mut shell = exec::shell(command);
+    shell.current_dir(ctx.root.join(cwd));
+
+    let output = exec::run(ctx, CmdOutput::NAME, &mut shell)?;
+    if !output.status.success() {
+        return Err(format!(
+            "{}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    if let Err(e) = ctx.cache.put("cmd-output", &key, &output.stdout) {
+        ctx.warn(CmdOutput::NAME, &format!("failed to cache output: {}", e));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Returns the length of the longest run of backticks in `s`, which a code fence around it must
+/// be longer than.
+pub(crate) fn longest_backtick_run(s: &str) -> usize {
+    s.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_cmd_output() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("examples")).unwrap();
+        std::fs::write(dir.path().join("examples/a.txt"), "").unwrap();
+        let config = Config {
+            allow_exec: true,
+            ..Default::default()
+        };
+        let mut ctx = Context::new(&config, dir.path().to_owned(), dir.path().join("src"));
+
+        assert_eq!(
+            CmdOutput::process_raw("{{#cmd-output \"ls\" cwd=\"examples\" /}}", &mut ctx),
+            Ok("\n```text\na.txt\n```\n".to_owned())
+        );
+        assert_eq!(
+            CmdOutput::process_raw("{{#cmd-output \"printf '```'\" lang=console /}}", &mut ctx),
+            Ok("\n````console\n```\n````\n".to_owned())
+        );
+        assert!(ctx.errors.is_empty());
+        CmdOutput::process_raw("{{#cmd-output \"exit 1\" /}}", &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 1);
+    }
+
+    #[test]
+    fn test_watch_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("examples")).unwrap();
+        std::fs::write(dir.path().join("examples/a.txt"), "").unwrap();
+        let config = Config {
+            allow_exec: true,
+            watch_dirs: vec!["examples".to_owned()],
+            ..Default::default()
+        };
+        let mut ctx = Context::new(&config, dir.path().to_owned(), dir.path().join("src"));
+        ctx.start_chapter(Some(std::path::Path::new("chapter.md")));
+
+        let input = "{{#cmd-output \"ls\" cwd=\"examples\" /}}";
+        assert_eq!(
+            CmdOutput::process_raw(input, &mut ctx),
+            Ok("\n```text\na.txt\n```\n".to_owned())
+        );
+        // The cached output is discarded once the directory changes.
+        std::fs::write(dir.path().join("examples/b.txt"), "").unwrap();
+        assert_eq!(
+            CmdOutput::process_raw(input, &mut ctx),
+            Ok("\n```text\na.txt\nb.txt\n```\n".to_owned())
+        );
+        assert_eq!(
+            ctx.deps.dependents(&dir.path().join("examples/b.txt")),
+            [dir.path().join("src/chapter.md")]
+        );
+    }
+
+    #[test]
+    fn test_cmd_output_disabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::default();
+        let mut ctx = Context::new(&config, dir.path().to_owned(), dir.path().join("src"));
+        assert_eq!(
+            CmdOutput::process_raw("{{#cmd-output \"ls\" /}}", &mut ctx),
+            Ok(String::new())
+        );
+        assert_eq!(ctx.errors.len(), 1);
+        assert!(ctx.errors[0].contains("requires `allow-exec = true`"));
+
+        // Output cached while `allow-exec` was set isn't used once it is unset.
+        let key = Cache::key(&[b"ls", b".", ctx.dir_fingerprint(".").as_bytes()]);
+        ctx.cache.put("cmd-output", &key, b"a.txt").unwrap();
+        CmdOutput::process_raw("{{#cmd-output \"ls\" /}}", &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 2);
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{parse, Attrs, Context, MatchResult, Shortcode};
+
+/// Includes its body only if a flag from the `[preprocessor.shortcodes.flags]` table is set,
+/// e.g. `{{#if internal}}...{{#else}}...{{/if}}`, so that one book can be built in several
+/// editions. `!internal` includes the body if the flag isn't set. Content after an
+/// `{{#else}}` is included otherwise.
+pub(crate) struct If;
+
+impl Shortcode for If {
+    const NAME: &'static str = "if";
+    const HEADER: &'static str = "";
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(1, &[])?;
+        let condition = match attrs.positional() {
+            [condition] => *condition,
+            _ => return Err("no flag given to if shortcode".to_owned()),
+        };
+        let (flag, negated) = match condition.strip_prefix('!') {
+            Some(flag) => (flag, true),
+            None => (condition, false),
+        };
+        let set = match ctx.config.flags.get(flag) {
+            Some(set) => *set,
+            None => {
+                return Err(format!(
+                    "unknown flag `{}`; define it in the `[preprocessor.shortcodes.flags]` table",
+                    flag
+                ))
+            }
+        };
+
+        let delimiters = &ctx.config.delimiters;
+        match parse::split_outside(input, &delimiters.tag("else"), Self::NAME, delimiters)[..] {
+            [then] if set != negated => Ok(then.to_owned()),
+            [_] => Ok(String::new()),
+            [then, _] if set != negated => Ok(then.to_owned()),
+            [_, otherwise] => Ok(otherwise.to_owned()),
+            _ => Err("an if shortcode can only have one else".to_owned()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_if() {
+        let mut config = Config::default();
+        config.flags.insert("internal".to_owned(), true);
+        config.flags.insert("beta".to_owned(), false);
+        let mut ctx = Context::test(&config);
+        let input = "{{#if internal}}A{{#if beta}}B{{#else}}C{{/if}}{{#else}}D{{/if}}";
+        assert_eq!(If::process_raw(input, &mut ctx), Ok("AC".to_owned()));
+        assert_eq!(
+            If::process_raw("{{#if !internal}}A{{/if}}{{#if !beta}}B{{/if}}", &mut ctx),
+            Ok("B".to_owned())
+        );
+        assert!(If::process_raw("{{#if public}}A{{/if}}", &mut ctx).is_err());
+        let input = "{{#if beta}}A{{#else}}B{{#else}}C{{/if}}";
+        assert!(If::process_raw(input, &mut ctx).is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use serde_json::Value;
+
+use crate::{analytics, html, Attrs, Capability, Context, MatchResult, Shortcode};
+
+/// How many `$ref`s are followed before giving up, so that recursive schemas terminate.
+const MAX_DEPTH: usize = 16;
+
+/// A configuration reference generated from a JSON Schema, e.g.
+/// `{{#config-schema path="schema/config.schema.json" /}}`. The path is relative to the book's
+/// root directory.
+///
+/// Every property is listed with its type, default and description, with nested objects in
+/// expandable sections.
+pub(crate) struct ConfigSchema;
+
+impl Shortcode for ConfigSchema {
+    const NAME: &'static str = "config-schema";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-config-schema dd {
+        margin-bottom: 1em;
+    }
+    .mdbook-shortcodes-config-schema .mdbook-shortcodes-config-schema-type {
+        color: var(--sidebar-non-existant);
+    }
+</style>
+";
+    const CAPABILITY: Capability = Capability::Filesystem;
+
+    fn process_match(_input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &["path"])?;
+        let path = attrs
+            .get("path")
+            .ok_or("no path given to config-schema shortcode")?;
+
+        ctx.watch(path);
+        let schema = std::fs::read_to_string(ctx.root.join(path))
+            .map_err(|e| e.to_string())
+            .and_then(|s| serde_json::from_str::<Value>(&s).map_err(|e| e.to_string()));
+        match schema {
+            Ok(schema) => {
+                let mut attrs = analytics::attr(ctx.config, "config-schema-toggle");
+                if ctx.is_static() {
+                    attrs.insert_str(0, " open");
+                }
+                Ok(render_properties(&schema, &schema, 0, &attrs))
+            }
+            Err(e) => {
+                ctx.fail(Self::NAME, &format!("couldn't read `{}`: {}", path, e));
+                Ok(String::new())
+            }
+        }
+    }
+}
+
+/// Follows a local reference (e.g. `#/$defs/Server`) if `schema` is one.
+fn resolve<'a>(root: &'a Value, schema: &'a Value) -> &'a Value {
+    match schema.get("$ref").and_then(Value::as_str) {
+        Some(reference) => reference
+            .strip_prefix('#')
+            .and_then(|pointer| root.pointer(pointer))
+            .unwrap_or(schema),
+        None => schema,
+    }
+}
+
+/// `attrs` are added to the `<details>` of nested objects.
+fn render_properties(root: &Value, schema: &Value, depth: usize, attrs: &str) -> String {
+    let schema = resolve(root, schema);
+    let properties = match schema.get("properties").and_then(Value::as_object) {
+        Some(properties) if depth < MAX_DEPTH => properties,
+        _ => return String::new(),
+    };
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect();
+
+    let mut result = String::from("<dl class=\"mdbook-shortcodes-config-schema\">");
+    for (name, property) in properties {
+        let property = resolve(root, property);
+        result.push_str(&format!("<dt><code>{}</code>", html::escape(name)));
+        if let Some(ty) = type_name(property) {
+            result.push_str(&format!(
+                " <span class=\"mdbook-shortcodes-config-schema-type\">{}</span>",
+                html::escape(&ty)
+            ));
+        }
+        if required.contains(&name.as_str()) {
+            result.push_str(" <strong>required</strong>");
+        }
+        result.push_str("</dt><dd>");
+
+        let mut text = Vec::new();
+        if let Some(description) = property.get("description").and_then(Value::as_str) {
+            text.push(html::escape(description));
+        }
+        if let Some(default) = property.get("default") {
+            text.push(format!(
+                "Defaults to <code>{}</code>.",
+                html::escape(&default.to_string())
+            ));
+        }
+        result.push_str(&text.join(" "));
+        let nested = render_properties(root, property, depth + 1, attrs);
+        if !nested.is_empty() {
+            result.push_str(&format!(
+                "<details{}><summary>Properties of <code>{}</code></summary>{}</details>",
+                attrs,
+                html::escape(name),
+                nested
+            ));
+        }
+        result.push_str("</dd>");
+    }
+    result.push_str("</dl>");
+    result
+}
+
+/// Returns a short description of a property's type, e.g. `string`, `integer | null` or
+/// `"a" | "b"`.
+fn type_name(schema: &Value) -> Option<String> {
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        let values: Vec<String> = values.iter().map(Value::to_string).collect();
+        return Some(values.join(" | "));
+    }
+    let name = match schema.get("type")? {
+        Value::String(ty) => ty.clone(),
+        Value::Array(types) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" | "),
+        _ => return None,
+    };
+    match schema.get("items").and_then(type_name) {
+        Some(items) if name == "array" => Some(format!("array of {}", items)),
+        _ => Some(name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, Profile};
+
+    #[test]
+    fn test_config_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("schema.json"),
+            r##"{
+                "type": "object",
+                "required": ["name"],
+                "properties": {
+                    "name": { "type": "string", "description": "The <name>." },
+                    "tags": { "type": "array", "items": { "type": "string" }, "default": [] },
+                    "mode": { "enum": ["fast", "slow"] },
+                    "server": { "$ref": "#/$defs/Server" }
+                },
+                "$defs": {
+                    "Server": {
+                        "type": "object",
+                        "description": "Server options.",
+                        "properties": { "port": { "type": "integer", "default": 80 } }
+                    }
+                }
+            }"##,
+        )
+        .unwrap();
+
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        ctx.root = dir.path().to_owned();
+        let result =
+            ConfigSchema::process_raw("{{#config-schema path=schema.json /}}", &mut ctx).unwrap();
+        assert!(result.contains(
+            "<dt><code>name</code> <span class=\"mdbook-shortcodes-config-schema-type\">string</span> \
+            <strong>required</strong></dt><dd>The &lt;name&gt;.</dd>"
+        ));
+        assert!(result.contains("array of string</span></dt><dd>Defaults to <code>[]</code>.</dd>"));
+        assert!(result.contains("&quot;fast&quot; | &quot;slow&quot;"));
+        assert!(result.contains(
+            "<dd>Server options.<details><summary>Properties of <code>server</code></summary>\
+            <dl class=\"mdbook-shortcodes-config-schema\"><dt><code>port</code>"
+        ));
+
+        ConfigSchema::process_raw("{{#config-schema path=missing.json /}}", &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 1);
+
+        let config = Config {
+            profile: Profile::Print,
+            analytics: true,
+            ..Config::default()
+        };
+        let mut ctx = Context::test(&config);
+        ctx.root = dir.path().to_owned();
+        let result =
+            ConfigSchema::process_raw("{{#config-schema path=schema.json /}}", &mut ctx).unwrap();
+        assert!(result.contains(
+            "<details open data-analytics-event=\"config-schema-toggle\">\
+            <summary>Properties of <code>server</code>"
+        ));
+    }
+}
@@ -0,0 +1,27 @@
+use crate::{analytics, html, shortcodes::script::script_open_tag, Config};
+
+/// Returns a button that copies `text` to the clipboard. The page must also include [`script`].
+pub(crate) fn button(text: &str, label: &str, config: &Config) -> String {
+    format!(
+        "<button type=\"button\" class=\"mdbook-shortcodes-copy\" title=\"{0}\" aria-label=\"{0}\" \
+        data-copy=\"{1}\"{2}>Copy</button>",
+        html::escape(label),
+        html::escape_single_line(text),
+        analytics::attr(config, "copy")
+    )
+}
+
+/// Returns the script that makes copy buttons work. It only sets itself up once per page, so it
+/// can be included by every shortcode that uses copy buttons.
+pub(crate) fn script(config: &Config) -> String {
+    format!(
+        "{}if (!window.mdbookShortcodesCopy) {{\
+        window.mdbookShortcodesCopy = true;\
+        document.addEventListener(\"click\", function (e) {{\
+        var button = e.target.closest && e.target.closest(\".mdbook-shortcodes-copy\");\
+        if (button) navigator.clipboard.writeText(button.dataset.copy);\
+        }});\
+        }}</script>",
+        script_open_tag(false, config)
+    )
+}
@@ -0,0 +1,406 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    analytics, html, shortcodes::script::script_open_tag, Attrs, Context, MatchResult, Shortcode,
+};
+
+/// An interactive crossword, with one `ANSWER :: clue` line per word. The grid is laid out at
+/// build time and answers are checked in the browser.
+pub(crate) struct Crossword;
+
+impl Shortcode for Crossword {
+    const NAME: &'static str = "crossword";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-crossword table {
+        border-collapse: collapse;
+        margin: 1em 0;
+    }
+    .mdbook-shortcodes-crossword td {
+        position: relative;
+        width: 2em;
+        height: 2em;
+        padding: 0;
+        border: 1px solid var(--fg);
+    }
+    .mdbook-shortcodes-crossword td.mdbook-shortcodes-crossword-block {
+        border: none;
+    }
+    .mdbook-shortcodes-crossword td span {
+        position: absolute;
+        top: 0;
+        left: 2px;
+        font-size: 0.6em;
+    }
+    .mdbook-shortcodes-crossword input {
+        width: 100%;
+        height: 100%;
+        padding: 0;
+        border: none;
+        background: transparent;
+        color: var(--fg);
+        text-align: center;
+        text-transform: uppercase;
+    }
+    .mdbook-shortcodes-crossword input.mdbook-shortcodes-crossword-wrong {
+        background: rgba(255, 0, 0, 0.25);
+    }
+</style>
+";
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        if !attrs.is_empty() {
+            return Err("too many arguments given to crossword shortcode".to_owned());
+        }
+
+        let mut words = Vec::new();
+        for line in input.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let (answer, clue) = line
+                .split_once("::")
+                .ok_or("crossword words must be given as `answer :: clue`")?;
+            let answer: Vec<char> = answer
+                .chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_uppercase)
+                .collect();
+            if answer.is_empty() {
+                return Err("empty answer given to crossword shortcode".to_owned());
+            }
+            words.push(Word {
+                answer,
+                clue: clue.trim().to_owned(),
+            });
+        }
+
+        let placements = match layout(&words) {
+            Some(placements) => placements,
+            None => {
+                ctx.fail(Self::NAME, "the words could not all be connected in a grid");
+                return Ok(String::new());
+            }
+        };
+        let id = ctx.ids.generate(Self::NAME);
+        // When printing, the grid is left blank for the reader to fill in by hand.
+        let script = if ctx.is_static() {
+            None
+        } else {
+            Some((
+                script_open_tag(false, ctx.config),
+                analytics::attr(ctx.config, "crossword-check"),
+            ))
+        };
+        Ok(render(&id, &words, &placements, script))
+    }
+}
+
+struct Word {
+    answer: Vec<char>,
+    clue: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Placement {
+    row: isize,
+    col: isize,
+    across: bool,
+}
+
+impl Placement {
+    fn cell(&self, i: usize) -> (isize, isize) {
+        if self.across {
+            (self.row, self.col + i as isize)
+        } else {
+            (self.row + i as isize, self.col)
+        }
+    }
+}
+
+type Grid = BTreeMap<(isize, isize), char>;
+
+/// Places every word so that it crosses at least one earlier word, greedily picking the position
+/// that keeps the grid most compact. The longest word is placed first.
+fn layout(words: &[Word]) -> Option<Vec<Placement>> {
+    let mut order: Vec<usize> = (0..words.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(words[i].answer.len()));
+
+    let mut grid = Grid::new();
+    let mut placements = vec![None; words.len()];
+    for (n, &i) in order.iter().enumerate() {
+        let answer = &words[i].answer;
+        let placement = if n == 0 {
+            Placement {
+                row: 0,
+                col: 0,
+                across: true,
+            }
+        } else {
+            candidates(&grid, answer)
+                .into_iter()
+                .min_by_key(|p| area(&grid, answer, p))?
+        };
+        for (j, c) in answer.iter().enumerate() {
+            grid.insert(placement.cell(j), *c);
+        }
+        placements[i] = Some(placement);
+    }
+    placements.into_iter().collect()
+}
+
+/// Returns every valid position for `answer` that crosses a letter already in the grid.
+fn candidates(grid: &Grid, answer: &[char]) -> Vec<Placement> {
+    let mut result = Vec::new();
+    for (&(row, col), &c) in grid {
+        for (i, _) in answer.iter().enumerate().filter(|(_, a)| **a == c) {
+            for across in [true, false] {
+                let placement = if across {
+                    Placement {
+                        row,
+                        col: col - i as isize,
+                        across,
+                    }
+                } else {
+                    Placement {
+                        row: row - i as isize,
+                        col,
+                        across,
+                    }
+                };
+                if fits(grid, answer, &placement) && !result.contains(&placement) {
+                    result.push(placement);
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Returns whether `answer` can be written at `placement` without clashing with, or running
+/// alongside, other words.
+fn fits(grid: &Grid, answer: &[char], placement: &Placement) -> bool {
+    let (before, after) = (placement.cell(0), placement.cell(answer.len() - 1));
+    let (before, after) = if placement.across {
+        ((before.0, before.1 - 1), (after.0, after.1 + 1))
+    } else {
+        ((before.0 - 1, before.1), (after.0 + 1, after.1))
+    };
+    if grid.contains_key(&before) || grid.contains_key(&after) {
+        return false;
+    }
+
+    let mut crossings = 0;
+    for (i, c) in answer.iter().enumerate() {
+        let (row, col) = placement.cell(i);
+        match grid.get(&(row, col)) {
+            Some(existing) if existing == c => crossings += 1,
+            Some(_) => return false,
+            None => {
+                let neighbours = if placement.across {
+                    [(row - 1, col), (row + 1, col)]
+                } else {
+                    [(row, col - 1), (row, col + 1)]
+                };
+                if neighbours.iter().any(|n| grid.contains_key(n)) {
+                    return false;
+                }
+            }
+        }
+    }
+    // A word entirely made of existing letters would overlap another word.
+    crossings > 0 && crossings < answer.len()
+}
+
+fn area(grid: &Grid, answer: &[char], placement: &Placement) -> isize {
+    let cells = grid
+        .keys()
+        .copied()
+        .chain((0..answer.len()).map(|i| placement.cell(i)));
+    let (mut min_row, mut max_row, mut min_col, mut max_col) = (0, 0, 0, 0);
+    for (row, col) in cells {
+        min_row = min_row.min(row);
+        max_row = max_row.max(row);
+        min_col = min_col.min(col);
+        max_col = max_col.max(col);
+    }
+    (max_row - min_row + 1) * (max_col - min_col + 1)
+}
+
+fn render(
+    id: &str,
+    words: &[Word],
+    placements: &[Placement],
+    script: Option<(String, String)>,
+) -> String {
+    let mut grid = Grid::new();
+    for (word, placement) in words.iter().zip(placements) {
+        for (i, c) in word.answer.iter().enumerate() {
+            grid.insert(placement.cell(i), *c);
+        }
+    }
+    let min_row = grid.keys().map(|(row, _)| *row).min().unwrap_or(0);
+    let max_row = grid.keys().map(|(row, _)| *row).max().unwrap_or(0);
+    let min_col = grid.keys().map(|(_, col)| *col).min().unwrap_or(0);
+    let max_col = grid.keys().map(|(_, col)| *col).max().unwrap_or(0);
+
+    // Clues are numbered in reading order of their first cell, with words starting in the same
+    // cell sharing a number.
+    let mut numbers = BTreeMap::new();
+    for placement in placements {
+        numbers.insert((placement.row, placement.col), 0);
+    }
+    for (n, number) in numbers.values_mut().enumerate() {
+        *number = n + 1;
+    }
+
+    let mut result = format!(
+        "<div id=\"{}\" class=\"mdbook-shortcodes-crossword\"><table>",
+        id
+    );
+    for row in min_row..=max_row {
+        result.push_str("<tr>");
+        for col in min_col..=max_col {
+            match grid.get(&(row, col)) {
+                Some(c) => {
+                    result.push_str("<td>");
+                    if let Some(number) = numbers.get(&(row, col)) {
+                        result.push_str(&format!("<span>{}</span>", number));
+                    }
+                    result.push_str(&format!(
+                        "<input maxlength=\"1\" aria-label=\"Row {} column {}\" data-answer=\"{}\"></td>",
+                        row - min_row + 1,
+                        col - min_col + 1,
+                        html::escape(&c.to_string())
+                    ));
+                }
+                None => result.push_str("<td class=\"mdbook-shortcodes-crossword-block\"></td>"),
+            }
+        }
+        result.push_str("</tr>");
+    }
+    result.push_str("</table>");
+
+    for (heading, across) in [("Across", true), ("Down", false)] {
+        let mut clues: Vec<_> = words
+            .iter()
+            .zip(placements)
+            .filter(|(_, p)| p.across == across)
+            .map(|(word, p)| (numbers[&(p.row, p.col)], word))
+            .collect();
+        if clues.is_empty() {
+            continue;
+        }
+        clues.sort_by_key(|(number, _)| *number);
+        result.push_str(&format!("<p><strong>{}</strong></p><ol>", heading));
+        for (number, word) in clues {
+            result.push_str(&format!(
+                "<li value=\"{}\">{} ({})</li>",
+                number,
+                html::escape(&word.clue),
+                word.answer.len()
+            ));
+        }
+        result.push_str("</ol>");
+    }
+
+    let (script, analytics) = match script {
+        Some(script) => script,
+        None => {
+            result.push_str("</div>");
+            return result;
+        }
+    };
+    result.push_str(&format!(
+        "<button type=\"button\"{analytics}>Check</button> <span role=\"status\"></span></div>\
+        {script}(function () {{\
+        var root = document.getElementById(\"{id}\");\
+        root.querySelector(\"button\").addEventListener(\"click\", function () {{\
+        var inputs = root.querySelectorAll(\"input\"), correct = 0;\
+        inputs.forEach(function (input) {{\
+        var right = input.value.toUpperCase() === input.dataset.answer;\
+        input.classList.toggle(\"mdbook-shortcodes-crossword-wrong\", input.value !== \"\" && !right);\
+        if (right) correct++;\
+        }});\
+        root.querySelector(\"[role=status]\").textContent = correct === inputs.length \
+        ? \"Solved!\" : correct + \" of \" + inputs.length + \" letters correct\";\
+        }});\
+        }})();</script>",
+        script = script,
+        analytics = analytics,
+        id = id
+    ));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, Profile};
+
+    fn words(answers: &[&str]) -> Vec<Word> {
+        answers
+            .iter()
+            .map(|a| Word {
+                answer: a.chars().collect(),
+                clue: String::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_layout() {
+        let words = words(&["CARGO", "RUST", "TOML"]);
+        let placements = layout(&words).unwrap();
+        assert_eq!(
+            placements[0],
+            Placement {
+                row: 0,
+                col: 0,
+                across: true
+            }
+        );
+        assert_eq!(
+            placements[1],
+            Placement {
+                row: 0,
+                col: 2,
+                across: false
+            }
+        );
+        assert_eq!(
+            placements[2],
+            Placement {
+                row: 3,
+                col: 2,
+                across: true
+            }
+        );
+
+        assert_eq!(layout(&self::words(&["RUST", "XYZ"])), None);
+    }
+
+    #[test]
+    fn test_crossword() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let input = "{{#crossword}}
+Cargo :: Rust's package manager
+Rust :: A systems language
+{{/crossword}}";
+        let result = Crossword::process_raw(input, &mut ctx).unwrap();
+        assert!(result.contains("<div id=\"mdbook-shortcodes-crossword-0\""));
+        assert_eq!(result.matches("<input").count(), 8);
+        assert!(result.contains(
+            "<p><strong>Across</strong></p><ol><li value=\"1\">Rust&#39;s package manager (5)</li></ol>"
+        ));
+        assert!(result.contains(
+            "<p><strong>Down</strong></p><ol><li value=\"2\">A systems language (4)</li></ol>"
+        ));
+
+        let config = Config {
+            profile: Profile::Print,
+            ..Config::default()
+        };
+        let mut ctx = Context::test(&config);
+        let result = Crossword::process_raw(input, &mut ctx).unwrap();
+        assert!(result.ends_with("</ol></div>"));
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{
+    analytics, html, markdown, shortcodes::script_open_tag, Attrs, Context, MatchResult, Shortcode,
+};
+
+pub(crate) struct Demo;
+
+impl Shortcode for Demo {
+    const NAME: &'static str = "demo";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-demo {
+        margin: 1em 0;
+    }
+    .mdbook-shortcodes-demo-frame {
+        width: 100%;
+        border: 1px solid rgba(127,127,127,.5);
+        border-radius: .25em;
+        background-color: #fff;
+    }
+    .mdbook-shortcodes-demo-code > summary {
+        cursor: pointer;
+    }
+</style>
+";
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &["height"])?;
+        let height = attrs.get("height").unwrap_or("150px");
+
+        let mut html = String::new();
+        let mut css = String::new();
+        let mut js = String::new();
+        for block in markdown::fenced_blocks(input) {
+            match block.lang {
+                "html" => html.push_str(block.content),
+                "css" => css.push_str(block.content),
+                "js" | "javascript" => js.push_str(block.content),
+                _ => {}
+            }
+        }
+
+        // The document inherits the page's content security policy, so its script needs the
+        // nonce too.
+        let document = format!(
+            "<!DOCTYPE html><html><head><style>{}</style></head><body>{}{}{}</script></body></html>",
+            html::escape_end_tags(&css, "style"),
+            html,
+            script_open_tag(false, ctx.config),
+            html::escape_end_tags(&js, "script")
+        );
+
+        let open = if ctx.is_static() { " open" } else { "" };
+        Ok(format!(
+            "<div class=\"mdbook-shortcodes-demo\">\
+            <iframe class=\"mdbook-shortcodes-demo-frame\" sandbox=\"allow-scripts\" \
+            style=\"height: {}\" srcdoc=\"{}\"></iframe>\
+            <details class=\"mdbook-shortcodes-demo-code\"{}{}><summary>Show code</summary>\n{}\n</details></div>",
+            html::escape(height),
+            html::escape_single_line(&document),
+            open,
+            analytics::attr(ctx.config, "demo-code-toggle"),
+            input
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, Profile};
+
+    #[test]
+    fn test_demo() {
+        let input = "{{#demo height=300px}}
+```html
+<p>\"Hi\"</p>
+```
+
+```js
+console.log(1);
+```
+{{/demo}}";
+        let expected = "<div class=\"mdbook-shortcodes-demo\">\
+            <iframe class=\"mdbook-shortcodes-demo-frame\" sandbox=\"allow-scripts\" \
+            style=\"height: 300px\" srcdoc=\"&lt;!DOCTYPE html&gt;&lt;html&gt;&lt;head&gt;\
+            &lt;style&gt;&lt;/style&gt;&lt;/head&gt;&lt;body&gt;&lt;p&gt;&quot;Hi&quot;&lt;/p&gt;&#10;\
+            &lt;script&gt;console.log(1);&#10;&lt;/script&gt;&lt;/body&gt;&lt;/html&gt;\"></iframe>\
+            <details class=\"mdbook-shortcodes-demo-code\"><summary>Show code</summary>\n\n\
+            ```html\n<p>\"Hi\"</p>\n```\n\n```js\nconsole.log(1);\n```\n\n</details></div>";
+        assert_eq!(
+            Demo::process_raw(input, &mut Context::test(&Default::default())),
+            Ok(expected.to_owned())
+        );
+
+        let config = Config {
+            profile: Profile::Print,
+            analytics: true,
+            ..Config::default()
+        };
+        let result = Demo::process_raw(input, &mut Context::test(&config)).unwrap();
+        assert!(result.contains(
+            "<details class=\"mdbook-shortcodes-demo-code\" open \
+            data-analytics-event=\"demo-code-toggle\">"
+        ));
+
+        // End tags in the script and styles are escaped in any case, and the script gets the
+        // page's nonce.
+        let input = "{{#demo}}
+```css
+p::after { content: '</STYLE>'; }
+```
+
+```js
+document.write('</SCRIPT>');
+```
+{{/demo}}";
+        let config = Config {
+            csp_nonce: Some("abc".to_owned()),
+            ..Config::default()
+        };
+        let result = Demo::process_raw(input, &mut Context::test(&config)).unwrap();
+        assert!(result.contains(
+            "content: &#39;&lt;\\/STYLE&gt;&#39;; }&#10;&lt;/style&gt;&lt;/head&gt;&lt;body&gt;\
+            &lt;script nonce=&quot;abc&quot;&gt;document.write(&#39;&lt;\\/SCRIPT&gt;&#39;);"
+        ));
+    }
+}
@@ -0,0 +1,74 @@
+use std::env;
+
+use crate::{html, Attrs, Context, MatchResult, Shortcode};
+
+/// Inserts the value of an environment variable at build time, e.g. `{{#env CI_COMMIT_SHA /}}`,
+/// or the `default` attribute if it isn't set. Only the variables in the `env-allowlist` option
+/// can be read, so that a chapter can't leak secrets from the build environment.
+pub(crate) struct Env;
+
+impl Shortcode for Env {
+    const NAME: &'static str = "env";
+    const HEADER: &'static str = "";
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(1, &["default"])?;
+        if !input.trim().is_empty() {
+            return Err("env shortcode must be self-closing".to_owned());
+        }
+        let name = match attrs.positional() {
+            [name] => *name,
+            _ => return Err("no variable given to env shortcode".to_owned()),
+        };
+        if !ctx
+            .config
+            .env_allowlist
+            .iter()
+            .any(|allowed| allowed == name)
+        {
+            return Err(format!(
+                "`{}` isn't in the `env-allowlist` option, so it can't be read",
+                name
+            ));
+        }
+        match (env::var(name), attrs.get("default")) {
+            (Ok(value), _) => Ok(html::escape(&value)),
+            (Err(_), Some(default)) => Ok(html::escape(default)),
+            (Err(_), None) => Err(format!(
+                "environment variable `{}` isn't set, and no default was given",
+                name
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_env() {
+        let config = Config {
+            env_allowlist: vec![
+                "CARGO_PKG_NAME".to_owned(),
+                "MDBOOK_SHORTCODES_UNSET".to_owned(),
+            ],
+            ..Config::default()
+        };
+        let mut ctx = Context::test(&config);
+        assert_eq!(
+            Env::process_raw("Built by {{#env CARGO_PKG_NAME /}}.", &mut ctx),
+            Ok("Built by mdbook-shortcodes.".to_owned())
+        );
+        assert_eq!(
+            Env::process_raw(
+                "{{#env MDBOOK_SHORTCODES_UNSET default=\"<local>\" /}}",
+                &mut ctx
+            ),
+            Ok("&lt;local&gt;".to_owned())
+        );
+        assert!(Env::process_raw("{{#env MDBOOK_SHORTCODES_UNSET /}}", &mut ctx).is_err());
+        assert!(Env::process_raw("{{#env HOME /}}", &mut ctx).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{html, shortcodes::copy, Attrs, Context, MatchResult, Shortcode};
+
+/// A reference table of environment variables, with one `NAME :: default :: description` line
+/// per variable. The default can be left empty. Every variable has an anchor (e.g. `#env-HOME`)
+/// and a button that copies its name.
+pub(crate) struct Envvars;
+
+impl Shortcode for Envvars {
+    const NAME: &'static str = "envvars";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-envvars td:first-child {
+        white-space: nowrap;
+    }
+    .mdbook-shortcodes-copy {
+        margin-inline-start: .5em;
+        font-size: .75em;
+        cursor: pointer;
+    }
+</style>
+";
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        if !attrs.is_empty() {
+            return Err("too many arguments given to envvars shortcode".to_owned());
+        }
+
+        let mut result = String::from(
+            "<table class=\"mdbook-shortcodes-envvars\"><thead><tr>\
+            <th>Variable</th><th>Default</th><th>Description</th></tr></thead><tbody>",
+        );
+        for line in input.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let mut parts = line.splitn(3, "::").map(str::trim);
+            let (name, default, description) =
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(name), Some(default), Some(description)) => (name, default, description),
+                    _ => return Err(
+                        "environment variables must be given as `name :: default :: description`"
+                            .to_owned(),
+                    ),
+                };
+            let default = if default.is_empty() {
+                String::new()
+            } else {
+                format!("<code>{}</code>", html::escape(default))
+            };
+            result.push_str(&format!(
+                "<tr id=\"{0}\"><td><a href=\"#{0}\"><code>{1}</code></a>{2}</td>\
+                <td>{3}</td><td>{4}</td></tr>",
+                html::escape(&ctx.ids.unique(format!("env-{}", name))),
+                html::escape(name),
+                copy::button(name, &format!("Copy {}", name), ctx.config),
+                default,
+                html::escape(description)
+            ));
+        }
+        result.push_str("</tbody></table>");
+        result.push_str(&copy::script(ctx.config));
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_envvars() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let input = "{{#envvars}}
+RUST_LOG :: info :: The log level.
+NO_COLOR :: :: Disables colored output.
+{{/envvars}}";
+        let result = Envvars::process_raw(input, &mut ctx).unwrap();
+        assert!(result.contains(
+            "<tr id=\"env-RUST_LOG\"><td><a href=\"#env-RUST_LOG\"><code>RUST_LOG</code></a>\
+            <button type=\"button\" class=\"mdbook-shortcodes-copy\" title=\"Copy RUST_LOG\" \
+            aria-label=\"Copy RUST_LOG\" data-copy=\"RUST_LOG\">Copy</button></td>\
+            <td><code>info</code></td><td>The log level.</td></tr>"
+        ));
+        assert!(result.contains("<td></td><td>Disables colored output.</td>"));
+        assert!(result.ends_with("</script>"));
+    }
+}
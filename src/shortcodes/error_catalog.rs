@@ -0,0 +1,180 @@
+use syn::{Attribute, Expr, ExprLit, Item, ItemEnum, Lit, Meta};
+
+use crate::{html, Attrs, Capability, Context, MatchResult, Shortcode};
+
+/// A table of the variants of the error enums in a Rust file, along with their doc comments,
+/// e.g. `{{#error-catalog path="src/error.rs" /}}`. The path is relative to the book's root
+/// directory.
+///
+/// By default, every enum whose name ends with `Error` is included; `enum` picks a single one.
+/// If variants have `thiserror` messages (i.e. `#[error("...")]`), they are shown too.
+pub(crate) struct ErrorCatalog;
+
+impl Shortcode for ErrorCatalog {
+    const NAME: &'static str = "error-catalog";
+    const HEADER: &'static str = "";
+    const CAPABILITY: Capability = Capability::Filesystem;
+
+    fn process_match(_input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &["path", "enum"])?;
+        let path = attrs
+            .get("path")
+            .ok_or("no path given to error-catalog shortcode")?;
+        let name = attrs.get("enum");
+
+        ctx.watch(path);
+        let file = match std::fs::read_to_string(ctx.root.join(path)) {
+            Ok(source) => match syn::parse_file(&source) {
+                Ok(file) => file,
+                Err(e) => {
+                    ctx.fail(Self::NAME, &format!("couldn't parse `{}`: {}", path, e));
+                    return Ok(String::new());
+                }
+            },
+            Err(e) => {
+                ctx.fail(Self::NAME, &format!("couldn't read `{}`: {}", path, e));
+                return Ok(String::new());
+            }
+        };
+
+        let mut enums = Vec::new();
+        collect_enums(&file.items, &mut enums);
+        enums.retain(|item| match name {
+            Some(name) => item.ident == name,
+            None => item.ident.to_string().ends_with("Error"),
+        });
+        if enums.is_empty() {
+            ctx.fail(Self::NAME, &format!("no error enums found in `{}`", path));
+            return Ok(String::new());
+        }
+
+        let show_enum = enums.len() > 1;
+        let show_message = enums
+            .iter()
+            .flat_map(|item| &item.variants)
+            .any(|variant| message(&variant.attrs).is_some());
+
+        let mut result =
+            String::from("<table class=\"mdbook-shortcodes-error-catalog\"><thead><tr>");
+        if show_enum {
+            result.push_str("<th>Enum</th>");
+        }
+        result.push_str("<th>Variant</th>");
+        if show_message {
+            result.push_str("<th>Message</th>");
+        }
+        result.push_str("<th>Description</th></tr></thead><tbody>");
+        for item in &enums {
+            for variant in &item.variants {
+                result.push_str("<tr>");
+                if show_enum {
+                    result.push_str(&format!("<td><code>{}</code></td>", item.ident));
+                }
+                result.push_str(&format!("<td><code>{}</code></td>", variant.ident));
+                if show_message {
+                    result.push_str(&format!(
+                        "<td>{}</td>",
+                        html::escape(&message(&variant.attrs).unwrap_or_default())
+                    ));
+                }
+                result.push_str(&format!(
+                    "<td>{}</td></tr>",
+                    html::escape(&doc_comment(&variant.attrs))
+                ));
+            }
+        }
+        result.push_str("</tbody></table>");
+        Ok(result)
+    }
+}
+
+/// Collects the enums in `items`, including those in inline modules.
+fn collect_enums<'a>(items: &'a [Item], enums: &mut Vec<&'a ItemEnum>) {
+    for item in items {
+        match item {
+            Item::Enum(item) => enums.push(item),
+            Item::Mod(module) => {
+                if let Some((_, items)) = &module.content {
+                    collect_enums(items, enums);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the doc comment of an item as a single paragraph.
+fn doc_comment(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) => string_literal(&meta.value),
+            _ => None,
+        })
+        .map(|line| line.trim().to_owned())
+        .take_while(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the message of a `thiserror` variant.
+fn message(attrs: &[Attribute]) -> Option<String> {
+    let attr = attrs.iter().find(|attr| attr.path().is_ident("error"))?;
+    string_literal(&attr.parse_args().ok()?)
+}
+
+fn string_literal(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(s), ..
+        }) => Some(s.value()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_error_catalog() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("error.rs"),
+            r#"
+/// Not an error.
+pub enum Kind { A }
+
+mod inner {
+    pub enum ParseError {
+        /// The input ended early.
+        ///
+        /// More details.
+        #[error("unexpected end of input")]
+        Eof,
+        Invalid(char),
+    }
+}
+"#,
+        )
+        .unwrap();
+
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        ctx.root = dir.path().to_owned();
+        let expected = "<table class=\"mdbook-shortcodes-error-catalog\"><thead><tr>\
+            <th>Variant</th><th>Message</th><th>Description</th></tr></thead><tbody>\
+            <tr><td><code>Eof</code></td><td>unexpected end of input</td><td>The input ended early.</td></tr>\
+            <tr><td><code>Invalid</code></td><td></td><td></td></tr></tbody></table>";
+        assert_eq!(
+            ErrorCatalog::process_raw("{{#error-catalog path=error.rs /}}", &mut ctx),
+            Ok(expected.to_owned())
+        );
+
+        ErrorCatalog::process_raw("{{#error-catalog path=error.rs enum=Other /}}", &mut ctx)
+            .unwrap();
+        assert_eq!(ctx.errors.len(), 1);
+    }
+}
@@ -0,0 +1,200 @@
+use std::path::Path;
+
+use crate::{
+    shortcodes::cmd_output::longest_backtick_run, Attrs, Capability, Context, MatchResult,
+    Shortcode,
+};
+
+/// Shows a file as a code block, e.g. `{{#snippet path="../src/main.rs" lines="10:30" /}}`,
+/// as mdBook's `{{#include}}` can't be used in chapters where this preprocessor handles its
+/// tags. The path is relative to the book's root directory.
+///
+/// `lines` is a range of line numbers, either end of which can be left out (e.g. `"10:"`), or
+/// a single line. `anchor` instead selects the lines between `ANCHOR: name` and
+/// `ANCHOR_END: name` comments, like mdBook. The language of the code block is detected from
+/// the file's extension unless `lang` is given.
+pub(crate) struct FileSnippet;
+
+impl Shortcode for FileSnippet {
+    const NAME: &'static str = "snippet";
+    const HEADER: &'static str = "";
+    const CAPABILITY: Capability = Capability::Filesystem;
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &["path", "lines", "anchor", "lang"])?;
+        if !input.trim().is_empty() {
+            return Err("snippet shortcode must be self-closing".to_owned());
+        }
+        let path = attrs
+            .get("path")
+            .ok_or("no path given to snippet shortcode")?;
+
+        ctx.watch(path);
+        let content = match std::fs::read_to_string(ctx.root.join(path)) {
+            Ok(content) => content,
+            Err(e) => {
+                ctx.fail(Self::NAME, &format!("couldn't read `{}`: {}", path, e));
+                return Ok(String::new());
+            }
+        };
+        let lines: Vec<&str> = match (attrs.get("lines"), attrs.get("anchor")) {
+            (Some(_), Some(_)) => {
+                return Err("only one of lines and anchor can be given".to_owned())
+            }
+            (Some(range), None) => select_lines(&content, range)?,
+            (None, Some(anchor)) => select_anchor(&content, anchor)?,
+            (None, None) => content.lines().collect(),
+        };
+        let code = lines.join("\n");
+
+        let lang = attrs
+            .get("lang")
+            .unwrap_or_else(|| language(Path::new(path)));
+        let fence = "`".repeat(longest_backtick_run(&code).max(2) + 1);
+        Ok(format!(
+            "\n{fence}{}\n{}\n{fence}\n",
+            lang,
+            code,
+            fence = fence
+        ))
+    }
+}
+
+/// Returns the lines of `content` in `range`, e.g. `"10:30"`, numbered from 1.
+fn select_lines<'a>(content: &'a str, range: &str) -> Result<Vec<&'a str>, String> {
+    let invalid = || format!("invalid lines `{}`", range);
+    let parse = |number: &str| -> Result<Option<usize>, String> {
+        match number.trim() {
+            "" => Ok(None),
+            number => match number.parse() {
+                Ok(0) | Err(_) => Err(invalid()),
+                Ok(number) => Ok(Some(number)),
+            },
+        }
+    };
+    let (start, end) = match range.split_once(':') {
+        Some((start, end)) => (parse(start)?, parse(end)?),
+        None => {
+            let line = parse(range)?.ok_or_else(invalid)?;
+            (Some(line), Some(line))
+        }
+    };
+    let start = start.unwrap_or(1);
+    if end.is_some_and(|end| end < start) {
+        return Err(invalid());
+    }
+    let lines: Vec<&str> = content
+        .lines()
+        .skip(start - 1)
+        .take(end.map_or(usize::MAX, |end| end - start + 1))
+        .collect();
+    if lines.is_empty() {
+        return Err(format!("the file has no lines in `{}`", range));
+    }
+    Ok(lines)
+}
+
+/// Returns the lines of `content` between the `ANCHOR: anchor` and `ANCHOR_END: anchor`
+/// comments, without the comments of any anchors in them.
+fn select_anchor<'a>(content: &'a str, anchor: &str) -> Result<Vec<&'a str>, String> {
+    let is_marker = |line: &str, marker: &str| {
+        line.split_once(marker)
+            .and_then(|(_, name)| name.trim_start().strip_prefix(anchor))
+            .is_some_and(is_name_end)
+    };
+    let mut lines = content.lines();
+    if !lines.any(|line| is_marker(line, "ANCHOR:")) {
+        return Err(format!("no anchor `{}` in the file", anchor));
+    }
+    Ok(lines
+        .take_while(|line| !is_marker(line, "ANCHOR_END:"))
+        .filter(|line| !line.contains("ANCHOR:") && !line.contains("ANCHOR_END:"))
+        .collect())
+}
+
+/// Returns whether `rest`, which follows an anchor's name, ends it, so that `main` doesn't
+/// match `main_loop`.
+fn is_name_end(rest: &str) -> bool {
+    !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Returns the language of the code in the file at `path`, for highlighting.
+fn language(path: &Path) -> &'static str {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    match extension {
+        "rs" => "rust",
+        "py" => "python",
+        "js" | "mjs" | "cjs" => "javascript",
+        "ts" => "typescript",
+        "rb" => "ruby",
+        "go" => "go",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "swift" => "swift",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" => "cpp",
+        "cs" => "csharp",
+        "sh" | "bash" => "bash",
+        "ps1" => "powershell",
+        "toml" => "toml",
+        "json" => "json",
+        "yaml" | "yml" => "yaml",
+        "xml" => "xml",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "sql" => "sql",
+        "md" => "markdown",
+        "proto" => "protobuf",
+        "dockerfile" => "dockerfile",
+        _ if path.file_name() == Some("Dockerfile".as_ref()) => "dockerfile",
+        _ if path.file_name() == Some("Makefile".as_ref()) => "makefile",
+        _ => "text",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_snippet() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = "fn main() {
+    // ANCHOR: greet
+    // ANCHOR: name
+    let name = \"Ferris\";
+    // ANCHOR_END: name
+    println!(\"Hi {}\", name);
+    // ANCHOR_END: greet
+}
+";
+        std::fs::write(dir.path().join("main.rs"), source).unwrap();
+        let config = Config::default();
+        let mut ctx = Context::new(&config, dir.path().to_owned(), dir.path().join("src"));
+
+        assert_eq!(
+            FileSnippet::process_raw("{{#snippet path=main.rs lines=\"4:6\" /}}", &mut ctx),
+            Ok(
+                "\n```rust\n    let name = \"Ferris\";\n    // ANCHOR_END: name\n    \
+                println!(\"Hi {}\", name);\n```\n"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            FileSnippet::process_raw("{{#snippet path=main.rs anchor=greet lang=rs /}}", &mut ctx),
+            Ok(
+                "\n```rs\n    let name = \"Ferris\";\n    println!(\"Hi {}\", name);\n```\n"
+                    .to_owned()
+            )
+        );
+        assert_eq!(select_lines(source, "8:").unwrap(), ["}"]);
+        assert_eq!(select_lines(source, "1").unwrap(), ["fn main() {"]);
+        assert!(select_lines(source, "3:2").is_err());
+        assert!(select_lines(source, "20:").is_err());
+        assert!(select_anchor(source, "gree").is_err());
+
+        FileSnippet::process_raw("{{#snippet path=missing.rs /}}", &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 1);
+    }
+}
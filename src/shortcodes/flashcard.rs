@@ -0,0 +1,106 @@
+use crate::{analytics, html, markdown, Attrs, Context, MatchResult, Shortcode};
+
+/// A card that shows its back when clicked, with the front and back separated by a `---` line.
+///
+/// If `export-flashcards` is enabled, every card in the book (along with crossword clues) is also
+/// exported as an Anki deck.
+pub(crate) struct Flashcard;
+
+impl Shortcode for Flashcard {
+    const NAME: &'static str = "flashcard";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-flashcard {
+        padding: 1em;
+        margin: 1em 0;
+        border: 1px solid var(--quote-border);
+        border-radius: .5rem;
+    }
+    .mdbook-shortcodes-flashcard summary {
+        cursor: pointer;
+    }
+    .mdbook-shortcodes-flashcard[open] summary {
+        margin-bottom: 1em;
+    }
+</style>
+";
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        if !attrs.is_empty() {
+            return Err("too many arguments given to flashcard shortcode".to_owned());
+        }
+        let (front, back) = match split(input) {
+            Some(sides) => sides,
+            None => {
+                ctx.fail(
+                    Self::NAME,
+                    "the front and back must be separated by a `---` line",
+                );
+                return Ok(String::new());
+            }
+        };
+
+        if ctx.config.export_flashcards {
+            let path = ctx.chapter_path.clone();
+            ctx.deck
+                .add(&html::escape(front), &html::escape(back), &path);
+        }
+        let open = if ctx.is_static() { " open" } else { "" };
+        Ok(format!(
+            "<details class=\"mdbook-shortcodes-flashcard\"{}{}><summary>{}</summary>\n\n{}\n\n</details>",
+            open,
+            analytics::attr(ctx.config, "flashcard-toggle"),
+            markdown::inline(front),
+            back
+        ))
+    }
+}
+
+/// Splits a card into its front and back.
+fn split(input: &str) -> Option<(&str, &str)> {
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        if line.trim() == "---" {
+            let front = input[..offset].trim();
+            let back = input[offset + line.len()..].trim();
+            return Some((front, back));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, Profile};
+
+    #[test]
+    fn test_flashcard() {
+        let config = Config {
+            export_flashcards: true,
+            ..Config::default()
+        };
+        let mut ctx = Context::test(&config);
+        let input = "{{#flashcard}}
+What does `&mut` mean?
+---
+A *mutable* borrow.
+{{/flashcard}}";
+        let expected = "<details class=\"mdbook-shortcodes-flashcard\"><summary>What does <code>&amp;mut</code> mean?</summary>\n\n\
+            A *mutable* borrow.\n\n</details>";
+        let result = Flashcard::process_raw(input, &mut ctx).unwrap();
+        assert!(result.ends_with(expected));
+        assert!(!ctx.deck.is_empty());
+
+        Flashcard::process_raw("{{#flashcard}}No back{{/flashcard}}", &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 1);
+
+        let config = Config {
+            profile: Profile::Print,
+            ..Config::default()
+        };
+        let result = Flashcard::process_raw(input, &mut Context::test(&config)).unwrap();
+        assert!(result.contains("<details class=\"mdbook-shortcodes-flashcard\" open>"));
+    }
+}
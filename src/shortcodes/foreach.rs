@@ -0,0 +1,105 @@
+use crate::{data, template, Attrs, Capability, Context, MatchResult, Shortcode};
+
+/// Repeats its body for every record in a YAML, JSON or CSV file, e.g.
+/// `{{#foreach data="data/plugins.yaml" as=p}} - [{{p.name}}]({{p.url}}){{/foreach}}`. The path
+/// is relative to the book's root directory.
+///
+/// The records can be narrowed down with `filter="stars > 100"`, ordered with `sort=name` (or
+/// `sort=-stars` for descending order) and truncated with `limit=10`, in that order.
+pub(crate) struct Foreach;
+
+impl Shortcode for Foreach {
+    const NAME: &'static str = "foreach";
+    const HEADER: &'static str = "";
+    const CAPABILITY: Capability = Capability::Filesystem;
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &["data", "as", "sort", "filter", "limit"])?;
+        let path = attrs
+            .get("data")
+            .ok_or("no data given to foreach shortcode")?;
+        let name = attrs.get("as").unwrap_or("item");
+        let limit = attrs.get_parsed::<usize>("limit")?;
+
+        ctx.watch(path);
+        let mut records = match data::load(&ctx.root.join(path)) {
+            Ok(records) => records,
+            Err(e) => {
+                ctx.fail(Self::NAME, &format!("couldn't read `{}`: {}", path, e));
+                return Ok(String::new());
+            }
+        };
+
+        if let Some(filter) = attrs.get("filter") {
+            match data::Filter::parse(filter) {
+                Ok(filter) => records.retain(|record| filter.matches(record)),
+                Err(e) => {
+                    ctx.fail(Self::NAME, &e);
+                    return Ok(String::new());
+                }
+            }
+        }
+        if let Some(field) = attrs.get("sort") {
+            data::sort(&mut records, field);
+        }
+        if let Some(limit) = limit {
+            records.truncate(limit);
+        }
+
+        let body = input.trim_matches('\n');
+        let mut result = Vec::new();
+        for record in &records {
+            let rendered = template::render(body, |placeholder| {
+                let field = match placeholder.strip_prefix(name)? {
+                    "" => "",
+                    field => field.strip_prefix('.')?,
+                };
+                data::field(record, field)
+            });
+            match rendered {
+                Ok(rendered) => result.push(rendered),
+                Err(placeholder) => {
+                    let message = format!("no value for `{}` in `{}`", placeholder, path);
+                    ctx.fail(Self::NAME, &message);
+                    return Ok(String::new());
+                }
+            }
+        }
+        Ok(result.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_foreach() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("plugins.json"),
+            r#"[{"name": "a", "url": "https://a.rs"}, {"name": "b", "url": "https://b.rs"}]"#,
+        )
+        .unwrap();
+
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        ctx.root = dir.path().to_owned();
+        let input = "{{#foreach data=\"plugins.json\" as=p}}
+- [{{p.name}}]({{ p.url }})
+{{/foreach}}";
+        assert_eq!(
+            Foreach::process_raw(input, &mut ctx),
+            Ok("- [a](https://a.rs)\n- [b](https://b.rs)".to_owned())
+        );
+
+        let input = "{{#foreach data=\"plugins.json\" filter=\"name != a\" sort=-name limit=1}}\
+            {{item.name}}{{/foreach}}";
+        assert_eq!(Foreach::process_raw(input, &mut ctx), Ok("b".to_owned()));
+
+        let input = "{{#foreach data=\"plugins.json\"}}{{item.missing}}{{/foreach}}";
+        Foreach::process_raw(input, &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 1);
+    }
+}
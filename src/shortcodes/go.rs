@@ -0,0 +1,139 @@
+use crate::{shortcodes::chess::figure, Attrs, Context, MatchResult, Shortcode};
+
+/// The distance between lines in the generated SVG.
+const SPACING: usize = 24;
+
+/// The columns of a go board, which skip `I` by convention.
+const COLUMNS: &str = "ABCDEFGHJKLMNOPQRST";
+
+/// Draws a go position, e.g. `{{#go size=9 black="C3 D4" white="E5" /}}`. The body, if any, is
+/// used as a caption.
+pub(crate) struct Go;
+
+impl Shortcode for Go {
+    const NAME: &'static str = "go";
+    const HEADER: &'static str = "";
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &["size", "black", "white"])?;
+        let size = attrs.get_parsed::<usize>("size")?.unwrap_or(19);
+        if !(2..=19).contains(&size) {
+            return Err("go boards must be between 2x2 and 19x19".to_owned());
+        }
+
+        let mut stones = Vec::new();
+        for (color, fill) in [("black", "#000"), ("white", "#fff")] {
+            for point in attrs.get(color).unwrap_or("").split_whitespace() {
+                match parse_point(point, size) {
+                    Some((col, row)) => stones.push((col, row, fill)),
+                    None => {
+                        ctx.fail(Self::NAME, &format!("invalid point `{}`", point));
+                        return Ok(String::new());
+                    }
+                }
+            }
+        }
+
+        Ok(figure(&render_svg(size, &stones), input, "Go position"))
+    }
+}
+
+/// Returns the column and row (from the top) of a point such as `D4`.
+fn parse_point(point: &str, size: usize) -> Option<(usize, usize)> {
+    let mut chars = point.chars();
+    let col = COLUMNS.find(chars.next()?.to_ascii_uppercase())?;
+    let number: usize = chars.as_str().parse().ok()?;
+    if col >= size || number == 0 || number > size {
+        return None;
+    }
+    Some((col, size - number))
+}
+
+fn render_svg(size: usize, stones: &[(usize, usize, &str)]) -> String {
+    let margin = SPACING;
+    let extent = margin * 2 + SPACING * (size - 1);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {0} {0}\" width=\"{0}\" height=\"{0}\">\
+        <rect width=\"{0}\" height=\"{0}\" fill=\"#dcb35c\"/>",
+        extent
+    );
+    let end = margin + SPACING * (size - 1);
+    for i in 0..size {
+        let offset = margin + SPACING * i;
+        svg.push_str(&format!(
+            "<line x1=\"{0}\" y1=\"{1}\" x2=\"{0}\" y2=\"{2}\" stroke=\"#000\"/>\
+            <line x1=\"{1}\" y1=\"{0}\" x2=\"{2}\" y2=\"{0}\" stroke=\"#000\"/>",
+            offset, margin, end
+        ));
+    }
+
+    for (col, row) in star_points(size) {
+        svg.push_str(&format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"3\" fill=\"#000\"/>",
+            margin + SPACING * col,
+            margin + SPACING * row
+        ));
+    }
+    for (col, row, fill) in stones {
+        svg.push_str(&format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"#000\"/>",
+            margin + SPACING * col,
+            margin + SPACING * row,
+            SPACING / 2 - 1,
+            fill
+        ));
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+fn star_points(size: usize) -> Vec<(usize, usize)> {
+    let edge = match size {
+        19 | 13 => 3,
+        9 => 2,
+        _ => return Vec::new(),
+    };
+    let far = size - 1 - edge;
+    let mut points = vec![
+        (edge, edge),
+        (edge, far),
+        (far, edge),
+        (far, far),
+        (size / 2, size / 2),
+    ];
+    if size == 19 {
+        let mid = size / 2;
+        points.extend([(edge, mid), (far, mid), (mid, edge), (mid, far)]);
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_parse_point() {
+        assert_eq!(parse_point("A1", 9), Some((0, 8)));
+        assert_eq!(parse_point("j9", 9), Some((8, 0)));
+        assert_eq!(parse_point("I5", 9), None);
+        assert_eq!(parse_point("K1", 9), None);
+        assert_eq!(parse_point("A10", 9), None);
+    }
+
+    #[test]
+    fn test_go() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let result =
+            Go::process_raw("{{#go size=9 black=\"C3\" white=\"E5\" /}}", &mut ctx).unwrap();
+        assert!(result
+            .contains("<circle cx=\"72\" cy=\"168\" r=\"11\" fill=\"#000\" stroke=\"#000\"/>"));
+        assert!(result
+            .contains("<circle cx=\"120\" cy=\"120\" r=\"11\" fill=\"#fff\" stroke=\"#000\"/>"));
+        assert_eq!(star_points(9).len(), 5);
+        assert_eq!(star_points(19).len(), 9);
+    }
+}
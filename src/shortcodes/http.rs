@@ -0,0 +1,240 @@
+use crate::{html, shortcodes::copy, Attrs, Context, MatchResult, Shortcode};
+
+/// An example HTTP exchange, given as a raw request optionally followed by the raw response
+/// (starting with a `HTTP/1.1 200 OK` status line). A `curl` command equivalent to the request
+/// can be copied.
+pub(crate) struct Http;
+
+impl Shortcode for Http {
+    const NAME: &'static str = "http";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-http {
+        display: flex;
+        flex-wrap: wrap;
+        gap: 1em;
+        margin: 1em 0;
+    }
+    .mdbook-shortcodes-http > div {
+        flex: 1 1 20em;
+        min-width: 0;
+    }
+    .mdbook-shortcodes-http-method {
+        padding: .1em .5em;
+        border-radius: .25em;
+        color: #fff;
+        background: #888;
+        font-weight: bold;
+    }
+    .mdbook-shortcodes-http-method-get {
+        background: #2b7bb9;
+    }
+    .mdbook-shortcodes-http-method-post {
+        background: #49a35b;
+    }
+    .mdbook-shortcodes-http-method-put,
+    .mdbook-shortcodes-http-method-patch {
+        background: #d5901c;
+    }
+    .mdbook-shortcodes-http-method-delete {
+        background: #d33f3f;
+    }
+    .mdbook-shortcodes-http-header-name {
+        font-weight: bold;
+    }
+    .mdbook-shortcodes-copy {
+        margin-inline-start: .5em;
+        font-size: .75em;
+        cursor: pointer;
+    }
+</style>
+";
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        if !attrs.is_empty() {
+            return Err("too many arguments given to http shortcode".to_owned());
+        }
+        let input = input.trim_matches('\n');
+        let (request, response) = match input.find("\nHTTP/") {
+            Some(i) => (&input[..i], Some(&input[i + 1..])),
+            None => (input, None),
+        };
+
+        let request = Message::parse(request);
+        let mut parts = request.start_line.split_whitespace();
+        let (method, target) = match (parts.next(), parts.next()) {
+            (Some(method), Some(target)) => (method, target),
+            _ => {
+                let message = format!("invalid request line `{}`", request.start_line);
+                ctx.fail(Self::NAME, &message);
+                return Ok(String::new());
+            }
+        };
+
+        let curl = curl(method, target, &request);
+        let mut result = format!(
+            "<div class=\"mdbook-shortcodes-http\"><div class=\"mdbook-shortcodes-http-request\">\
+            <p><span class=\"mdbook-shortcodes-http-method mdbook-shortcodes-http-method-{}\">{}</span> \
+            <code>{}</code>{}</p>{}</div>",
+            html::escape(&method.to_lowercase()),
+            html::escape(method),
+            html::escape(target),
+            copy::button(&curl, "Copy as curl", ctx.config),
+            request.render()
+        );
+        if let Some(response) = response {
+            let response = Message::parse(response);
+            result.push_str(&format!(
+                "<div class=\"mdbook-shortcodes-http-response\"><p><code>{}</code></p>{}</div>",
+                html::escape(&response.start_line),
+                response.render()
+            ));
+        }
+        result.push_str("</div>");
+        result.push_str(&copy::script(ctx.config));
+        Ok(result)
+    }
+}
+
+/// A raw HTTP request or response.
+struct Message<'a> {
+    start_line: String,
+    headers: Vec<(&'a str, &'a str)>,
+    body: &'a str,
+}
+
+impl<'a> Message<'a> {
+    fn parse(raw: &'a str) -> Self {
+        let (head, body) = match raw.find("\n\n") {
+            Some(i) => (&raw[..i], raw[i + 2..].trim()),
+            None => (raw, ""),
+        };
+        let mut lines = head.lines();
+        let start_line = lines.next().unwrap_or_default().trim().to_owned();
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim(), value.trim()))
+            .collect();
+        Self {
+            start_line,
+            headers,
+            body,
+        }
+    }
+
+    fn header(&self, name: &str) -> Option<&'a str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| *value)
+    }
+
+    /// Renders the headers and body, highlighting the body based on its content type.
+    fn render(&self) -> String {
+        let mut result = String::new();
+        if !self.headers.is_empty() {
+            result.push_str("<pre><code class=\"nohighlight\">");
+            for (name, value) in &self.headers {
+                result.push_str(&format!(
+                    "<span class=\"mdbook-shortcodes-http-header-name\">{}</span>: {}\n",
+                    html::escape(name),
+                    html::escape(value)
+                ));
+            }
+            result.push_str("</code></pre>");
+        }
+        if !self.body.is_empty() {
+            let content_type = self.header("Content-Type").unwrap_or_default();
+            let language = if content_type.contains("json") {
+                "json"
+            } else if content_type.contains("xml") {
+                "xml"
+            } else if content_type.contains("html") {
+                "html"
+            } else {
+                "plaintext"
+            };
+            result.push_str(&format!(
+                "<pre><code class=\"language-{}\">{}</code></pre>",
+                language,
+                html::escape(self.body)
+            ));
+        }
+        result
+    }
+}
+
+/// Returns a `curl` command that sends the request.
+fn curl(method: &str, target: &str, request: &Message) -> String {
+    let url = match request.header("Host") {
+        Some(host) if target.starts_with('/') => format!("https://{}{}", host, target),
+        _ => target.to_owned(),
+    };
+    let mut command = String::from("curl");
+    if !(method == "GET" || method == "POST" && !request.body.is_empty()) {
+        command.push_str(&format!(" -X {}", method));
+    }
+    command.push_str(&format!(" {}", quote(&url)));
+    for (name, value) in &request.headers {
+        if !(name.eq_ignore_ascii_case("Host") || name.eq_ignore_ascii_case("Content-Length")) {
+            command.push_str(&format!(" -H {}", quote(&format!("{}: {}", name, value))));
+        }
+    }
+    if !request.body.is_empty() {
+        command.push_str(&format!(" --data-raw {}", quote(request.body)));
+    }
+    command
+}
+
+/// Quotes an argument for POSIX shells, which is what `curl` examples are usually run in.
+fn quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_curl() {
+        let request = Message::parse(
+            "POST /users HTTP/1.1\nHost: api.example.com\nContent-Type: application/json\n\n{\"name\": \"O'Neil\"}",
+        );
+        assert_eq!(
+            curl("POST", "/users", &request),
+            "curl 'https://api.example.com/users' -H 'Content-Type: application/json' \
+            --data-raw '{\"name\": \"O'\\''Neil\"}'"
+        );
+        let request = Message::parse("DELETE /users/1 HTTP/1.1\nHost: api.example.com");
+        assert_eq!(
+            curl("DELETE", "/users/1", &request),
+            "curl -X DELETE 'https://api.example.com/users/1'"
+        );
+    }
+
+    #[test]
+    fn test_http() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let input = "{{#http}}
+GET /users/1 HTTP/1.1
+Host: api.example.com
+
+HTTP/1.1 200 OK
+Content-Type: application/json
+
+{\"id\": 1}
+{{/http}}";
+        let result = Http::process_raw(input, &mut ctx).unwrap();
+        assert!(result.contains(
+            "<span class=\"mdbook-shortcodes-http-method mdbook-shortcodes-http-method-get\">GET</span> \
+            <code>/users/1</code><button"
+        ));
+        assert!(result.contains("data-copy=\"curl &#39;https://api.example.com/users/1&#39;\""));
+        assert!(result.contains(
+            "<div class=\"mdbook-shortcodes-http-response\"><p><code>HTTP/1.1 200 OK</code></p>"
+        ));
+        assert!(result.contains("<code class=\"language-json\">{&quot;id&quot;: 1}</code>"));
+    }
+}
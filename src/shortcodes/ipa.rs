@@ -0,0 +1,69 @@
+use crate::{html, Attrs, Context, MatchResult, Shortcode};
+
+/// Shows a pronunciation in the International Phonetic Alphabet, e.g. `{{#ipa /ˈrʌst/ /}}`,
+/// optionally linking to a recording with `audio="rust.mp3"`.
+pub(crate) struct Ipa;
+
+impl Shortcode for Ipa {
+    const NAME: &'static str = "ipa";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-ipa {
+        font-family: \"Charis SIL\", \"Doulos SIL\", \"Gentium Plus\", \"Noto Sans\", \"Lucida Sans Unicode\", serif;
+    }
+    .mdbook-shortcodes-ipa-audio {
+        text-decoration: none;
+        margin-inline-start: .2em;
+    }
+</style>
+";
+
+    fn process_match(_input: &str, attrs: Attrs, _ctx: &mut Context) -> MatchResult {
+        attrs.expect(1, &["audio"])?;
+        let transcription = match attrs.positional() {
+            [transcription] => *transcription,
+            _ => return Err("no transcription given to ipa shortcode".to_owned()),
+        };
+
+        let mut result = format!(
+            "<span class=\"mdbook-shortcodes-ipa\" lang=\"und-fonipa\">{}</span>",
+            html::escape(transcription)
+        );
+        if let Some(audio) = attrs.get("audio") {
+            result.push_str(&format!(
+                "<a class=\"mdbook-shortcodes-ipa-audio\" href=\"{}\" title=\"Listen\" \
+                aria-label=\"Listen\">🔊</a>",
+                html::escape(audio)
+            ));
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_ipa() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        assert_eq!(
+            Ipa::process_raw("Rust {{#ipa /ˈrʌst/ /}}.", &mut ctx),
+            Ok(
+                "Rust <span class=\"mdbook-shortcodes-ipa\" lang=\"und-fonipa\">/ˈrʌst/</span>."
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            Ipa::process_raw("{{#ipa \"[ˈrʌst]\" audio=\"rust.mp3\" /}}", &mut ctx),
+            Ok(
+                "<span class=\"mdbook-shortcodes-ipa\" lang=\"und-fonipa\">[ˈrʌst]</span>\
+                <a class=\"mdbook-shortcodes-ipa-audio\" href=\"rust.mp3\" title=\"Listen\" \
+                aria-label=\"Listen\">🔊</a>"
+                    .to_owned()
+            )
+        );
+    }
+}
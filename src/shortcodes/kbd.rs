@@ -0,0 +1,58 @@
+use crate::{html, Attrs, Context, MatchResult, Shortcode};
+
+/// Shows a keyboard shortcut, e.g. `{{#kbd Ctrl+Shift+P /}}`.
+pub(crate) struct Kbd;
+
+impl Shortcode for Kbd {
+    const NAME: &'static str = "kbd";
+    const HEADER: &'static str = "";
+
+    fn process_match(_input: &str, attrs: Attrs, _ctx: &mut Context) -> MatchResult {
+        match (attrs.positional(), attrs.named().is_empty()) {
+            ([keys], true) => Ok(render(keys)),
+            _ => Err("wrong number of arguments given to kbd shortcode".to_owned()),
+        }
+    }
+}
+
+/// Renders each key of a shortcut such as `Ctrl+S` in its own `<kbd>` element. Keys are separated
+/// by `+`, with a trailing `+` referring to the plus key itself (e.g. `Ctrl++`).
+pub(crate) fn render(keys: &str) -> String {
+    let keys = keys.trim();
+    let mut parts: Vec<&str> = keys.split('+').collect();
+    if keys.ends_with("++") || keys == "+" {
+        parts.truncate(parts.len().saturating_sub(2));
+        parts.push("+");
+    }
+
+    let mut result = String::from("<kbd class=\"mdbook-shortcodes-kbd\">");
+    for (i, key) in parts.iter().enumerate() {
+        if i > 0 {
+            result.push('+');
+        }
+        result.push_str(&format!("<kbd>{}</kbd>", html::escape(key.trim())));
+    }
+    result.push_str("</kbd>");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render("Ctrl+S"),
+            "<kbd class=\"mdbook-shortcodes-kbd\"><kbd>Ctrl</kbd>+<kbd>S</kbd></kbd>"
+        );
+        assert_eq!(
+            render("Ctrl++"),
+            "<kbd class=\"mdbook-shortcodes-kbd\"><kbd>Ctrl</kbd>+<kbd>+</kbd></kbd>"
+        );
+        assert_eq!(
+            render("+"),
+            "<kbd class=\"mdbook-shortcodes-kbd\"><kbd>+</kbd></kbd>"
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{html, markdown, Attrs, Context, MatchResult, Shortcode};
+
+/// Constrains its content to an aspect ratio, e.g. `{{#ratio 16:9}}...{{/ratio}}`. Embedded
+/// media (e.g. an iframe) fills the box.
+pub(crate) struct Ratio;
+
+impl Shortcode for Ratio {
+    const NAME: &'static str = "ratio";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-ratio {
+        position: relative;
+        overflow: hidden;
+        margin: 1em 0;
+    }
+    .mdbook-shortcodes-ratio > iframe,
+    .mdbook-shortcodes-ratio > video,
+    .mdbook-shortcodes-ratio > img {
+        width: 100%;
+        height: 100%;
+        border: 0;
+    }
+</style>
+";
+
+    fn process_match(input: &str, attrs: Attrs, _ctx: &mut Context) -> MatchResult {
+        attrs.expect(1, &[])?;
+        let ratio = match attrs.positional() {
+            [ratio] => *ratio,
+            _ => return Err("no ratio given to ratio shortcode".to_owned()),
+        };
+        let (width, height) = parse_ratio(ratio).ok_or_else(|| {
+            format!(
+                "invalid ratio `{}`; ratios must be given as `width:height`",
+                ratio
+            )
+        })?;
+
+        Ok(format!(
+            "<div class=\"mdbook-shortcodes-ratio\" style=\"aspect-ratio: {} / {}\">{}</div>",
+            width,
+            height,
+            markdown::block(input)
+        ))
+    }
+
+    fn process_plain(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        Self::process_match(input, attrs, ctx)?;
+        Ok(markdown::block(input))
+    }
+}
+
+/// Returns the width and height of a ratio such as `16:9`, which must both be positive.
+fn parse_ratio(ratio: &str) -> Option<(f64, f64)> {
+    let (width, height) = ratio.split_once(':')?;
+    let width: f64 = width.trim().parse().ok()?;
+    let height: f64 = height.trim().parse().ok()?;
+    if width > 0.0 && height > 0.0 && width.is_finite() && height.is_finite() {
+        Some((width, height))
+    } else {
+        None
+    }
+}
+
+/// Inserts vertical whitespace, e.g. `{{#spacer 3rem /}}`.
+pub(crate) struct Spacer;
+
+impl Shortcode for Spacer {
+    const NAME: &'static str = "spacer";
+    const HEADER: &'static str = "";
+
+    fn process_match(input: &str, attrs: Attrs, _ctx: &mut Context) -> MatchResult {
+        attrs.expect(1, &[])?;
+        if !input.trim().is_empty() {
+            return Err("spacer shortcode must be self-closing".to_owned());
+        }
+        let height = attrs.positional().first().copied().unwrap_or("1em");
+        Ok(format!(
+            "<div class=\"mdbook-shortcodes-spacer\" style=\"height: {}\" aria-hidden=\"true\"></div>",
+            html::escape(height)
+        ))
+    }
+
+    fn process_plain(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        Self::process_match(input, attrs, ctx).map(|_| String::new())
+    }
+}
+
+/// Floats supplementary content beside the text on wide screens, e.g.
+/// `{{#pullout side=right width=35%}}...{{/pullout}}`. On narrow screens, it is shown inline.
+pub(crate) struct Pullout;
+
+impl Shortcode for Pullout {
+    const NAME: &'static str = "pullout";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-pullout {
+        margin: 1em 0;
+        padding: 0 1em;
+        border-inline-start: .25em solid
+            var(--mdbook-shortcodes-pullout-border-color, var(--quote-border));
+        background-color: var(--mdbook-shortcodes-pullout-background, var(--quote-bg));
+    }
+    @media only screen and (min-width: 1080px) {
+        .mdbook-shortcodes-pullout-left {
+            float: left;
+            clear: left;
+            width: var(--mdbook-shortcodes-pullout-width);
+            margin: .5em 2em 1em 0;
+        }
+        .mdbook-shortcodes-pullout-right {
+            float: right;
+            clear: right;
+            width: var(--mdbook-shortcodes-pullout-width);
+            margin: .5em 0 1em 2em;
+        }
+    }
+</style>
+";
+
+    fn process_match(input: &str, attrs: Attrs, _ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &["side", "width"])?;
+        let side = match attrs.get("side").unwrap_or("right") {
+            side @ ("left" | "right") => side,
+            side => return Err(format!("unknown side `{}`", side)),
+        };
+        let width = attrs.get("width").unwrap_or("35%");
+
+        // The width only applies while the pullout is floated.
+        Ok(format!(
+            "<aside class=\"mdbook-shortcodes-pullout mdbook-shortcodes-pullout-{}\" \
+            style=\"--mdbook-shortcodes-pullout-width: {}\">{}</aside>",
+            side,
+            html::escape(width),
+            markdown::block(input)
+        ))
+    }
+
+    fn process_plain(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        Self::process_match(input, attrs, ctx)?;
+        Ok(markdown::blockquote(input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_ratio() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        assert_eq!(
+            Ratio::process_raw("{{#ratio 16:9}}<iframe></iframe>{{/ratio}}", &mut ctx),
+            Ok(
+                "<div class=\"mdbook-shortcodes-ratio\" style=\"aspect-ratio: 16 / 9\">\
+                \n\n<iframe></iframe>\n\n</div>"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            Ratio::process_raw("{{#ratio 2.39:1}}A{{/ratio}}", &mut ctx),
+            Ok(
+                "<div class=\"mdbook-shortcodes-ratio\" style=\"aspect-ratio: 2.39 / 1\">\
+                \n\nA\n\n</div>"
+                    .to_owned()
+            )
+        );
+        for ratio in ["16", "16:0", "a:b", "-1:2"] {
+            let input = format!("{{{{#ratio {}}}}}A{{{{/ratio}}}}", ratio);
+            assert!(Ratio::process_raw(&input, &mut ctx).is_err());
+        }
+    }
+
+    #[test]
+    fn test_spacer() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        assert_eq!(
+            Spacer::process_raw("A{{#spacer 3rem /}}B", &mut ctx),
+            Ok(
+                "A<div class=\"mdbook-shortcodes-spacer\" style=\"height: 3rem\" \
+                aria-hidden=\"true\"></div>B"
+                    .to_owned()
+            )
+        );
+        assert!(Spacer::process_raw("{{#spacer /}}", &mut ctx)
+            .unwrap()
+            .contains("height: 1em"));
+        assert!(Spacer::process_raw("{{#spacer}}A{{/spacer}}", &mut ctx).is_err());
+    }
+
+    #[test]
+    fn test_pullout() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        assert_eq!(
+            Pullout::process_raw(
+                "{{#pullout side=left width=\"40%\"}}Aside{{/pullout}}",
+                &mut ctx
+            ),
+            Ok(
+                "<aside class=\"mdbook-shortcodes-pullout mdbook-shortcodes-pullout-left\" \
+                style=\"--mdbook-shortcodes-pullout-width: 40%\">\n\nAside\n\n</aside>"
+                    .to_owned()
+            )
+        );
+        assert!(Pullout::process_raw("{{#pullout}}A{{/pullout}}", &mut ctx)
+            .unwrap()
+            .contains("pullout-right\" style=\"--mdbook-shortcodes-pullout-width: 35%\""));
+        assert!(Pullout::process_raw("{{#pullout side=top}}A{{/pullout}}", &mut ctx).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use crate::{html, Attrs, Context, MatchResult, Shortcode};
+
+/// Shows a license notice, e.g. `{{#license CC-BY-4.0 author="Jane Doe" year=2024 /}}`, marked
+/// up with RDFa so that the license can be read by machines.
+pub(crate) struct License;
+
+impl Shortcode for License {
+    const NAME: &'static str = "license";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-license {
+        display: flex;
+        align-items: center;
+        gap: 1em;
+        margin: 2em 0;
+        font-size: .9em;
+    }
+    .mdbook-shortcodes-license img {
+        flex: none;
+    }
+</style>
+";
+
+    fn process_match(input: &str, attrs: Attrs, _ctx: &mut Context) -> MatchResult {
+        attrs.expect(1, &["author", "year", "title", "url"])?;
+        if !input.trim().is_empty() {
+            return Err("license shortcode must be self-closing".to_owned());
+        }
+        let id = match attrs.positional() {
+            [id] => *id,
+            _ => return Err("no license given to license shortcode".to_owned()),
+        };
+        let known = LICENSES
+            .iter()
+            .find(|license| license.id.eq_ignore_ascii_case(id));
+        let (id, name, url, icon) = match (known, attrs.get("url")) {
+            (_, Some(url)) => (id, id.to_owned(), url.to_owned(), None),
+            (Some(license), None) => (license.id, license.name(), license.url(), license.icon()),
+            (None, None) => {
+                return Err(format!(
+                    "unknown license `{}`; give its `url` to use it",
+                    id
+                ))
+            }
+        };
+
+        let mut result = String::from(
+            "<div class=\"mdbook-shortcodes-license\" xmlns:cc=\"http://creativecommons.org/ns#\" \
+            xmlns:dct=\"http://purl.org/dc/terms/\">",
+        );
+        if let Some(icon) = icon {
+            result.push_str(&format!(
+                "<a rel=\"license\" href=\"{}\"><img src=\"{}\" alt=\"{}\" width=\"88\" height=\"31\"></a>",
+                html::escape(&url),
+                html::escape(&icon),
+                html::escape(id)
+            ));
+        }
+        result.push_str("<p>");
+        if let Some(title) = attrs.get("title") {
+            result.push_str(&format!(
+                "<span property=\"dct:title\">{}</span> ",
+                html::escape(title)
+            ));
+        }
+        match (attrs.get("year"), attrs.get("author")) {
+            (Some(year), Some(author)) => result.push_str(&format!(
+                "© {} <span property=\"cc:attributionName\">{}</span>. ",
+                html::escape(year),
+                html::escape(author)
+            )),
+            (None, Some(author)) => result.push_str(&format!(
+                "By <span property=\"cc:attributionName\">{}</span>. ",
+                html::escape(author)
+            )),
+            (Some(year), None) => result.push_str(&format!("© {}. ", html::escape(year))),
+            (None, None) => {}
+        }
+        result.push_str(&format!(
+            "Licensed under <a rel=\"license\" href=\"{}\">{}</a>.</p></div>",
+            html::escape(&url),
+            html::escape(&name)
+        ));
+        Ok(result)
+    }
+}
+
+/// A license that can be given by its SPDX identifier.
+struct KnownLicense {
+    id: &'static str,
+    kind: Kind,
+}
+
+enum Kind {
+    /// A Creative Commons 4.0 license, with the code of its conditions (e.g. `by-sa`).
+    CreativeCommons(&'static str),
+    /// The CC0 public domain dedication.
+    PublicDomain,
+    /// Any other license, with its name and URL.
+    Other(&'static str, &'static str),
+}
+
+const LICENSES: &[KnownLicense] = &[
+    KnownLicense {
+        id: "CC-BY-4.0",
+        kind: Kind::CreativeCommons("by"),
+    },
+    KnownLicense {
+        id: "CC-BY-SA-4.0",
+        kind: Kind::CreativeCommons("by-sa"),
+    },
+    KnownLicense {
+        id: "CC-BY-ND-4.0",
+        kind: Kind::CreativeCommons("by-nd"),
+    },
+    KnownLicense {
+        id: "CC-BY-NC-4.0",
+        kind: Kind::CreativeCommons("by-nc"),
+    },
+    KnownLicense {
+        id: "CC-BY-NC-SA-4.0",
+        kind: Kind::CreativeCommons("by-nc-sa"),
+    },
+    KnownLicense {
+        id: "CC-BY-NC-ND-4.0",
+        kind: Kind::CreativeCommons("by-nc-nd"),
+    },
+    KnownLicense {
+        id: "CC0-1.0",
+        kind: Kind::PublicDomain,
+    },
+    KnownLicense {
+        id: "MIT",
+        kind: Kind::Other("MIT License", "https://opensource.org/licenses/MIT"),
+    },
+    KnownLicense {
+        id: "Apache-2.0",
+        kind: Kind::Other(
+            "Apache License 2.0",
+            "https://www.apache.org/licenses/LICENSE-2.0",
+        ),
+    },
+    KnownLicense {
+        id: "MPL-2.0",
+        kind: Kind::Other(
+            "Mozilla Public License 2.0",
+            "https://www.mozilla.org/en-US/MPL/2.0/",
+        ),
+    },
+];
+
+impl KnownLicense {
+    fn name(&self) -> String {
+        match self.kind {
+            Kind::CreativeCommons(code) => {
+                let conditions: Vec<&str> = code
+                    .split('-')
+                    .map(|condition| match condition {
+                        "by" => "Attribution",
+                        "sa" => "ShareAlike",
+                        "nd" => "NoDerivatives",
+                        "nc" => "NonCommercial",
+                        _ => unreachable!(),
+                    })
+                    .collect();
+                format!(
+                    "Creative Commons {} 4.0 International",
+                    conditions.join("-")
+                )
+            }
+            Kind::PublicDomain => "CC0 1.0 Universal".to_owned(),
+            Kind::Other(name, _) => name.to_owned(),
+        }
+    }
+
+    fn url(&self) -> String {
+        match self.kind {
+            Kind::CreativeCommons(code) => {
+                format!("https://creativecommons.org/licenses/{}/4.0/", code)
+            }
+            Kind::PublicDomain => "https://creativecommons.org/publicdomain/zero/1.0/".to_owned(),
+            Kind::Other(_, url) => url.to_owned(),
+        }
+    }
+
+    /// The URL of the license's button, if it has one.
+    fn icon(&self) -> Option<String> {
+        match self.kind {
+            Kind::CreativeCommons(code) => Some(format!(
+                "https://licensebuttons.net/l/{}/4.0/88x31.png",
+                code
+            )),
+            Kind::PublicDomain => {
+                Some("https://licensebuttons.net/p/zero/1.0/88x31.png".to_owned())
+            }
+            Kind::Other(..) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_license() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        assert_eq!(
+            License::process_raw(
+                "{{#license cc-by-sa-4.0 author=\"Jane Doe\" year=2024 /}}",
+                &mut ctx
+            ),
+            Ok("<div class=\"mdbook-shortcodes-license\" \
+                xmlns:cc=\"http://creativecommons.org/ns#\" xmlns:dct=\"http://purl.org/dc/terms/\">\
+                <a rel=\"license\" href=\"https://creativecommons.org/licenses/by-sa/4.0/\">\
+                <img src=\"https://licensebuttons.net/l/by-sa/4.0/88x31.png\" alt=\"CC-BY-SA-4.0\" \
+                width=\"88\" height=\"31\"></a><p>© 2024 \
+                <span property=\"cc:attributionName\">Jane Doe</span>. Licensed under \
+                <a rel=\"license\" href=\"https://creativecommons.org/licenses/by-sa/4.0/\">\
+                Creative Commons Attribution-ShareAlike 4.0 International</a>.</p></div>"
+                .to_owned())
+        );
+
+        let result = License::process_raw("{{#license MIT /}}", &mut ctx).unwrap();
+        assert!(!result.contains("<img"));
+        assert!(result.contains(
+            "<p>Licensed under <a rel=\"license\" \
+            href=\"https://opensource.org/licenses/MIT\">MIT License</a>.</p>"
+        ));
+
+        let result = License::process_raw(
+            "{{#license Custom url=\"https://example.com\" /}}",
+            &mut ctx,
+        );
+        assert!(result
+            .unwrap()
+            .contains("href=\"https://example.com\">Custom</a>"));
+        assert!(License::process_raw("{{#license Custom /}}", &mut ctx).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use serde::Deserialize;
+
+use crate::{markdown, Attrs, Context, MatchResult, Shortcode};
+
+/// The markers that `# (1)` to `# (10)` callouts are shown as.
+const MARKERS: [char; 10] = ['❶', '❷', '❸', '❹', '❺', '❻', '❼', '❽', '❾', '❿'];
+
+/// An annotated YAML example (e.g. a Kubernetes manifest), given as a fenced code block followed
+/// by a numbered list of annotations. Lines ending in a `# (1)` comment are marked with the
+/// matching callout, and the YAML is checked for syntax errors at build time.
+pub(crate) struct Manifest;
+
+impl Shortcode for Manifest {
+    const NAME: &'static str = "manifest";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-manifest ol {
+        list-style: none;
+        counter-reset: mdbook-shortcodes-manifest;
+    }
+    .mdbook-shortcodes-manifest ol li {
+        counter-increment: mdbook-shortcodes-manifest;
+    }
+    .mdbook-shortcodes-manifest ol li::before {
+        content: counter(mdbook-shortcodes-manifest);
+        display: inline-block;
+        width: 1.4em;
+        margin-inline-start: -1.8em;
+        margin-inline-end: .4em;
+        border-radius: 50%;
+        color: var(--bg);
+        background: var(--fg);
+        text-align: center;
+        font-size: .8em;
+    }
+</style>
+";
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        if !attrs.is_empty() {
+            return Err("too many arguments given to manifest shortcode".to_owned());
+        }
+        let code = match markdown::fenced_blocks(input).first() {
+            Some(block) => block.content,
+            None => {
+                ctx.fail(Self::NAME, "no YAML code block given");
+                return Ok(String::new());
+            }
+        };
+
+        for document in serde_yaml::Deserializer::from_str(code) {
+            if let Err(e) = serde_yaml::Value::deserialize(document) {
+                ctx.fail(Self::NAME, &format!("invalid YAML: {}", e));
+                break;
+            }
+        }
+
+        let mut callouts = 0;
+        let mut annotated = String::new();
+        for line in code.split_inclusive('\n') {
+            let (line, ending) = match line.strip_suffix('\n') {
+                Some(line) => (line, "\n"),
+                None => (line, ""),
+            };
+            match callout(line) {
+                Some((code, number)) => {
+                    callouts = callouts.max(number);
+                    let marker = MARKERS
+                        .get(number - 1)
+                        .map(char::to_string)
+                        .unwrap_or_else(|| format!("({})", number));
+                    annotated.push_str(&format!("{}# {}", code, marker));
+                }
+                None => annotated.push_str(line),
+            }
+            annotated.push_str(ending);
+        }
+
+        // `code` is a slice of `input`, so the text around it can be kept as is.
+        let start = code.as_ptr() as usize - input.as_ptr() as usize;
+        let (before, after) = (&input[..start], &input[start + code.len()..]);
+        let annotations = after
+            .lines()
+            .filter(|line| {
+                let line = line.trim_start();
+                let digits = line.chars().take_while(char::is_ascii_digit).count();
+                digits > 0 && line[digits..].starts_with(". ")
+            })
+            .count();
+        if annotations != callouts {
+            ctx.warn(
+                Self::NAME,
+                &format!("{} callouts but {} annotations", callouts, annotations),
+            );
+        }
+
+        Ok(format!(
+            "<div class=\"mdbook-shortcodes-manifest\">\n\n{}{}{}\n\n</div>",
+            before.trim_start_matches('\n'),
+            annotated,
+            after.trim_end()
+        ))
+    }
+}
+
+/// Splits a line ending in a callout comment (e.g. `replicas: 3 # (1)`) into the code before the
+/// comment and the callout number.
+fn callout(line: &str) -> Option<(&str, usize)> {
+    let rest = line.trim_end().strip_suffix(')')?;
+    let open = rest.rfind('(')?;
+    let number = rest[open + 1..].parse().ok().filter(|n| *n > 0)?;
+    let code = rest[..open].trim_end().strip_suffix('#')?;
+    Some((code, number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_callout() {
+        assert_eq!(callout("  replicas: 3 # (2)"), Some(("  replicas: 3 ", 2)));
+        assert_eq!(callout("# (1)"), Some(("", 1)));
+        assert_eq!(callout("name: f(1)"), None);
+        assert_eq!(callout("name: x # (0)"), None);
+    }
+
+    #[test]
+    fn test_manifest() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let input = "{{#manifest}}
+```yaml
+apiVersion: apps/v1
+kind: Deployment
+spec:
+  replicas: 3 # (1)
+```
+
+1. The number of pods.
+{{/manifest}}";
+        let expected = "<div class=\"mdbook-shortcodes-manifest\">\n\n```yaml
+apiVersion: apps/v1
+kind: Deployment
+spec:
+  replicas: 3 # ❶
+```
+
+1. The number of pods.\n\n</div>";
+        assert!(Manifest::process_raw(input, &mut ctx)
+            .unwrap()
+            .ends_with(expected));
+        assert!(ctx.errors.is_empty());
+
+        let input = "{{#manifest}}\n```yaml\nkey: [unclosed\n```\n{{/manifest}}";
+        Manifest::process_raw(input, &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 1);
+    }
+}
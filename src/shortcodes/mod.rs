@@ -0,0 +1,98 @@
+mod assert_output;
+mod badge;
+mod book_stats;
+mod cargo_features;
+mod chem;
+mod chess;
+mod cli_help;
+mod cmd_output;
+mod conditional;
+mod config_schema;
+mod copy;
+mod crossword;
+mod demo;
+mod env;
+mod envvars;
+mod error_catalog;
+mod file_snippet;
+mod flashcard;
+mod foreach;
+#[cfg(feature = "go")]
+mod go;
+mod http;
+mod ipa;
+mod kbd;
+mod layout;
+mod license;
+mod manifest;
+mod money;
+mod notes;
+mod num;
+mod options;
+#[cfg(feature = "proto")]
+mod proto;
+mod regex;
+mod remote_include;
+mod rustc_output;
+mod rustdoc;
+mod script;
+mod shortcuts;
+mod slide;
+mod snippet;
+mod sql;
+mod unit;
+mod var;
+mod wasm_demo;
+mod watermark;
+mod workspace_crates;
+#[cfg(feature = "xiangqi")]
+mod xiangqi;
+
+pub(crate) use assert_output::AssertOutput;
+pub(crate) use badge::{CrateBadge, RepoBadge};
+pub(crate) use book_stats::{book_stats, BookStats, BOOK_STATS_MARKER};
+pub(crate) use cargo_features::CargoFeatures;
+pub(crate) use chem::{Chem, Smiles};
+pub(crate) use chess::Chess;
+pub(crate) use cli_help::CliHelp;
+pub(crate) use cmd_output::CmdOutput;
+pub(crate) use conditional::If;
+pub(crate) use config_schema::ConfigSchema;
+pub(crate) use crossword::Crossword;
+pub(crate) use demo::Demo;
+pub(crate) use env::Env;
+pub(crate) use envvars::Envvars;
+pub(crate) use error_catalog::ErrorCatalog;
+pub(crate) use file_snippet::FileSnippet;
+pub(crate) use flashcard::Flashcard;
+pub(crate) use foreach::Foreach;
+#[cfg(feature = "go")]
+pub(crate) use go::Go;
+pub(crate) use http::Http;
+pub(crate) use ipa::Ipa;
+pub(crate) use kbd::Kbd;
+pub(crate) use layout::{Pullout, Ratio, Spacer};
+pub(crate) use license::License;
+pub(crate) use manifest::Manifest;
+pub(crate) use money::Money;
+pub(crate) use notes::{todo_list, CitationNeeded, Note, Review, Todo, TodoList, TODO_LIST_MARKER};
+pub(crate) use num::Num;
+pub(crate) use options::Options;
+#[cfg(feature = "proto")]
+pub(crate) use proto::Proto;
+pub(crate) use regex::Regex;
+pub(crate) use remote_include::RemoteInclude;
+pub(crate) use rustc_output::RustcOutput;
+pub(crate) use rustdoc::Rustdoc;
+pub(crate) use script::{script_open_tag, Script};
+pub(crate) use shortcuts::Shortcuts;
+pub(crate) use slide::Slide;
+pub(crate) use snippet::{DefineSnippet, Snippet, UseSnippet};
+pub(crate) use sql::Sql;
+pub(crate) use unit::Unit;
+pub(crate) use var::Var;
+pub(crate) use wasm_demo::WasmDemo;
+pub(crate) use watermark::Watermark;
+pub(crate) use workspace_crates::WorkspaceCrates;
+#[cfg(feature = "xiangqi")]
+pub(crate) use xiangqi::Xiangqi;
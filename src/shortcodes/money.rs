@@ -0,0 +1,87 @@
+use crate::{locale::Locale, Attrs, Context, MatchResult, Shortcode};
+
+/// The symbol and number of decimal places of each currency with its own symbol. Other currencies
+/// are shown using their code.
+const CURRENCIES: &[(&str, &str, usize)] = &[
+    ("USD", "$", 2),
+    ("EUR", "€", 2),
+    ("GBP", "£", 2),
+    ("JPY", "¥", 0),
+    ("CNY", "¥", 2),
+    ("KRW", "₩", 0),
+    ("INR", "₹", 2),
+    ("RUB", "₽", 2),
+    ("BRL", "R$", 2),
+    ("AUD", "A$", 2),
+    ("CAD", "CA$", 2),
+];
+
+/// Shows an amount of money formatted for the book's language, e.g. `{{#money 1234.5 USD /}}`
+/// becomes "$1,234.50" in English and "1.234,50 $" in German.
+pub(crate) struct Money;
+
+impl Shortcode for Money {
+    const NAME: &'static str = "money";
+    const HEADER: &'static str = "";
+
+    fn process_match(_input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(2, &[])?;
+        let (amount, currency) = match attrs.positional() {
+            [amount] => (*amount, ctx.config.currency.as_str()),
+            [amount, currency] => (*amount, *currency),
+            _ => return Err("wrong number of arguments given to money shortcode".to_owned()),
+        };
+        let amount: f64 = amount
+            .parse()
+            .map_err(|_| "invalid amount given to money shortcode")?;
+        let currency = currency.to_ascii_uppercase();
+
+        let (symbol, precision) = CURRENCIES
+            .iter()
+            .find(|(code, _, _)| *code == currency)
+            .map_or((currency.as_str(), 2), |(_, symbol, precision)| {
+                (*symbol, *precision)
+            });
+        let locale = Locale::from_language(&ctx.language);
+        let number = locale.format_number(amount, precision);
+
+        // A no-break space keeps the symbol on the same line as the amount.
+        if locale.currency_after || symbol == currency {
+            Ok(format!("{}\u{a0}{}", number, symbol))
+        } else if let Some(number) = number.strip_prefix('-') {
+            Ok(format!("-{}{}", symbol, number))
+        } else {
+            Ok(format!("{}{}", symbol, number))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_money() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let cases = [
+            ("{{#money 1234.5 USD /}}", "$1,234.50"),
+            ("{{#money -3 /}}", "-$3.00"),
+            ("{{#money 1500 jpy /}}", "¥1,500"),
+            ("{{#money 10 CHF /}}", "10.00\u{a0}CHF"),
+        ];
+        for (input, expected) in cases.iter() {
+            assert_eq!(
+                Money::process_raw(input, &mut ctx),
+                Ok((*expected).to_owned())
+            );
+        }
+
+        ctx.language = "de".to_owned();
+        assert_eq!(
+            Money::process_raw("{{#money 1234.5 EUR /}}", &mut ctx),
+            Ok("1.234,50\u{a0}€".to_owned())
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use std::path::PathBuf;
+
+use crate::{html, Attrs, Context, MatchResult, Profile, Shortcode};
+
+const HEADER: &str = "
+<style>
+    .mdbook-shortcodes-note {
+        padding: 0 .25em;
+        border-radius: .25em;
+        font-size: .85em;
+        color: #000;
+        background-color: #fd6;
+    }
+    .mdbook-shortcodes-note-citation {
+        background-color: #fcc;
+    }
+    mark.mdbook-shortcodes-reviewed {
+        background-color: rgba(255, 221, 102, .3);
+        color: inherit;
+    }
+</style>
+";
+
+/// An editorial note (e.g. a `{{#todo}}`) used somewhere in the book.
+#[derive(Clone, Debug)]
+pub(crate) struct Note {
+    /// The path of the chapter containing the note, relative to the source directory.
+    chapter: PathBuf,
+    shortcode: &'static str,
+    text: String,
+    /// The id of the note's element, which a release build leaves out.
+    id: String,
+}
+
+impl std::fmt::Display for Note {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.chapter.display(), self.shortcode)?;
+        if !self.text.is_empty() {
+            write!(f, ": {}", self.text)?;
+        }
+        Ok(())
+    }
+}
+
+/// Records a note in the current chapter, and returns the id of its element.
+fn record(ctx: &mut Context, shortcode: &'static str, text: &str) -> String {
+    let id = ctx.ids.generate("note");
+    ctx.notes.push(Note {
+        chapter: ctx.chapter_path.clone(),
+        shortcode,
+        text: text.trim().to_owned(),
+        id: id.clone(),
+    });
+    id
+}
+
+/// Marks unfinished work, e.g. `{{#todo}}Add an example{{/todo}}` or
+/// `{{#todo "add an example" /}}`.
+pub(crate) struct Todo;
+
+impl Shortcode for Todo {
+    const NAME: &'static str = "todo";
+    const ALIASES: &'static [&'static str] = &["fixme"];
+    const HEADER: &'static str = HEADER;
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(1, &[])?;
+        let text = match (attrs.positional(), input.trim()) {
+            (&[text], "") | (&[], text) => text,
+            _ => return Err("todo shortcode takes either an argument or a body".to_owned()),
+        };
+        let id = record(ctx, Self::NAME, text);
+        if ctx.config.profile == Profile::Release {
+            return Ok(String::new());
+        }
+        // Unlike the body, the argument isn't markdown.
+        let text = if input.trim().is_empty() {
+            html::escape(text)
+        } else {
+            text.to_owned()
+        };
+        Ok(format!(
+            "<span id=\"{}\" class=\"mdbook-shortcodes-note mdbook-shortcodes-note-todo\">\
+            <strong>TODO</strong> {}</span>",
+            id, text
+        ))
+    }
+}
+
+/// Attaches a note for reviewers to a passage, e.g.
+/// `{{#review "check this number"}}42%{{/review}}`. In a release build, only the passage is
+/// kept.
+pub(crate) struct Review;
+
+impl Shortcode for Review {
+    const NAME: &'static str = "review";
+    const HEADER: &'static str = HEADER;
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(1, &[])?;
+        let note = match attrs.positional() {
+            [note] => *note,
+            _ => return Err("no note given to review shortcode".to_owned()),
+        };
+        let id = record(ctx, Self::NAME, note);
+        if ctx.config.profile == Profile::Release {
+            return Ok(input.to_owned());
+        }
+        let passage = if input.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "<mark class=\"mdbook-shortcodes-reviewed\">{}</mark>",
+                input
+            )
+        };
+        Ok(format!(
+            "{}<span id=\"{}\" class=\"mdbook-shortcodes-note mdbook-shortcodes-note-review\">\
+            <strong>Review</strong> {}</span>",
+            passage,
+            id,
+            html::escape(note)
+        ))
+    }
+}
+
+/// Flags a claim that needs a source, e.g. `{{#citation-needed /}}`.
+pub(crate) struct CitationNeeded;
+
+impl Shortcode for CitationNeeded {
+    const NAME: &'static str = "citation-needed";
+    const HEADER: &'static str = HEADER;
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &[])?;
+        if !input.trim().is_empty() {
+            return Err("citation-needed shortcode must be self-closing".to_owned());
+        }
+        let id = record(ctx, Self::NAME, "");
+        if ctx.config.profile == Profile::Release {
+            return Ok(String::new());
+        }
+        Ok(format!(
+            "<sup id=\"{}\" class=\"mdbook-shortcodes-note mdbook-shortcodes-note-citation\">\
+            citation needed</sup>",
+            id
+        ))
+    }
+}
+
+/// What the `todo-list` shortcode is replaced with until every chapter has been processed.
+pub(crate) const TODO_LIST_MARKER: &str = "<!-- mdbook-shortcodes-todo-list -->";
+
+/// Lists every editorial note in the book, linking to where each one is, e.g.
+/// `{{#todo-list /}}`. Release builds leave the list out along with the notes.
+pub(crate) struct TodoList;
+
+impl Shortcode for TodoList {
+    const NAME: &'static str = "todo-list";
+    const HEADER: &'static str = "";
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &[])?;
+        if !input.trim().is_empty() {
+            return Err("todo-list shortcode must be self-closing".to_owned());
+        }
+        if ctx.config.profile == Profile::Release {
+            return Ok(String::new());
+        }
+        // Notes in later chapters haven't been found yet.
+        Ok(TODO_LIST_MARKER.to_owned())
+    }
+}
+
+/// Returns the list of every note in the book, as seen from the current chapter.
+pub(crate) fn todo_list(ctx: &Context) -> String {
+    if ctx.notes.is_empty() {
+        return "<p class=\"mdbook-shortcodes-todo-list\">There are no outstanding notes.</p>"
+            .to_owned();
+    }
+    let mut result = String::from("<ul class=\"mdbook-shortcodes-todo-list\">");
+    for note in &ctx.notes {
+        result.push_str("<li>");
+        // Draft chapters don't have a page to link to.
+        if note.chapter.as_os_str().is_empty() {
+            result.push_str(note.shortcode);
+        } else {
+            result.push_str(&format!(
+                "<a href=\"{}{}#{}\">{}</a>: {}",
+                ctx.path_to_root(),
+                html::escape(&note.chapter.with_extension("html").to_string_lossy()),
+                note.id,
+                html::escape(&note.chapter.display().to_string()),
+                note.shortcode
+            ));
+        }
+        if !note.text.is_empty() {
+            result.push_str(&format!(": {}", html::escape(&note.text)));
+        }
+        result.push_str("</li>");
+    }
+    result.push_str("</ul>");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_notes() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        assert_eq!(
+            Todo::process_raw("{{#todo \"add <b>\" /}}", &mut ctx),
+            Ok("<span id=\"mdbook-shortcodes-note-0\" \
+                class=\"mdbook-shortcodes-note mdbook-shortcodes-note-todo\">\
+                <strong>TODO</strong> add &lt;b&gt;</span>"
+                .to_owned())
+        );
+        assert_eq!(
+            Review::process_raw("{{#review \"check this\"}}42%{{/review}}", &mut ctx),
+            Ok("<mark class=\"mdbook-shortcodes-reviewed\">42%</mark>\
+                <span id=\"mdbook-shortcodes-note-1\" \
+                class=\"mdbook-shortcodes-note mdbook-shortcodes-note-review\">\
+                <strong>Review</strong> check this</span>"
+                .to_owned())
+        );
+        assert!(Todo::process_raw("{{#todo \"A\"}}B{{/todo}}", &mut ctx).is_err());
+    }
+
+    #[test]
+    fn test_release_notes() {
+        let config = Config {
+            profile: Profile::Release,
+            ..Config::default()
+        };
+        let mut ctx = Context::test(&config);
+        ctx.start_chapter(Some(Path::new("intro.md")));
+        assert_eq!(
+            Todo::process_raw("A{{#todo}}Add an example{{/todo}}B", &mut ctx),
+            Ok("AB".to_owned())
+        );
+        assert_eq!(
+            Review::process_raw("{{#review \"check this\"}}42%{{/review}}", &mut ctx),
+            Ok("42%".to_owned())
+        );
+        assert_eq!(
+            CitationNeeded::process_raw("A{{#citation-needed /}}.", &mut ctx),
+            Ok("A.".to_owned())
+        );
+        assert_eq!(
+            TodoList::process_raw("{{#todo-list /}}", &mut ctx),
+            Ok(String::new())
+        );
+        let notes: Vec<String> = ctx.notes.iter().map(Note::to_string).collect();
+        assert_eq!(
+            notes,
+            [
+                "intro.md: todo: Add an example",
+                "intro.md: review: check this",
+                "intro.md: citation-needed"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_todo_list() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        ctx.start_chapter(Some(Path::new("appendix/todo.md")));
+        assert_eq!(
+            TodoList::process_raw("{{#todo-list /}}", &mut ctx),
+            Ok(TODO_LIST_MARKER.to_owned())
+        );
+        assert!(todo_list(&ctx).contains("no outstanding notes"));
+
+        ctx.start_chapter(Some(Path::new("guide/setup.md")));
+        Todo::process_raw("{{#todo \"Add <b>\" /}}", &mut ctx).unwrap();
+        ctx.start_chapter(Some(Path::new("appendix/todo.md")));
+        assert_eq!(
+            todo_list(&ctx),
+            "<ul class=\"mdbook-shortcodes-todo-list\"><li>\
+            <a href=\"../guide/setup.html#mdbook-shortcodes-note-guide-setup-0\">guide/setup.md</a>: \
+            todo: Add &lt;b&gt;</li></ul>"
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use crate::{locale::Locale, Attrs, Context, MatchResult, Shortcode};
+
+/// Shows a number formatted for the book's language, e.g. `{{#num 1234567.891 precision=2 /}}`
+/// becomes "1,234,567.89" in English and "1.234.567,89" in German.
+pub(crate) struct Num;
+
+impl Shortcode for Num {
+    const NAME: &'static str = "num";
+    const HEADER: &'static str = "";
+
+    fn process_match(_input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(1, &["precision"])?;
+        let value = match attrs.positional() {
+            [value] => *value,
+            _ => return Err("no number given to num shortcode".to_owned()),
+        };
+        let number: f64 = value
+            .parse()
+            .map_err(|_| "invalid number given to num shortcode")?;
+        // By default, the number is shown as precisely as it was written.
+        let precision = match attrs.get_parsed("precision")? {
+            Some(precision) => precision,
+            None => value.split_once('.').map_or(0, |(_, d)| d.len()),
+        };
+
+        Ok(Locale::from_language(&ctx.language).format_number(number, precision))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_num() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        assert_eq!(
+            Num::process_raw("{{#num 1234567.891 precision=2 /}}", &mut ctx),
+            Ok("1,234,567.89".to_owned())
+        );
+        assert_eq!(
+            Num::process_raw("{{#num 1234.5 /}}", &mut ctx),
+            Ok("1,234.5".to_owned())
+        );
+
+        ctx.language = "fr".to_owned();
+        assert_eq!(
+            Num::process_raw("{{#num -1234567.891 precision=1 /}}", &mut ctx),
+            Ok("-1\u{202f}234\u{202f}567,9".to_owned())
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{html, Attrs, Context, MatchResult, Shortcode};
+
+/// A man page style list of command line options, with one `--flag, -f <VALUE> :: description`
+/// line per option. Every option has an anchor (e.g. `#option-flag`) so that it can be linked to.
+pub(crate) struct Options;
+
+impl Shortcode for Options {
+    const NAME: &'static str = "options";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-options dt {
+        font-family: var(--mono-font);
+        margin-top: 1em;
+    }
+    .mdbook-shortcodes-options dt a {
+        color: inherit;
+    }
+    .mdbook-shortcodes-options dd {
+        margin-inline-start: 2em;
+    }
+</style>
+";
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        if !attrs.is_empty() {
+            return Err("too many arguments given to options shortcode".to_owned());
+        }
+
+        let mut result = String::from("<dl class=\"mdbook-shortcodes-options\">");
+        for line in input.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let (synopsis, description) = line
+                .split_once("::")
+                .ok_or("options must be given as `flags :: description`")?;
+            let synopsis = synopsis.trim();
+            let id = ctx.ids.unique(format!("option-{}", anchor(synopsis)));
+            result.push_str(&format!(
+                "<dt id=\"{0}\"><a href=\"#{0}\">{1}</a></dt><dd>{2}</dd>",
+                html::escape(&id),
+                format_synopsis(synopsis),
+                html::escape(description.trim())
+            ));
+        }
+        result.push_str("</dl>");
+        Ok(result)
+    }
+}
+
+/// Returns the name used in an option's anchor, preferring its first long flag.
+fn anchor(synopsis: &str) -> String {
+    let flags: Vec<&str> = synopsis
+        .split([',', ' '])
+        .filter(|word| word.starts_with('-'))
+        .collect();
+    let flag = flags
+        .iter()
+        .find(|flag| flag.starts_with("--"))
+        .or_else(|| flags.first())
+        .copied()
+        .unwrap_or(synopsis);
+    flag.trim_start_matches('-')
+        .split('=')
+        .next()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+/// Formats flags in bold and values (e.g. `<VALUE>`) in italics, like man pages do.
+fn format_synopsis(synopsis: &str) -> String {
+    let mut result = String::new();
+    for (i, part) in synopsis.split(' ').enumerate() {
+        if i > 0 {
+            result.push(' ');
+        }
+        if part.starts_with('-') {
+            let (flag, rest) = match part.find(['=', ',']) {
+                Some(i) => part.split_at(i),
+                None => (part, ""),
+            };
+            result.push_str(&format!("<strong>{}</strong>", html::escape(flag)));
+            result.push_str(&format_values(rest));
+        } else {
+            result.push_str(&format_values(part));
+        }
+    }
+    result
+}
+
+fn format_values(text: &str) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end + 1,
+            None => break,
+        };
+        result.push_str(&html::escape(&rest[..start]));
+        result.push_str(&format!("<em>{}</em>", html::escape(&rest[start..end])));
+        rest = &rest[end..];
+    }
+    result.push_str(&html::escape(rest));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_anchor() {
+        assert_eq!(anchor("--flag, -f <VALUE>"), "flag");
+        assert_eq!(anchor("-v, --verbose"), "verbose");
+        assert_eq!(anchor("-q"), "q");
+        assert_eq!(anchor("--color=<WHEN>"), "color");
+    }
+
+    #[test]
+    fn test_options() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let input = "{{#options}}
+--output, -o <FILE> :: Write to FILE instead of stdout.
+-v :: Be verbose.
+{{/options}}";
+        let expected = "<dl class=\"mdbook-shortcodes-options\">\
+            <dt id=\"option-output\"><a href=\"#option-output\"><strong>--output</strong>, \
+            <strong>-o</strong> <em>&lt;FILE&gt;</em></a></dt><dd>Write to FILE instead of stdout.</dd>\
+            <dt id=\"option-v\"><a href=\"#option-v\"><strong>-v</strong></a></dt><dd>Be verbose.</dd></dl>";
+        assert!(Options::process_raw(input, &mut ctx)
+            .unwrap()
+            .ends_with(expected));
+    }
+}
@@ -0,0 +1,186 @@
+use std::path::Path;
+
+use protox::{
+    prost_reflect::{FieldDescriptor, Kind, MessageDescriptor},
+    Compiler,
+};
+
+use crate::{html, Attrs, Capability, Context, MatchResult, Shortcode};
+
+/// A table of the fields of a protobuf message, along with their comments, e.g.
+/// `{{#proto path="proto/api.proto" message=User /}}`.
+///
+/// The path is relative to the book's root directory, as is `include`, the directory imports are
+/// resolved from (by default, the one containing the file).
+pub(crate) struct Proto;
+
+impl Shortcode for Proto {
+    const NAME: &'static str = "proto";
+    const HEADER: &'static str = "";
+    const CAPABILITY: Capability = Capability::Filesystem;
+
+    fn process_match(_input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &["path", "message", "include"])?;
+        let path = ctx.root.join(
+            attrs
+                .get("path")
+                .ok_or("no path given to proto shortcode")?,
+        );
+        let name = attrs
+            .get("message")
+            .ok_or("no message given to proto shortcode")?;
+        let include = match attrs.get("include") {
+            Some(include) => ctx.root.join(include),
+            None => path.parent().unwrap_or_else(|| Path::new("")).to_owned(),
+        };
+
+        ctx.watch(&path);
+        let pool = Compiler::new([include]).and_then(|mut compiler| {
+            compiler.include_source_info(true).open_file(&path)?;
+            Ok(compiler.descriptor_pool())
+        });
+        let pool = match pool {
+            Ok(pool) => pool,
+            Err(e) => {
+                ctx.fail(
+                    Self::NAME,
+                    &format!("couldn't compile `{}`: {}", path.display(), e),
+                );
+                return Ok(String::new());
+            }
+        };
+        let message = pool
+            .all_messages()
+            .find(|message| message.full_name() == name || message.name() == name);
+        match message {
+            Some(message) => Ok(render(&message)),
+            None => {
+                ctx.fail(
+                    Self::NAME,
+                    &format!("no message `{}` in `{}`", name, path.display()),
+                );
+                Ok(String::new())
+            }
+        }
+    }
+}
+
+fn render(message: &MessageDescriptor) -> String {
+    let mut result = String::from(
+        "<table class=\"mdbook-shortcodes-proto\"><thead><tr>\
+        <th>Field</th><th>Number</th><th>Type</th><th>Description</th></tr></thead><tbody>",
+    );
+    for field in message.fields() {
+        result.push_str(&format!(
+            "<tr><td><code>{}</code></td><td>{}</td><td><code>{}</code></td><td>{}</td></tr>",
+            html::escape(field.name()),
+            field.number(),
+            html::escape(&field_type(&field)),
+            html::escape(&comment(&field))
+        ));
+    }
+    result.push_str("</tbody></table>");
+    result
+}
+
+/// Returns the type of a field as written in the proto file, e.g. `repeated string` or
+/// `map<string, User>`.
+fn field_type(field: &FieldDescriptor) -> String {
+    if field.is_map() {
+        if let Kind::Message(entry) = field.kind() {
+            return format!(
+                "map<{}, {}>",
+                kind_name(&entry.map_entry_key_field().kind()),
+                kind_name(&entry.map_entry_value_field().kind())
+            );
+        }
+    }
+    if field.is_list() {
+        format!("repeated {}", kind_name(&field.kind()))
+    } else {
+        kind_name(&field.kind())
+    }
+}
+
+fn kind_name(kind: &Kind) -> String {
+    match kind {
+        Kind::Message(message) => message.name().to_owned(),
+        Kind::Enum(enumeration) => enumeration.name().to_owned(),
+        kind => format!("{:?}", kind).to_lowercase(),
+    }
+}
+
+/// Returns the comment above (or after) a field.
+fn comment(field: &FieldDescriptor) -> String {
+    let file = field.parent_file();
+    let file = file.file_descriptor_proto();
+    let location = file
+        .source_code_info
+        .as_ref()
+        .and_then(|info| info.location.iter().find(|l| l.path == field.path()));
+    let comment = location.and_then(|l| {
+        l.leading_comments
+            .as_deref()
+            .or(l.trailing_comments.as_deref())
+    });
+    comment
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_proto() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("proto")).unwrap();
+        std::fs::write(
+            dir.path().join("proto/api.proto"),
+            r#"syntax = "proto3";
+package api;
+
+enum Role { ROLE_UNSPECIFIED = 0; }
+
+message User {
+  // The user's unique
+  // identifier.
+  int64 id = 1;
+  repeated string emails = 2; // Verified addresses.
+  map<string, Role> roles = 3;
+}
+"#,
+        )
+        .unwrap();
+
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        ctx.root = dir.path().to_owned();
+        let expected = "<table class=\"mdbook-shortcodes-proto\"><thead><tr>\
+            <th>Field</th><th>Number</th><th>Type</th><th>Description</th></tr></thead><tbody>\
+            <tr><td><code>id</code></td><td>1</td><td><code>int64</code></td>\
+            <td>The user&#39;s unique identifier.</td></tr>\
+            <tr><td><code>emails</code></td><td>2</td><td><code>repeated string</code></td>\
+            <td>Verified addresses.</td></tr>\
+            <tr><td><code>roles</code></td><td>3</td><td><code>map&lt;string, Role&gt;</code></td>\
+            <td></td></tr></tbody></table>";
+        assert_eq!(
+            Proto::process_raw(
+                "{{#proto path=\"proto/api.proto\" message=User /}}",
+                &mut ctx
+            ),
+            Ok(expected.to_owned())
+        );
+
+        Proto::process_raw(
+            "{{#proto path=\"proto/api.proto\" message=Other /}}",
+            &mut ctx,
+        )
+        .unwrap();
+        assert_eq!(ctx.errors.len(), 1);
+    }
+}
@@ -0,0 +1,316 @@
+use regex_syntax::ast::{
+    parse::Parser, Assertion, AssertionKind, Ast, ClassPerlKind, Flag, Flags, FlagsItemKind,
+    GroupKind, RepetitionKind, RepetitionRange, Span,
+};
+
+use crate::{html, Attrs, Context, MatchResult, Shortcode};
+
+/// Shows a highlighted regular expression along with an explanation of its components, e.g.
+/// `{{#regex}}^a(b|c)+${{/regex}}`.
+pub(crate) struct Regex;
+
+impl Shortcode for Regex {
+    const NAME: &'static str = "regex";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-regex-anchor {
+        color: #c678dd;
+    }
+    .mdbook-shortcodes-regex-group {
+        color: #e5a50a;
+    }
+    .mdbook-shortcodes-regex-quantifier {
+        color: #56b6c2;
+    }
+    .mdbook-shortcodes-regex-class {
+        color: #98c379;
+    }
+    .mdbook-shortcodes-regex-alternation {
+        color: #e06c75;
+    }
+</style>
+";
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        if !attrs.is_empty() {
+            return Err("too many arguments given to regex shortcode".to_owned());
+        }
+        let pattern = input.trim();
+
+        let ast = match Parser::new().parse(pattern) {
+            Ok(ast) => ast,
+            Err(e) => {
+                ctx.fail(Self::NAME, &format!("invalid regex `{}`: {}", pattern, e));
+                return Ok(String::new());
+            }
+        };
+
+        let mut tokens = Vec::new();
+        highlight(&ast, &mut tokens);
+        tokens.sort_by_key(|(span, _)| span.start.offset);
+
+        let mut highlighted = String::new();
+        let mut last = 0;
+        for (span, class) in tokens {
+            highlighted.push_str(&html::escape(&pattern[last..span.start.offset]));
+            highlighted.push_str(&format!(
+                "<span class=\"mdbook-shortcodes-regex-{}\">{}</span>",
+                class,
+                html::escape(&pattern[span.start.offset..span.end.offset])
+            ));
+            last = span.end.offset;
+        }
+        highlighted.push_str(&html::escape(&pattern[last..]));
+
+        Ok(format!(
+            "<div class=\"mdbook-shortcodes-regex\"><pre><code>{}</code></pre>{}</div>",
+            highlighted,
+            explain_list(&ast, pattern, false)
+        ))
+    }
+}
+
+/// Collects the spans of the syntax in `ast` that is highlighted, along with its class.
+fn highlight(ast: &Ast, tokens: &mut Vec<(Span, &'static str)>) {
+    match ast {
+        Ast::Assertion(assertion) => tokens.push((assertion.span, "anchor")),
+        Ast::ClassPerl(class) => tokens.push((class.span, "class")),
+        Ast::ClassUnicode(class) => tokens.push((class.span, "class")),
+        Ast::ClassBracketed(class) => tokens.push((class.span, "class")),
+        Ast::Dot(span) => tokens.push((**span, "class")),
+        Ast::Repetition(repetition) => {
+            highlight(&repetition.ast, tokens);
+            tokens.push((repetition.op.span, "quantifier"));
+        }
+        Ast::Group(group) => {
+            let inner = group.ast.span();
+            tokens.push((Span::new(group.span.start, inner.start), "group"));
+            highlight(&group.ast, tokens);
+            tokens.push((Span::new(inner.end, group.span.end), "group"));
+        }
+        Ast::Alternation(alternation) => {
+            for (i, ast) in alternation.asts.iter().enumerate() {
+                if i > 0 {
+                    // The `|` is between the previous alternative and this one.
+                    let previous = alternation.asts[i - 1].span();
+                    tokens.push((Span::new(previous.end, ast.span().start), "alternation"));
+                }
+                highlight(ast, tokens);
+            }
+        }
+        Ast::Concat(concat) => {
+            for ast in &concat.asts {
+                highlight(ast, tokens);
+            }
+        }
+        Ast::Empty(_) | Ast::Flags(_) | Ast::Literal(_) => {}
+    }
+}
+
+/// Explains the components of `ast` as a list. `multi_line` is whether the `m` flag is set,
+/// under which `^` and `$` match at the start and end of lines rather than only of the text.
+fn explain_list(ast: &Ast, pattern: &str, multi_line: bool) -> String {
+    let items = match ast {
+        Ast::Concat(concat) => explain_concat(&concat.asts, pattern, multi_line),
+        Ast::Empty(_) => Vec::new(),
+        _ => vec![explain(ast, pattern, multi_line)],
+    };
+    let mut result = String::from("<ul>");
+    for item in items {
+        result.push_str(&format!("<li>{}</li>", item));
+    }
+    result.push_str("</ul>");
+    result
+}
+
+/// Explains each component of a concatenation, merging consecutive literals into one.
+fn explain_concat(asts: &[Ast], pattern: &str, mut multi_line: bool) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut literal = String::new();
+    for ast in asts {
+        // Flags (e.g. `(?m)`) apply to the rest of the group they are in.
+        if let Ast::Flags(flags) = ast {
+            multi_line = is_multi_line(&flags.flags, multi_line);
+        }
+        if let Ast::Literal(l) = ast {
+            literal.push(l.c);
+            continue;
+        }
+        if !literal.is_empty() {
+            result.push(explain_literal(&literal));
+            literal.clear();
+        }
+        result.push(explain(ast, pattern, multi_line));
+    }
+    if !literal.is_empty() {
+        result.push(explain_literal(&literal));
+    }
+    result
+}
+
+fn explain_literal(literal: &str) -> String {
+    let noun = if literal.chars().count() == 1 {
+        "the character"
+    } else {
+        "the text"
+    };
+    format!("{} <code>{}</code>", noun, html::escape(literal))
+}
+
+fn explain(ast: &Ast, pattern: &str, multi_line: bool) -> String {
+    let source = |span: &Span| {
+        format!(
+            "<code>{}</code>",
+            html::escape(&pattern[span.start.offset..span.end.offset])
+        )
+    };
+    match ast {
+        Ast::Empty(_) => "nothing".to_owned(),
+        Ast::Flags(flags) => format!("the flags {}", source(&flags.span)),
+        Ast::Literal(literal) => explain_literal(&literal.c.to_string()),
+        Ast::Dot(_) => "any character".to_owned(),
+        Ast::Assertion(assertion) => explain_assertion(assertion, multi_line).to_owned(),
+        Ast::ClassPerl(class) => {
+            let name = match (class.kind.clone(), class.negated) {
+                (ClassPerlKind::Digit, false) => "a digit",
+                (ClassPerlKind::Digit, true) => "a character that isn't a digit",
+                (ClassPerlKind::Space, false) => "a whitespace character",
+                (ClassPerlKind::Space, true) => "a character that isn't whitespace",
+                (ClassPerlKind::Word, false) => "a word character",
+                (ClassPerlKind::Word, true) => "a character that isn't a word character",
+            };
+            format!("{} ({})", name, source(&class.span))
+        }
+        Ast::ClassUnicode(class) => format!("a character in the class {}", source(&class.span)),
+        Ast::ClassBracketed(class) => {
+            if class.negated {
+                format!("any character not in {}", source(&class.span))
+            } else {
+                format!("any character in {}", source(&class.span))
+            }
+        }
+        Ast::Repetition(repetition) => {
+            let count = match &repetition.op.kind {
+                RepetitionKind::ZeroOrOne => "optionally".to_owned(),
+                RepetitionKind::ZeroOrMore => "zero or more of".to_owned(),
+                RepetitionKind::OneOrMore => "one or more of".to_owned(),
+                RepetitionKind::Range(RepetitionRange::Exactly(n)) => format!("exactly {} of", n),
+                RepetitionKind::Range(RepetitionRange::AtLeast(n)) => format!("at least {} of", n),
+                RepetitionKind::Range(RepetitionRange::Bounded(m, n)) => {
+                    format!("between {} and {} of", m, n)
+                }
+            };
+            let laziness = if repetition.greedy {
+                ""
+            } else {
+                " (as few as possible)"
+            };
+            format!(
+                "{}{}{}",
+                count,
+                laziness,
+                explain_list(&repetition.ast, pattern, multi_line)
+            )
+        }
+        Ast::Group(group) => {
+            let multi_line = match &group.kind {
+                GroupKind::NonCapturing(flags) => is_multi_line(flags, multi_line),
+                _ => multi_line,
+            };
+            let kind = match &group.kind {
+                GroupKind::CaptureIndex(i) => format!("capturing group #{}", i),
+                GroupKind::CaptureName { name, .. } => format!(
+                    "capturing group #{} named <code>{}</code>",
+                    name.index,
+                    html::escape(&name.name)
+                ),
+                GroupKind::NonCapturing(_) => "non-capturing group".to_owned(),
+            };
+            format!("{}{}", kind, explain_list(&group.ast, pattern, multi_line))
+        }
+        Ast::Alternation(alternation) => {
+            let mut result = String::from("either<ul>");
+            for ast in &alternation.asts {
+                result.push_str(&format!("<li>{}</li>", explain(ast, pattern, multi_line)));
+            }
+            result.push_str("</ul>");
+            result
+        }
+        Ast::Concat(concat) => explain_concat(&concat.asts, pattern, multi_line).join(", then "),
+    }
+}
+
+/// Returns whether the `m` flag is set after `flags` are applied, given whether it was before.
+fn is_multi_line(flags: &Flags, multi_line: bool) -> bool {
+    let mut negated = false;
+    let mut result = multi_line;
+    for item in &flags.items {
+        match item.kind {
+            FlagsItemKind::Negation => negated = true,
+            FlagsItemKind::Flag(Flag::MultiLine) => result = !negated,
+            FlagsItemKind::Flag(_) => {}
+        }
+    }
+    result
+}
+
+fn explain_assertion(assertion: &Assertion, multi_line: bool) -> &'static str {
+    match assertion.kind {
+        // `^` and `$` only match at line breaks with the `m` flag.
+        AssertionKind::StartLine if multi_line => "the start of a line",
+        AssertionKind::EndLine if multi_line => "the end of a line",
+        AssertionKind::StartLine => "the start of the text",
+        AssertionKind::EndLine => "the end of the text",
+        AssertionKind::StartText => "the start of the text",
+        AssertionKind::EndText => "the end of the text",
+        AssertionKind::WordBoundary => "a word boundary",
+        AssertionKind::NotWordBoundary => "not a word boundary",
+        AssertionKind::WordBoundaryStart
+        | AssertionKind::WordBoundaryStartAngle
+        | AssertionKind::WordBoundaryStartHalf => "the start of a word",
+        AssertionKind::WordBoundaryEnd
+        | AssertionKind::WordBoundaryEndAngle
+        | AssertionKind::WordBoundaryEndHalf => "the end of a word",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_regex() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let result = Regex::process_raw("{{#regex}}^ab(b|c)+${{/regex}}", &mut ctx).unwrap();
+        let expected = "<div class=\"mdbook-shortcodes-regex\"><pre><code>\
+            <span class=\"mdbook-shortcodes-regex-anchor\">^</span>ab\
+            <span class=\"mdbook-shortcodes-regex-group\">(</span>b\
+            <span class=\"mdbook-shortcodes-regex-alternation\">|</span>c\
+            <span class=\"mdbook-shortcodes-regex-group\">)</span>\
+            <span class=\"mdbook-shortcodes-regex-quantifier\">+</span>\
+            <span class=\"mdbook-shortcodes-regex-anchor\">$</span></code></pre>\
+            <ul><li>the start of the text</li><li>the text <code>ab</code></li>\
+            <li>one or more of<ul><li>capturing group #1<ul><li>either<ul>\
+            <li>the character <code>b</code></li><li>the character <code>c</code></li>\
+            </ul></li></ul></li></ul></li>\
+            <li>the end of the text</li></ul></div>";
+        assert_eq!(result, expected);
+
+        let result = Regex::process_raw("{{#regex}}(?m)^a(?-m:$)${{/regex}}", &mut ctx).unwrap();
+        assert!(result.ends_with(
+            "<ul><li>the flags <code>(?m)</code></li><li>the start of a line</li>\
+            <li>the character <code>a</code></li><li>non-capturing group\
+            <ul><li>the end of the text</li></ul></li><li>the end of a line</li></ul></div>"
+        ));
+    }
+
+    #[test]
+    fn test_invalid_regex() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        Regex::process_raw("{{#regex}}a(b{{/regex}}", &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 1);
+    }
+}
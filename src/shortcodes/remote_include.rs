@@ -0,0 +1,95 @@
+use crate::{
+    find_handler, handlers, parse, remote, shortcodes::cmd_output::longest_backtick_run, Attrs,
+    Capability, Context, MatchResult, Shortcode,
+};
+
+/// Includes a file downloaded at build time, e.g.
+/// `{{#remote url="https://example.com/README.md" /}}`, or shows it as a code block in the
+/// language `lang` if one is given.
+///
+/// Downloads are cached in the `cache-dir` according to their HTTP caching headers, so that
+/// repeated builds don't download them again and offline builds use the cached copy. Shortcodes
+/// in the file are shown as they are written rather than expanded, so that a file can't use
+/// the capabilities of the book (e.g. to run programs).
+pub(crate) struct RemoteInclude;
+
+impl Shortcode for RemoteInclude {
+    const NAME: &'static str = "remote";
+    const HEADER: &'static str = "";
+    const CAPABILITY: Capability = Capability::Network;
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &["url", "lang"])?;
+        if !input.trim().is_empty() {
+            return Err("remote shortcode must be self-closing".to_owned());
+        }
+        let url = attrs.get("url").ok_or("no url given to remote shortcode")?;
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(format!("`{}` isn't an HTTP URL", url));
+        }
+
+        let content = match remote::fetch_revalidated(&ctx.cache, url, ctx.config.offline) {
+            Ok(content) => content,
+            Err(e) => {
+                ctx.fail(Self::NAME, &format!("couldn't download {}: {}", url, e));
+                return Ok(String::new());
+            }
+        };
+        let content =
+            String::from_utf8(content).map_err(|_| format!("{} isn't UTF-8 text", url))?;
+        let content = content.trim_end_matches('\n');
+        let output = match attrs.get("lang") {
+            Some(lang) => {
+                let fence = "`".repeat(longest_backtick_run(content).max(2) + 1);
+                format!("\n{fence}{}\n{}\n{fence}\n", lang, content, fence = fence)
+            }
+            None => format!("\n\n{}\n\n", content),
+        };
+
+        let config = ctx.config;
+        let handlers = handlers(config, ctx.custom);
+        let is_shortcode = |name: &str| find_handler(&handlers, name, config).is_some();
+        Ok(parse::escape(&output, &config.delimiters, is_shortcode))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cache::Cache, Config};
+
+    #[test]
+    fn test_remote() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            offline: true,
+            ..Config::default()
+        };
+        let mut ctx = Context::new(&config, dir.path().to_owned(), dir.path().join("src"));
+        let url = "https://example.com/README.md";
+        ctx.cache
+            .put(
+                "remote",
+                &Cache::key(&[url.as_bytes()]),
+                b"# Example\n\nPress {{#kbd C /}}.\n",
+            )
+            .unwrap();
+
+        assert_eq!(
+            RemoteInclude::process_raw(&format!("{{{{#remote url=\"{}\" /}}}}", url), &mut ctx),
+            Ok("\n\n# Example\n\nPress \\{{#kbd C /}}.\n\n".to_owned())
+        );
+        assert_eq!(
+            RemoteInclude::process_raw(
+                &format!("{{{{#remote url=\"{}\" lang=md /}}}}", url),
+                &mut ctx
+            ),
+            Ok("\n```md\n# Example\n\nPress {{#kbd C /}}.\n```\n".to_owned())
+        );
+        assert!(RemoteInclude::process_raw("{{#remote url=README.md /}}", &mut ctx).is_err());
+
+        RemoteInclude::process_raw("{{#remote url=\"https://example.com/a.md\" /}}", &mut ctx)
+            .unwrap();
+        assert_eq!(ctx.errors.len(), 1);
+    }
+}
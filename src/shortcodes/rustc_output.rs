@@ -0,0 +1,205 @@
+use std::{fs, process::Command};
+
+use crate::{
+    cache::Cache, exec, html, markdown, Attrs, Capability, Context, MatchResult, Shortcode,
+};
+
+/// Compiles a Rust snippet at build time and shows the compiler's output below it.
+pub(crate) struct RustcOutput;
+
+impl Shortcode for RustcOutput {
+    const NAME: &'static str = "rustc-output";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-rustc-output {
+        border-inline-start: .25rem solid #f66;
+        padding-inline-start: 1rem;
+        margin: -1rem 0 1rem;
+    }
+    .mdbook-shortcodes-rustc-output-ok {
+        border-color: #5b6;
+    }
+</style>
+";
+    const CAPABILITY: Capability = Capability::Exec;
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &["edition"])?;
+        let edition = attrs.get("edition").unwrap_or("2021");
+
+        // The body is usually a fenced code block, but bare code is also accepted.
+        let (display, code) = match markdown::fenced_blocks(input)
+            .into_iter()
+            .find(|block| block.lang.is_empty() || block.lang == "rust")
+        {
+            Some(block) => (input.to_owned(), block.content.to_owned()),
+            None => (
+                format!("\n```rust\n{}\n```\n", input.trim()),
+                input.to_owned(),
+            ),
+        };
+
+        let output = match compile(ctx, &code, edition) {
+            Ok(output) => output,
+            // Compile errors are output, so this is rustc itself failing to run.
+            Err(e) => {
+                ctx.fail(Self::NAME, &format!("couldn't run rustc: {}", e));
+                return Ok(display);
+            }
+        };
+        let class = if output.is_empty() {
+            "mdbook-shortcodes-rustc-output mdbook-shortcodes-rustc-output-ok"
+        } else {
+            "mdbook-shortcodes-rustc-output"
+        };
+        let output = if output.is_empty() {
+            "Compiled successfully.".to_owned()
+        } else {
+            output
+        };
+
+        Ok(format!(
+            "{}\n<div class=\"{}\"><pre><code>{}</code></pre></div>\n",
+            display,
+            class,
+            html::escape(output.trim_end())
+        ))
+    }
+}
+
+/// Returns the diagnostics emitted when compiling `code`.
+fn compile(ctx: &mut Context, code: &str, edition: &str) -> Result<String, String> {
+    // Lines hidden by mdBook (i.e. those starting with `#`) are still compiled.
+    let code: String = code
+        .lines()
+        .map(|line| match line.trim_start().strip_prefix('#') {
+            Some(rest) if rest.is_empty() || rest.starts_with(' ') => rest.trim_start_matches(' '),
+            _ => line,
+        })
+        .flat_map(|line| [line, "\n"])
+        .collect();
+    let toolchain = ctx.config.rustc_toolchain.as_deref().unwrap_or("");
+    let crate_type = if code.contains("fn main") {
+        "bin"
+    } else {
+        "lib"
+    };
+
+    // The diagnostics change with the compiler, so the cached ones are only used by the same
+    // version.
+    let version = version(ctx, toolchain)?;
+    let key = Cache::key(&[
+        toolchain.as_bytes(),
+        version.as_bytes(),
+        edition.as_bytes(),
+        code.as_bytes(),
+    ]);
+    if let Some(output) = ctx.cache.get("rustc-output", &key, None) {
+        return Ok(String::from_utf8_lossy(&output).into_owned());
+    }
+
+    let dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+    fs::write(dir.path().join("main.rs"), &code).map_err(|e| e.to_string())?;
+
+    let mut command = Command::new("rustc");
+    if !toolchain.is_empty() {
+        command.arg(format!("+{}", toolchain));
+    }
+    command
+        .current_dir(dir.path())
+        .args(["--edition", edition, "--crate-type", crate_type])
+        .args(["--crate-name", "playground", "--color", "never"])
+        .args(["--emit", "metadata", "-o", "out", "main.rs"]);
+    let output = exec::run(ctx, RustcOutput::NAME, &mut command)?;
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+
+    if let Err(e) = ctx.cache.put("rustc-output", &key, stderr.as_bytes()) {
+        ctx.warn(RustcOutput::NAME, &format!("failed to cache result: {}", e));
+    }
+    Ok(stderr)
+}
+
+/// Returns the verbose version of the compiler used for `toolchain` (i.e. `rustc -vV`), which
+/// is only asked for once per build.
+fn version(ctx: &mut Context, toolchain: &str) -> Result<String, String> {
+    if let Some(version) = ctx.rustc_versions.get(toolchain) {
+        return Ok(version.clone());
+    }
+    let mut command = Command::new("rustc");
+    if !toolchain.is_empty() {
+        command.arg(format!("+{}", toolchain));
+    }
+    let output = exec::run(ctx, RustcOutput::NAME, command.arg("-vV"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "failed to get the version of rustc: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let version = String::from_utf8_lossy(&output.stdout).into_owned();
+    ctx.rustc_versions
+        .insert(toolchain.to_owned(), version.clone());
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_rustc_output_disabled() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let input = "{{#rustc-output}}\nlet x = 1;\n{{/rustc-output}}";
+        assert_eq!(
+            RustcOutput::process_raw(input, &mut ctx),
+            Ok("\n```rust\nlet x = 1;\n```\n".to_owned())
+        );
+        assert_eq!(ctx.errors.len(), 1);
+        assert!(ctx.errors[0].contains("requires `allow-exec = true`"));
+    }
+
+    #[test]
+    fn test_rustc_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            allow_exec: true,
+            ..Default::default()
+        };
+        let mut ctx = Context::new(&config, dir.path().to_owned(), dir.path().join("src"));
+        let input = "{{#rustc-output}}
+```rust
+fn main() {
+    let x: i32 = \"a\";
+}
+```
+{{/rustc-output}}";
+        let result = RustcOutput::process_raw(input, &mut ctx).unwrap();
+        assert!(result.contains("error[E0308]: mismatched types"));
+        assert!(result.contains("--&gt; main.rs:2:18"));
+        // A compile error is the output, rather than an error in the book.
+        assert!(ctx.errors.is_empty());
+        // The second build uses the cache, unless the compiler changed.
+        assert_eq!(RustcOutput::process_raw(input, &mut ctx).unwrap(), result);
+        let cache_dir = dir.path().join(".shortcodes-cache/rustc-output");
+        for entry in std::fs::read_dir(cache_dir).unwrap() {
+            std::fs::write(entry.unwrap().path(), "stale").unwrap();
+        }
+        let cached = RustcOutput::process_raw(input, &mut ctx).unwrap();
+        assert!(cached.contains("<code>stale</code>"));
+        ctx.rustc_versions
+            .insert(String::new(), "rustc 0.0.0".to_owned());
+        assert_eq!(RustcOutput::process_raw(input, &mut ctx).unwrap(), result);
+
+        let config = Config {
+            allow_exec: true,
+            rustc_toolchain: Some("no-such-toolchain".to_owned()),
+            ..Default::default()
+        };
+        let mut ctx = Context::new(&config, dir.path().to_owned(), dir.path().join("src"));
+        RustcOutput::process_raw(input, &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 1);
+        assert!(ctx.errors[0].contains("couldn't run rustc"));
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::BTreeMap;
+
+use crate::{html, Attrs, Context, MatchResult, Shortcode};
+
+/// The crates documented on doc.rust-lang.org rather than docs.rs.
+const STD_CRATES: [&str; 5] = ["std", "core", "alloc", "proc_macro", "test"];
+
+/// The item kinds that can be given as a prefix, like rustdoc's intra-doc link disambiguators
+/// (e.g. `trait@std::io::Read`).
+const KINDS: [&str; 14] = [
+    "struct",
+    "enum",
+    "trait",
+    "fn",
+    "macro",
+    "mod",
+    "type",
+    "const",
+    "static",
+    "union",
+    "primitive",
+    "keyword",
+    "derive",
+    "attr",
+];
+
+/// The item kinds that can have associated items, whose prefix applies to the type of an
+/// associated item (e.g. `trait@std::iter::Iterator::map`).
+const TYPE_KINDS: [&str; 5] = ["struct", "enum", "trait", "union", "primitive"];
+
+/// Links to the documentation of a Rust item, e.g.
+/// `{{#rustdoc struct@std::collections::HashMap /}}`.
+///
+/// Types and associated items are linked to directly when their kind is given (e.g. as
+/// `struct@`), and otherwise to a search of the crate's documentation, as a capitalised name
+/// could be a struct, an enum, a trait or a type alias.
+pub(crate) struct Rustdoc;
+
+impl Shortcode for Rustdoc {
+    const NAME: &'static str = "rustdoc";
+    const HEADER: &'static str = "";
+
+    fn process_match(_input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(1, &["text"])?;
+        let path = match attrs.positional() {
+            [path] => *path,
+            _ => return Err("no path given to rustdoc shortcode".to_owned()),
+        };
+        let item = Item::parse(path);
+        let text = match attrs.get("text") {
+            Some(text) => text.to_owned(),
+            None => item.display_name(),
+        };
+        let url = item.url(&ctx.config.rustdoc);
+
+        Ok(format!(
+            "<a href=\"{}\"><code>{}</code></a>",
+            html::escape(&url),
+            html::escape(&text)
+        ))
+    }
+}
+
+/// A path to a Rust item, e.g. `std::collections::HashMap`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct Item<'a> {
+    /// The kind of item, if it could be determined.
+    kind: Option<&'a str>,
+    segments: Vec<&'a str>,
+}
+
+impl<'a> Item<'a> {
+    fn parse(path: &'a str) -> Self {
+        let (mut kind, path) = match path.split_once('@') {
+            Some((kind, path)) if KINDS.contains(&kind) => (Some(kind), path),
+            _ => (None, path),
+        };
+        let path = if let Some(path) = path.strip_suffix('!') {
+            kind = Some("macro");
+            path
+        } else if let Some(path) = path.strip_suffix("()") {
+            kind = kind.or(Some("fn"));
+            path
+        } else {
+            path
+        };
+
+        Self {
+            kind,
+            segments: path.split("::").collect(),
+        }
+    }
+
+    /// Returns the text shown for the link, e.g. `HashMap`, `vec!` or `HashMap::insert`.
+    fn display_name(&self) -> String {
+        let mut name = match self.associated_item() {
+            Some((ty, item)) => format!("{}::{}", ty, item),
+            None => self.segments[self.segments.len() - 1].to_owned(),
+        };
+        match self.kind {
+            Some("macro") => name.push('!'),
+            Some("fn") => name.push_str("()"),
+            _ => {}
+        }
+        name
+    }
+
+    /// Returns the type and name of the item if it is a method or another associated item
+    /// (e.g. `HashMap::insert`), which is inferred from the capitalisation of the path.
+    fn associated_item(&self) -> Option<(&'a str, &'a str)> {
+        let len = self.segments.len();
+        let can_be_associated = |kind| kind == "fn" || TYPE_KINDS.contains(&kind);
+        if len < 3 || self.kind.is_some_and(|kind| !can_be_associated(kind)) {
+            return None;
+        }
+        let ty = self.segments[len - 2];
+        let item = self.segments[len - 1];
+        if starts_uppercase(ty) && !starts_uppercase(item) {
+            Some((ty, item))
+        } else {
+            None
+        }
+    }
+
+    fn url(&self, bases: &BTreeMap<String, String>) -> String {
+        let krate = self.segments[0];
+        let mut url = match bases.get(krate) {
+            Some(base) => base.clone(),
+            None if STD_CRATES.contains(&krate) => {
+                format!("https://doc.rust-lang.org/stable/{}/", krate)
+            }
+            None => format!("https://docs.rs/{0}/latest/{0}/", krate),
+        };
+        if !url.ends_with('/') {
+            url.push('/');
+        }
+
+        if self.segments.len() == 1 {
+            url.push_str("index.html");
+            return url;
+        }
+
+        if let Some((ty, item)) = self.associated_item() {
+            let kind = match self.kind {
+                Some(kind) if kind != "fn" => kind,
+                _ => return self.search_url(url),
+            };
+            let modules = &self.segments[1..self.segments.len() - 2];
+            for module in modules {
+                url.push_str(module);
+                url.push('/');
+            }
+            url.push_str(&format!("{}.{}.html#method.{}", kind, ty, item));
+            return url;
+        }
+
+        let name = self.segments[self.segments.len() - 1];
+        let kind = match self.kind {
+            Some(kind) => kind,
+            None if starts_uppercase(name) => return self.search_url(url),
+            None => "mod",
+        };
+        for module in &self.segments[1..self.segments.len() - 1] {
+            url.push_str(module);
+            url.push('/');
+        }
+        match kind {
+            "mod" => url.push_str(&format!("{}/index.html", name)),
+            _ => url.push_str(&format!("{}.{}.html", kind, name)),
+        }
+        url
+    }
+
+    /// Returns the URL of a search for the item in the crate's documentation at `base`.
+    fn search_url(&self, mut base: String) -> String {
+        base.push_str("index.html?search=");
+        base.push_str(&self.segments[1..].join("::"));
+        base
+    }
+}
+
+fn starts_uppercase(s: &str) -> bool {
+    s.chars().next().is_some_and(char::is_uppercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    fn render(input: &str, config: &Config) -> String {
+        Rustdoc::process_raw(input, &mut Context::test(config)).unwrap()
+    }
+
+    #[test]
+    fn test_rustdoc() {
+        let config = Config::default();
+        let cases = [
+            (
+                "{{#rustdoc struct@std::collections::HashMap /}}",
+                "<a href=\"https://doc.rust-lang.org/stable/std/collections/struct.HashMap.html\"><code>HashMap</code></a>",
+            ),
+            (
+                "{{#rustdoc std::collections::HashMap /}}",
+                "<a href=\"https://doc.rust-lang.org/stable/std/index.html?search=collections::HashMap\"><code>HashMap</code></a>",
+            ),
+            (
+                "{{#rustdoc trait@std::io::Read /}}",
+                "<a href=\"https://doc.rust-lang.org/stable/std/io/trait.Read.html\"><code>Read</code></a>",
+            ),
+            (
+                "{{#rustdoc std::vec! /}}",
+                "<a href=\"https://doc.rust-lang.org/stable/std/macro.vec.html\"><code>vec!</code></a>",
+            ),
+            (
+                "{{#rustdoc std::mem::swap() /}}",
+                "<a href=\"https://doc.rust-lang.org/stable/std/mem/fn.swap.html\"><code>swap()</code></a>",
+            ),
+            (
+                "{{#rustdoc struct@std::collections::HashMap::insert /}}",
+                "<a href=\"https://doc.rust-lang.org/stable/std/collections/struct.HashMap.html#method.insert\"><code>HashMap::insert</code></a>",
+            ),
+            (
+                "{{#rustdoc trait@std::iter::Iterator::map /}}",
+                "<a href=\"https://doc.rust-lang.org/stable/std/iter/trait.Iterator.html#method.map\"><code>Iterator::map</code></a>",
+            ),
+            (
+                "{{#rustdoc std::option::Option::map() /}}",
+                "<a href=\"https://doc.rust-lang.org/stable/std/index.html?search=option::Option::map\"><code>Option::map()</code></a>",
+            ),
+            (
+                "{{#rustdoc std::collections /}}",
+                "<a href=\"https://doc.rust-lang.org/stable/std/collections/index.html\"><code>collections</code></a>",
+            ),
+            (
+                "{{#rustdoc trait@serde::Serialize text=\"the trait\" /}}",
+                "<a href=\"https://docs.rs/serde/latest/serde/trait.Serialize.html\"><code>the trait</code></a>",
+            ),
+            (
+                "{{#rustdoc serde /}}",
+                "<a href=\"https://docs.rs/serde/latest/serde/index.html\"><code>serde</code></a>",
+            ),
+        ];
+        for (input, expected) in cases.iter() {
+            assert_eq!(render(input, &config), *expected);
+        }
+    }
+
+    #[test]
+    fn test_rustdoc_base_url() {
+        let mut config = Config::default();
+        config.rustdoc.insert(
+            "mycrate".to_owned(),
+            "https://example.com/docs/mycrate".to_owned(),
+        );
+        assert_eq!(
+            render("{{#rustdoc enum@mycrate::Error /}}", &config),
+            "<a href=\"https://example.com/docs/mycrate/enum.Error.html\"><code>Error</code></a>"
+        );
+    }
+}
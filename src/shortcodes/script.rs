@@ -0,0 +1,88 @@
+use crate::{Attrs, Config, Context, MatchResult, Shortcode};
+
+pub(crate) struct Script;
+
+impl Shortcode for Script {
+    const NAME: &'static str = "script";
+    const HEADER: &'static str = "";
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        let mut defer = false;
+        let mut module = false;
+        attrs.expect(usize::MAX, &[])?;
+        for attr in attrs.positional() {
+            match *attr {
+                "defer" => defer = true,
+                "module" => module = true,
+                _ => return Err("unknown argument given to script shortcode".to_owned()),
+            }
+        }
+
+        // Inline scripts ignore the `defer` attribute, so the body is instead run once the
+        // document has been parsed. Module scripts are always deferred.
+        let body = if defer && !module {
+            format!(
+                "document.addEventListener(\"DOMContentLoaded\", function () {{{}}});",
+                input
+            )
+        } else {
+            input.to_owned()
+        };
+
+        Ok(format!(
+            "{}{}</script>",
+            script_open_tag(module, ctx.config),
+            body
+        ))
+    }
+}
+
+/// Returns the opening tag of an inline script, including the nonce if CSP mode is active.
+pub(crate) fn script_open_tag(module: bool, config: &Config) -> String {
+    let mut tag = String::from("<script");
+    if module {
+        tag.push_str(" type=\"module\"");
+    }
+    if let Some(nonce) = &config.csp_nonce {
+        tag.push_str(&format!(" nonce=\"{}\"", nonce));
+    }
+    tag.push('>');
+    tag
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script() {
+        let config = Config::default();
+        let input = "{{#script}}\nconsole.log(1);\n{{/script}}";
+        assert_eq!(
+            Script::process_raw(input, &mut Context::test(&config)),
+            Ok("<script>\nconsole.log(1);\n</script>".to_owned())
+        );
+
+        let input = "{{#script defer}}\nrun();\n{{/script}}";
+        assert_eq!(
+            Script::process_raw(input, &mut Context::test(&config)),
+            Ok("<script>document.addEventListener(\"DOMContentLoaded\", function () {\nrun();\n});</script>".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_script_nonce() {
+        let config = Config {
+            csp_nonce: Some("__CSP_NONCE__".to_owned()),
+            ..Default::default()
+        };
+        let input = "{{#script module}}\nimport \"./a.js\";\n{{/script}}";
+        assert_eq!(
+            Script::process_raw(input, &mut Context::test(&config)),
+            Ok(
+                "<script type=\"module\" nonce=\"__CSP_NONCE__\">\nimport \"./a.js\";\n</script>"
+                    .to_owned()
+            )
+        );
+    }
+}
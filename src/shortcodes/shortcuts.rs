@@ -0,0 +1,94 @@
+use crate::{html, shortcodes::kbd, Attrs, Context, MatchResult, Shortcode};
+
+/// A table of keyboard shortcuts, with one `Ctrl+S :: Save file` line per shortcut.
+///
+/// With the `platforms` attribute, a separate macOS column is shown. Its shortcuts can be given
+/// explicitly (`Ctrl+S | Cmd+S :: Save file`) or are derived from the first one.
+pub(crate) struct Shortcuts;
+
+impl Shortcode for Shortcuts {
+    const NAME: &'static str = "shortcuts";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-shortcuts td:not(:last-child) {
+        white-space: nowrap;
+    }
+</style>
+";
+
+    fn process_match(input: &str, attrs: Attrs, _ctx: &mut Context) -> MatchResult {
+        attrs.expect(1, &[])?;
+        let platforms = match attrs.positional() {
+            [] => false,
+            ["platforms"] => true,
+            _ => return Err("unknown argument given to shortcuts shortcode".to_owned()),
+        };
+
+        let mut result = String::from("<table class=\"mdbook-shortcodes-shortcuts\"><thead><tr>");
+        if platforms {
+            result.push_str("<th>Windows/Linux</th><th>macOS</th>");
+        } else {
+            result.push_str("<th>Shortcut</th>");
+        }
+        result.push_str("<th>Action</th></tr></thead><tbody>");
+
+        for line in input.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let (keys, action) = line
+                .split_once("::")
+                .ok_or("shortcuts must be given as `keys :: action`")?;
+            let (keys, mac_keys) = match keys.split_once('|') {
+                Some((keys, mac_keys)) => (keys.trim(), mac_keys.trim().to_owned()),
+                None => (keys.trim(), mac_equivalent(keys.trim())),
+            };
+
+            result.push_str(&format!("<tr><td>{}</td>", kbd::render(keys)));
+            if platforms {
+                result.push_str(&format!("<td>{}</td>", kbd::render(&mac_keys)));
+            }
+            result.push_str(&format!("<td>{}</td></tr>", html::escape(action.trim())));
+        }
+
+        result.push_str("</tbody></table>");
+        Ok(result)
+    }
+}
+
+/// Returns the macOS version of a Windows/Linux shortcut.
+fn mac_equivalent(keys: &str) -> String {
+    keys.split('+')
+        .map(|key| match key.trim() {
+            "Ctrl" => "Cmd",
+            "Alt" => "Option",
+            key => key,
+        })
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_shortcuts() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let input = "{{#shortcuts platforms}}
+Ctrl+S :: Save file
+Ctrl+Y | Cmd+Shift+Z :: Redo
+{{/shortcuts}}";
+        let expected = "<table class=\"mdbook-shortcodes-shortcuts\"><thead><tr>\
+            <th>Windows/Linux</th><th>macOS</th><th>Action</th></tr></thead><tbody>\
+            <tr><td><kbd class=\"mdbook-shortcodes-kbd\"><kbd>Ctrl</kbd>+<kbd>S</kbd></kbd></td>\
+            <td><kbd class=\"mdbook-shortcodes-kbd\"><kbd>Cmd</kbd>+<kbd>S</kbd></kbd></td>\
+            <td>Save file</td></tr>\
+            <tr><td><kbd class=\"mdbook-shortcodes-kbd\"><kbd>Ctrl</kbd>+<kbd>Y</kbd></kbd></td>\
+            <td><kbd class=\"mdbook-shortcodes-kbd\"><kbd>Cmd</kbd>+<kbd>Shift</kbd>+<kbd>Z</kbd></kbd></td>\
+            <td>Redo</td></tr></tbody></table>";
+        assert_eq!(
+            Shortcuts::process_raw(input, &mut ctx),
+            Ok(expected.to_owned())
+        );
+    }
+}
@@ -0,0 +1,36 @@
+use crate::{slides, Attrs, Context, MatchResult, Shortcode};
+
+/// Marks the start of a new slide with `{{#slide /}}`. It is invisible in the book, but if
+/// `slides` is enabled, chapters containing it are also exported as presentations.
+pub(crate) struct Slide;
+
+impl Shortcode for Slide {
+    const NAME: &'static str = "slide";
+    const HEADER: &'static str = "";
+
+    fn process_match(input: &str, attrs: Attrs, _ctx: &mut Context) -> MatchResult {
+        if !attrs.is_empty() {
+            return Err("too many arguments given to slide shortcode".to_owned());
+        }
+        if !input.trim().is_empty() {
+            return Err("slide shortcode must be self-closing".to_owned());
+        }
+        Ok(slides::MARKER.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_slide() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        assert_eq!(
+            Slide::process_raw("# One\n{{#slide /}}\n# Two", &mut ctx),
+            Ok(format!("# One\n{}\n# Two", slides::MARKER))
+        );
+    }
+}
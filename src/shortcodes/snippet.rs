@@ -0,0 +1,129 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{template, Attrs, Context, MatchResult, Shortcode};
+
+/// A reusable piece of content, along with the default values of its parameters.
+pub(crate) struct Snippet {
+    body: String,
+    defaults: BTreeMap<String, String>,
+    /// The file the snippet is defined in.
+    defined_in: PathBuf,
+}
+
+/// Defines a snippet that can be used in any chapter, e.g.
+/// `{{#define-snippet install-step platform=linux}}...{{/define-snippet}}`. Parameters are
+/// written as `{{platform}}` in the body.
+///
+/// Definitions are collected from every chapter before any are used, and are removed from the
+/// chapter they are defined in.
+pub(crate) struct DefineSnippet;
+
+impl Shortcode for DefineSnippet {
+    const NAME: &'static str = "define-snippet";
+    const HEADER: &'static str = "";
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        let name = params(&attrs)?.ok_or("no name given to define-snippet shortcode")?;
+        let snippet = Snippet {
+            body: input.trim_matches('\n').to_owned(),
+            defaults: args(&attrs),
+            defined_in: ctx.chapter_path.clone(),
+        };
+        if ctx.snippets.insert(name.to_owned(), snippet).is_some() {
+            ctx.fail(Self::NAME, &format!("snippet `{}` is defined twice", name));
+        }
+        Ok(String::new())
+    }
+}
+
+/// Inserts a snippet, e.g. `{{#use-snippet install-step platform=macos /}}`.
+pub(crate) struct UseSnippet;
+
+impl Shortcode for UseSnippet {
+    const NAME: &'static str = "use-snippet";
+    const HEADER: &'static str = "";
+
+    fn process_match(_input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        let name = params(&attrs)?.ok_or("no name given to use-snippet shortcode")?;
+        let defined_in = match ctx.snippets.get(name) {
+            Some(snippet) => snippet.defined_in.clone(),
+            None => {
+                ctx.fail(Self::NAME, &format!("unknown snippet `{}`", name));
+                return Ok(String::new());
+            }
+        };
+        // Chapters are watched by `mdbook serve` already, but the `snippets-file` isn't.
+        if ctx.config.snippets_file.as_deref().map(Path::new) == Some(&defined_in) {
+            ctx.watch(defined_in);
+        }
+
+        let snippet = &ctx.snippets[name];
+        let args = args(&attrs);
+        let result = template::render(&snippet.body, |param| {
+            args.get(param)
+                .or_else(|| snippet.defaults.get(param))
+                .cloned()
+        });
+        match result {
+            Ok(result) => Ok(result),
+            Err(param) => {
+                let message = format!("no value given for `{}` in snippet `{}`", param, name);
+                ctx.fail(Self::NAME, &message);
+                Ok(String::new())
+            }
+        }
+    }
+}
+
+/// Returns the name of the snippet, which is the only positional attribute.
+fn params<'a>(attrs: &Attrs<'a>) -> Result<Option<&'a str>, String> {
+    match attrs.positional() {
+        [] => Ok(None),
+        [name] => Ok(Some(name)),
+        _ => Err("snippet parameters must be given as `key=value`".to_owned()),
+    }
+}
+
+/// Returns the values given for the parameters of a snippet.
+fn args(attrs: &Attrs) -> BTreeMap<String, String> {
+    attrs
+        .named()
+        .iter()
+        .map(|(key, value)| ((*key).to_owned(), (*value).to_owned()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_snippets() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let definition = "Intro\n{{#define-snippet install-step platform=linux}}
+Run `install.sh` on {{platform}} {{version}}.
+{{/define-snippet}}";
+        assert_eq!(
+            DefineSnippet::process_raw(definition, &mut ctx),
+            Ok("Intro\n".to_owned())
+        );
+
+        let input = "{{#use-snippet install-step version=\"1.0 beta\" /}}\n\
+            {{#use-snippet install-step platform=macos version=2 /}}";
+        assert_eq!(
+            UseSnippet::process_raw(input, &mut ctx),
+            Ok("Run `install.sh` on linux 1.0 beta.\nRun `install.sh` on macos 2.".to_owned())
+        );
+        assert!(ctx.errors.is_empty());
+
+        UseSnippet::process_raw("{{#use-snippet install-step /}}", &mut ctx).unwrap();
+        UseSnippet::process_raw("{{#use-snippet missing /}}", &mut ctx).unwrap();
+        DefineSnippet::process_raw(definition, &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 3);
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{html, Attrs, Capability, Context, MatchResult, Shortcode};
+
+/// Runs a query against an SQLite database at build time and shows the result as a table, e.g.
+/// `{{#sql db="fixtures/demo.sqlite"}}SELECT * FROM users LIMIT 5{{/sql}}`.
+pub(crate) struct Sql;
+
+impl Shortcode for Sql {
+    const NAME: &'static str = "sql";
+    const HEADER: &'static str = "";
+    const CAPABILITY: Capability = Capability::Filesystem;
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &["db"])?;
+        let db = attrs.get("db").ok_or("no db given to sql shortcode")?;
+        let query = input.trim();
+
+        ctx.watch(db);
+        match run_query(ctx, db, query) {
+            Ok((columns, rows)) => Ok(render_table(&columns, &rows)),
+            Err(e) => {
+                ctx.fail(Self::NAME, &format!("`{}` failed: {}", query, e));
+                Ok(String::new())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "sql")]
+fn run_query(
+    ctx: &Context,
+    db: &str,
+    query: &str,
+) -> Result<(Vec<String>, Vec<Vec<String>>), String> {
+    use rusqlite::{types::ValueRef, Connection, OpenFlags};
+
+    let connection =
+        Connection::open_with_flags(ctx.root.join(db), OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| e.to_string())?;
+    let mut statement = connection.prepare(query).map_err(|e| e.to_string())?;
+    let columns: Vec<String> = statement
+        .column_names()
+        .into_iter()
+        .map(str::to_owned)
+        .collect();
+
+    let mut rows = Vec::new();
+    let mut result = statement.query([]).map_err(|e| e.to_string())?;
+    while let Some(row) = result.next().map_err(|e| e.to_string())? {
+        let mut values = Vec::with_capacity(columns.len());
+        for i in 0..columns.len() {
+            let value = match row.get_ref(i).map_err(|e| e.to_string())? {
+                ValueRef::Null => "NULL".to_owned(),
+                ValueRef::Integer(i) => i.to_string(),
+                ValueRef::Real(f) => f.to_string(),
+                ValueRef::Text(t) => String::from_utf8_lossy(t).into_owned(),
+                ValueRef::Blob(b) => format!("<{} bytes>", b.len()),
+            };
+            values.push(value);
+        }
+        rows.push(values);
+    }
+    Ok((columns, rows))
+}
+
+#[cfg(not(feature = "sql"))]
+fn run_query(
+    _ctx: &Context,
+    _db: &str,
+    _query: &str,
+) -> Result<(Vec<String>, Vec<Vec<String>>), String> {
+    Err("mdbook-shortcodes was built without the `sql` feature".to_owned())
+}
+
+fn render_table(columns: &[String], rows: &[Vec<String>]) -> String {
+    let mut result = String::from("<table class=\"mdbook-shortcodes-sql\"><thead><tr>");
+    for column in columns {
+        result.push_str(&format!("<th>{}</th>", html::escape(column)));
+    }
+    result.push_str("</tr></thead><tbody>");
+    for row in rows {
+        result.push_str("<tr>");
+        for value in row {
+            result.push_str(&format!("<td>{}</td>", html::escape(value)));
+        }
+        result.push_str("</tr>");
+    }
+    result.push_str("</tbody></table>");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_table() {
+        assert_eq!(
+            render_table(
+                &["id".to_owned(), "name".to_owned()],
+                &[vec!["1".to_owned(), "<Ann>".to_owned()]]
+            ),
+            "<table class=\"mdbook-shortcodes-sql\"><thead><tr><th>id</th><th>name</th></tr></thead>\
+            <tbody><tr><td>1</td><td>&lt;Ann&gt;</td></tr></tbody></table>"
+        );
+    }
+
+    #[cfg(feature = "sql")]
+    #[test]
+    fn test_sql() {
+        let dir = tempfile::tempdir().unwrap();
+        let connection = rusqlite::Connection::open(dir.path().join("demo.sqlite")).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE users (id INTEGER, name TEXT, score REAL);
+                INSERT INTO users VALUES (1, 'Ann', 1.5), (2, NULL, 2.0);",
+            )
+            .unwrap();
+        drop(connection);
+
+        let config = crate::Config::default();
+        let mut ctx = Context::new(&config, dir.path().to_owned(), dir.path().join("src"));
+        assert_eq!(
+            Sql::process_raw(
+                "{{#sql db=\"demo.sqlite\"}}SELECT name, score FROM users{{/sql}}",
+                &mut ctx
+            ),
+            Ok("<table class=\"mdbook-shortcodes-sql\"><thead><tr><th>name</th><th>score</th></tr></thead>\
+            <tbody><tr><td>Ann</td><td>1.5</td></tr><tr><td>NULL</td><td>2</td></tr></tbody></table>"
+                .to_owned())
+        );
+
+        Sql::process_raw("{{#sql db=\"demo.sqlite\"}}SELECT nope{{/sql}}", &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 1);
+    }
+}
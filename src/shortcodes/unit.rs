@@ -0,0 +1,153 @@
+use crate::{Attrs, Context, MatchResult, Shortcode, UnitSystem};
+
+/// A unit and how to convert it to the equivalent unit in the other system.
+struct Conversion {
+    /// The symbols that refer to the unit; the first is the one shown.
+    symbols: &'static [&'static str],
+    system: UnitSystem,
+    /// The symbol of the equivalent unit in the other system.
+    other: &'static str,
+    convert: fn(f64) -> f64,
+}
+
+const CONVERSIONS: &[Conversion] = &[
+    metric(&["km"], "mi", |v| v / 1.609_344),
+    metric(&["m"], "ft", |v| v / 0.3048),
+    metric(&["cm"], "in", |v| v / 2.54),
+    metric(&["mm"], "in", |v| v / 25.4),
+    metric(&["kg"], "lb", |v| v / 0.453_592_37),
+    metric(&["g"], "oz", |v| v / 28.349_523_125),
+    metric(&["l", "L"], "gal", |v| v / 3.785_411_784),
+    metric(&["ml", "mL"], "fl oz", |v| v / 29.573_529_562_5),
+    metric(&["km/h"], "mph", |v| v / 1.609_344),
+    metric(&["m²", "m2"], "ft²", |v| v / 0.092_903_04),
+    // Bare `C` and `F` would be coulombs and farads, so only `degC` is accepted as ASCII.
+    metric(&["°C", "degC"], "°F", |v| v * 9.0 / 5.0 + 32.0),
+    imperial(&["mi"], "km", |v| v * 1.609_344),
+    imperial(&["ft"], "m", |v| v * 0.3048),
+    imperial(&["in"], "cm", |v| v * 2.54),
+    imperial(&["lb", "lbs"], "kg", |v| v * 0.453_592_37),
+    imperial(&["oz"], "g", |v| v * 28.349_523_125),
+    imperial(&["gal"], "l", |v| v * 3.785_411_784),
+    imperial(&["fl oz", "floz"], "ml", |v| v * 29.573_529_562_5),
+    imperial(&["mph"], "km/h", |v| v * 1.609_344),
+    imperial(&["ft²", "ft2"], "m²", |v| v * 0.092_903_04),
+    imperial(&["°F", "degF"], "°C", |v| (v - 32.0) * 5.0 / 9.0),
+];
+
+const fn metric(
+    symbols: &'static [&'static str],
+    other: &'static str,
+    convert: fn(f64) -> f64,
+) -> Conversion {
+    Conversion {
+        symbols,
+        system: UnitSystem::Metric,
+        other,
+        convert,
+    }
+}
+
+const fn imperial(
+    symbols: &'static [&'static str],
+    other: &'static str,
+    convert: fn(f64) -> f64,
+) -> Conversion {
+    Conversion {
+        symbols,
+        system: UnitSystem::Imperial,
+        other,
+        convert,
+    }
+}
+
+/// Shows a measurement in both metric and imperial units, e.g. `{{#unit 5 km /}}` becomes
+/// "5 km (3.1 mi)".
+pub(crate) struct Unit;
+
+impl Shortcode for Unit {
+    const NAME: &'static str = "unit";
+    const HEADER: &'static str = "";
+
+    fn process_match(_input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(2, &["precision"])?;
+        let (value, symbol) = match attrs.positional() {
+            [value, symbol] => (*value, *symbol),
+            _ => return Err("unit shortcode needs a value and a unit".to_owned()),
+        };
+        let number: f64 = value
+            .parse()
+            .map_err(|_| "invalid value given to unit shortcode")?;
+        let conversion = CONVERSIONS
+            .iter()
+            .find(|c| c.symbols.contains(&symbol))
+            .ok_or("unknown unit given to unit shortcode")?;
+
+        // By default, the converted value is one decimal place more precise than the original.
+        let precision = match attrs.get_parsed("precision")? {
+            Some(precision) => precision,
+            None => value.split_once('.').map_or(0, |(_, d)| d.len()) + 1,
+        };
+        let original = format!("{} {}", value, conversion.symbols[0]);
+        let converted = format!(
+            "{} {}",
+            format_number((conversion.convert)(number), precision),
+            conversion.other
+        );
+
+        if conversion.system == ctx.config.units {
+            Ok(format!("{} ({})", original, converted))
+        } else {
+            Ok(format!("{} ({})", converted, original))
+        }
+    }
+}
+
+/// Formats `value` with at most `precision` decimal places.
+fn format_number(value: f64, precision: usize) -> String {
+    let result = format!("{:.*}", precision, value);
+    if result.contains('.') {
+        result
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_owned()
+    } else {
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_unit() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let cases = [
+            ("{{#unit 5 km /}}", "5 km (3.1 mi)"),
+            ("{{#unit 20 °C /}}", "20 °C (68 °F)"),
+            ("{{#unit 212 degF /}}", "100 °C (212 °F)"),
+            ("{{#unit 2.5 kg precision=3 /}}", "2.5 kg (5.512 lb)"),
+            ("{{#unit 10 mi /}}", "16.1 km (10 mi)"),
+        ];
+        for (input, expected) in cases.iter() {
+            assert_eq!(
+                Unit::process_raw(input, &mut ctx),
+                Ok((*expected).to_owned())
+            );
+        }
+
+        let config = Config {
+            units: UnitSystem::Imperial,
+            ..Default::default()
+        };
+        let mut ctx = Context::test(&config);
+        assert_eq!(
+            Unit::process_raw("{{#unit 5 km /}}", &mut ctx),
+            Ok("3.1 mi (5 km)".to_owned())
+        );
+        assert!(Unit::process_raw("{{#unit 20 C /}}", &mut ctx).is_err());
+    }
+}
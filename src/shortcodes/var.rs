@@ -0,0 +1,49 @@
+use crate::{Attrs, Context, MatchResult, Shortcode};
+
+/// Inserts the value of a variable from the `[preprocessor.shortcodes.variables]` table, e.g.
+/// `{{#var version /}}`, so that values such as version numbers are defined once.
+pub(crate) struct Var;
+
+impl Shortcode for Var {
+    const NAME: &'static str = "var";
+    const HEADER: &'static str = "";
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(1, &[])?;
+        if !input.trim().is_empty() {
+            return Err("var shortcode must be self-closing".to_owned());
+        }
+        let name = match attrs.positional() {
+            [name] => *name,
+            _ => return Err("no variable given to var shortcode".to_owned()),
+        };
+        match ctx.config.variables.get(name) {
+            Some(value) => Ok(value.clone()),
+            None => Err(format!(
+                "unknown variable `{}`; define it in the `[preprocessor.shortcodes.variables]` \
+                 table",
+                name
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_var() {
+        let mut config = Config::default();
+        config
+            .variables
+            .insert("product_name".to_owned(), "Widget **Pro**".to_owned());
+        let mut ctx = Context::test(&config);
+        assert_eq!(
+            Var::process_raw("Welcome to {{#var product_name /}}.", &mut ctx),
+            Ok("Welcome to Widget **Pro**.".to_owned())
+        );
+        assert!(Var::process_raw("{{#var version /}}", &mut ctx).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+use std::path::{Component, Path, PathBuf};
+
+use crate::{
+    html, shortcodes::script::script_open_tag, Attrs, Capability, Context, MatchResult, Shortcode,
+};
+
+/// Embeds the output of `wasm-pack build --target web` (or `wasm-bindgen --target web`).
+pub(crate) struct WasmDemo;
+
+impl Shortcode for WasmDemo {
+    const NAME: &'static str = "wasm-demo";
+    const HEADER: &'static str = "";
+    const CAPABILITY: Capability = Capability::Filesystem;
+
+    fn process_match(_input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &["pkg", "name", "entry"])?;
+        let pkg = attrs
+            .get("pkg")
+            .ok_or("no pkg given to wasm-demo shortcode")?;
+        // The destination is kept inside the assets directory by ignoring components such as
+        // `..` and `/`.
+        let pkg_dest: PathBuf = Path::new(pkg)
+            .components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .collect();
+        // wasm-bindgen names the JS module after the crate, replacing dashes with underscores.
+        let name = match attrs.get("name") {
+            Some(name) => name.to_owned(),
+            None => pkg_dest
+                .file_name()
+                .ok_or("empty pkg given to wasm-demo shortcode")?
+                .to_string_lossy()
+                .replace('-', "_"),
+        };
+
+        let dest = Path::new("wasm-demo").join(&pkg_dest);
+        ctx.watch(pkg);
+        ctx.assets.add(ctx.root.join(pkg), &dest);
+
+        let root = match ctx.path_to_root() {
+            root if root.is_empty() => "./".to_owned(),
+            root => root,
+        };
+        let module_url = format!(
+            "{}{}/{}/{}.js",
+            root,
+            ctx.config.assets_dir,
+            dest.to_string_lossy().replace('\\', "/"),
+            name
+        );
+
+        let instance = ctx.ids.next_instance(Self::NAME);
+        let id = ctx
+            .ids
+            .unique(format!("mdbook-shortcodes-wasm-demo-{}", instance));
+        let module = format!("wasm_demo_{}", instance);
+        let entry = match attrs.get("entry") {
+            Some(entry) => format!("{}.{}(document.getElementById(\"{}\"));", module, entry, id),
+            None => String::new(),
+        };
+
+        Ok(format!(
+            "<div id=\"{id}\" class=\"mdbook-shortcodes-wasm-demo\"></div>\
+            {open}import init, * as {module} from \"{url}\";\
+            init().then(() => {{{entry}}});</script>",
+            id = id,
+            open = script_open_tag(true, ctx.config),
+            module = module,
+            url = html::escape(&module_url),
+            entry = entry,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_wasm_demo() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        ctx.start_chapter(Some(Path::new("examples/wasm.md")));
+
+        let input = "{{#wasm-demo pkg=\"../demos/counter-app\" entry=run /}}\n\
+            {{#wasm-demo pkg=demos/other name=lib /}}";
+        let expected = "<div id=\"mdbook-shortcodes-wasm-demo-0\" class=\"mdbook-shortcodes-wasm-demo\"></div>\
+            <script type=\"module\">import init, * as wasm_demo_0 from \
+            \"../shortcodes-assets/wasm-demo/demos/counter-app/counter_app.js\";\
+            init().then(() => {wasm_demo_0.run(document.getElementById(\"mdbook-shortcodes-wasm-demo-0\"));});</script>\n\
+            <div id=\"mdbook-shortcodes-wasm-demo-1\" class=\"mdbook-shortcodes-wasm-demo\"></div>\
+            <script type=\"module\">import init, * as wasm_demo_1 from \
+            \"../shortcodes-assets/wasm-demo/demos/other/lib.js\";\
+            init().then(() => {});</script>";
+        assert_eq!(
+            WasmDemo::process_raw(input, &mut ctx),
+            Ok(expected.to_owned())
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{html, Attrs, Context, MatchResult, Profile, Shortcode};
+
+/// Overlays a diagonal, repeating watermark across the chapter, e.g.
+/// `{{#watermark "DRAFT — do not distribute" /}}`. With `profile=web`, `profile=print` or
+/// `profile=release`, it is only shown when building for that profile.
+pub(crate) struct Watermark;
+
+impl Shortcode for Watermark {
+    const NAME: &'static str = "watermark";
+    const HEADER: &'static str = "
+<style>
+    .mdbook-shortcodes-watermark {
+        position: fixed;
+        inset: 0;
+        z-index: 1000;
+        pointer-events: none;
+        background-repeat: repeat;
+        -webkit-print-color-adjust: exact;
+        print-color-adjust: exact;
+    }
+</style>
+";
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(1, &["profile"])?;
+        if !input.trim().is_empty() {
+            return Err("watermark shortcode must be self-closing".to_owned());
+        }
+        let text = match attrs.positional() {
+            [text] => *text,
+            _ => return Err("no text given to watermark shortcode".to_owned()),
+        };
+        let profile = match attrs.get("profile") {
+            None => None,
+            Some("web") => Some(Profile::Web),
+            Some("print") => Some(Profile::Print),
+            Some("release") => Some(Profile::Release),
+            Some(profile) => return Err(format!("unknown profile `{}`", profile)),
+        };
+        if profile.is_some_and(|profile| profile != ctx.config.profile) {
+            return Ok(String::new());
+        }
+
+        Ok(format!(
+            "<div class=\"mdbook-shortcodes-watermark\" aria-hidden=\"true\" \
+            style=\"background-image: url(&quot;data:image/svg+xml,{}&quot;)\"></div>",
+            percent_encode(&tile(text))
+        ))
+    }
+
+    fn process_plain(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        Self::process_match(input, attrs, ctx).map(|_| String::new())
+    }
+}
+
+/// Returns an SVG image of `text` at an angle, which is repeated across the page.
+fn tile(text: &str) -> String {
+    // Long text is given a wider tile so that the copies don't overlap.
+    let width = 200 + 12 * text.chars().count();
+    let height = width / 2;
+    format!(
+        "<svg xmlns='http://www.w3.org/2000/svg' width='{w}' height='{h}'>\
+        <text x='50%' y='50%' text-anchor='middle' dominant-baseline='middle' \
+        transform='rotate(-30 {cx} {cy})' font-family='sans-serif' font-size='24' \
+        fill='rgba(128,128,128,0.25)'>{}</text></svg>",
+        html::escape(text),
+        w = width,
+        h = height,
+        cx = width / 2,
+        cy = height / 2,
+    )
+}
+
+/// Encodes `input` so that it can be used in a URL, leaving only unreserved characters as they
+/// are.
+fn percent_encode(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            result.push(byte as char);
+        } else {
+            result.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_watermark() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let result = Watermark::process_raw("{{#watermark \"DRAFT & co\" /}}", &mut ctx).unwrap();
+        assert!(result.starts_with(
+            "<div class=\"mdbook-shortcodes-watermark\" aria-hidden=\"true\" \
+            style=\"background-image: url(&quot;data:image/svg+xml,%3Csvg%20xmlns"
+        ));
+        assert!(result.contains("%3EDRAFT%20%26amp%3B%20co%3C%2Ftext%3E"));
+        // The only tags are those of the overlay itself.
+        assert_eq!(result.matches('<').count(), 2);
+
+        let input = "{{#watermark DRAFT profile=print /}}";
+        assert_eq!(Watermark::process_raw(input, &mut ctx), Ok(String::new()));
+        let config = Config {
+            profile: Profile::Print,
+            ..Config::default()
+        };
+        let mut ctx = Context::test(&config);
+        assert!(Watermark::process_raw(input, &mut ctx)
+            .unwrap()
+            .contains("DRAFT"));
+        assert!(Watermark::process_raw("{{#watermark /}}", &mut ctx).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use cargo_metadata::{MetadataCommand, Package};
+
+use crate::{exec, html, Attrs, Capability, Context, MatchResult, Shortcode};
+
+/// A table of the members of a Cargo workspace, with their versions, descriptions and
+/// documentation links, e.g. `{{#workspace-crates manifest="../Cargo.toml" /}}`.
+///
+/// By default, the workspace containing the book is used. Requires `allow-exec`, as the
+/// metadata is read by running `cargo metadata`.
+pub(crate) struct WorkspaceCrates;
+
+impl Shortcode for WorkspaceCrates {
+    const NAME: &'static str = "workspace-crates";
+    const HEADER: &'static str = "";
+    const CAPABILITY: Capability = Capability::Exec;
+
+    fn process_match(_input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &["manifest"])?;
+
+        let mut metadata = MetadataCommand::new();
+        metadata.no_deps().current_dir(&ctx.root);
+        if let Some(manifest) = attrs.get("manifest") {
+            metadata.manifest_path(ctx.root.join(manifest));
+        }
+        ctx.watch(attrs.get("manifest").unwrap_or("Cargo.toml"));
+        let output = match exec::run(ctx, Self::NAME, &mut metadata.cargo_command()) {
+            Ok(output) if output.status.success() => output,
+            Ok(output) => {
+                let message = format!(
+                    "`cargo metadata` failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+                ctx.fail(Self::NAME, &message);
+                return Ok(String::new());
+            }
+            Err(e) => {
+                ctx.fail(Self::NAME, &e);
+                return Ok(String::new());
+            }
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let metadata = match MetadataCommand::parse(stdout.as_ref()) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                ctx.fail(
+                    Self::NAME,
+                    &format!("invalid `cargo metadata` output: {}", e),
+                );
+                return Ok(String::new());
+            }
+        };
+
+        let mut packages = metadata.workspace_packages();
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(table(&packages, ctx))
+    }
+}
+
+fn table(packages: &[&Package], ctx: &Context) -> String {
+    let mut result = String::from(
+        "<table class=\"mdbook-shortcodes-workspace-crates\"><thead><tr>\
+        <th>Crate</th><th>Version</th><th>Description</th></tr></thead><tbody>",
+    );
+    for package in packages {
+        result.push_str(&format!(
+            "<tr><td><a href=\"{}\"><code>{}</code></a></td><td>{}</td><td>{}</td></tr>",
+            html::escape(&docs_url(package, ctx)),
+            html::escape(&package.name),
+            package.version,
+            html::escape(package.description.as_deref().unwrap_or("").trim())
+        ));
+    }
+    result.push_str("</tbody></table>");
+    result
+}
+
+/// Returns where a crate's documentation is, preferring the `rustdoc` option over the
+/// crate's `documentation` field and docs.rs.
+fn docs_url(package: &Package, ctx: &Context) -> String {
+    if let Some(url) = ctx.config.rustdoc.get(package.name.as_str()) {
+        return url.clone();
+    }
+    match &package.documentation {
+        Some(url) => url.clone(),
+        None => format!("https://docs.rs/{}/{}/", package.name, package.version),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_workspace_crates() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        WorkspaceCrates::process_raw("{{#workspace-crates /}}", &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 1);
+
+        let config = Config {
+            allow_exec: true,
+            ..Config::default()
+        };
+        let mut ctx = Context::test(&config);
+        ctx.root = env!("CARGO_MANIFEST_DIR").into();
+        let result = WorkspaceCrates::process_raw("{{#workspace-crates /}}", &mut ctx).unwrap();
+        assert!(ctx.errors.is_empty());
+        assert!(result.contains(&format!(
+            "<tr><td><a href=\"https://docs.rs/mdbook-shortcodes/{0}/\"><code>mdbook-shortcodes</code></a></td><td>{0}</td>",
+            env!("CARGO_PKG_VERSION")
+        )));
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{html, shortcodes::chess::figure, Attrs, Context, MatchResult, Shortcode};
+
+/// The distance between lines in the generated SVG.
+const SPACING: usize = 40;
+
+/// Draws a xiangqi (Chinese chess) position given in FEN, e.g.
+/// `{{#xiangqi fen="rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w" /}}`, where
+/// uppercase pieces are red. The body, if any, is used as a caption.
+pub(crate) struct Xiangqi;
+
+impl Shortcode for Xiangqi {
+    const NAME: &'static str = "xiangqi";
+    const HEADER: &'static str = "";
+
+    fn process_match(input: &str, attrs: Attrs, ctx: &mut Context) -> MatchResult {
+        attrs.expect(0, &["fen"])?;
+        let fen = attrs
+            .get("fen")
+            .ok_or("no fen given to xiangqi shortcode")?;
+
+        let board = match parse_fen(fen) {
+            Ok(board) => board,
+            Err(e) => {
+                ctx.fail(Self::NAME, &format!("invalid FEN `{}`: {}", fen, e));
+                return Ok(String::new());
+            }
+        };
+        Ok(figure(
+            &render_svg(&board),
+            input,
+            &format!("Xiangqi position: {}", fen),
+        ))
+    }
+}
+
+/// Returns the piece on each point, starting from the top left and going rank by rank.
+fn parse_fen(fen: &str) -> Result<Vec<Vec<Option<char>>>, String> {
+    let placement = fen.split_whitespace().next().unwrap_or("");
+    let mut board = Vec::new();
+    for rank in placement.split('/') {
+        let mut points = Vec::new();
+        for c in rank.chars() {
+            match c {
+                '1'..='9' => {
+                    for _ in 0..c.to_digit(10).unwrap() {
+                        points.push(None);
+                    }
+                }
+                c if "rnbakcpRNBAKCP".contains(c) => points.push(Some(c)),
+                _ => return Err(format!("unknown piece `{}`", c)),
+            }
+        }
+        if points.len() != 9 {
+            return Err("every rank must have 9 points".to_owned());
+        }
+        board.push(points);
+    }
+    if board.len() != 10 {
+        return Err("there must be 10 ranks".to_owned());
+    }
+    Ok(board)
+}
+
+/// Returns the character for a piece, which differs between red (uppercase) and black.
+fn glyph(piece: char) -> char {
+    match piece {
+        'R' => '俥',
+        'N' => '傌',
+        'B' => '相',
+        'A' => '仕',
+        'K' => '帥',
+        'C' => '炮',
+        'P' => '兵',
+        'r' => '車',
+        'n' => '馬',
+        'b' => '象',
+        'a' => '士',
+        'k' => '將',
+        'c' => '砲',
+        _ => '卒',
+    }
+}
+
+fn render_svg(board: &[Vec<Option<char>>]) -> String {
+    let margin = SPACING;
+    let (width, height) = (margin * 2 + SPACING * 8, margin * 2 + SPACING * 9);
+    let x = |col: usize| margin + SPACING * col;
+    let y = |row: usize| margin + SPACING * row;
+    let line = |x1: usize, y1: usize, x2: usize, y2: usize| {
+        format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#000\"/>",
+            x1, y1, x2, y2
+        )
+    };
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {0} {1}\" width=\"{0}\" height=\"{1}\">\
+        <rect width=\"{0}\" height=\"{1}\" fill=\"#f2d6a2\"/>",
+        width, height
+    );
+    for row in 0..10 {
+        svg.push_str(&line(x(0), y(row), x(8), y(row)));
+    }
+    for col in 0..9 {
+        // The river interrupts the inner files between the fifth and sixth ranks.
+        if col == 0 || col == 8 {
+            svg.push_str(&line(x(col), y(0), x(col), y(9)));
+        } else {
+            svg.push_str(&line(x(col), y(0), x(col), y(4)));
+            svg.push_str(&line(x(col), y(5), x(col), y(9)));
+        }
+    }
+    // The palaces.
+    for (top, bottom) in [(0, 2), (7, 9)] {
+        svg.push_str(&line(x(3), y(top), x(5), y(bottom)));
+        svg.push_str(&line(x(5), y(top), x(3), y(bottom)));
+    }
+
+    for (row, points) in board.iter().enumerate() {
+        for (col, piece) in points.iter().enumerate() {
+            if let Some(piece) = piece {
+                let color = if piece.is_uppercase() { "#c00" } else { "#000" };
+                svg.push_str(&format!(
+                    "<circle cx=\"{0}\" cy=\"{1}\" r=\"{2}\" fill=\"#fbe9c4\" stroke=\"{3}\" stroke-width=\"2\"/>\
+                    <text x=\"{0}\" y=\"{1}\" font-size=\"{4}\" text-anchor=\"middle\" \
+                    dominant-baseline=\"central\" fill=\"{3}\">{5}</text>",
+                    x(col),
+                    y(row),
+                    SPACING / 2 - 2,
+                    color,
+                    SPACING / 2,
+                    html::escape(&glyph(*piece).to_string())
+                ));
+            }
+        }
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_xiangqi() {
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let result = Xiangqi::process_raw(
+            "{{#xiangqi fen=\"rnbakabnr/9/1c5c1/p1p1p1p1p/9/9/P1P1P1P1P/1C5C1/9/RNBAKABNR w\" /}}",
+            &mut ctx,
+        )
+        .unwrap();
+        assert_eq!(result.matches("<circle").count(), 32);
+        assert!(result.contains(">帥</text>"));
+        assert!(result.contains(">將</text>"));
+
+        Xiangqi::process_raw("{{#xiangqi fen=\"9/9\" /}}", &mut ctx).unwrap();
+        assert_eq!(ctx.errors.len(), 1);
+    }
+}
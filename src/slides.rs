@@ -0,0 +1,71 @@
+use std::path::{Path, PathBuf};
+
+use crate::html;
+
+/// What the `slide` shortcode is replaced with in the book, and where chapters are split.
+pub(crate) const MARKER: &str = "<!-- mdbook-shortcodes-slide -->";
+
+const REVEAL_JS: &str = "https://cdn.jsdelivr.net/npm/reveal.js@5";
+
+/// Returns where the slides for a chapter are written, relative to the assets directory.
+pub(crate) fn path(chapter_path: &Path) -> PathBuf {
+    Path::new("slides").join(chapter_path.with_extension("html"))
+}
+
+/// Returns a reveal.js presentation with one slide per section of `content` between markers.
+/// The slides are rendered from Markdown by reveal.js, so the chapter is used as is.
+pub(crate) fn deck(title: &str, content: &str) -> String {
+    let mut result = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+        <link rel=\"stylesheet\" href=\"{reveal}/dist/reveal.css\">\n\
+        <link rel=\"stylesheet\" href=\"{reveal}/dist/theme/white.css\">\n\
+        </head>\n<body>\n<div class=\"reveal\"><div class=\"slides\">\n",
+        title = html::escape(title),
+        reveal = REVEAL_JS
+    );
+    for slide in content.split(MARKER) {
+        if slide.trim().is_empty() {
+            continue;
+        }
+        result.push_str(&format!(
+            "<section data-markdown><textarea data-template>\n{}\n</textarea></section>\n",
+            // The slide can't end the textarea early.
+            slide.trim().replace("</textarea", "&lt;/textarea")
+        ));
+    }
+    result.push_str(&format!(
+        "</div></div>\n<script src=\"{reveal}/dist/reveal.js\"></script>\n\
+        <script src=\"{reveal}/plugin/markdown/markdown.js\"></script>\n\
+        <script>Reveal.initialize({{ hash: true, plugins: [RevealMarkdown] }});</script>\n\
+        </body>\n</html>\n",
+        reveal = REVEAL_JS
+    ));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path() {
+        assert_eq!(
+            path(Path::new("guide/intro.md")),
+            Path::new("slides/guide/intro.html")
+        );
+    }
+
+    #[test]
+    fn test_deck() {
+        let content = format!(
+            "# Title\n{0}\n## First\n\n</textarea>\n{0}\n{0}\n## Second\n",
+            MARKER
+        );
+        let deck = deck("A & B", &content);
+        assert!(deck.contains("<title>A &amp; B</title>"));
+        assert_eq!(deck.matches("<section").count(), 3);
+        assert!(deck.contains(
+            "<section data-markdown><textarea data-template>\n## First\n\n&lt;/textarea>\n</textarea></section>"
+        ));
+    }
+}
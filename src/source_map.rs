@@ -0,0 +1,166 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+/// Maps the byte ranges of an expanded chapter to those of the chapter as it was written, so
+/// that problems found in the output (e.g. by a link checker) can be reported where the author
+/// can fix them.
+///
+/// Written for every chapter to the `source-map-file`, as a JSON object keyed by the paths of
+/// the chapters.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SourceMap {
+    segments: Vec<Segment>,
+}
+
+/// A range of the output along with the range of the input it came from.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Segment {
+    pub output: Range<usize>,
+    pub input: Range<usize>,
+    /// Whether the output is a copy of the input, so that each byte maps to the one at the
+    /// same offset in the input. Otherwise, the output (e.g. of a shortcode) maps to the input
+    /// as a whole.
+    pub verbatim: bool,
+}
+
+impl SourceMap {
+    /// The segments of the output that came from the input, in order. Text that was added to
+    /// the output (e.g. the headers of shortcodes) isn't in any of them.
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Returns the offset in the input that `offset` in the output came from: the same byte in
+    /// copied text, or the start of the shortcode that output it.
+    pub fn input_offset(&self, offset: usize) -> Option<usize> {
+        let after = self
+            .segments
+            .partition_point(|segment| segment.output.start <= offset);
+        let segment = &self.segments[after.checked_sub(1)?];
+        if !segment.output.contains(&offset) {
+            None
+        } else if segment.verbatim {
+            Some(segment.input.start + offset - segment.output.start)
+        } else {
+            Some(segment.input.start)
+        }
+    }
+
+    /// Records that the output at `output` came from the input at `input`.
+    pub(crate) fn push(&mut self, output: Range<usize>, input: Range<usize>, verbatim: bool) {
+        if !output.is_empty() {
+            self.segments.push(Segment {
+                output,
+                input,
+                verbatim,
+            });
+        }
+    }
+
+    /// Adds the segments of `other`, the map of a part of the input at `input` that was output
+    /// at `output`.
+    pub(crate) fn append(&mut self, other: SourceMap, output: usize, input: usize) {
+        for segment in other.segments {
+            self.segments.push(Segment {
+                output: output + segment.output.start..output + segment.output.end,
+                input: input + segment.input.start..input + segment.input.end,
+                verbatim: segment.verbatim,
+            });
+        }
+    }
+
+    /// Records that `range` of the output was replaced by `len` bytes that didn't come from the
+    /// input. Copied text is split around the replacement, while the output of a shortcode
+    /// that contains it grows or shrinks with it.
+    pub(crate) fn replace(&mut self, range: Range<usize>, len: usize) {
+        let shift = |offset: usize| offset + len - range.len();
+        let mut segments = Vec::with_capacity(self.segments.len() + 1);
+        for segment in self.segments.drain(..) {
+            let Segment {
+                output,
+                input,
+                verbatim,
+            } = segment;
+            if output.end <= range.start {
+                segments.push(Segment {
+                    output,
+                    input,
+                    verbatim,
+                });
+            } else if output.start >= range.end {
+                segments.push(Segment {
+                    output: shift(output.start)..shift(output.end),
+                    input,
+                    verbatim,
+                });
+            } else if verbatim {
+                let before = output.start..output.start.max(range.start);
+                let after = output.end.min(range.end)..output.end;
+                let to_input = |offset: usize| input.start + offset - output.start;
+                if !before.is_empty() {
+                    segments.push(Segment {
+                        input: input.start..to_input(before.end),
+                        output: before,
+                        verbatim,
+                    });
+                }
+                if !after.is_empty() {
+                    segments.push(Segment {
+                        input: to_input(after.start)..input.end,
+                        output: shift(after.start)..shift(after.end),
+                        verbatim,
+                    });
+                }
+            } else {
+                segments.push(Segment {
+                    output: output.start.min(range.start)..shift(output.end.max(range.end)),
+                    input,
+                    verbatim,
+                });
+            }
+        }
+        self.segments = segments;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_map() {
+        // `A {{#kbd C /}} B` expanded to `A <kbd>C</kbd> B`.
+        let mut map = SourceMap::default();
+        map.push(0..2, 0..2, true);
+        map.push(2..14, 2..14, false);
+        map.push(14..16, 14..16, true);
+        assert_eq!(map.input_offset(1), Some(1));
+        assert_eq!(map.input_offset(9), Some(2));
+        assert_eq!(map.input_offset(15), Some(15));
+        assert_eq!(map.input_offset(16), None);
+
+        // A header inserted before the text, a marker in the output of the shortcode replaced
+        // by something longer and text inserted in the middle of copied text.
+        map.replace(0..0, 10);
+        map.replace(15..17, 5);
+        map.replace(28..28, 3);
+        let segment = |output, input, verbatim| Segment {
+            output,
+            input,
+            verbatim,
+        };
+        assert_eq!(
+            map.segments(),
+            [
+                segment(10..12, 0..2, true),
+                segment(12..27, 2..14, false),
+                segment(27..28, 14..15, true),
+                segment(31..32, 15..16, true),
+            ]
+        );
+        assert_eq!(map.input_offset(31), Some(15));
+    }
+}
@@ -0,0 +1,62 @@
+use pulldown_cmark::{Event, Tag, TagEnd};
+use serde::Serialize;
+
+/// The number of words read per minute, used to estimate the reading time.
+const WORDS_PER_MINUTE: usize = 200;
+
+/// Statistics about the content of a book, counted as each chapter is processed.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct Stats {
+    pub(crate) chapters: usize,
+    /// The words in the text, not counting code blocks or HTML.
+    pub(crate) words: usize,
+    /// The images, whether written in markdown or with an `<img>` tag.
+    pub(crate) figures: usize,
+    /// The estimated time it takes to read the book.
+    pub(crate) reading_minutes: usize,
+}
+
+impl Stats {
+    /// Counts a chapter, after its shortcodes have been expanded.
+    pub(crate) fn add_chapter(&mut self, content: &str) {
+        self.chapters += 1;
+        let mut in_code = false;
+        for event in mdbook::utils::new_cmark_parser(content, false) {
+            match event {
+                Event::Start(Tag::CodeBlock(_)) => in_code = true,
+                Event::End(TagEnd::CodeBlock) => in_code = false,
+                Event::Start(Tag::Image { .. }) => self.figures += 1,
+                Event::Text(text) | Event::Code(text) if !in_code => {
+                    self.words += text.split_whitespace().count();
+                }
+                Event::Html(html) | Event::InlineHtml(html) => {
+                    self.figures += html.matches("<img").count();
+                }
+                _ => {}
+            }
+        }
+        self.reading_minutes = self.words.div_ceil(WORDS_PER_MINUTE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let mut stats = Stats::default();
+        stats.add_chapter("# Title\n\nSome `inline code` here.\n\n```rust\nlet x = 1;\n```\n");
+        stats.add_chapter("![A figure](a.png)\n\n<div><img src=\"b.png\"></div>\n");
+        assert_eq!(
+            stats,
+            Stats {
+                chapters: 2,
+                words: 7,
+                figures: 2,
+                reading_minutes: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use std::{path::Path, rc::Rc};
+
+use mdbook::errors::{Error, Result};
+
+use crate::{
+    analytics, expand, find_handler, handlers, parse, theme, Config, Context, CustomShortcode,
+    Delimiters, Handler,
+};
+
+/// Expands the shortcodes in markdown that is generated a piece at a time (e.g. piped from a
+/// documentation generator), without holding all of it in memory, for use outside of mdBook.
+///
+/// Content is expanded a block at a time: [`feed`](Self::feed) returns the output of the
+/// blocks its content completes, which end at a blank line outside of any shortcode or code
+/// block, and [`finish`](Self::finish) that of the rest. The headers of shortcodes are output
+/// before the block that first uses them.
+///
+/// Created by [`ShortcodesProcessor::expander`](crate::ShortcodesProcessor::expander).
+pub struct Expander<'a> {
+    ctx: Context<'a>,
+    handlers: Vec<Handler<'a>>,
+    /// The content that has been fed but not expanded, as it doesn't end a block yet.
+    pending: String,
+    /// The number of the headers used so far that have been output.
+    headers_written: usize,
+    /// Whether the output contains analytics events, which need the script that reports them.
+    has_analytics: bool,
+}
+
+impl<'a> Expander<'a> {
+    pub(crate) fn new(
+        config: &'a Config,
+        custom: &'a [Rc<dyn CustomShortcode>],
+        dir: &Path,
+    ) -> Self {
+        let mut ctx = Context::new(config, dir.to_owned(), dir.to_owned());
+        ctx.custom = custom;
+        Self {
+            handlers: handlers(config, custom),
+            ctx,
+            pending: String::new(),
+            headers_written: 0,
+            has_analytics: false,
+        }
+    }
+
+    /// Adds `content` to the markdown being expanded, returning the output of the blocks it
+    /// completes. The output may be empty, e.g. while a shortcode's body is being fed.
+    pub fn feed(&mut self, content: &str) -> Result<String> {
+        self.pending.push_str(content);
+        let config = self.ctx.config;
+        let handlers = &self.handlers;
+        let is_shortcode = |name: &str| find_handler(handlers, name, config).is_some();
+        match block_end(&self.pending, &config.delimiters, is_shortcode) {
+            Some(end) => {
+                let block: String = self.pending.drain(..end).collect();
+                self.expand(&block)
+            }
+            None => Ok(String::new()),
+        }
+    }
+
+    /// Returns the output of the content that hasn't been expanded yet. Errors recorded by
+    /// shortcodes (which don't stop the rest of the content from being expanded) are returned
+    /// now, unless `fail-on-error` is unset or `errors` is `"inline"`, in which case they are
+    /// logged instead.
+    pub fn finish(mut self) -> Result<String> {
+        let pending = std::mem::take(&mut self.pending);
+        let mut output = self.expand(&pending)?;
+        let config = self.ctx.config;
+        if self.has_analytics && config.analytics && !self.ctx.is_epub() {
+            output.push_str(&analytics::dispatcher(config));
+        }
+
+        if !self.ctx.errors.is_empty() {
+            if config.fails_on_error() {
+                return Err(Error::msg(self.ctx.errors.join("\n\n")));
+            }
+            for error in &self.ctx.errors {
+                log::warn!("{}", error);
+            }
+        }
+        self.ctx
+            .assets
+            .stage(&self.ctx.src_dir, &config.assets_dir)?;
+        Ok(output)
+    }
+
+    /// Returns the output of a complete block, preceded by the headers it uses for the first
+    /// time.
+    fn expand(&mut self, block: &str) -> Result<String> {
+        self.ctx.source.clear();
+        self.ctx.source.push_str(block);
+        let expanded = expand(block, &self.handlers, &mut self.ctx, 0)?;
+        self.has_analytics |= self.ctx.is_html() && expanded.contains("data-analytics-event");
+
+        let mut output = String::with_capacity(expanded.len());
+        let new_headers = &self.ctx.headers[self.headers_written..];
+        if !new_headers.is_empty() {
+            let theme = theme::css(&self.ctx.config.theme);
+            if self.headers_written == 0 && !theme.is_empty() {
+                output.push_str(&format!("\n<style>\n{}</style>\n", theme));
+            }
+            output.push_str(&new_headers.concat());
+            self.headers_written = self.ctx.headers.len();
+        }
+        output.push_str(&expanded);
+        Ok(output)
+    }
+}
+
+/// Returns the end of the last complete block in `content`: the end of a blank line that
+/// isn't in a shortcode or a fenced code block.
+fn block_end<F>(content: &str, delimiters: &Delimiters, is_shortcode: F) -> Option<usize>
+where
+    F: Fn(&str) -> bool,
+{
+    let mut ends = Vec::new();
+    let mut offset = 0;
+    let mut in_code = false;
+    let mut blank = false;
+    for line in content.split_inclusive('\n') {
+        offset += line.len();
+        if !line.ends_with('\n') {
+            break;
+        }
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+        }
+        // A block ends at the first of the blank lines after it.
+        let is_blank = line.trim().is_empty();
+        if is_blank && !blank && !in_code {
+            ends.push(offset);
+        }
+        blank = is_blank;
+    }
+    ends.into_iter()
+        .rev()
+        .find(|&end| parse::parse(&content[..end], delimiters, &is_shortcode).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Hint, Shortcode, ShortcodesProcessor};
+
+    #[test]
+    fn test_expander() {
+        let dir = tempfile::tempdir().unwrap();
+        let processor = ShortcodesProcessor::new();
+        let config = Config::default();
+        let mut expander = processor.expander(&config, dir.path());
+
+        assert_eq!(
+            expander.feed("Intro\n\n{{#hint info}}\nA").unwrap(),
+            "Intro\n\n"
+        );
+        assert_eq!(expander.feed("\n\n```\n{{/hint}}\n\n").unwrap(), "");
+        let output = expander
+            .feed("```\n{{/hint}}\n\nPress {{#kbd C /}}")
+            .unwrap();
+        assert!(output.starts_with(Hint::HEADER));
+        assert!(output.contains("```\n{{/hint}}\n\n```"));
+        assert!(output.ends_with("</div>\n\n"));
+        assert_eq!(
+            expander
+                .feed(" to copy.\n\n{{#hint info}}B{{/hint}}\n")
+                .unwrap(),
+            "Press <kbd class=\"mdbook-shortcodes-kbd\"><kbd>C</kbd></kbd> to copy.\n\n"
+        );
+        let output = expander.finish().unwrap();
+        assert!(output.starts_with("<div"));
+    }
+}
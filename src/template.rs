@@ -0,0 +1,48 @@
+/// Replaces every `{{name}}` placeholder in `body` with the value returned by `lookup`.
+///
+/// Shortcodes (e.g. `{{#hint}}`) are left alone. If a placeholder has no value, its name is
+/// returned as the error.
+pub(crate) fn render<F>(body: &str, lookup: F) -> Result<String, String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut result = String::new();
+    let mut rest = body;
+    while let Some(start) = rest.find("{{") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = match after.find("}}") {
+            Some(end) if !after.starts_with('#') && !after.starts_with('/') => end,
+            _ => {
+                result.push_str("{{");
+                rest = after;
+                continue;
+            }
+        };
+        let name = after[..end].trim();
+        result.push_str(&lookup(name).ok_or_else(|| name.to_owned())?);
+        rest = &after[end + 2..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let lookup = |name: &str| match name {
+            "os" => Some("linux".to_owned()),
+            "p.name" => Some("serde".to_owned()),
+            _ => None,
+        };
+        assert_eq!(
+            render("Install on {{ os }}: {{p.name}} {{#kbd}}C{{/kbd}}", lookup),
+            Ok("Install on linux: serde {{#kbd}}C{{/kbd}}".to_owned())
+        );
+        assert_eq!(render("{{missing}}", lookup), Err("missing".to_owned()));
+        assert_eq!(render("{{ unclosed", lookup), Ok("{{ unclosed".to_owned()));
+    }
+}
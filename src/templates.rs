@@ -0,0 +1,120 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use mdbook::errors::{Error, Result};
+
+use crate::{html, template, Attrs, Context, CustomShortcode};
+
+/// A shortcode defined by an HTML template in the `templates-dir`, which is named after its file
+/// (e.g. `video.html` is used as `{{#video intro.mp4 width=640 /}}`).
+///
+/// Placeholders in the template are replaced by the shortcode's attributes: `{{0}}`, `{{1}}`
+/// etc. by its positional ones and `{{width}}` by the named one with that key, escaped for
+/// HTML. `{{body}}` is replaced by its body as it was written.
+pub(crate) struct Template {
+    name: String,
+    source: String,
+    /// The template's file, relative to the book's root directory.
+    path: PathBuf,
+}
+
+impl CustomShortcode for Template {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn process(&self, input: &str, attrs: &Attrs, ctx: &mut Context) -> Result<String, String> {
+        ctx.watch(&self.path);
+        template::render(&self.source, |placeholder| {
+            match placeholder.parse::<usize>() {
+                Ok(index) => attrs
+                    .positional()
+                    .get(index)
+                    .map(|value| html::escape(value)),
+                Err(_) if placeholder == "body" => Some(input.to_owned()),
+                Err(_) => attrs.get(placeholder).map(html::escape),
+            }
+        })
+        .map_err(|placeholder| {
+            format!(
+                "no value given for `{}` in template {}",
+                placeholder,
+                self.path.display()
+            )
+        })
+    }
+}
+
+/// Returns the templates in `dir`, relative to the book's root directory `root`, in the order
+/// of their names. A book without the directory has none.
+pub(crate) fn load(root: &Path, dir: &str) -> Result<Vec<Template>> {
+    let entries = match fs::read_dir(root.join(dir)) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(Vec::new()),
+    };
+    let mut templates = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension() != Some("html".as_ref()) {
+            continue;
+        }
+        let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(name) => name.to_owned(),
+            None => continue,
+        };
+        let source = fs::read_to_string(&path)
+            .map_err(|e| Error::msg(format!("failed to read {}: {}", path.display(), e)))?;
+        templates.push(Template {
+            path: Path::new(dir).join(path.file_name().unwrap()),
+            name,
+            source: source.trim_end_matches('\n').to_owned(),
+        });
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(templates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        let templates_dir = dir.path().join("shortcodes");
+        fs::create_dir(&templates_dir).unwrap();
+        fs::write(
+            templates_dir.join("video.html"),
+            "<video src=\"{{0}}\" width=\"{{ width }}\">{{body}}</video>\n",
+        )
+        .unwrap();
+        fs::write(templates_dir.join("notes.txt"), "").unwrap();
+
+        let templates = load(dir.path(), "shortcodes").unwrap();
+        assert_eq!(templates.len(), 1);
+        let video = &templates[0];
+        assert_eq!(video.name(), "video");
+
+        let config = Config::default();
+        let mut ctx = Context::test(&config);
+        let attrs = Attrs::parse("\"a&b.mp4\" width=640").unwrap();
+        assert_eq!(
+            video.process("Your browser can't play *videos*.", &attrs, &mut ctx),
+            Ok(
+                "<video src=\"a&amp;b.mp4\" width=\"640\">Your browser can't play *videos*.\
+                </video>"
+                    .to_owned()
+            )
+        );
+        let attrs = Attrs::parse("a.mp4").unwrap();
+        assert_eq!(
+            video.process("", &attrs, &mut ctx),
+            Err("no value given for `width` in template shortcodes/video.html".to_owned())
+        );
+
+        assert!(load(dir.path(), "missing").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+
+/// The CSS custom properties used by the styles of shortcodes, which can be set in the
+/// `[preprocessor.shortcodes.theme]` table without their `--mdbook-shortcodes-` prefix.
+pub(crate) const KEYS: &[&str] = &[
+    "hint-border-radius",
+    "hint-info-color",
+    "hint-info-background",
+    "hint-ok-color",
+    "hint-ok-background",
+    "hint-warning-color",
+    "hint-warning-background",
+    "hint-danger-color",
+    "hint-danger-background",
+    "column-padding",
+    "columns-gap",
+    "pullout-border-color",
+    "pullout-background",
+];
+
+/// The classes mdBook gives the `html` element for its dark themes. The styles of shortcodes
+/// use other defaults under them (e.g. `.navy .mdbook-shortcodes-hint-info`), so that colours
+/// that are meant for a light background don't look out of place.
+pub(crate) const DARK_THEMES: &[&str] = &["navy", "coal", "ayu"];
+
+/// Returns an error if `theme` sets a property that doesn't exist, or sets one to a value that
+/// would end the rule it is written in.
+pub(crate) fn validate(theme: &BTreeMap<String, String>) -> Result<(), String> {
+    for (key, value) in theme {
+        if !KEYS.contains(&key.as_str()) {
+            return Err(format!(
+                "unknown theme key `{}`; the keys are: {}",
+                key,
+                KEYS.join(", ")
+            ));
+        }
+        if value.contains(['{', '}', ';', '<', '>']) {
+            return Err(format!("invalid value for theme key `{}`: {}", key, value));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the rule that sets the properties in `theme`, or an empty string if it sets none.
+pub(crate) fn css(theme: &BTreeMap<String, String>) -> String {
+    rule(":root", theme)
+}
+
+/// Returns the rule that sets the properties in `theme` under mdBook's dark themes, or an
+/// empty string if it sets none.
+pub(crate) fn dark_css(theme: &BTreeMap<String, String>) -> String {
+    let selector: Vec<String> = DARK_THEMES.iter().map(|t| format!(".{}", t)).collect();
+    rule(&selector.join(", "), theme)
+}
+
+fn rule(selector: &str, theme: &BTreeMap<String, String>) -> String {
+    if theme.is_empty() {
+        return String::new();
+    }
+    let mut result = format!("{} {{\n", selector);
+    for (key, value) in theme {
+        result.push_str(&format!(
+            "    --mdbook-shortcodes-{}: {};\n",
+            key,
+            value.trim()
+        ));
+    }
+    result.push_str("}\n");
+    result
+}
+
+/// Returns the default value of each property in `KEYS`, as given by the fallbacks of the
+/// `var()`s in `headers` (e.g. `var(--mdbook-shortcodes-columns-gap, 2em)`). With `dark`, only
+/// the rules for mdBook's dark themes are used, and otherwise only the rest.
+pub(crate) fn defaults(headers: &[&str], dark: bool) -> BTreeMap<String, String> {
+    const PREFIX: &str = "var(--mdbook-shortcodes-";
+    let mut result = BTreeMap::new();
+    let rules = headers
+        .iter()
+        .flat_map(|header| header.split_inclusive('}'));
+    for rule in rules {
+        let (selector, body) = rule.rsplit_once('{').unwrap_or(("", rule));
+        let is_dark = DARK_THEMES
+            .iter()
+            .any(|theme| selector.contains(&format!(".{} ", theme)));
+        if is_dark != dark {
+            continue;
+        }
+        for (i, _) in body.match_indices(PREFIX) {
+            let rest = &body[i + PREFIX.len()..];
+            let key = match rest.split_once(',') {
+                Some((key, _)) if KEYS.contains(&key) => key,
+                _ => continue,
+            };
+            // The fallback ends at the parenthesis closing the `var(`, and can contain others.
+            let fallback = &rest[key.len() + 1..];
+            let mut depth = 0;
+            let end = fallback.char_indices().find_map(|(i, c)| match c {
+                '(' => {
+                    depth += 1;
+                    None
+                }
+                ')' if depth == 0 => Some(i),
+                ')' => {
+                    depth -= 1;
+                    None
+                }
+                _ => None,
+            });
+            if let Some(end) = end {
+                result
+                    .entry(key.to_owned())
+                    .or_insert_with(|| fallback[..end].trim().to_owned());
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme() {
+        let mut theme = BTreeMap::new();
+        assert_eq!(css(&theme), "");
+        theme.insert("hint-info-color".to_owned(), "#88f".to_owned());
+        theme.insert("columns-gap".to_owned(), " 3em".to_owned());
+        assert_eq!(validate(&theme), Ok(()));
+        assert_eq!(
+            css(&theme),
+            ":root {\n    --mdbook-shortcodes-columns-gap: 3em;\n    \
+            --mdbook-shortcodes-hint-info-color: #88f;\n}\n"
+        );
+
+        theme.insert(
+            "hint-info-color".to_owned(),
+            "red; } body { color: red".to_owned(),
+        );
+        assert!(validate(&theme).is_err());
+        theme.clear();
+        theme.insert("hint-colour".to_owned(), "red".to_owned());
+        assert!(validate(&theme)
+            .unwrap_err()
+            .starts_with("unknown theme key `hint-colour`"));
+
+        let header = "a { b: var(--mdbook-shortcodes-columns-gap, 2em); \
+            c: var(--mdbook-shortcodes-pullout-background, var(--quote-bg)); \
+            d: var(--mdbook-shortcodes-pullout-width); }";
+        let found: Vec<_> = defaults(&[header], false).into_iter().collect();
+        assert_eq!(
+            found,
+            [
+                ("columns-gap".to_owned(), "2em".to_owned()),
+                (
+                    "pullout-background".to_owned(),
+                    "var(--quote-bg)".to_owned()
+                )
+            ]
+        );
+
+        let header = ".a { b: var(--mdbook-shortcodes-columns-gap, 2em); }\n\
+            .navy .a, .coal .a { b: var(--mdbook-shortcodes-columns-gap, 3em); }";
+        assert_eq!(defaults(&[header], false)["columns-gap"], "2em");
+        assert_eq!(defaults(&[header], true)["columns-gap"], "3em");
+        assert_eq!(
+            dark_css(&defaults(&[header], true)),
+            ".navy, .coal, .ayu {\n    --mdbook-shortcodes-columns-gap: 3em;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_dark_themes() {
+        // A rule for one dark theme is written for all of them, with the same properties.
+        for handler in crate::builtins() {
+            for rule in handler.header().split_inclusive('}') {
+                let (selector, _) = rule.rsplit_once('{').unwrap_or(("", rule));
+                let themes = DARK_THEMES
+                    .iter()
+                    .filter(|theme| selector.contains(&format!(".{} ", theme)))
+                    .count();
+                assert!(themes == 0 || themes == DARK_THEMES.len(), "{}", selector);
+            }
+        }
+        let headers: Vec<_> = crate::builtins().iter().map(|h| h.header()).collect();
+        let dark = defaults(&headers, true);
+        assert_eq!(dark["hint-info-color"], "#3b8eea");
+        assert!(dark
+            .keys()
+            .all(|key| defaults(&headers, false).contains_key(key)));
+    }
+}
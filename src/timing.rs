@@ -0,0 +1,79 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::Serialize;
+
+/// How long expanding shortcodes took, recorded as each chapter is processed, in milliseconds.
+#[derive(Clone, PartialEq, Debug, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct Timings {
+    /// The time spent in each shortcode, keyed by name. The time spent expanding the shortcodes
+    /// in the body of another isn't counted as part of its time.
+    pub(crate) shortcodes: BTreeMap<String, Timing>,
+    /// The time spent expanding the shortcodes in each chapter, keyed by path.
+    pub(crate) chapters: BTreeMap<PathBuf, f64>,
+}
+
+/// The time spent in every use of a shortcode.
+#[derive(Clone, PartialEq, Debug, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct Timing {
+    pub(crate) uses: usize,
+    pub(crate) total_ms: f64,
+    /// The time taken by the slowest use.
+    pub(crate) max_ms: f64,
+}
+
+impl Timings {
+    /// Records that a use of the `name` shortcode took `duration`.
+    pub(crate) fn add_use(&mut self, name: &str, duration: Duration) {
+        let timing = self.shortcodes.entry(name.to_owned()).or_default();
+        timing.uses += 1;
+        timing.total_ms += millis(duration);
+        timing.max_ms = timing.max_ms.max(millis(duration));
+    }
+
+    /// Records that expanding the shortcodes in the chapter at `path` took `duration`.
+    pub(crate) fn add_chapter(&mut self, path: &Path, duration: Duration) {
+        *self.chapters.entry(path.to_owned()).or_default() += millis(duration);
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timings() {
+        let mut timings = Timings::default();
+        timings.add_use("kbd", Duration::from_millis(2));
+        timings.add_use("cmd-output", Duration::from_millis(1500));
+        timings.add_use("kbd", Duration::from_millis(4));
+        timings.add_chapter(Path::new("intro.md"), Duration::from_millis(1510));
+        assert_eq!(
+            timings.shortcodes["kbd"],
+            Timing {
+                uses: 2,
+                total_ms: 6.0,
+                max_ms: 4.0,
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(&timings).unwrap(),
+            serde_json::json!({
+                "shortcodes": {
+                    "cmd-output": { "uses": 1, "total-ms": 1500.0, "max-ms": 1500.0 },
+                    "kbd": { "uses": 2, "total-ms": 6.0, "max-ms": 4.0 },
+                },
+                "chapters": { "intro.md": 1510.0 },
+            })
+        );
+    }
+}
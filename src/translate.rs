@@ -0,0 +1,292 @@
+use std::{collections::BTreeMap, ops::Range, path::Path};
+
+use crate::{parse, Attrs, Delimiters, Result};
+
+/// The shortcodes whose bodies are prose, which is extracted for translation.
+const BODIES: &[&str] = &["hint", "pullout", "flashcard", "review", "columns"];
+
+/// The attributes of shortcodes that are shown to readers, e.g. the title of a hint.
+const ATTRS: &[(&str, &str)] = &[("hint", "title"), ("license", "title"), ("rustdoc", "text")];
+
+/// A piece of a chapter that can be translated.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct Segment {
+    /// Where the text is in the chapter, without surrounding whitespace.
+    range: Range<usize>,
+    /// Whether the text is the unquoted value of an attribute, which has to be quoted if its
+    /// translation contains whitespace.
+    unquoted: bool,
+}
+
+/// Returns the translatable segments of `content`, which starts at `offset` in its chapter.
+/// `resolve` returns the name of the shortcode called by a name, which may be an alias.
+pub(crate) fn segments<'n, F>(
+    content: &str,
+    offset: usize,
+    delimiters: &Delimiters,
+    resolve: &F,
+) -> Result<Vec<Segment>>
+where
+    F: Fn(&str) -> Option<&'n str>,
+{
+    let parsed = parse::parse(content, delimiters, |name| resolve(name).is_some())?;
+    let mut result = Vec::new();
+    for node in parsed.nodes {
+        let name = resolve(node.name).unwrap();
+        let attrs = Attrs::parse(node.attrs)?;
+        for (key, value) in attrs.named() {
+            if !ATTRS.contains(&(name, key)) || value.trim().is_empty() {
+                continue;
+            }
+            let start = offset + address(content, value);
+            let unquoted = !matches!(content[..start - offset].chars().last(), Some('"' | '\''));
+            result.push(Segment {
+                range: start..start + value.len(),
+                unquoted,
+            });
+        }
+
+        let body = match node.body {
+            Some(body) => body,
+            None => continue,
+        };
+        let body_offset = offset + address(content, body);
+        let nested = segments(body, body_offset, delimiters, resolve)?;
+        // The body is translated as a whole, unless it contains other prose that is translated
+        // on its own, e.g. a hint in a column.
+        let has_prose = parse::parse(body, delimiters, |name| {
+            resolve(name).is_some_and(|name| BODIES.contains(&name))
+        })?
+        .nodes
+        .iter()
+        .any(|node| node.body.is_some());
+        if BODIES.contains(&name) && !has_prose && !body.trim().is_empty() {
+            let start = body_offset + (body.len() - body.trim_start().len());
+            result.push(Segment {
+                range: start..start + body.trim().len(),
+                unquoted: false,
+            });
+        } else {
+            result.extend(nested);
+        }
+    }
+    Ok(result)
+}
+
+/// Returns where `part`, a slice of `content`, starts in it.
+fn address(content: &str, part: &str) -> usize {
+    part.as_ptr() as usize - content.as_ptr() as usize
+}
+
+/// The translatable text of a book, written as a gettext template.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub(crate) struct Catalog {
+    /// Each text, with the places it is used in (e.g. `intro.md:3`), in the order it was first
+    /// used.
+    messages: Vec<(String, Vec<String>)>,
+}
+
+impl Catalog {
+    /// Adds the `segments` of the chapter at `chapter`, whose content is `source`.
+    pub(crate) fn add(&mut self, chapter: &Path, source: &str, segments: &[Segment]) {
+        for segment in segments {
+            let text = &source[segment.range.clone()];
+            let line = source[..segment.range.start].matches('\n').count() + 1;
+            let reference = format!("{}:{}", chapter.display(), line);
+            match self.messages.iter_mut().find(|(id, _)| id == text) {
+                Some((_, references)) => references.push(reference),
+                None => self.messages.push((text.to_owned(), vec![reference])),
+            }
+        }
+    }
+
+    /// Returns the catalog as a PO template, with an empty translation of each text.
+    pub(crate) fn to_pot(&self) -> String {
+        let mut result = String::from(
+            "msgid \"\"\nmsgstr \"\"\n\"Content-Type: text/plain; charset=UTF-8\\n\"\n",
+        );
+        for (id, references) in &self.messages {
+            result.push_str(&format!(
+                "\n#: {}\nmsgid {}\nmsgstr \"\"\n",
+                references.join(" "),
+                po_string(id)
+            ));
+        }
+        result
+    }
+}
+
+/// Returns `text` as a PO string, split after each newline.
+fn po_string(text: &str) -> String {
+    let escape = |line: &str| {
+        line.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+    };
+    if !text.contains('\n') {
+        return format!("\"{}\"", escape(text));
+    }
+    let mut result = String::from("\"\"");
+    for line in text.split_inclusive('\n') {
+        result.push_str(&format!("\n\"{}\"", escape(line)));
+    }
+    result
+}
+
+/// Returns the translations in a PO file, keyed by the text they translate. Fuzzy and empty
+/// translations are left out.
+pub(crate) fn parse_po(input: &str) -> std::result::Result<BTreeMap<String, String>, String> {
+    let mut result = BTreeMap::new();
+    let mut id = String::new();
+    let mut translation = String::new();
+    // Which of the strings continuation lines are added to.
+    let mut in_translation = false;
+    let mut fuzzy = false;
+
+    let mut finish = |id: &mut String, translation: &mut String, fuzzy: &mut bool| {
+        if !id.is_empty() && !translation.is_empty() && !*fuzzy {
+            result.insert(std::mem::take(id), std::mem::take(translation));
+        }
+        id.clear();
+        translation.clear();
+        *fuzzy = false;
+    };
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        // A comment or `msgid` after a translation starts the next entry.
+        let starts_entry = line.starts_with('#') || line.starts_with("msgid ");
+        if starts_entry && in_translation {
+            finish(&mut id, &mut translation, &mut fuzzy);
+            in_translation = false;
+        }
+        let string = if let Some(rest) = line.strip_prefix("msgid ") {
+            rest
+        } else if let Some(rest) = line.strip_prefix("msgstr ") {
+            in_translation = true;
+            rest
+        } else if line.starts_with('"') {
+            line
+        } else {
+            if line.starts_with("#,") && line.contains("fuzzy") {
+                fuzzy = true;
+            }
+            // Other comments, contexts and blank lines don't hold any text.
+            continue;
+        };
+        let string = unescape(string).ok_or_else(|| format!("invalid string on line {}", i + 1))?;
+        if in_translation {
+            translation.push_str(&string);
+        } else {
+            id.push_str(&string);
+        }
+    }
+    finish(&mut id, &mut translation, &mut fuzzy);
+    Ok(result)
+}
+
+/// Returns the contents of a quoted PO string.
+fn unescape(string: &str) -> Option<String> {
+    let inner = string.strip_prefix('"')?.strip_suffix('"')?;
+    let mut result = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        result.push(match chars.next()? {
+            'n' => '\n',
+            't' => '\t',
+            c @ ('"' | '\\') => c,
+            _ => return None,
+        });
+    }
+    Some(result)
+}
+
+/// Returns `source` with the text of its `segments` replaced by their `translations`. Text
+/// without a translation is left as it is.
+pub(crate) fn merge(
+    source: &str,
+    segments: &[Segment],
+    translations: &BTreeMap<String, String>,
+) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut last = 0;
+    for segment in segments {
+        let translation = match translations.get(&source[segment.range.clone()]) {
+            Some(translation) => translation,
+            None => continue,
+        };
+        result.push_str(&source[last..segment.range.start]);
+        if segment.unquoted && translation.contains(char::is_whitespace) {
+            result.push_str(&format!("\"{}\"", translation));
+        } else {
+            result.push_str(translation);
+        }
+        last = segment.range.end;
+    }
+    result.push_str(&source[last..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(name: &str) -> Option<&'static str> {
+        ["hint", "columns", "kbd"]
+            .iter()
+            .copied()
+            .find(|&n| n == name)
+    }
+
+    #[test]
+    fn test_extract() {
+        let source = "{{#hint info title=Careful}}\nBe *careful*.\n{{/hint}}\n\n\
+            {{#columns}}{{#hint ok title='Fine \"now\"'}}Be careful.{{/hint}}\
+            {{#column}}Press {{#kbd Ctrl /}}.{{/columns}}";
+        let segments = segments(source, 0, &Delimiters::default(), &resolve).unwrap();
+        let texts: Vec<_> = segments.iter().map(|s| &source[s.range.clone()]).collect();
+        assert_eq!(
+            texts,
+            ["Careful", "Be *careful*.", "Fine \"now\"", "Be careful."]
+        );
+
+        let mut catalog = Catalog::default();
+        catalog.add(Path::new("intro.md"), source, &segments);
+        assert_eq!(
+            catalog.to_pot(),
+            "msgid \"\"\nmsgstr \"\"\n\"Content-Type: text/plain; charset=UTF-8\\n\"\n\
+            \n#: intro.md:1\nmsgid \"Careful\"\nmsgstr \"\"\n\
+            \n#: intro.md:2\nmsgid \"Be *careful*.\"\nmsgstr \"\"\n\
+            \n#: intro.md:5\nmsgid \"Fine \\\"now\\\"\"\nmsgstr \"\"\n\
+            \n#: intro.md:5\nmsgid \"Be careful.\"\nmsgstr \"\"\n"
+        );
+        assert_eq!(po_string("A\nB"), "\"\"\n\"A\\n\"\n\"B\"");
+    }
+
+    #[test]
+    fn test_merge() {
+        let po = "msgid \"\"\nmsgstr \"\"\n\"Language: fr\\n\"\n\n\
+            #: intro.md:1\nmsgid \"Careful\"\nmsgstr \"Attention \"\n\"danger\"\n\n\
+            #, fuzzy\nmsgid \"Be careful.\"\nmsgstr \"Soyez prudent.\"\n\n\
+            msgid \"Untranslated\"\nmsgstr \"\"\n";
+        let translations = parse_po(po).unwrap();
+        assert_eq!(
+            translations.into_iter().collect::<Vec<_>>(),
+            [("Careful".to_owned(), "Attention danger".to_owned())]
+        );
+        assert!(parse_po("msgid \"A\\q\"").is_err());
+
+        let source = "{{#hint info title=Careful}}Be careful.{{/hint}}";
+        let segments = segments(source, 0, &Delimiters::default(), &resolve).unwrap();
+        let mut translations = parse_po(po).unwrap();
+        translations.insert("Be careful.".to_owned(), "Soyez prudent.".to_owned());
+        assert_eq!(
+            merge(source, &segments, &translations),
+            "{{#hint info title=\"Attention danger\"}}Soyez prudent.{{/hint}}"
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use mdbook::Config as BookConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    deps::{self, DependencyGraph, Snapshot},
+    Config,
+};
+
+/// How often the watcher checks whether files have changed.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The files read by shortcodes that `mdbook serve` doesn't watch, along with the chapters that
+/// use them.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct WatchList {
+    /// Identifies the build that wrote the list. A watcher stops once a newer build replaces it.
+    generation: u128,
+    graph: DependencyGraph,
+    /// The `watch-dirs`, every file in which is watched.
+    dirs: Vec<PathBuf>,
+}
+
+impl WatchList {
+    /// Returns the list of the dependencies in `graph` and `watch-dirs` that `mdbook serve`
+    /// doesn't watch already, i.e. those outside the source directory, the theme directory and
+    /// `build.extra-watch-dirs`.
+    pub(crate) fn new(
+        mut graph: DependencyGraph,
+        root: &Path,
+        book: &BookConfig,
+        config: &Config,
+    ) -> Self {
+        let mut watched = vec![root.join(&book.book.src), root.join("theme")];
+        watched.extend(book.build.extra_watch_dirs.iter().map(|dir| root.join(dir)));
+        let is_watched = |path: &Path| watched.iter().any(|dir| path.starts_with(dir));
+
+        graph.retain(|path| !is_watched(path));
+        let dirs = config
+            .watch_dirs
+            .iter()
+            .map(|dir| root.join(dir))
+            .filter(|dir| !is_watched(dir))
+            .collect();
+        Self {
+            generation: 0,
+            graph,
+            dirs,
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let mut snapshot = Snapshot::new();
+        for path in self.graph.paths() {
+            deps::snapshot(path, &mut snapshot);
+        }
+        for dir in &self.dirs {
+            deps::snapshot(dir, &mut snapshot);
+        }
+        snapshot
+    }
+}
+
+/// Returns whether the book is being built by `mdbook serve`, which sets the live reload
+/// endpoint.
+pub(crate) fn is_serving(config: &BookConfig) -> bool {
+    config.get("output.html.live-reload-endpoint").is_some()
+}
+
+/// Writes `list` into `cache_dir` and starts the `watch` subcommand of this executable on it in
+/// the background.
+///
+/// A watcher is started after every build, and the one started by the previous build stops
+/// once it sees the new list. The watcher is in the same process group as `mdbook serve`, so
+/// it is stopped along with it.
+pub(crate) fn spawn(mut list: WatchList, cache_dir: &Path) -> io::Result<()> {
+    list.generation = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    fs::create_dir_all(cache_dir)?;
+    let path = cache_dir.join("watch.json");
+    fs::write(&path, serde_json::to_vec(&list)?)?;
+    if list.graph.paths().is_empty() {
+        return Ok(());
+    }
+
+    // mdBook reads the output of preprocessors until every copy of it is closed, so the
+    // watcher mustn't inherit it.
+    Command::new(std::env::current_exe()?)
+        .arg("watch")
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
+
+/// Waits for a file in the watch list at `path` to change, then touches the chapters that use it
+/// so that `mdbook serve` rebuilds the book.
+///
+/// Returns early if a newer build replaces the list. This is run by the `watch` subcommand of
+/// the preprocessor, which is started after every build under `mdbook serve`.
+pub fn watch(path: &Path) -> io::Result<()> {
+    let list = read(path)?;
+    let mut previous = list.snapshot();
+
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        match read(path) {
+            Ok(current) if current.generation == list.generation => {}
+            _ => return Ok(()),
+        }
+
+        let current = list.snapshot();
+        let changed = previous
+            .keys()
+            .chain(current.keys())
+            .filter(|file| previous.get(*file) != current.get(*file));
+        let chapters: BTreeSet<&Path> = changed
+            .flat_map(|file| list.graph.dependents(file))
+            .collect();
+        for chapter in &chapters {
+            fs::File::options()
+                .append(true)
+                .open(chapter)?
+                .set_modified(SystemTime::now())?;
+        }
+        if !chapters.is_empty() {
+            return Ok(());
+        }
+        // Nothing uses the files that changed (e.g. a new file in a `watch-dirs` directory).
+        previous = current;
+    }
+}
+
+fn read(path: &Path) -> io::Result<WatchList> {
+    Ok(serde_json::from_slice(&fs::read(path)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let book: BookConfig = "[build]\nextra-watch-dirs = [\"data\"]\n".parse().unwrap();
+        let config = Config {
+            watch_dirs: vec!["data".to_owned(), "partials".to_owned()],
+            ..Default::default()
+        };
+        let root = Path::new("/book");
+        let mut graph = DependencyGraph::default();
+        for file in ["src/a.csv", "data/b.csv", "theme/c.css", "d.csv"] {
+            graph.add(root.join("src/chapter.md"), root.join(file));
+        }
+        let list = WatchList::new(graph, root, &book, &config);
+        assert_eq!(
+            list.graph.paths().into_iter().collect::<Vec<_>>(),
+            [root.join("d.csv")]
+        );
+        assert_eq!(list.dirs, [root.join("partials")]);
+    }
+
+    #[test]
+    fn test_watch() {
+        let dir = tempfile::tempdir().unwrap();
+        let partials = dir.path().join("partials");
+        fs::create_dir(&partials).unwrap();
+        let data = partials.join("data.csv");
+        let chapter = dir.path().join("chapter.md");
+        fs::write(&data, "a").unwrap();
+        fs::write(&chapter, "# Chapter").unwrap();
+        let old = SystemTime::now() - Duration::from_secs(60);
+        for file in [&data, &chapter] {
+            fs::File::options()
+                .append(true)
+                .open(file)
+                .unwrap()
+                .set_modified(old)
+                .unwrap();
+        }
+
+        let mut graph = DependencyGraph::default();
+        graph.add(chapter.clone(), data.clone());
+        let list = WatchList {
+            generation: 0,
+            graph,
+            dirs: vec![partials.clone()],
+        };
+        let path = dir.path().join("watch.json");
+        fs::write(&path, serde_json::to_vec(&list).unwrap()).unwrap();
+
+        let watcher = thread::spawn(move || watch(&path));
+        thread::sleep(POLL_INTERVAL);
+        // Nothing uses the new file, so the watcher keeps going.
+        fs::write(partials.join("unused.csv"), "").unwrap();
+        thread::sleep(POLL_INTERVAL * 2);
+        assert!(!watcher.is_finished());
+        fs::write(&data, "b").unwrap();
+        watcher.join().unwrap().unwrap();
+
+        let modified = fs::metadata(&chapter).unwrap().modified().unwrap();
+        assert!(modified > old);
+        assert_eq!(fs::read_to_string(&chapter).unwrap(), "# Chapter");
+    }
+}